@@ -165,7 +165,7 @@ impl<T: BufferVecItem> BufferVec<T> for BufferVecStatic<T> {
 	fn get(&self, index: usize) -> Result<T, GLCoreError> {
 		let offset = index * size_of::<T>();
 		let bind = self.buffer.bind()?;
-		let (map, addr) = bind.map_ranged(offset, size_of::<T>(), MapAccess::WriteOnly)?;
+		let (map, addr) = bind.map_ranged(offset, size_of::<T>(), MapAccess::ReadOnly)?;
 		let addr = addr as *mut T;
 		let ret = unsafe { *addr };
 		map.unmap();
@@ -185,13 +185,15 @@ impl<T: BufferVecItem> BufferVec<T> for BufferVecStatic<T> {
 	}
 
 	fn get_slice_of_data(&self, start_index: usize, len: usize) -> Result<Vec<T>, GLCoreError> {
+		if len == 0 {
+			return Ok(Vec::new());
+		}
 		let offset = start_index * size_of::<T>();
-		let end_index = start_index + len;
 		let bind = self.buffer.bind()?;
-		let (map, addr) = bind.map_ranged(offset, len * size_of::<T>(), MapAccess::WriteOnly)?;
+		let (map, addr) = bind.map_ranged(offset, len * size_of::<T>(), MapAccess::ReadOnly)?;
 		let addr = addr as *mut T;
 		let mut ret: Vec<T> = Vec::with_capacity(len);
-		for i in start_index..end_index {
+		for i in 0..len {
 			ret.push(unsafe {*addr.wrapping_add(i)});
 		}
 		map.unmap();
@@ -199,6 +201,9 @@ impl<T: BufferVecItem> BufferVec<T> for BufferVecStatic<T> {
 	}
 
 	fn set_slice_of_data(&mut self, index: usize, data: &[T]) -> Result<(), GLCoreError> {
+		if data.is_empty() {
+			return Ok(());
+		}
 		let offset = index * size_of::<T>();
 		let bind = self.buffer.bind()?;
 		let (map, addr) = bind.map_ranged(offset, size_of_val(data), MapAccess::WriteOnly)?;
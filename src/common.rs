@@ -1,3 +1,5 @@
+use crate::prelude::*;
+use std::ffi::{CStr, c_char};
 
 /// Convert a snake_case string to the camel case string
 pub fn to_camel_case(snake_case: &str, first_letter_uppercase: bool) -> String {
@@ -19,3 +21,110 @@ pub fn to_camel_case(snake_case: &str, first_letter_uppercase: bool) -> String {
 	}
 	ret
 }
+
+/// Check if the context supports the extension, e.g. `GL_KHR_debug`
+pub fn has_gl_extension(glcore: &GLCore, extension: &str) -> Result<bool, GLCoreError> {
+	let mut num_extensions: i32 = 0;
+	glcore.glGetIntegerv(GL_NUM_EXTENSIONS, &mut num_extensions as *mut _)?;
+	for i in 0..num_extensions as u32 {
+		let name = glcore.glGetStringi(GL_EXTENSIONS, i)?;
+		if !name.is_null() && unsafe {CStr::from_ptr(name as *const c_char)}.to_bytes() == extension.as_bytes() {
+			return Ok(true);
+		}
+	}
+	Ok(false)
+}
+
+/// The axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+	pub min: Vec3,
+	pub max: Vec3,
+}
+
+/// The six planes of the view frustum extracted from a view-projection matrix of the OpenGL clip space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+	/// The left, right, bottom, top, near and far planes, `(a, b, c, d)` of `ax + by + cz + d >= 0` for the inner side, the normals are normalized
+	pub planes: [Vec4; 6],
+}
+
+impl Aabb {
+	/// Create the box from the corners
+	pub fn new(min: Vec3, max: Vec3) -> Self {
+		Self {
+			min,
+			max,
+		}
+	}
+
+	/// Get the bounding box of the points, `None` if there's no point
+	pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+		let mut points = points.into_iter();
+		let first = points.next()?;
+		Some(points.fold(Self::new(first, first), |aabb, point| Self::new(aabb.min.inf(&point), aabb.max.sup(&point))))
+	}
+
+	/// Get the center
+	pub fn center(&self) -> Vec3 {
+		(self.min + self.max) * 0.5
+	}
+
+	/// Get the size of each axis
+	pub fn size(&self) -> Vec3 {
+		self.max - self.min
+	}
+
+	/// Get the 8 corners
+	pub fn corners(&self) -> [Vec3; 8] {
+		let (a, b) = (self.min, self.max);
+		[
+			Vec3::new(a.x, a.y, a.z), Vec3::new(b.x, a.y, a.z), Vec3::new(a.x, b.y, a.z), Vec3::new(b.x, b.y, a.z),
+			Vec3::new(a.x, a.y, b.z), Vec3::new(b.x, a.y, b.z), Vec3::new(a.x, b.y, b.z), Vec3::new(b.x, b.y, b.z),
+		]
+	}
+
+	/// Get the bounding box of this box transformed by the matrix, it's larger than the transformed box if the matrix rotates it
+	pub fn transform(&self, matrix: &Mat4) -> Self {
+		Self::from_points(self.corners().iter().map(|corner| (matrix * corner.push(1.0)).xyz())).unwrap()
+	}
+
+	/// Get the matrix that transforms the unit cube centered at the origin into this box
+	pub fn to_matrix(&self) -> Mat4 {
+		translation(&self.center()) * scaling(&self.size())
+	}
+}
+
+impl Frustum {
+	/// Extract the planes from the view-projection matrix, the planes are in the space before the matrix, e.g. the world space
+	pub fn from_matrix(view_projection: &Mat4) -> Self {
+		let row = |i: usize| -> Vec4 {view_projection.row(i).transpose()};
+		let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+		let planes = [w + x, w - x, w + y, w - y, w + z, w - z].map(|plane| {
+			let length = plane.xyz().norm();
+			if length > 0.0 {plane / length} else {plane}
+		});
+		Self {
+			planes,
+		}
+	}
+
+	/// Check if the point is inside the frustum
+	pub fn contains_point(&self, point: &Vec3) -> bool {
+		self.planes.iter().all(|plane| plane.xyz().dot(point) + plane.w >= 0.0)
+	}
+
+	/// Check if the box may be visible. A box is only rejected when it's entirely outside one of the planes, so there's no false negative,
+	/// but a box near an edge or a corner of the frustum that's outside of it but not entirely outside any single plane is a false positive.
+	pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+		self.planes.iter().all(|plane| {
+			// The corner farthest along the normal of the plane
+			let corner = Vec3::new(
+				if plane.x >= 0.0 {aabb.max.x} else {aabb.min.x},
+				if plane.y >= 0.0 {aabb.max.y} else {aabb.min.y},
+				if plane.z >= 0.0 {aabb.max.z} else {aabb.min.z},
+			);
+			plane.xyz().dot(&corner) + plane.w >= 0.0
+		})
+	}
+}
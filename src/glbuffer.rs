@@ -18,6 +18,7 @@ pub enum BufferTarget {
 	DispatchIndirectBuffer = GL_DISPATCH_INDIRECT_BUFFER as isize,
 	DrawIndirectBuffer = GL_DRAW_INDIRECT_BUFFER as isize,
 	ElementArrayBuffer = GL_ELEMENT_ARRAY_BUFFER as isize,
+	ParameterBuffer = GL_PARAMETER_BUFFER as isize,
 	PixelPackBuffer = GL_PIXEL_PACK_BUFFER as isize,
 	PixelUnpackBuffer = GL_PIXEL_UNPACK_BUFFER as isize,
 	QueryBuffer = GL_QUERY_BUFFER as isize,
@@ -56,6 +57,7 @@ pub struct Buffer {
 	usage: BufferUsage,
 	target: BufferTarget,
 	size: usize,
+	tracked: bool,
 }
 
 /// When to use a buffer, must bind the buffer first. The RAII system could help automatically unbind the buffer.
@@ -85,6 +87,9 @@ impl Buffer {
 	/// Release the OpenGL handle of the buffer object, consuming the `Buffer` struct.
 	/// You have to delete the buffer object manually.
 	pub unsafe fn to_raw(mut self) -> u32 {
+		if self.tracked {
+			MemoryTracker::track_delete(MemoryCategory::Buffers, self.size);
+		}
 		let ret = self.name;
 		self.name = 0;
 		ret
@@ -94,7 +99,7 @@ impl Buffer {
 	///
 	/// Create a `Buffer` struct from an OpenGL handle to the buffer object.
 	/// After the call, the OpenGL handle is managed by this struct.
-	/// On `drop()`, the OpenGL handle is deleted.
+	/// On `drop()`, the OpenGL handle is deleted. The buffer is not counted by the `MemoryTracker`.
 	pub unsafe fn from_raw(glcore: Rc<GLCore>, name: u32, target: BufferTarget) -> Result<Self, GLCoreError> {
 		glcore.glBindBuffer(target as u32, name)?;
 		let mut size = 0;
@@ -120,6 +125,7 @@ impl Buffer {
 			usage,
 			target,
 			size: size as usize,
+			tracked: false,
 		})
 	}
 
@@ -130,12 +136,14 @@ impl Buffer {
 		glcore.glBindBuffer(target as u32, name)?;
 		glcore.glBufferData(target as u32, size, data_ptr, usage as u32)?;
 		glcore.glBindBuffer(target as u32, 0)?;
+		MemoryTracker::track_new(MemoryCategory::Buffers, size);
 		Ok(Self {
 			glcore,
 			name,
 			usage,
 			target,
 			size,
+			tracked: true,
 		})
 	}
 
@@ -198,6 +206,9 @@ impl Drop for Buffer {
 	fn drop(&mut self) {
 		if self.name != 0 {
 			self.glcore.glDeleteBuffers(1, &self.name as *const u32).unwrap();
+			if self.tracked {
+				MemoryTracker::track_delete(MemoryCategory::Buffers, self.size);
+			}
 		}
 	}
 }
@@ -212,12 +223,14 @@ impl Clone for Buffer {
 		self.glcore.glCopyBufferSubData(BufferTarget::CopyReadBuffer as u32, self.target as u32, 0, 0, self.size).unwrap();
 		self.glcore.glBindBuffer(self.target as u32, 0).unwrap();
 		self.glcore.glBindBuffer(BufferTarget::CopyReadBuffer as u32, 0).unwrap();
+		MemoryTracker::track_new(MemoryCategory::Buffers, self.size);
 		Self {
 			glcore: self.glcore.clone(),
 			name,
 			usage: self.usage,
 			target: self.target,
 			size: self.size,
+			tracked: true,
 		}
 	}
 }
@@ -283,7 +296,7 @@ impl<'a> BufferMapping<'a> {
 
 	/// Map to the buffer partially to modify or retrieve the data of the buffer
 	fn new_ranged(buffer: &'a Buffer, target: BufferTarget, offset: usize, length: usize, access: MapAccess) -> Result<(Self, *mut c_void), GLCoreError> {
-		let address = buffer.glcore.glMapBufferRange(target as u32, offset, length, access as u32)?;
+		let address = buffer.glcore.glMapBufferRange(target as u32, offset, length, access.get_range_access_bits())?;
 		Ok((Self {
 			buffer,
 			target,
@@ -318,6 +331,17 @@ impl<'a> Drop for BufferMapping<'a> {
 	}
 }
 
+impl MapAccess {
+	/// Get the access bits for `glMapBufferRange()`, which doesn't take the enums of `glMapBuffer()`
+	pub fn get_range_access_bits(&self) -> u32 {
+		match self {
+			Self::ReadOnly => GL_MAP_READ_BIT,
+			Self::WriteOnly => GL_MAP_WRITE_BIT,
+			Self::ReadWrite => GL_MAP_READ_BIT | GL_MAP_WRITE_BIT,
+		}
+	}
+}
+
 impl Debug for BufferTarget {
 	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
 		match self {
@@ -328,6 +352,7 @@ impl Debug for BufferTarget {
 			Self::DispatchIndirectBuffer => write!(f, "DispatchIndirectBuffer"),
 			Self::DrawIndirectBuffer => write!(f, "DrawIndirectBuffer"),
 			Self::ElementArrayBuffer => write!(f, "ElementArrayBuffer"),
+			Self::ParameterBuffer => write!(f, "ParameterBuffer"),
 			Self::PixelPackBuffer => write!(f, "PixelPackBuffer"),
 			Self::PixelUnpackBuffer => write!(f, "PixelUnpackBuffer"),
 			Self::QueryBuffer => write!(f, "QueryBuffer"),
@@ -39,3 +39,46 @@ pub trait DrawCommand: Default + Clone + Copy + Sized + Debug {}
 impl DrawCommand for DrawArrayCommand {}
 impl DrawCommand for DrawElementsCommand {}
 impl DrawCommand for DispatchIndirectCommand {}
+
+impl DrawElementsCommand {
+	/// Create a command to draw `element_count` elements from `first_index` for `instance_count` instances, `base_vertex` is added to the elements
+	pub fn new(element_count: u32, instance_count: u32, first_index: u32, base_vertex: i32, base_instance: u32) -> Self {
+		Self {
+			element_count,
+			instance_count,
+			first_index,
+			base_vertex,
+			base_instance,
+		}
+	}
+
+	/// Get the number of the elements
+	pub fn get_element_count(&self) -> u32 {
+		self.element_count
+	}
+
+	/// Get the number of the instances
+	pub fn get_instance_count(&self) -> u32 {
+		self.instance_count
+	}
+
+	/// Set the number of the instances, 0 skips the command
+	pub fn set_instance_count(&mut self, instance_count: u32) {
+		self.instance_count = instance_count;
+	}
+
+	/// Get the first element
+	pub fn get_first_index(&self) -> u32 {
+		self.first_index
+	}
+
+	/// Get the value added to the elements
+	pub fn get_base_vertex(&self) -> i32 {
+		self.base_vertex
+	}
+
+	/// Get the first instance of the instanced attribs, it's also `gl_BaseInstance` of the shaders
+	pub fn get_base_instance(&self) -> u32 {
+		self.base_instance
+	}
+}
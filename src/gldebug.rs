@@ -0,0 +1,93 @@
+use crate::prelude::*;
+use std::{
+	cell::Cell,
+	fmt::{self, Debug, Formatter},
+};
+
+thread_local! {
+	static INTERNAL_GROUPS: Cell<bool> = const {Cell::new(true)};
+	static DEBUG_AVAILABLE: Cell<Option<bool>> = const {Cell::new(None)};
+}
+
+/// The named region of the OpenGL calls between `glPushDebugGroup()` and `glPopDebugGroup()`, shown by the frame capture tools, the RAII system helps to pop it.
+/// Without OpenGL 4.3 or `GL_KHR_debug` it does nothing, the support is checked once per thread.
+///
+/// The pipelines, the pipeline sets and the framebuffers push their own groups while drawing, call `DebugScope::set_internal_groups(false)` to disable them.
+pub struct DebugScope<'a> {
+	pub glcore: &'a GLCore,
+	pushed: bool,
+}
+
+impl<'a> DebugScope<'a> {
+	/// Push a debug group of the label
+	pub fn push(glcore: &'a GLCore, label: &str) -> Result<Self, GLCoreError> {
+		let pushed = has_khr_debug(glcore)?;
+		if pushed {
+			glcore.glPushDebugGroup(GL_DEBUG_SOURCE_APPLICATION, 0, label.len() as i32, label.as_ptr() as *const _)?;
+		}
+		Ok(Self {
+			glcore,
+			pushed,
+		})
+	}
+
+	/// Push a debug group of this crate if the internal groups are enabled, `label` is only called when the group is pushed
+	pub(crate) fn push_internal(glcore: &'a GLCore, label: impl FnOnce() -> String) -> Result<Option<Self>, GLCoreError> {
+		if Self::is_internal_groups_enabled() && has_khr_debug(glcore)? {
+			Ok(Some(Self::push(glcore, &label())?))
+		} else {
+			Ok(None)
+		}
+	}
+
+	/// Enable or disable the debug groups pushed by this crate on the current thread, they are enabled by default
+	pub fn set_internal_groups(enabled: bool) {
+		INTERNAL_GROUPS.set(enabled);
+	}
+
+	/// Check if the debug groups pushed by this crate are enabled on the current thread
+	pub fn is_internal_groups_enabled() -> bool {
+		INTERNAL_GROUPS.get()
+	}
+
+	/// Pop the debug group
+	pub fn pop(self) {} // Pop by owning it in the function and `drop()`
+}
+
+impl Drop for DebugScope<'_> {
+	fn drop(&mut self) {
+		if self.pushed {
+			self.glcore.glPopDebugGroup().unwrap();
+		}
+	}
+}
+
+impl Debug for DebugScope<'_> {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.debug_struct("DebugScope")
+		.field("pushed", &self.pushed)
+		.finish()
+	}
+}
+
+/// Insert a marker message into the debug output, e.g. to find a point of the frame in the capture tools. Does nothing without OpenGL 4.3 or `GL_KHR_debug`.
+pub fn insert_marker(glcore: &GLCore, message: &str) -> Result<(), GLCoreError> {
+	if has_khr_debug(glcore)? {
+		glcore.glDebugMessageInsert(GL_DEBUG_SOURCE_APPLICATION, GL_DEBUG_TYPE_MARKER, 0, GL_DEBUG_SEVERITY_NOTIFICATION, message.len() as i32, message.as_ptr() as *const _)?;
+	}
+	Ok(())
+}
+
+/// Is `KHR_debug` available, it's the core of OpenGL 4.3. The result is cached for the current thread.
+pub(crate) fn has_khr_debug(glcore: &GLCore) -> Result<bool, GLCoreError> {
+	if let Some(available) = DEBUG_AVAILABLE.get() {
+		return Ok(available);
+	}
+	let mut major: i32 = 0;
+	let mut minor: i32 = 0;
+	glcore.glGetIntegerv(GL_MAJOR_VERSION, &mut major as *mut _)?;
+	glcore.glGetIntegerv(GL_MINOR_VERSION, &mut minor as *mut _)?;
+	let available = (major, minor) >= (4, 3) || has_gl_extension(glcore, "GL_KHR_debug")?;
+	DEBUG_AVAILABLE.set(Some(available));
+	Ok(available)
+}
@@ -1,19 +1,102 @@
 
+#![allow(clippy::too_many_arguments)]
+
 use crate::prelude::*;
+use crate::gltexture::PixelStore;
+use image::RgbaImage;
 use std::{
+	cell::{Cell, RefCell},
 	cmp::max,
 	collections::BTreeMap,
+	ffi::{CString, c_void},
 	fmt::{self, Debug, Formatter},
+	ops::BitOr,
 	rc::Rc,
 };
 
+/// How the texture is attached to the framebuffer
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentMode {
+	/// Attach a 2D image, the face `texture_target` of the cube map or the first layer of the 3D texture, use `Layer` for the other layers
+	#[default]
+	Single,
+
+	/// Attach all of the layers of the cube map or the 3D texture by `glFramebufferTexture()`, the geometry shader selects the layer by `gl_Layer`
+	Layered,
+
+	/// Attach the layer of the 3D texture or the texture array by `glFramebufferTextureLayer()`
+	Layer(i32),
+}
+
 /// The framebuffer render target type
 pub struct FramebufferTarget {
 	/// The texture binding target
 	pub texture_target: TextureTarget,
 
-	/// The layer index of the 3D texture to bind (Only bind a 2D layer to the framebuffer)
-	pub layer_of_3d: i32,
+	/// How the texture is attached, all of the attachments must be either layered or not
+	pub mode: AttachmentMode,
+
+	/// The mip level of the texture to attach, the viewport is set to the size of the level
+	pub level: u32,
+
+	/// The size of the texture relative to the framebuffer for `Framebuffer::resize_attachments()`, e.g. 0.5 for a half-resolution buffer. `None` keeps the size of the texture.
+	pub resize_scale: Option<f32>,
+}
+
+/// The color attachment of a draw target, either the explicit index or the name of the fragment shader output that decides the index
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Attachment {
+	/// Attach to `GL_COLOR_ATTACHMENT0 + n` without consulting the shader, e.g. for `layout(location = n) out`
+	Index(u32),
+
+	/// The location of the fragment shader output is queried by `glGetFragDataLocation()`
+	FragOutput(String),
+}
+
+/// The attachment point of the framebuffer
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AttachmentPoint {
+	/// The color attachment of the index
+	Color(u32),
+	Depth,
+	Stencil,
+	DepthStencil,
+}
+
+/// The renderbuffer object type, a write-only image for the framebuffer attachments that are never sampled, e.g. the MSAA color/depth targets
+pub struct Renderbuffer {
+	pub glcore: Rc<GLCore>,
+	name: u32,
+	format: TextureFormat,
+	width: Cell<u32>,
+	height: Cell<u32>,
+	samples: u32,
+}
+
+/// The buffers to copy by `glBlitFramebuffer()`, combine them by `|`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct BlitMask {
+	pub color: bool,
+	pub depth: bool,
+	pub stencil: bool,
+}
+
+/// The filter of the scaled blits, the depth and stencil blits must use `Nearest`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BlitFilter {
+	Nearest = GL_NEAREST as isize,
+	Linear = GL_LINEAR as isize,
+}
+
+/// What to do when the source and the destination of a resolve have different sizes
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum SizeMismatchPolicy {
+	/// Return `FramebufferError::SizeMismatch`
+	#[default]
+	Error,
+
+	/// Blit the overlapping region from the bottom-left corner
+	Overlap,
 }
 
 /// The framebuffer object type
@@ -21,15 +104,47 @@ pub struct Framebuffer {
 	pub glcore: Rc<GLCore>,
 	name: u32,
 
-	/// The name of the draw targets and the binding target and the texture
-	pub draw_targets: BTreeMap<String, (FramebufferTarget, Rc<dyn GenericTexture>)>,
+	/// The color attachments of the draw targets and the binding target and the texture
+	draw_targets: BTreeMap<Attachment, (FramebufferTarget, Rc<dyn GenericTexture>)>,
+
+	/// The depth or depth-stencil texture and its binding target
+	depth_target: Option<(FramebufferTarget, Rc<dyn GenericTexture>)>,
+
+	/// The renderbuffers attached to the attachment points
+	renderbuffers: BTreeMap<AttachmentPoint, Rc<Renderbuffer>>,
+
+	/// The attachments applied by the last `FramebufferBind::setup()`
+	attachment_cache: RefCell<AttachmentCache>,
+
+	/// Enable `GL_FRAMEBUFFER_SRGB` while the framebuffer is bound for drawing, `None` decides by the formats of the color attachments
+	srgb_write: Option<bool>,
+
+	/// Set the viewport to the size of the attachments by `FramebufferBind::setup()`
+	auto_viewport: bool,
+
+	/// Is `glInvalidateFramebuffer()` available, queried by the first invalidation
+	invalidate_supported: Cell<Option<bool>>,
+}
+
+/// The attachments applied to the framebuffer object, `FramebufferBind::setup()` skips attaching if nothing changed
+#[derive(Default)]
+struct AttachmentCache {
+	/// The shader program that the draw targets were attached for, `None` if the attachments are dirty
+	program: Option<u32>,
+
+	/// The attachment points that have the images attached
+	attached: Vec<u32>,
+
+	/// How many times `FramebufferBind::setup()` applied the attachments
+	apply_count: u64,
 }
 
 /// The error of the framebuffers
 #[derive(Debug, Clone)]
 pub enum FramebufferError {
 	NoDefaultFramebuffer,
-	IncompleteAttachment,
+	/// The attachment is incomplete, the hint (the first one) tells the likely reason if known
+	IncompleteAttachment(Option<String>),
 	IncompleteMissingAttachment,
 	IncompleteDrawBuffer,
 	IncompleteReadBuffer,
@@ -38,6 +153,24 @@ pub enum FramebufferError {
 	IncompleteLayerTarget,
 	UnknownError(GLenum),
 	GLCoreError(GLCoreError),
+
+	/// The requested sample count (the first one) exceeds `GL_MAX_SAMPLES` (the second one)
+	InvalidSampleCount(u32, u32),
+
+	/// The size of the source (the first one) differs from the size of the destination (the second one)
+	SizeMismatch([u32; 2], [u32; 2]),
+
+	/// The name of the draw target (the first one) contains a NUL byte
+	InvalidTargetName(String),
+
+	/// Texture error
+	TextureError(String),
+
+	/// The texture couldn't be attached as the draw target, the reason is the first one
+	InvalidTargetTexture(String),
+
+	/// The depth or the stencil blit (the mask is the first one) couldn't use the filter (the second one), only `Nearest` is allowed
+	InvalidBlitFilter(BlitMask, BlitFilter),
 }
 
 impl From<GLCoreError> for FramebufferError {
@@ -46,9 +179,297 @@ impl From<GLCoreError> for FramebufferError {
 	}
 }
 
+/// The binding target of the framebuffer, the blits and the pixel readbacks read from the read framebuffer
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FramebufferBindTarget {
+	Draw = GL_DRAW_FRAMEBUFFER as isize,
+	Read = GL_READ_FRAMEBUFFER as isize,
+	Both = GL_FRAMEBUFFER as isize,
+}
+
 /// The binding guard of the framebuffer
 pub struct FramebufferBind<'a> {
 	framebuffer: &'a Framebuffer,
+	target: FramebufferBindTarget,
+
+	/// The previous state of `GL_FRAMEBUFFER_SRGB` to restore, `None` if the framebuffer isn't bound for drawing
+	prev_srgb_write: Option<bool>,
+}
+
+/// The mip chain of a texture with a framebuffer attached to each of the mip levels, e.g. for the bloom that downsamples into the successive levels, then upsamples and accumulates back.
+/// Each framebuffer sets the viewport to the size of its level, draw into them by `PipelineBind::draw()` as usual.
+pub struct MipChainTarget {
+	texture: Rc<dyn GenericTexture>,
+	framebuffers: Vec<Framebuffer>,
+}
+
+impl AttachmentPoint {
+	/// Get the `GLenum` of the attachment point
+	pub fn to_gl(&self) -> u32 {
+		match self {
+			Self::Color(index) => GL_COLOR_ATTACHMENT0 + index,
+			Self::Depth => GL_DEPTH_ATTACHMENT,
+			Self::Stencil => GL_STENCIL_ATTACHMENT,
+			Self::DepthStencil => GL_DEPTH_STENCIL_ATTACHMENT,
+		}
+	}
+}
+
+impl Debug for AttachmentPoint {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Color(index) => write!(f, "Color({index})"),
+			Self::Depth => write!(f, "Depth"),
+			Self::Stencil => write!(f, "Stencil"),
+			Self::DepthStencil => write!(f, "DepthStencil"),
+		}
+	}
+}
+
+impl Debug for FramebufferBindTarget {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Draw => write!(f, "Draw"),
+			Self::Read => write!(f, "Read"),
+			Self::Both => write!(f, "Both"),
+		}
+	}
+}
+
+impl From<u32> for Attachment {
+	fn from(index: u32) -> Self {
+		Self::Index(index)
+	}
+}
+
+impl From<&str> for Attachment {
+	fn from(name: &str) -> Self {
+		Self::FragOutput(name.to_owned())
+	}
+}
+
+impl From<String> for Attachment {
+	fn from(name: String) -> Self {
+		Self::FragOutput(name)
+	}
+}
+
+impl Debug for Attachment {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Index(index) => write!(f, "Index({index})"),
+			Self::FragOutput(name) => write!(f, "FragOutput({name:?})"),
+		}
+	}
+}
+
+impl Debug for AttachmentMode {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Single => write!(f, "Single"),
+			Self::Layered => write!(f, "Layered"),
+			Self::Layer(layer) => write!(f, "Layer({layer})"),
+		}
+	}
+}
+
+impl FramebufferTarget {
+	/// Create a target that attaches a 2D image of the texture target, e.g. a face of the cube map
+	pub fn new(texture_target: TextureTarget) -> Self {
+		Self {
+			texture_target,
+			mode: AttachmentMode::Single,
+			level: 0,
+			resize_scale: Some(1.0),
+		}
+	}
+
+	/// Create a target that attaches all of the layers of the cube map or the 3D texture for the layered rendering
+	pub fn layered(texture_target: TextureTarget) -> Self {
+		Self {
+			texture_target,
+			mode: AttachmentMode::Layered,
+			level: 0,
+			resize_scale: Some(1.0),
+		}
+	}
+
+	/// Create a target that attaches one layer of the 3D texture or the texture array
+	pub fn layer(texture_target: TextureTarget, layer: i32) -> Self {
+		Self {
+			texture_target,
+			mode: AttachmentMode::Layer(layer),
+			level: 0,
+			resize_scale: Some(1.0),
+		}
+	}
+
+	/// Create the default target of the texture dimension, the cube maps and the 3D textures are attached as layered
+	pub fn for_dimension(dim: TextureDimension) -> Self {
+		match dim {
+			TextureDimension::Tex1d => Self::new(TextureTarget::Tex1d),
+			TextureDimension::Tex2d => Self::new(TextureTarget::Tex2d),
+			TextureDimension::Tex3d => Self::layered(TextureTarget::Tex3d),
+			TextureDimension::TexCube => Self::layered(TextureTarget::TexCube),
+			TextureDimension::Tex2dMultisample => Self::new(TextureTarget::Tex2dMultisample),
+		}
+	}
+
+	/// Attach the mip level instead of the level 0, e.g. for the downsampling chains
+	pub fn with_level(mut self, level: u32) -> Self {
+		self.level = level;
+		self
+	}
+
+	/// Set the size of the texture relative to the framebuffer for `Framebuffer::resize_attachments()`, `None` keeps the size of the texture
+	pub fn with_resize_scale(mut self, resize_scale: Option<f32>) -> Self {
+		self.resize_scale = resize_scale;
+		self
+	}
+}
+
+impl Default for FramebufferTarget {
+	fn default() -> Self {
+		Self::new(TextureTarget::Tex2d)
+	}
+}
+
+impl BlitMask {
+	pub const COLOR: Self = Self {color: true, depth: false, stencil: false};
+	pub const DEPTH: Self = Self {color: false, depth: true, stencil: false};
+	pub const STENCIL: Self = Self {color: false, depth: false, stencil: true};
+	pub const ALL: Self = Self {color: true, depth: true, stencil: true};
+
+	/// Get the bits of the mask for `glBlitFramebuffer()`
+	pub fn to_gl(&self) -> u32 {
+		let mut bits = 0;
+		if self.color {bits |= GL_COLOR_BUFFER_BIT}
+		if self.depth {bits |= GL_DEPTH_BUFFER_BIT}
+		if self.stencil {bits |= GL_STENCIL_BUFFER_BIT}
+		bits
+	}
+}
+
+impl BitOr for BlitMask {
+	type Output = Self;
+	fn bitor(self, rhs: Self) -> Self {
+		Self {
+			color: self.color || rhs.color,
+			depth: self.depth || rhs.depth,
+			stencil: self.stencil || rhs.stencil,
+		}
+	}
+}
+
+impl Debug for BlitMask {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		let mut names = Vec::with_capacity(3);
+		if self.color {names.push("COLOR")}
+		if self.depth {names.push("DEPTH")}
+		if self.stencil {names.push("STENCIL")}
+		if names.is_empty() {
+			write!(f, "BlitMask(empty)")
+		} else {
+			write!(f, "BlitMask({})", names.join(" | "))
+		}
+	}
+}
+
+impl Debug for BlitFilter {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Nearest => write!(f, "Nearest"),
+			Self::Linear => write!(f, "Linear"),
+		}
+	}
+}
+
+impl Debug for SizeMismatchPolicy {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Error => write!(f, "Error"),
+			Self::Overlap => write!(f, "Overlap"),
+		}
+	}
+}
+
+impl Renderbuffer {
+	/// Create a new renderbuffer
+	pub fn new(glcore: Rc<GLCore>, format: TextureFormat, width: u32, height: u32) -> Result<Self, FramebufferError> {
+		Self::new_multisample(glcore, format, width, height, 0)
+	}
+
+	/// Create a new multisample renderbuffer, the `samples` of 0 creates a single sample renderbuffer. The sample count is validated against `GL_MAX_SAMPLES`.
+	pub fn new_multisample(glcore: Rc<GLCore>, format: TextureFormat, width: u32, height: u32, samples: u32) -> Result<Self, FramebufferError> {
+		if samples > 0 {
+			let mut max_samples: i32 = 0;
+			glcore.glGetIntegerv(GL_MAX_SAMPLES, &mut max_samples as *mut _)?;
+			let max_samples = max_samples.max(0) as u32;
+			if samples > max_samples {
+				return Err(FramebufferError::InvalidSampleCount(samples, max_samples));
+			}
+		}
+		let mut name: u32 = 0;
+		glcore.glGenRenderbuffers(1, &mut name as *mut _)?;
+		let ret = Self {
+			glcore,
+			name,
+			format,
+			width: Cell::new(width),
+			height: Cell::new(height),
+			samples,
+		};
+		ret.resize(width, height)?;
+		MemoryTracker::track_new(MemoryCategory::Renderbuffers, ret.gpu_bytes());
+		Ok(ret)
+	}
+
+	/// Re-specify the storage of the renderbuffer with the new size, keeping the name, the format and the sample count. The pixels are unspecified after resizing.
+	pub fn resize(&self, width: u32, height: u32) -> Result<(), FramebufferError> {
+		let glcore = self.glcore.as_ref();
+		glcore.glBindRenderbuffer(GL_RENDERBUFFER, self.name)?;
+		if self.samples == 0 {
+			glcore.glRenderbufferStorage(GL_RENDERBUFFER, self.format as u32, width as i32, height as i32)?;
+		} else {
+			glcore.glRenderbufferStorageMultisample(GL_RENDERBUFFER, self.samples as i32, self.format as u32, width as i32, height as i32)?;
+		}
+		glcore.glBindRenderbuffer(GL_RENDERBUFFER, 0)?;
+		let old_bytes = self.gpu_bytes();
+		self.width.set(width);
+		self.height.set(height);
+		MemoryTracker::track_resize(MemoryCategory::Renderbuffers, old_bytes, self.gpu_bytes());
+		Ok(())
+	}
+
+	/// Get the estimated size of the storage in bytes, every sample of the pixels is counted, the padding of the driver is ignored
+	pub fn gpu_bytes(&self) -> usize {
+		(self.width.get() as usize * self.height.get() as usize * self.format.bits_of_pixel()).div_ceil(8) * self.samples.max(1) as usize
+	}
+
+	/// Get the name of the renderbuffer
+	pub fn get_name(&self) -> u32 {
+		self.name
+	}
+
+	/// Get the internal format of the renderbuffer
+	pub fn get_format(&self) -> TextureFormat {
+		self.format
+	}
+
+	/// Get the width of the renderbuffer
+	pub fn get_width(&self) -> u32 {
+		self.width.get()
+	}
+
+	/// Get the height of the renderbuffer
+	pub fn get_height(&self) -> u32 {
+		self.height.get()
+	}
+
+	/// Get the number of samples, 0 means it's not a multisample renderbuffer
+	pub fn get_samples(&self) -> u32 {
+		self.samples
+	}
 }
 
 impl Framebuffer {
@@ -60,62 +481,645 @@ impl Framebuffer {
 			glcore,
 			name,
 			draw_targets: BTreeMap::new(),
+			depth_target: None,
+			renderbuffers: BTreeMap::new(),
+			attachment_cache: RefCell::new(AttachmentCache::default()),
+			srgb_write: None,
+			auto_viewport: true,
+			invalidate_supported: Cell::new(None),
 		})
 	}
 
-	/// Utilize the RAII rules to manage binding states.
+	/// Set whether `FramebufferBind::setup()` sets the viewport to the size of the attachments, the default is `true`.
+	/// Turn it off to render into the sub-regions of the attachments by `FramebufferBind::set_viewport()`.
+	pub fn set_auto_viewport(&mut self, enabled: bool) {
+		self.auto_viewport = enabled;
+	}
+
+	/// Does `FramebufferBind::setup()` set the viewport to the size of the attachments
+	pub fn get_auto_viewport(&self) -> bool {
+		self.auto_viewport
+	}
+
+	/// Enable or disable the sRGB encoding by `GL_FRAMEBUFFER_SRGB` while the framebuffer is bound for drawing.
+	/// By default it's enabled if any of the color attachments uses an sRGB format. If the shaders already apply the gamma manually, disable it to avoid the double correction.
+	pub fn set_srgb_write(&mut self, enabled: bool) {
+		self.srgb_write = Some(enabled);
+	}
+
+	/// Decide the sRGB encoding by the formats of the color attachments again, this is the default
+	pub fn set_srgb_write_auto(&mut self) {
+		self.srgb_write = None;
+	}
+
+	/// Is the sRGB encoding enabled while the framebuffer is bound for drawing
+	pub fn get_srgb_write(&self) -> bool {
+		self.srgb_write.unwrap_or_else(|| {
+			self.draw_targets.values().any(|(_, texture)| texture.get_format().is_srgb()) ||
+			self.renderbuffers.iter().any(|(attachment, renderbuffer)| matches!(attachment, AttachmentPoint::Color(_)) && renderbuffer.get_format().is_srgb())
+		})
+	}
+
+	/// Let the next `FramebufferBind::setup()` apply the attachments again, call it after modifying the textures of the draw targets
+	pub fn mark_dirty(&self) {
+		self.attachment_cache.borrow_mut().program = None;
+	}
+
+	/// Get how many times `FramebufferBind::setup()` applied the attachments, a cached setup doesn't count
+	pub fn get_attachment_apply_count(&self) -> u64 {
+		self.attachment_cache.borrow().apply_count
+	}
+
+	/// Is `glInvalidateFramebuffer()` available for `FramebufferBind::invalidate()`, the result is cached by the framebuffer
+	fn is_invalidate_supported(&self) -> Result<bool, GLCoreError> {
+		if let Some(supported) = self.invalidate_supported.get() {
+			return Ok(supported);
+		}
+		let supported = has_invalidate_subdata(&self.glcore)?;
+		self.invalidate_supported.set(Some(supported));
+		Ok(supported)
+	}
+
+	/// Get the color draw targets added by `add_color_target()`
+	pub fn get_draw_targets(&self) -> &BTreeMap<Attachment, (FramebufferTarget, Rc<dyn GenericTexture>)> {
+		&self.draw_targets
+	}
+
+	/// Get the depth texture set by `set_depth_target()`
+	pub fn get_depth_target(&self) -> Option<&(FramebufferTarget, Rc<dyn GenericTexture>)> {
+		self.depth_target.as_ref()
+	}
+
+	/// Get the renderbuffers attached by `attach_renderbuffer()`
+	pub fn get_renderbuffers(&self) -> &BTreeMap<AttachmentPoint, Rc<Renderbuffer>> {
+		&self.renderbuffers
+	}
+
+	/// Attach the mip level of the color texture to the attachment, the cube maps and the 3D textures are attached as layered.
+	/// The texture is validated here instead of failing the completeness check at drawing. Returns the previous draw target of the attachment.
+	pub fn add_color_target(&mut self, attachment: impl Into<Attachment>, texture: Rc<dyn GenericTexture>, level: u32) -> Result<Option<(FramebufferTarget, Rc<dyn GenericTexture>)>, FramebufferError> {
+		let target = FramebufferTarget::for_dimension(texture.get_dim()).with_level(level);
+		self.add_color_target_with(attachment, target, texture)
+	}
+
+	/// Attach the color texture by the explicit target, e.g. a face of the cube map, a layer of the 3D texture or a texture with a `resize_scale`
+	pub fn add_color_target_with(&mut self, attachment: impl Into<Attachment>, target: FramebufferTarget, texture: Rc<dyn GenericTexture>) -> Result<Option<(FramebufferTarget, Rc<dyn GenericTexture>)>, FramebufferError> {
+		let attachment = attachment.into();
+		if let Attachment::FragOutput(target_name) = &attachment {
+			if target_name.contains('\0') {
+				return Err(FramebufferError::InvalidTargetName(target_name.clone()));
+			}
+		}
+		let format = texture.get_format();
+		if format.is_depth() || format.is_compressed() {
+			return Err(FramebufferError::InvalidTargetTexture(format!("The format {format:?} couldn't be attached as a color target")));
+		}
+		Self::check_target(&target, texture.as_ref())?;
+		self.mark_dirty();
+		Ok(self.draw_targets.insert(attachment, (target, texture)))
+	}
+
+	/// Attach the depth or depth-stencil texture, e.g. for sampling the shadow maps. The cube maps are attached as layered.
+	/// It takes the place of the depth renderbuffer if both are attached. Returns the previous depth texture.
+	pub fn set_depth_target(&mut self, texture: Rc<dyn GenericTexture>) -> Result<Option<(FramebufferTarget, Rc<dyn GenericTexture>)>, FramebufferError> {
+		let format = texture.get_format();
+		if !format.is_depth() {
+			return Err(FramebufferError::InvalidTargetTexture(format!("The format {format:?} couldn't be attached as a depth target")));
+		}
+		let target = FramebufferTarget::for_dimension(texture.get_dim());
+		Self::check_target(&target, texture.as_ref())?;
+		self.mark_dirty();
+		Ok(self.depth_target.replace((target, texture)))
+	}
+
+	/// Remove the color draw target of the attachment, returns the removed one
+	pub fn remove_target(&mut self, attachment: impl Into<Attachment>) -> Option<(FramebufferTarget, Rc<dyn GenericTexture>)> {
+		let ret = self.draw_targets.remove(&attachment.into());
+		if ret.is_some() {
+			self.mark_dirty();
+		}
+		ret
+	}
+
+	/// Remove the depth texture, returns the removed one
+	pub fn remove_depth_target(&mut self) -> Option<(FramebufferTarget, Rc<dyn GenericTexture>)> {
+		let ret = self.depth_target.take();
+		if ret.is_some() {
+			self.mark_dirty();
+		}
+		ret
+	}
+
+	/// Remove all of the color draw targets and the depth texture, the renderbuffers are kept
+	pub fn clear_targets(&mut self) {
+		self.draw_targets.clear();
+		self.depth_target = None;
+		self.mark_dirty();
+	}
+
+	/// Check that the binding target, the attaching mode, the layer and the mip level fit the texture
+	fn check_target(target: &FramebufferTarget, texture: &dyn GenericTexture) -> Result<(), FramebufferError> {
+		let dim = texture.get_dim();
+		let target_fits = match dim {
+			TextureDimension::Tex1d => target.texture_target == TextureTarget::Tex1d,
+			TextureDimension::Tex2d => target.texture_target == TextureTarget::Tex2d,
+			TextureDimension::Tex3d => target.texture_target == TextureTarget::Tex3d,
+			TextureDimension::TexCube => match target.mode {
+				AttachmentMode::Single => matches!(target.texture_target,
+					TextureTarget::TexCubePosX | TextureTarget::TexCubeNegX |
+					TextureTarget::TexCubePosY | TextureTarget::TexCubeNegY |
+					TextureTarget::TexCubePosZ | TextureTarget::TexCubeNegZ),
+				_ => target.texture_target == TextureTarget::TexCube,
+			},
+			TextureDimension::Tex2dMultisample => target.texture_target == TextureTarget::Tex2dMultisample,
+		};
+		if !target_fits {
+			return Err(FramebufferError::InvalidTargetTexture(format!("The binding target doesn't fit the {dim:?} texture, the single cube map target must be one of the faces")));
+		}
+		let layer = match target.mode {
+			AttachmentMode::Single => None,
+			AttachmentMode::Layered if matches!(dim, TextureDimension::Tex3d | TextureDimension::TexCube) => None,
+			AttachmentMode::Layer(layer) if dim == TextureDimension::Tex3d => Some(layer),
+			mode => return Err(FramebufferError::InvalidTargetTexture(format!("The {dim:?} texture couldn't be attached as {mode:?}"))),
+		};
+		if let Some(layer) = layer {
+			if layer < 0 || layer as u32 >= texture.get_depth() {
+				return Err(FramebufferError::InvalidTargetTexture(format!("The layer {layer} is out of the {} layer(s) of the texture", texture.get_depth())));
+			}
+		}
+		let level_count = texture.level_count();
+		if target.level >= level_count {
+			return Err(FramebufferError::InvalidTargetTexture(format!("The mip level {} is out of the {level_count} level(s) of the texture", target.level)));
+		}
+		Ok(())
+	}
+
+	/// Iterate the color draw targets and the depth texture
+	fn texture_targets(&self) -> impl Iterator<Item = &(FramebufferTarget, Rc<dyn GenericTexture>)> {
+		self.draw_targets.values().chain(self.depth_target.iter())
+	}
+
+	/// Create a framebuffer with a multisample color renderbuffer and an optional multisample depth renderbuffer, render into it and then `resolve_to()` a single sample framebuffer.
+	/// The sample count is validated against `GL_MAX_SAMPLES`.
+	pub fn new_msaa(glcore: Rc<GLCore>, width: u32, height: u32, samples: u32, color_format: TextureFormat, depth_format: Option<DepthFormat>) -> Result<Self, FramebufferError> {
+		let mut ret = Self::new(glcore.clone())?;
+		ret.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new_multisample(glcore.clone(), color_format, width, height, samples)?));
+		if let Some(depth_format) = depth_format {
+			let depth_format = TextureFormat::from(depth_format);
+			let attachment = if depth_format.is_depth_stencil() {AttachmentPoint::DepthStencil} else {AttachmentPoint::Depth};
+			ret.attach_renderbuffer(attachment, Rc::new(Renderbuffer::new_multisample(glcore, depth_format, width, height, samples)?));
+		}
+		let bind = ret.bind()?;
+		let mut color_indices = Vec::with_capacity(1);
+		bind.attach_renderbuffers(&mut color_indices)?;
+		bind.apply_draw_buffers(&color_indices)?;
+		bind.validate()?;
+		bind.unbind();
+		ret.attachment_cache.borrow_mut().attached = ret.renderbuffers.keys().map(|attachment| attachment.to_gl()).collect();
+		Ok(ret)
+	}
+
+	/// Bind the framebuffer and check its completeness, unbinds afterwards. The `GLCoreError` is returned as the error, the incompleteness is returned as `false`.
+	pub fn is_complete(&self) -> Result<bool, FramebufferError> {
+		let bind = self.bind()?;
+		let result = bind.validate();
+		bind.unbind();
+		match result {
+			Ok(()) => Ok(true),
+			Err(FramebufferError::GLCoreError(e)) => Err(FramebufferError::GLCoreError(e)),
+			Err(_) => Ok(false),
+		}
+	}
+
+	/// Resize the textures of the draw targets by their `resize_scale` and all of the renderbuffers to the new size, e.g. when the window is resized.
+	/// The names of the textures and the renderbuffers are kept, the attachments are applied and validated again by the next `FramebufferBind::setup()`.
+	pub fn resize_attachments(&mut self, width: u32, height: u32) -> Result<(), FramebufferError> {
+		for (target, texture) in self.texture_targets() {
+			if let Some(scale) = target.resize_scale {
+				let scaled_width = ((width as f32 * scale).round() as u32).max(1);
+				let scaled_height = ((height as f32 * scale).round() as u32).max(1);
+				if texture.get_width() != scaled_width || texture.get_height() != scaled_height {
+					texture.resize_storage(scaled_width, scaled_height, texture.get_depth()).map_err(|err| FramebufferError::TextureError(format!("{err:?}")))?;
+				}
+			}
+		}
+		for renderbuffer in self.renderbuffers.values() {
+			if renderbuffer.get_width() != width || renderbuffer.get_height() != height {
+				renderbuffer.resize(width, height)?;
+			}
+		}
+		self.mark_dirty();
+		Ok(())
+	}
+
+	/// Get the size of the attachments, the largest width and the largest height of them, the textures count the size of their attached mip levels
+	pub fn get_size(&self) -> [u32; 2] {
+		let mut size = [0, 0];
+		for (target, texture) in self.texture_targets() {
+			size = [max(size[0], (texture.get_width() >> target.level).max(1)), max(size[1], (texture.get_height() >> target.level).max(1))];
+		}
+		for renderbuffer in self.renderbuffers.values() {
+			size = [max(size[0], renderbuffer.get_width()), max(size[1], renderbuffer.get_height())];
+		}
+		size
+	}
+
+	/// Resolve the multisample attachments into `dst` over the full size by `glBlitFramebuffer()`, the attachments of both framebuffers must have been set up.
+	/// The read and draw framebuffer bindings are restored afterwards. If the multisample contents are no longer needed, `FramebufferBind::invalidate()` them to save the bandwidth.
+	pub fn resolve_to(&self, dst: &Framebuffer, mask: BlitMask, filter: BlitFilter, on_mismatch: SizeMismatchPolicy) -> Result<(), FramebufferError> {
+		let src_size = self.get_size();
+		let dst_size = dst.get_size();
+		if src_size != dst_size && on_mismatch == SizeMismatchPolicy::Error {
+			return Err(FramebufferError::SizeMismatch(src_size, dst_size));
+		}
+		let width = src_size[0].min(dst_size[0]) as i32;
+		let height = src_size[1].min(dst_size[1]) as i32;
+		self.blit(dst.name, [0, 0, width, height], [0, 0, width, height], mask, filter, None)
+	}
+
+	/// Copy the rectangle `src_rect` (`[x0, y0, x1, y1]`) of this framebuffer into the rectangle `dst_rect` of `dst` or the default framebuffer, scaling if the sizes differ.
+	/// The color attachment `read_buffer` is the source of the color blit if there are multiple color attachments, the attachments of both framebuffers must have been set up.
+	/// The depth and stencil blits must use `BlitFilter::Nearest`. The read and draw framebuffer bindings are restored afterwards.
+	pub fn blit_to(&self, dst: Option<&Framebuffer>, src_rect: [i32; 4], dst_rect: [i32; 4], mask: BlitMask, filter: BlitFilter, read_buffer: Option<u32>) -> Result<(), FramebufferError> {
+		self.blit(dst.map_or(0, |dst| dst.name), src_rect, dst_rect, mask, filter, read_buffer)
+	}
+
+	/// Blit the rectangle `[x0, y0, x1, y1]` of this framebuffer to the framebuffer name `dst_name`, restores the read and draw framebuffer bindings and the read buffer afterwards
+	fn blit(&self, dst_name: u32, src_rect: [i32; 4], dst_rect: [i32; 4], mask: BlitMask, filter: BlitFilter, read_buffer: Option<u32>) -> Result<(), FramebufferError> {
+		if (mask.depth || mask.stencil) && filter != BlitFilter::Nearest {
+			return Err(FramebufferError::InvalidBlitFilter(mask, filter));
+		}
+		let glcore = self.glcore.as_ref();
+		let mut prev_read: i32 = 0;
+		let mut prev_draw: i32 = 0;
+		glcore.glGetIntegerv(GL_READ_FRAMEBUFFER_BINDING, &mut prev_read as *mut _)?;
+		glcore.glGetIntegerv(GL_DRAW_FRAMEBUFFER_BINDING, &mut prev_draw as *mut _)?;
+		glcore.glBindFramebuffer(GL_READ_FRAMEBUFFER, self.name)?;
+		glcore.glBindFramebuffer(GL_DRAW_FRAMEBUFFER, dst_name)?;
+		let mut prev_read_buffer: i32 = GL_COLOR_ATTACHMENT0 as i32;
+		if let Some(index) = read_buffer {
+			glcore.glGetIntegerv(GL_READ_BUFFER, &mut prev_read_buffer as *mut _)?;
+			glcore.glReadBuffer(GL_COLOR_ATTACHMENT0 + index)?;
+		}
+		let result = glcore.glBlitFramebuffer(src_rect[0], src_rect[1], src_rect[2], src_rect[3], dst_rect[0], dst_rect[1], dst_rect[2], dst_rect[3], mask.to_gl(), filter as u32);
+		if read_buffer.is_some() {
+			glcore.glReadBuffer(prev_read_buffer as u32)?;
+		}
+		glcore.glBindFramebuffer(GL_READ_FRAMEBUFFER, prev_read as u32)?;
+		glcore.glBindFramebuffer(GL_DRAW_FRAMEBUFFER, prev_draw as u32)?;
+		Ok(result?)
+	}
+
+	/// Read the pixels of the rectangle from the color attachment of the index, the rows of the returned pixels are tightly packed and bottom-up as the GL's origin is at the bottom-left.
+	/// The read framebuffer binding, the read buffer and the pack alignment are restored afterwards.
+	pub fn read_pixels(&self, attachment_index: u32, x: i32, y: i32, width: u32, height: u32, format: ChannelType, type_: ComponentType) -> Result<Vec<u8>, FramebufferError> {
+		let mut pixels = vec![0u8; width as usize * height as usize * size_of_pixel(format, type_)];
+		unsafe {read_pixels_from(&self.glcore, self.name, Some(GL_COLOR_ATTACHMENT0 + attachment_index), x, y, width, height, format, type_, pixels.as_mut_ptr() as *mut c_void)?};
+		Ok(pixels)
+	}
+
+	/// Read the pixels of the rectangle from the color attachment of the index as an RGBA8 image, the image is flipped vertically to have the top row first e.g. for the screenshots
+	pub fn read_rgba_image(&self, attachment_index: u32, x: i32, y: i32, width: u32, height: u32) -> Result<RgbaImage, FramebufferError> {
+		let pixels = self.read_pixels(attachment_index, x, y, width, height, ChannelType::Rgba, ComponentType::U8)?;
+		Ok(flip_into_rgba_image(width, height, pixels))
+	}
+
+	/// Read a single RGBA8 pixel from the color attachment of the index, e.g. for the color picking
+	pub fn pick_pixel(&self, attachment_index: u32, x: i32, y: i32) -> Result<[u8; 4], FramebufferError> {
+		let mut pixel = [0u8; 4];
+		unsafe {read_pixels_from(&self.glcore, self.name, Some(GL_COLOR_ATTACHMENT0 + attachment_index), x, y, 1, 1, ChannelType::Rgba, ComponentType::U8, pixel.as_mut_ptr() as *mut c_void)?};
+		Ok(pixel)
+	}
+
+	/// Read the red channel of a single pixel from the color attachment of the index as `f32`, e.g. for picking the object IDs or the values of an `R32f` attachment
+	pub fn pick_pixel_f32(&self, attachment_index: u32, x: i32, y: i32) -> Result<f32, FramebufferError> {
+		let mut value = 0.0f32;
+		unsafe {read_pixels_from(&self.glcore, self.name, Some(GL_COLOR_ATTACHMENT0 + attachment_index), x, y, 1, 1, ChannelType::Red, ComponentType::F32, &mut value as *mut f32 as *mut c_void)?};
+		Ok(value)
+	}
+
+	/// Attach a renderbuffer to the attachment point, returns the renderbuffer that was attached there
+	pub fn attach_renderbuffer(&mut self, attachment: AttachmentPoint, renderbuffer: Rc<Renderbuffer>) -> Option<Rc<Renderbuffer>> {
+		self.mark_dirty();
+		self.renderbuffers.insert(attachment, renderbuffer)
+	}
+
+	/// Detach the renderbuffer from the attachment point
+	pub fn detach_renderbuffer(&mut self, attachment: AttachmentPoint) -> Option<Rc<Renderbuffer>> {
+		self.mark_dirty();
+		self.renderbuffers.remove(&attachment)
+	}
+
+	/// Bind for drawing, utilize the RAII rules to manage binding states.
 	pub fn bind<'a>(&'a self) -> Result<FramebufferBind<'a>, FramebufferError> {
-		FramebufferBind::new(self)
+		FramebufferBind::new(self, FramebufferBindTarget::Draw)
 	}
 
-	/// Bind to the default framebuffer
-	pub fn default_bind(glcore: &GLCore) -> Result<(), FramebufferError> {
-		glcore.glBindFramebuffer(GL_DRAW_FRAMEBUFFER, 0)?;
+	/// Bind to the target, utilize the RAII rules to manage binding states. The target is unbound when the binding state is dropped.
+	pub fn bind_as<'a>(&'a self, target: FramebufferBindTarget) -> Result<FramebufferBind<'a>, FramebufferError> {
+		FramebufferBind::new(self, target)
+	}
+
+	/// Bind the default framebuffer to the target
+	pub fn default_bind(glcore: &GLCore, target: FramebufferBindTarget) -> Result<(), FramebufferError> {
+		glcore.glBindFramebuffer(target as u32, 0)?;
+		Ok(())
+	}
+
+	/// Enable or disable the sRGB encoding by `GL_FRAMEBUFFER_SRGB` for drawing to the default framebuffer, the window surfaces are usually sRGB.
+	/// If the shaders already apply the gamma manually, keep it disabled to avoid the double correction.
+	pub fn set_default_srgb_write(glcore: &GLCore, enabled: bool) -> Result<(), FramebufferError> {
+		if enabled {
+			glcore.glEnable(GL_FRAMEBUFFER_SRGB)?;
+		} else {
+			glcore.glDisable(GL_FRAMEBUFFER_SRGB)?;
+		}
 		Ok(())
 	}
 }
 
+/// Read the pixels of the rectangle from the current read buffer of the default framebuffer (the window), the rows are tightly packed and bottom-up
+pub fn read_default_framebuffer(glcore: &GLCore, x: i32, y: i32, width: u32, height: u32, format: ChannelType, type_: ComponentType) -> Result<Vec<u8>, FramebufferError> {
+	let mut pixels = vec![0u8; width as usize * height as usize * size_of_pixel(format, type_)];
+	unsafe {read_pixels_from(glcore, 0, None, x, y, width, height, format, type_, pixels.as_mut_ptr() as *mut c_void)?};
+	Ok(pixels)
+}
+
+/// Take a screenshot of the rectangle of the default framebuffer (the window) as an RGBA8 image, the image is flipped vertically to have the top row first
+pub fn read_default_framebuffer_rgba_image(glcore: &GLCore, x: i32, y: i32, width: u32, height: u32) -> Result<RgbaImage, FramebufferError> {
+	let pixels = read_default_framebuffer(glcore, x, y, width, height, ChannelType::Rgba, ComponentType::U8)?;
+	Ok(flip_into_rgba_image(width, height, pixels))
+}
+
+/// Flip the bottom-up RGBA8 rows into a top-down image
+fn flip_into_rgba_image(width: u32, height: u32, pixels: Vec<u8>) -> RgbaImage {
+	let pitch = width as usize * 4;
+	let mut flipped = Vec::with_capacity(pixels.len());
+	for row in pixels.chunks_exact(pitch).rev() {
+		flipped.extend_from_slice(row);
+	}
+	RgbaImage::from_raw(width, height, flipped).unwrap()
+}
+
+/// Read the pixels by `glReadPixels()` from the framebuffer `name` with the tightly packed rows, `read_buffer` selects the color attachment to read.
+/// The read framebuffer binding, the read buffer and the pack alignment are restored afterwards.
+/// # Safety
+/// `data` must be large enough for `width * height` pixels of the format and the type
+unsafe fn read_pixels_from(glcore: &GLCore, name: u32, read_buffer: Option<u32>, x: i32, y: i32, width: u32, height: u32, format: ChannelType, type_: ComponentType, data: *mut c_void) -> Result<(), FramebufferError> {
+	let mut prev_read: i32 = 0;
+	glcore.glGetIntegerv(GL_READ_FRAMEBUFFER_BINDING, &mut prev_read as *mut _)?;
+	glcore.glBindFramebuffer(GL_READ_FRAMEBUFFER, name)?;
+	let mut prev_read_buffer: i32 = 0;
+	if let Some(read_buffer) = read_buffer {
+		glcore.glGetIntegerv(GL_READ_BUFFER, &mut prev_read_buffer as *mut _)?;
+		glcore.glReadBuffer(read_buffer)?;
+	}
+	let pixel_store = PixelStore::pack(glcore, 1, 0)?;
+	let result = glcore.glReadPixels(x, y, width as i32, height as i32, format as u32, type_ as u32, data);
+	pixel_store.restore();
+	if read_buffer.is_some() {
+		glcore.glReadBuffer(prev_read_buffer as u32)?;
+	}
+	glcore.glBindFramebuffer(GL_READ_FRAMEBUFFER, prev_read as u32)?;
+	Ok(result?)
+}
+
+/// Is `glInvalidateFramebuffer()` available, it's the core of OpenGL 4.3 or by `GL_ARB_invalidate_subdata`
+fn has_invalidate_subdata(glcore: &GLCore) -> Result<bool, GLCoreError> {
+	let mut major: i32 = 0;
+	let mut minor: i32 = 0;
+	glcore.glGetIntegerv(GL_MAJOR_VERSION, &mut major as *mut _)?;
+	glcore.glGetIntegerv(GL_MINOR_VERSION, &mut minor as *mut _)?;
+	if (major, minor) >= (4, 3) {
+		return Ok(true);
+	}
+	has_gl_extension(glcore, "GL_ARB_invalidate_subdata")
+}
+
 impl<'a> FramebufferBind<'a> {
 	/// Create a new binding state to the framebuffer object, utilizing the RAII rules to manage the binding state.
-	fn new(framebuffer: &'a Framebuffer) -> Result<Self, FramebufferError> {
-		framebuffer.glcore.glBindFramebuffer(GL_DRAW_FRAMEBUFFER, framebuffer.name)?;
+	fn new(framebuffer: &'a Framebuffer, target: FramebufferBindTarget) -> Result<Self, FramebufferError> {
+		let glcore = framebuffer.glcore.as_ref();
+		glcore.glBindFramebuffer(target as u32, framebuffer.name)?;
+		let prev_srgb_write = if target != FramebufferBindTarget::Read {
+			let prev = glcore.glIsEnabled(GL_FRAMEBUFFER_SRGB)? != 0;
+			Framebuffer::set_default_srgb_write(glcore, framebuffer.get_srgb_write())?;
+			Some(prev)
+		} else {
+			None
+		};
 		Ok(Self {
 			framebuffer,
+			target,
+			prev_srgb_write,
 		})
 	}
 
-	/// Set up the framebuffer, apply `draw_targets`
+	/// Get the binding target
+	pub fn get_target(&self) -> FramebufferBindTarget {
+		self.target
+	}
+
+	/// Set up the framebuffer, apply the draw targets and the renderbuffers, `validate()` the completeness and set the viewport to the size of the attachments if the auto viewport is on
+	/// The attachments are cached, they are applied again only if the framebuffer is dirty or the shader program differs from the last one.
+	/// The framebuffer must be bound for drawing.
 	pub fn setup(&self, program: &Shader) -> Result<(), FramebufferError> {
+		assert_ne!(self.target, FramebufferBindTarget::Read, "The framebuffer bound for reading couldn't be set up for drawing.");
 		let draw_targets = &self.framebuffer.draw_targets;
-		assert!(!draw_targets.is_empty());
+		let renderbuffers = &self.framebuffer.renderbuffers;
 		let glcore = self.framebuffer.glcore.clone();
-		let mut draw_buffers: Vec<u32> = Vec::with_capacity(draw_targets.len());
+		if self.framebuffer.attachment_cache.borrow().program == Some(program.get_name()) {
+			let [width, height] = self.framebuffer.get_size();
+			return self.apply_auto_viewport(width, height);
+		}
+		let debug_scope = DebugScope::push_internal(&glcore, || format!("Framebuffer {} setup", self.framebuffer.get_name()))?;
+		let mut color_indices: Vec<u32> = Vec::with_capacity(draw_targets.len());
 		let mut max_width: u32 = 0;
 		let mut max_height: u32 = 0;
-		for (target_name, target) in draw_targets.iter() {
-			let location = glcore.glGetFragDataLocation(program.get_name(), target_name.as_ptr() as *const i8)?;
+		for (target_attachment, (target, texture)) in draw_targets.iter() {
+			let location = match target_attachment {
+				Attachment::Index(index) => *index as i32,
+				Attachment::FragOutput(target_name) => {
+					let c_name = CString::new(target_name.as_str()).map_err(|_| FramebufferError::InvalidTargetName(target_name.clone()))?;
+					glcore.glGetFragDataLocation(program.get_name(), c_name.as_ptr())?
+				}
+			};
 			if location >= 0 {
 				let location = location as u32;
-				let (target, texture) = target;
-				let attachment = GL_COLOR_ATTACHMENT0 + location;
-				max_width = max(max_width, texture.get_width());
-				max_height = max(max_height, texture.get_height());
-				match texture.get_dim() {
-					TextureDimension::Tex1d =>		glcore.glFramebufferTexture1D(GL_DRAW_FRAMEBUFFER, attachment, target.texture_target as u32, texture.get_name(), 0)?,
-					TextureDimension::Tex2d =>		glcore.glFramebufferTexture2D(GL_DRAW_FRAMEBUFFER, attachment, target.texture_target as u32, texture.get_name(), 0)?,
-					TextureDimension::Tex3d =>		glcore.glFramebufferTexture3D(GL_DRAW_FRAMEBUFFER, attachment, target.texture_target as u32, texture.get_name(), 0, target.layer_of_3d)?,
-					TextureDimension::TexCube =>	glcore.glFramebufferTexture2D(GL_DRAW_FRAMEBUFFER, attachment, target.texture_target as u32, texture.get_name(), 0)?,
-				}
-				draw_buffers.push(attachment);
+				let [width, height] = self.attach_texture(GL_COLOR_ATTACHMENT0 + location, target, texture.as_ref())?;
+				max_width = max(max_width, width);
+				max_height = max(max_height, height);
+				color_indices.push(location);
 			} else {
-				eprintln!("Location of shader output `{target_name}` couldn't be found.");
+				eprintln!("Location of shader output `{target_attachment:?}` couldn't be found.");
 			}
 		}
-		glcore.glDrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr())?;
-		match glcore.glCheckFramebufferStatus(GL_DRAW_FRAMEBUFFER) ?{
+		let [width, height] = self.attach_renderbuffers(&mut color_indices)?;
+		max_width = max(max_width, width);
+		max_height = max(max_height, height);
+		let mut attached: Vec<u32> = color_indices.iter().map(|index| GL_COLOR_ATTACHMENT0 + index).collect();
+		attached.extend(renderbuffers.keys().filter(|attachment| !matches!(attachment, AttachmentPoint::Color(_))).map(|attachment| attachment.to_gl()));
+		if let Some((target, texture)) = &self.framebuffer.depth_target {
+			let attachment = if texture.get_format().is_depth_stencil() {GL_DEPTH_STENCIL_ATTACHMENT} else {GL_DEPTH_ATTACHMENT};
+			let [width, height] = self.attach_texture(attachment, target, texture.as_ref())?;
+			max_width = max(max_width, width);
+			max_height = max(max_height, height);
+			attached.push(attachment);
+		}
+		self.apply_draw_buffers(&color_indices)?;
+		let mut cache = self.framebuffer.attachment_cache.borrow_mut();
+		for stale in cache.attached.iter().filter(|attachment| !attached.contains(attachment)) {
+			glcore.glFramebufferRenderbuffer(GL_DRAW_FRAMEBUFFER, *stale, GL_RENDERBUFFER, 0)?;
+		}
+		cache.attached = attached;
+		self.validate().map_err(|err| match err {
+			FramebufferError::IncompleteAttachment(None) => FramebufferError::IncompleteAttachment(self.missing_level_hint()),
+			err => err,
+		})?;
+		cache.program = Some(program.get_name());
+		cache.apply_count += 1;
+		if let Some(s) = debug_scope { s.pop() }
+		self.apply_auto_viewport(max_width, max_height)
+	}
+
+	/// Set the viewport to the rectangle of the attachments, use it with `Framebuffer::set_auto_viewport(false)` to keep it over `setup()`
+	pub fn set_viewport(&self, x: i32, y: i32, width: i32, height: i32) -> Result<(), FramebufferError> {
+		self.framebuffer.glcore.glViewport(x, y, width, height)?;
+		Ok(())
+	}
+
+	/// Clear the color attachment `index` to `color` by `glClearBufferfv()`, call it after `setup()` that routes the draw buffers to the attachments.
+	/// The clears are masked by the write masks and the scissor test of the current states, like the draw calls.
+	pub fn clear_color(&self, index: u32, color: [f32; 4]) -> Result<(), FramebufferError> {
+		self.framebuffer.glcore.glClearBufferfv(GL_COLOR, index as i32, color.as_ptr())?;
+		Ok(())
+	}
+
+	/// Clear the depth attachment to `depth` by `glClearBufferfv()`
+	pub fn clear_depth(&self, depth: f32) -> Result<(), FramebufferError> {
+		self.framebuffer.glcore.glClearBufferfv(GL_DEPTH, 0, &depth as *const f32)?;
+		Ok(())
+	}
+
+	/// Clear the stencil attachment to `stencil` by `glClearBufferiv()`, e.g. before writing the mask by `StencilFace::write()`
+	pub fn clear_stencil(&self, stencil: i32) -> Result<(), FramebufferError> {
+		self.framebuffer.glcore.glClearBufferiv(GL_STENCIL, 0, &stencil as *const i32)?;
+		Ok(())
+	}
+
+	/// Clear the depth-stencil attachment to `depth` and `stencil` at once by `glClearBufferfi()`
+	pub fn clear_depth_stencil(&self, depth: f32, stencil: i32) -> Result<(), FramebufferError> {
+		self.framebuffer.glcore.glClearBufferfi(GL_DEPTH_STENCIL, 0, depth, stencil)?;
+		Ok(())
+	}
+
+	/// Set the viewport to the size of the attachments if the auto viewport is on, skip the call if the viewport is already the same
+	fn apply_auto_viewport(&self, width: u32, height: u32) -> Result<(), FramebufferError> {
+		if !self.framebuffer.auto_viewport {
+			return Ok(());
+		}
+		let glcore = self.framebuffer.glcore.as_ref();
+		let viewport = [0, 0, width as i32, height as i32];
+		let mut current = [0i32; 4];
+		glcore.glGetIntegerv(GL_VIEWPORT, current.as_mut_ptr())?;
+		if current != viewport {
+			glcore.glViewport(viewport[0], viewport[1], viewport[2], viewport[3])?;
+		}
+		Ok(())
+	}
+
+	/// Attach the texture to the attachment point by the target, returns the size of the attached mip level
+	fn attach_texture(&self, attachment: u32, target: &FramebufferTarget, texture: &dyn GenericTexture) -> Result<[u32; 2], FramebufferError> {
+		let glcore = self.framebuffer.glcore.as_ref();
+		let level = target.level as i32;
+		match (target.mode, texture.get_dim()) {
+			(AttachmentMode::Layered, _) =>	glcore.glFramebufferTexture(GL_DRAW_FRAMEBUFFER, attachment, texture.get_name(), level)?,
+			(AttachmentMode::Layer(layer), _) => glcore.glFramebufferTextureLayer(GL_DRAW_FRAMEBUFFER, attachment, texture.get_name(), level, layer)?,
+			(AttachmentMode::Single, TextureDimension::Tex1d) =>		glcore.glFramebufferTexture1D(GL_DRAW_FRAMEBUFFER, attachment, target.texture_target as u32, texture.get_name(), level)?,
+			(AttachmentMode::Single, TextureDimension::Tex2d) =>		glcore.glFramebufferTexture2D(GL_DRAW_FRAMEBUFFER, attachment, target.texture_target as u32, texture.get_name(), level)?,
+			(AttachmentMode::Single, TextureDimension::Tex3d) =>		glcore.glFramebufferTexture3D(GL_DRAW_FRAMEBUFFER, attachment, target.texture_target as u32, texture.get_name(), level, 0)?,
+			(AttachmentMode::Single, TextureDimension::TexCube) =>	glcore.glFramebufferTexture2D(GL_DRAW_FRAMEBUFFER, attachment, target.texture_target as u32, texture.get_name(), level)?,
+			(AttachmentMode::Single, TextureDimension::Tex2dMultisample) => glcore.glFramebufferTexture2D(GL_DRAW_FRAMEBUFFER, attachment, TextureTarget::Tex2dMultisample as u32, texture.get_name(), level)?,
+		}
+		Ok([(texture.get_width() >> target.level).max(1), (texture.get_height() >> target.level).max(1)])
+	}
+
+	/// Find the draw target that attaches a mip level the texture doesn't have
+	fn missing_level_hint(&self) -> Option<String> {
+		for (target_attachment, (target, texture)) in self.framebuffer.draw_targets.iter() {
+			let level_count = texture.level_count();
+			if target.level >= level_count {
+				return Some(format!("The draw target `{target_attachment:?}` attaches the mip level {}, but the texture has only {level_count} level(s)", target.level));
+			}
+		}
+		None
+	}
+
+	/// Attach the renderbuffers, push the indices of the color attachments to `color_indices`, returns the largest size of them
+	fn attach_renderbuffers(&self, color_indices: &mut Vec<u32>) -> Result<[u32; 2], FramebufferError> {
+		let glcore = self.framebuffer.glcore.as_ref();
+		let mut size = [0, 0];
+		for (attachment, renderbuffer) in self.framebuffer.renderbuffers.iter() {
+			glcore.glFramebufferRenderbuffer(GL_DRAW_FRAMEBUFFER, attachment.to_gl(), GL_RENDERBUFFER, renderbuffer.get_name())?;
+			size = [max(size[0], renderbuffer.get_width()), max(size[1], renderbuffer.get_height())];
+			if let AttachmentPoint::Color(index) = attachment {
+				color_indices.push(*index);
+			}
+		}
+		Ok(size)
+	}
+
+	/// Route the fragment output of the location `n` to the color attachment `n` by `glDrawBuffers()`, the draw buffer and the read buffer are `GL_NONE` if there are no color attachments
+	fn apply_draw_buffers(&self, color_indices: &[u32]) -> Result<(), FramebufferError> {
+		let glcore = self.framebuffer.glcore.as_ref();
+		if let Some(max_index) = color_indices.iter().max() {
+			let mut draw_buffers = vec![GL_NONE; *max_index as usize + 1];
+			for index in color_indices.iter() {
+				draw_buffers[*index as usize] = GL_COLOR_ATTACHMENT0 + index;
+			}
+			glcore.glDrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr())?;
+			glcore.glReadBuffer(GL_COLOR_ATTACHMENT0 + color_indices.iter().min().unwrap())?;
+		} else {
+			glcore.glDrawBuffer(GL_NONE)?;
+			glcore.glReadBuffer(GL_NONE)?;
+		}
+		Ok(())
+	}
+
+	/// Tell the driver that the contents of the attachments are no longer needed by `glInvalidateFramebuffer()`, it saves the bandwidth on the tile-based GPUs.
+	/// Use it on the multisample attachments after `Framebuffer::resolve_to()` or on the depth attachment after the last pass that tests it. It's a no-op below OpenGL 4.3 without `GL_ARB_invalidate_subdata`.
+	pub fn invalidate(&self, attachments: &[AttachmentPoint]) -> Result<(), FramebufferError> {
+		let glcore = self.framebuffer.glcore.as_ref();
+		if attachments.is_empty() || !self.framebuffer.is_invalidate_supported()? {
+			return Ok(());
+		}
+		let attachments: Vec<u32> = attachments.iter().map(|attachment| attachment.to_gl()).collect();
+		glcore.glInvalidateFramebuffer(self.gl_target(), attachments.len() as i32, attachments.as_ptr())?;
+		Ok(())
+	}
+
+	/// Invalidate the rectangle of the attachments by `glInvalidateSubFramebuffer()`, see `invalidate()`. It's a no-op below OpenGL 4.3 without `GL_ARB_invalidate_subdata`.
+	pub fn invalidate_sub_region(&self, attachments: &[AttachmentPoint], x: i32, y: i32, width: i32, height: i32) -> Result<(), FramebufferError> {
+		let glcore = self.framebuffer.glcore.as_ref();
+		if attachments.is_empty() || !self.framebuffer.is_invalidate_supported()? {
+			return Ok(());
+		}
+		let attachments: Vec<u32> = attachments.iter().map(|attachment| attachment.to_gl()).collect();
+		glcore.glInvalidateSubFramebuffer(self.gl_target(), attachments.len() as i32, attachments.as_ptr(), x, y, width, height)?;
+		Ok(())
+	}
+
+	/// Get the framebuffer target that the bound framebuffer is queried from
+	fn gl_target(&self) -> u32 {
+		if self.target == FramebufferBindTarget::Read {GL_READ_FRAMEBUFFER} else {GL_DRAW_FRAMEBUFFER}
+	}
+
+	/// Check the completeness of the framebuffer by `glCheckFramebufferStatus()`, the incompleteness is reported as the error
+	pub fn validate(&self) -> Result<(), FramebufferError> {
+		match self.framebuffer.glcore.glCheckFramebufferStatus(self.gl_target()) ?{
 			GL_FRAMEBUFFER_COMPLETE => {},
 			GL_FRAMEBUFFER_UNDEFINED => return Err(FramebufferError::NoDefaultFramebuffer),
-			GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT => return Err(FramebufferError::IncompleteAttachment),
+			GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT => return Err(FramebufferError::IncompleteAttachment(None)),
 			GL_FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => return Err(FramebufferError::IncompleteMissingAttachment),
 			GL_FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER => return Err(FramebufferError::IncompleteDrawBuffer),
 			GL_FRAMEBUFFER_INCOMPLETE_READ_BUFFER => return Err(FramebufferError::IncompleteReadBuffer),
@@ -124,7 +1128,6 @@ impl<'a> FramebufferBind<'a> {
 			GL_FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => return Err(FramebufferError::IncompleteLayerTarget),
 			other => return Err(FramebufferError::UnknownError(other)),
 		}
-		glcore.glViewport(0, 0, max_width as i32, max_height as i32)?;
 		Ok(())
 	}
 
@@ -132,9 +1135,73 @@ impl<'a> FramebufferBind<'a> {
 	pub fn unbind(self) {}
 }
 
+impl MipChainTarget {
+	/// Create the texture of `levels` mip levels, e.g. `TextureFormat::R11fg11fb10f` or `TextureFormat::Rgba32f` for HDR, and a framebuffer for each level.
+	/// The sampling of the texture is limited to the `levels` levels, sample the source level explicitly by `textureLod()` or `texelFetch()` in the passes.
+	pub fn new(glcore: Rc<GLCore>, format: TextureFormat, width: u32, height: u32, levels: u32) -> Result<Self, FramebufferError> {
+		let full_levels = 32 - width.max(height).max(1).leading_zeros();
+		if levels == 0 || levels > full_levels {
+			return Err(FramebufferError::TextureError(format!("{:?}", TextureError::InvalidMipLevel(levels, full_levels))));
+		}
+		let mut texture = Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), format, width, height)
+			.wrap(TextureWrapping::ClampToEdge)
+			.filters(SamplerMagFilter::Linear, SamplerFilter::LinearMipmapNearest)
+			.mipmaps(true)
+			.build()
+			.map_err(|err| FramebufferError::TextureError(format!("{err:?}")))?;
+		texture.set_mip_range(0, levels - 1).map_err(|err| FramebufferError::TextureError(format!("{err:?}")))?;
+		let texture: Rc<dyn GenericTexture> = Rc::new(texture);
+		let mut framebuffers = Vec::with_capacity(levels as usize);
+		for level in 0..levels {
+			let mut framebuffer = Framebuffer::new(glcore.clone())?;
+			framebuffer.add_color_target(0u32, texture.clone(), level)?;
+			framebuffers.push(framebuffer);
+		}
+		Ok(Self {
+			texture,
+			framebuffers,
+		})
+	}
+
+	/// Get the texture of the mip chain, bind it to sample the source levels
+	pub fn get_texture(&self) -> &Rc<dyn GenericTexture> {
+		&self.texture
+	}
+
+	/// Get the number of the mip levels
+	pub fn level_count(&self) -> u32 {
+		self.framebuffers.len() as u32
+	}
+
+	/// Get the framebuffer attached to the mip level, panics if the level is out of the chain
+	pub fn level_framebuffer(&self, level: u32) -> &Framebuffer {
+		&self.framebuffers[level as usize]
+	}
+
+	/// Get the size of the mip level
+	pub fn level_size(&self, level: u32) -> [u32; 2] {
+		[(self.texture.get_width() >> level).max(1), (self.texture.get_height() >> level).max(1)]
+	}
+
+	/// Iterate the downsampling passes from the level 1 to the last level, each pass reads the level `level - 1` and draws into the framebuffer of `level`
+	pub fn down_passes(&self) -> impl Iterator<Item = (u32, &Framebuffer)> {
+		self.framebuffers.iter().enumerate().skip(1).map(|(level, framebuffer)| (level as u32, framebuffer))
+	}
+
+	/// Iterate the upsampling passes from the second last level to the level 0, each pass reads the level `level + 1` and draws into the framebuffer of `level`
+	pub fn up_passes(&self) -> impl Iterator<Item = (u32, &Framebuffer)> {
+		let last = self.framebuffers.len().saturating_sub(1);
+		self.framebuffers[..last].iter().enumerate().rev().map(|(level, framebuffer)| (level as u32, framebuffer))
+	}
+}
+
 impl Drop for FramebufferBind<'_> {
 	fn drop(&mut self) {
-		self.framebuffer.glcore.glBindFramebuffer(GL_DRAW_FRAMEBUFFER, 0).unwrap();
+		let glcore = self.framebuffer.glcore.as_ref();
+		glcore.glBindFramebuffer(self.target as u32, 0).unwrap();
+		if let Some(prev_srgb_write) = self.prev_srgb_write {
+			Framebuffer::set_default_srgb_write(glcore, prev_srgb_write).unwrap();
+		}
 	}
 }
 
@@ -144,10 +1211,40 @@ impl Drop for Framebuffer {
 	}
 }
 
+impl Drop for Renderbuffer {
+	fn drop(&mut self) {
+		self.glcore.glDeleteRenderbuffers(1, &self.name as *const _).unwrap();
+		MemoryTracker::track_delete(MemoryCategory::Renderbuffers, self.gpu_bytes());
+	}
+}
+
 impl Debug for Framebuffer {
 	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
 		f.debug_struct("Framebuffer")
 		.field("name", &self.name)
+		.field("renderbuffers", &self.renderbuffers)
+		.finish()
+	}
+}
+
+impl Debug for Renderbuffer {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.debug_struct("Renderbuffer")
+		.field("name", &self.name)
+		.field("format", &self.format)
+		.field("width", &self.width.get())
+		.field("height", &self.height.get())
+		.field("samples", &self.samples)
+		.finish()
+	}
+}
+
+impl Debug for MipChainTarget {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.debug_struct("MipChainTarget")
+		.field("texture", &self.texture.get_name())
+		.field("level_count", &self.level_count())
+		.field("framebuffers", &self.framebuffers)
 		.finish()
 	}
 }
@@ -0,0 +1,183 @@
+use crate::prelude::*;
+use std::{
+	fmt::{self, Debug, Formatter},
+	rc::Rc,
+};
+
+/// What the query counts between `Query::begin()` and the end of the `QueryActive`
+#[derive(Clone, Copy, PartialEq)]
+pub enum QueryTarget {
+	/// The number of the samples that passed the depth test
+	SamplesPassed = GL_SAMPLES_PASSED as isize,
+
+	/// 1 if any of the samples passed the depth test, otherwise 0, for the occlusion culling
+	AnySamplesPassed = GL_ANY_SAMPLES_PASSED as isize,
+
+	/// The number of the primitives generated by the vertex shader, the tessellation or the geometry shader
+	PrimitivesGenerated = GL_PRIMITIVES_GENERATED as isize,
+
+	/// The number of the primitives written into the transform feedback buffers
+	TransformFeedbackPrimitivesWritten = GL_TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN as isize,
+
+	/// The GPU time in nanoseconds
+	TimeElapsed = GL_TIME_ELAPSED as isize,
+}
+
+/// How `glBeginConditionalRender()` waits for the result of the query
+#[derive(Clone, Copy, PartialEq)]
+pub enum ConditionalMode {
+	/// Wait for the result of the query
+	Wait = GL_QUERY_WAIT as isize,
+
+	/// Draw without waiting if the result isn't available yet
+	NoWait = GL_QUERY_NO_WAIT as isize,
+
+	/// Wait for the result, the framebuffer regions may be discarded independently
+	ByRegionWait = GL_QUERY_BY_REGION_WAIT as isize,
+
+	/// Draw without waiting if the result isn't available yet, the framebuffer regions may be discarded independently
+	ByRegionNoWait = GL_QUERY_BY_REGION_NO_WAIT as isize,
+}
+
+/// The OpenGL query object
+pub struct Query {
+	pub glcore: Rc<GLCore>,
+	name: u32,
+	target: QueryTarget,
+}
+
+/// The counting state between `glBeginQuery()` and `glEndQuery()`, the RAII system helps to end it
+#[derive(Debug)]
+pub struct QueryActive<'a> {
+	pub query: &'a Query,
+}
+
+/// The conditional rendering state between `glBeginConditionalRender()` and `glEndConditionalRender()`, the RAII system helps to end it.
+/// The draw calls in between are skipped by the GPU if the query counted zero, e.g. no sample of the bounding box passed the depth test.
+#[derive(Debug)]
+pub struct ConditionalRender<'a> {
+	pub query: &'a Query,
+}
+
+impl Query {
+	/// Create a new query object of the target
+	pub fn new(glcore: Rc<GLCore>, target: QueryTarget) -> Result<Self, GLCoreError> {
+		let mut name: u32 = 0;
+		glcore.glGenQueries(1, &mut name as *mut u32)?;
+		Ok(Self {
+			glcore,
+			name,
+			target,
+		})
+	}
+
+	/// Get the internal name
+	pub fn get_name(&self) -> u32 {
+		self.name
+	}
+
+	/// Get the target
+	pub fn get_target(&self) -> QueryTarget {
+		self.target
+	}
+
+	/// Begin counting, the counting ends when the returned `QueryActive` is dropped
+	pub fn begin<'a>(&'a self) -> Result<QueryActive<'a>, GLCoreError> {
+		QueryActive::new(self)
+	}
+
+	/// Begin the conditional rendering by the result of this query, it ends when the returned `ConditionalRender` is dropped
+	pub fn begin_conditional_render<'a>(&'a self, mode: ConditionalMode) -> Result<ConditionalRender<'a>, GLCoreError> {
+		ConditionalRender::new(self, mode)
+	}
+
+	/// Check if the result is available without blocking
+	pub fn is_result_available(&self) -> Result<bool, GLCoreError> {
+		let mut available: u32 = 0;
+		self.glcore.glGetQueryObjectuiv(self.name, GL_QUERY_RESULT_AVAILABLE, &mut available as *mut u32)?;
+		Ok(available != 0)
+	}
+
+	/// Get the result, blocks until it's available
+	pub fn get_result(&self) -> Result<u64, GLCoreError> {
+		let mut result: u64 = 0;
+		self.glcore.glGetQueryObjectui64v(self.name, GL_QUERY_RESULT, &mut result as *mut u64)?;
+		Ok(result)
+	}
+}
+
+impl Drop for Query {
+	fn drop(&mut self) {
+		self.glcore.glDeleteQueries(1, &self.name as *const u32).unwrap();
+	}
+}
+
+impl Debug for Query {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.debug_struct("Query")
+		.field("name", &self.name)
+		.field("target", &self.target)
+		.finish()
+	}
+}
+
+impl<'a> QueryActive<'a> {
+	/// Begin the query
+	fn new(query: &'a Query) -> Result<Self, GLCoreError> {
+		query.glcore.glBeginQuery(query.target as u32, query.name)?;
+		Ok(Self {
+			query,
+		})
+	}
+
+	/// End the query
+	pub fn end(self) {} // End by owning it in the function and `drop()`
+}
+
+impl Drop for QueryActive<'_> {
+	fn drop(&mut self) {
+		self.query.glcore.glEndQuery(self.query.target as u32).unwrap();
+	}
+}
+
+impl<'a> ConditionalRender<'a> {
+	/// Begin the conditional rendering
+	fn new(query: &'a Query, mode: ConditionalMode) -> Result<Self, GLCoreError> {
+		query.glcore.glBeginConditionalRender(query.name, mode as u32)?;
+		Ok(Self {
+			query,
+		})
+	}
+
+	/// End the conditional rendering
+	pub fn end(self) {} // End by owning it in the function and `drop()`
+}
+
+impl Drop for ConditionalRender<'_> {
+	fn drop(&mut self) {
+		self.query.glcore.glEndConditionalRender().unwrap();
+	}
+}
+
+impl Debug for QueryTarget {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::SamplesPassed => write!(f, "SamplesPassed"),
+			Self::AnySamplesPassed => write!(f, "AnySamplesPassed"),
+			Self::PrimitivesGenerated => write!(f, "PrimitivesGenerated"),
+			Self::TransformFeedbackPrimitivesWritten => write!(f, "TransformFeedbackPrimitivesWritten"),
+			Self::TimeElapsed => write!(f, "TimeElapsed"),
+		}
+	}
+}
+
+impl Debug for ConditionalMode {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Wait => write!(f, "Wait"),
+			Self::NoWait => write!(f, "NoWait"),
+			Self::ByRegionWait => write!(f, "ByRegionWait"),
+			Self::ByRegionNoWait => write!(f, "ByRegionNoWait"),
+		}
+	}
+}
@@ -7,6 +7,7 @@
 use crate::prelude::*;
 use std::{
 	any::{Any, type_name},
+	cell::Cell,
 	collections::BTreeMap,
 	ffi::{CString, c_void},
 	fmt::{self, Debug, Display, Formatter},
@@ -18,6 +19,11 @@ use std::{
 };
 use bincode::{Encode, Decode};
 
+thread_local! {
+	/// The context and the program of the innermost `ShaderUse` on the current thread
+	static BOUND_PROGRAM: Cell<(usize, u32)> = const {Cell::new((0, 0))};
+}
+
 /// Error produced from the shader
 #[derive(Clone)]
 pub enum ShaderError {
@@ -53,6 +59,12 @@ pub enum ShaderError {
 
 	/// Shader is not supported for the current OpenGL version
 	ShaderNotSupported(String),
+
+	/// Ran out of texture units, the names of the material components that couldn't be assigned
+	TextureUnitsExhausted(Vec<String>),
+
+	/// The array uniform (the first one) is shorter than the number of the given elements (the second one)
+	UniformArrayTooSmall(String, usize),
 }
 
 /// Error produced from the shader
@@ -69,10 +81,12 @@ pub struct Shader {
 	shader_type: ShaderType,
 }
 
-/// The struct for monitoring using the shader
+/// The struct for monitoring using the shader.
+/// Using the program that is already in use skips `glUseProgram()`, and unusing restores the previously used program, so the nested uses of a program are free.
 #[derive(Debug)]
 pub struct ShaderUse<'a> {
 	pub shader: &'a Shader,
+	previous: Option<(usize, u32)>,
 }
 
 /// The pre-compiled OpenGL shader binary
@@ -98,7 +112,7 @@ pub enum ShaderBinarySaveError {
 }
 
 /// The OpenGL attrib types
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum ShaderInputType {
 	Float = GL_FLOAT as isize,
 	Vec2 = GL_FLOAT_VEC2 as isize,
@@ -137,7 +151,7 @@ pub enum ShaderInputType {
 }
 
 /// The OpenGL attrib type with length
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ShaderInputVarType {
 	pub type_: ShaderInputType,
 	pub size: i32,
@@ -208,6 +222,11 @@ impl Shader {
 
 	/// Create a new traditional renderer shader program
 	pub fn new(glcore: Rc<GLCore>, vertex_shader: Option<&str>, geometry_shader: Option<&str>, fragment_shader: Option<&str>) -> Result<Self, ShaderError> {
+		Self::new_with_varyings(glcore, vertex_shader, geometry_shader, fragment_shader, &[], TransformFeedbackBufferMode::Interleaved)
+	}
+
+	/// Create a new traditional renderer shader program, the output `varyings` are captured by the transform feedback in the order given
+	pub fn new_with_varyings(glcore: Rc<GLCore>, vertex_shader: Option<&str>, geometry_shader: Option<&str>, fragment_shader: Option<&str>, varyings: &[&str], buffer_mode: TransformFeedbackBufferMode) -> Result<Self, ShaderError> {
 		let program = glcore.glCreateProgram()?;
 		if let Some(vertex_shader) = vertex_shader {
 			let shader = Self::compile_shader(glcore.as_ref(), GL_VERTEX_SHADER, vertex_shader)?;
@@ -224,6 +243,11 @@ impl Shader {
 			glcore.glAttachShader(program, shader)?;
 			glcore.glDeleteShader(shader)?;
 		}
+		if !varyings.is_empty() {
+			let varyings = varyings.iter().map(|varying| CString::new(*varying).map_err(|_| ShaderError::LinkageError(format!("The varying `{varying}` contains a NUL byte")))).collect::<Result<Vec<CString>, ShaderError>>()?;
+			let ptrs: Vec<*const i8> = varyings.iter().map(|varying| varying.as_ptr()).collect();
+			glcore.glTransformFeedbackVaryings(program, ptrs.len() as i32, ptrs.as_ptr(), buffer_mode as u32)?;
+		}
 		Self::link_program(glcore.as_ref(), program)?;
 		Ok(Self {
 			glcore,
@@ -269,7 +293,7 @@ impl Shader {
 
 	/// Get the location of the shader attrib
 	pub fn get_attrib_location(&self, attrib_name: &str) -> Result<i32, ShaderError> {
-		let attrib_name = CString::new(attrib_name).unwrap();
+		let attrib_name = CString::new(attrib_name).map_err(|_| ShaderError::AttribNotFound(attrib_name.to_string()))?;
 		Ok(self.glcore.glGetAttribLocation(self.program, attrib_name.as_ptr())?)
 	}
 
@@ -296,7 +320,7 @@ impl Shader {
 
 	/// Get the location of the shader attrib
 	pub fn get_uniform_location(&self, uniform_name: &str) -> Result<i32, ShaderError> {
-		let uniform_name = CString::new(uniform_name).unwrap();
+		let uniform_name = CString::new(uniform_name).map_err(|_| ShaderError::UniformNotFound(uniform_name.to_string()))?;
 		Ok(self.glcore.glGetUniformLocation(self.program, uniform_name.as_ptr())?)
 	}
 
@@ -336,9 +360,18 @@ impl Shader {
 impl<'a> ShaderUse<'a> {
 	/// Create a new `using` state to the `Shader`
 	fn new(shader: &'a Shader) -> Result<Self, ShaderError> {
-		shader.glcore.glUseProgram(shader.get_name())?;
+		let current = (Rc::as_ptr(&shader.glcore) as usize, shader.get_name());
+		let bound = BOUND_PROGRAM.get();
+		let previous = if bound == current {
+			None
+		} else {
+			shader.glcore.glUseProgram(shader.get_name())?;
+			BOUND_PROGRAM.set(current);
+			Some(bound)
+		};
 		Ok(Self {
 			shader,
+			previous,
 		})
 	}
 
@@ -365,25 +398,26 @@ impl<'a> ShaderUse<'a> {
 		Ok(())
 	}
 
-	/// Wrapper for matrices of attributes
+	/// Wrapper for matrices of attributes, each of the `rows` takes a location and `cols` components, the rows are tightly packed from `pointer`.
+	/// The `stride` is the distance between the matrices, 0 for the tightly packed matrices.
 	///
 	/// # Safety
 	///
 	/// When binding an array buffer, the parameter `pointer` refers to an offset of the data from the array buffer.
 	/// When not bound to any array buffers, the parameter `pointer` is the pointer to your vertex data from the system memory.
 	pub unsafe fn vertex_attrib_matrix_pointer(&self, location: u32, cols: u32, rows: u32, base_type: ShaderInputType, normalize: bool, stride: isize, pointer: *const c_void) -> Result<(), ShaderError> {
-		match base_type {
-			ShaderInputType::Float => {
-				for i in 0..rows {
-					self.shader.glcore.glVertexAttribPointer(location + i, cols as i32, base_type as u32, normalize as u8, stride as i32, pointer as *const _)?;
-				}
-			}
-			ShaderInputType::Double => {
-				for i in 0..rows {
-					self.shader.glcore.glVertexAttribLPointer(location + i, cols as i32, base_type as u32, stride as i32, pointer as *const _)?;
-				}
-			}
+		let row_size = match base_type {
+			ShaderInputType::Float => cols as usize * size_of::<f32>(),
+			ShaderInputType::Double => cols as usize * size_of::<f64>(),
 			_ => panic!("Bad parameter for `vertex_attrib_matrix_pointer()`: base_type = {base_type:?}"),
+		};
+		let stride = if stride == 0 {(row_size * rows as usize) as isize} else {stride};
+		for i in 0..rows {
+			let pointer = pointer.wrapping_byte_add(i as usize * row_size);
+			match base_type {
+				ShaderInputType::Float => self.shader.glcore.glVertexAttribPointer(location + i, cols as i32, base_type as u32, normalize as u8, stride as i32, pointer as *const _)?,
+				_ => self.shader.glcore.glVertexAttribLPointer(location + i, cols as i32, base_type as u32, stride as i32, pointer as *const _)?,
+			}
 		}
 		Ok(())
 	}
@@ -501,12 +535,44 @@ impl<'a> ShaderUse<'a> {
 		}
 	}
 
+	/// Set the bone matrices to the `mat4` array uniform `name`, e.g. `bones` of `uniform mat4 bones[64];`, the rest of the array is kept
+	pub fn set_bone_matrices(&self, name: &str, matrices: &[Mat4]) -> Result<(), ShaderError> {
+		let location = self.shader.get_uniform_location(name)?;
+		if location < 0 {
+			return Err(ShaderError::UniformNotFound(name.to_owned()));
+		}
+		if matrices.is_empty() {
+			return Ok(());
+		}
+		if self.shader.get_uniform_location(&format!("{name}[{}]", matrices.len() - 1))? < 0 {
+			return Err(ShaderError::UniformArrayTooSmall(name.to_owned(), matrices.len()));
+		}
+		self.shader.glcore.glUniformMatrix4fv(location, matrices.len() as i32, 0, matrices.as_ptr() as *const f32)?;
+		Ok(())
+	}
+
+	/// Set the image unit of an image uniform, e.g. `image2D`, the texture is bound to the unit by `Texture::bind_image_unit()`
+	pub fn set_uniform_image(&self, name: &str, unit: u32) -> Result<(), ShaderError> {
+		let location = self.shader.get_uniform_location(name)?;
+		if location >= 0 {
+			self.shader.glcore.glUniform1i(location, unit as i32)?;
+			Ok(())
+		} else {
+			Err(ShaderError::UniformNotFound(name.to_owned()))
+		}
+	}
+
 	/// Set shader uniform inputs by a material
-	pub fn setup_material_uniforms(&self, material: &dyn Material, prefix: Option<&str>, camel_case: bool) -> Result<(), ShaderError> {
+	///
+	/// If `sampler` is given, it's bound to every texture unit that a material texture uses, overriding the sampling parameters of the textures.
+	///
+	/// If `units` is given, the material textures are bound to the units allocated from it, otherwise the units are counted from 0.
+	/// The allocated units are kept until `TextureUnits::release_all()` is called, so several materials could be set up for the same draw call.
+	pub fn setup_material_uniforms(&self, material: &dyn Material, prefix: Option<&str>, camel_case: bool, sampler: Option<&Sampler>, units: Option<&mut TextureUnits>) -> Result<(), ShaderError> {
 		let glcore = &self.shader.glcore;
 		let shader_uniforms = self.shader.get_active_uniforms()?;
 		let texture_names = material.get_names();
-		let mut active_texture = 0u32;
+		let mut textures: Vec<(&str, i32, &Rc<dyn GenericTexture>)> = Vec::new();
 		for name in texture_names.iter() {
 			let mut name_mod = String::new();
 			if let Some(prefix) = prefix {
@@ -525,11 +591,7 @@ impl<'a> ShaderUse<'a> {
 				}
 				match texture {
 					MaterialComponent::Texture(texture) => {
-						texture.set_active_unit(active_texture)?;
-						let bind = texture.bind()?;
-						glcore.glUniform1i(location, active_texture as i32)?;
-						bind.unbind();
-						active_texture += 1;
+						textures.push((name, location, texture));
 					}
 					MaterialComponent::Color(color) => {
 						glcore.glUniform4f(location, color.x, color.y, color.z, color.w)?;
@@ -540,6 +602,32 @@ impl<'a> ShaderUse<'a> {
 				}
 			}
 		}
+		let assigned: Vec<u32> = if let Some(units) = units {
+			let mut assigned = Vec::with_capacity(textures.len());
+			let mut unassigned = Vec::new();
+			for (name, _, _) in textures.iter() {
+				match units.allocate() {
+					Some(unit) => assigned.push(unit),
+					None => unassigned.push(name.to_string()),
+				}
+			}
+			if !unassigned.is_empty() {
+				for unit in assigned {
+					units.release(unit);
+				}
+				return Err(ShaderError::TextureUnitsExhausted(unassigned));
+			}
+			assigned
+		} else {
+			(0..textures.len() as u32).collect()
+		};
+		for ((_, location, texture), unit) in textures.into_iter().zip(assigned) {
+			texture.set_active_unit(unit)?;
+			let bind = texture.bind()?;
+			glcore.glUniform1i(location, unit as i32)?;
+			glcore.glBindSampler(unit, sampler.map_or(0, |sampler| sampler.get_name()))?;
+			bind.unbind();
+		}
 		Ok(())
 	}
 
@@ -549,7 +637,12 @@ impl<'a> ShaderUse<'a> {
 
 impl Drop for ShaderUse<'_> {
 	fn drop(&mut self) {
-		self.shader.glcore.glUseProgram(0).unwrap();
+		if let Some((context, program)) = self.previous {
+			let current = Rc::as_ptr(&self.shader.glcore) as usize;
+			let program = if context == current {program} else {0};
+			self.shader.glcore.glUseProgram(program).unwrap();
+			BOUND_PROGRAM.set((current, program));
+		}
 	}
 }
 
@@ -694,6 +787,8 @@ impl Debug for ShaderError {
 			Self::FromUtf8Error(reason) => write!(f, "Decode UTF-8 string failed: {reason}"),
 			Self::TextureError(reason) => write!(f, "Texture error: {reason}"),
 			Self::ShaderNotSupported(reason) => write!(f, "OpenGL core error: {reason}"),
+			Self::TextureUnitsExhausted(names) => write!(f, "Ran out of texture units for the material components: {}", names.join(", ")),
+			Self::UniformArrayTooSmall(uniform, count) => write!(f, "The array uniform `{uniform}` is shorter than {count} elements"),
 		}
 	}
 }
@@ -0,0 +1,847 @@
+use crate::prelude::*;
+use std::fmt::{self, Debug, Formatter};
+
+/// The factor of the source color or the destination color of the blending
+#[derive(Clone, Copy, PartialEq)]
+pub enum BlendFactor {
+	Zero = GL_ZERO as isize,
+	One = GL_ONE as isize,
+	SrcColor = GL_SRC_COLOR as isize,
+	OneMinusSrcColor = GL_ONE_MINUS_SRC_COLOR as isize,
+	DstColor = GL_DST_COLOR as isize,
+	OneMinusDstColor = GL_ONE_MINUS_DST_COLOR as isize,
+	SrcAlpha = GL_SRC_ALPHA as isize,
+	OneMinusSrcAlpha = GL_ONE_MINUS_SRC_ALPHA as isize,
+	DstAlpha = GL_DST_ALPHA as isize,
+	OneMinusDstAlpha = GL_ONE_MINUS_DST_ALPHA as isize,
+	ConstantColor = GL_CONSTANT_COLOR as isize,
+	OneMinusConstantColor = GL_ONE_MINUS_CONSTANT_COLOR as isize,
+	ConstantAlpha = GL_CONSTANT_ALPHA as isize,
+	OneMinusConstantAlpha = GL_ONE_MINUS_CONSTANT_ALPHA as isize,
+	SrcAlphaSaturate = GL_SRC_ALPHA_SATURATE as isize,
+}
+
+/// How the weighted source color and destination color are combined
+#[derive(Clone, Copy, PartialEq)]
+pub enum BlendEquation {
+	Add = GL_FUNC_ADD as isize,
+	Subtract = GL_FUNC_SUBTRACT as isize,
+	ReverseSubtract = GL_FUNC_REVERSE_SUBTRACT as isize,
+	Min = GL_MIN as isize,
+	Max = GL_MAX as isize,
+}
+
+/// The faces to cull
+#[derive(Clone, Copy, PartialEq)]
+pub enum CullFace {
+	Front = GL_FRONT as isize,
+	Back = GL_BACK as isize,
+	FrontAndBack = GL_FRONT_AND_BACK as isize,
+}
+
+/// The winding of the front faces
+#[derive(Clone, Copy, PartialEq)]
+pub enum FrontFace {
+	Ccw = GL_CCW as isize,
+	Cw = GL_CW as isize,
+}
+
+/// How the polygons are rasterized, for both of the faces since the core profiles only accept `GL_FRONT_AND_BACK`
+#[derive(Clone, Copy, PartialEq)]
+pub enum PolygonMode {
+	/// Fill the polygons
+	Fill = GL_FILL as isize,
+
+	/// Draw the edges of the polygons as lines, e.g. the wireframe
+	Line = GL_LINE as isize,
+
+	/// Draw the vertices of the polygons as points
+	Point = GL_POINT as isize,
+}
+
+/// What to do with the stencil value of a sample after the stencil test and the depth test
+#[derive(Clone, Copy, PartialEq)]
+pub enum StencilOp {
+	/// Keep the current value
+	Keep = GL_KEEP as isize,
+
+	/// Set the value to 0
+	Zero = GL_ZERO as isize,
+
+	/// Set the value to the reference value
+	Replace = GL_REPLACE as isize,
+
+	/// Increment the value, clamped to the maximum
+	Incr = GL_INCR as isize,
+
+	/// Increment the value, wrapped to 0 over the maximum
+	IncrWrap = GL_INCR_WRAP as isize,
+
+	/// Decrement the value, clamped to 0
+	Decr = GL_DECR as isize,
+
+	/// Decrement the value, wrapped to the maximum below 0
+	DecrWrap = GL_DECR_WRAP as isize,
+
+	/// Invert the bits of the value
+	Invert = GL_INVERT as isize,
+}
+
+/// The factors and the equation of the blending
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlendFunc {
+	pub src: BlendFactor,
+	pub dst: BlendFactor,
+	pub equation: BlendEquation,
+}
+
+/// The blending, `alpha` is the separate blending of the alpha channel, the alpha is blended by `color` if it's `None`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlendState {
+	pub color: BlendFunc,
+	pub alpha: Option<BlendFunc>,
+}
+
+/// The stencil test and the stencil operations of the front faces or the back faces, the default values are the defaults of OpenGL
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StencilFace {
+	/// The sample passes if `(reference & read_mask) func (stencil & read_mask)` is true, by `glStencilFuncSeparate()`
+	pub func: CompareFunc,
+
+	/// The reference value of the test, also written by `StencilOp::Replace`
+	pub reference: i32,
+
+	/// The mask of the values for the test
+	pub read_mask: u32,
+
+	/// The operation when the stencil test fails, by `glStencilOpSeparate()`
+	pub fail: StencilOp,
+
+	/// The operation when the stencil test passes but the depth test fails
+	pub depth_fail: StencilOp,
+
+	/// The operation when both of the tests pass
+	pub pass: StencilOp,
+
+	/// The mask of the bits to write, by `glStencilMaskSeparate()`
+	pub write_mask: u32,
+}
+
+/// The stencil states of the front faces and the back faces
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StencilState {
+	pub front: StencilFace,
+	pub back: StencilFace,
+}
+
+/// The fixed-function states of drawing, the default values are the defaults of OpenGL.
+/// Set it to a `Pipeline` to apply it before drawing and restore the states after, or call `apply()` with a `StateGuard` to manage it by yourself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderState {
+	/// Enable `GL_DEPTH_TEST`
+	pub depth_test: bool,
+
+	/// Write to the depth buffer, by `glDepthMask()`
+	pub depth_write: bool,
+
+	/// The compare function of the depth test
+	pub depth_func: CompareFunc,
+
+	/// Enable `GL_BLEND` with the blending, or disable it for `None`
+	pub blend: Option<BlendState>,
+
+	/// Enable `GL_CULL_FACE` to cull the faces, or disable it for `None`
+	pub cull_face: Option<CullFace>,
+
+	/// The winding of the front faces
+	pub front_face: FrontFace,
+
+	/// Enable `GL_SCISSOR_TEST` with the rectangle `(x, y, width, height)`, or disable it for `None`
+	pub scissor: Option<(i32, i32, u32, u32)>,
+
+	/// Write the red, green, blue and alpha channels, by `glColorMask()`
+	pub color_mask: [bool; 4],
+
+	/// How the polygons are rasterized, by `glPolygonMode()`
+	pub polygon_mode: PolygonMode,
+
+	/// Offset the depth of the polygons rasterized in the `polygon_mode` by `(factor, units)` of `glPolygonOffset()`, or disable it for `None`
+	pub polygon_offset: Option<(f32, f32)>,
+
+	/// The width of the lines, the core profiles may only support 1.0
+	pub line_width: f32,
+
+	/// Enable `GL_PROGRAM_POINT_SIZE` to use the `gl_PointSize` written by the shaders
+	pub program_point_size: bool,
+
+	/// Enable `GL_STENCIL_TEST` with the stencil states, or disable it for `None`. The framebuffer needs a stencil attachment, e.g. `TextureFormat::Depth24Stencil8`.
+	pub stencil: Option<StencilState>,
+}
+
+/// The snapshot of the states set by `RenderState`, the RAII system helps to restore them
+pub struct StateGuard<'a> {
+	pub glcore: &'a GLCore,
+	depth_test: bool,
+	depth_write: u8,
+	depth_func: i32,
+	blend: bool,
+	blend_factors: [i32; 4],
+	blend_equations: [i32; 2],
+	cull_face: bool,
+	cull_face_mode: i32,
+	front_face: i32,
+	scissor_test: bool,
+	scissor_box: [i32; 4],
+	color_mask: [u8; 4],
+	polygon_mode: i32,
+	polygon_offset: [bool; 3],
+	polygon_offset_values: [f32; 2],
+	line_width: f32,
+	program_point_size: bool,
+	stencil_test: bool,
+	stencil_faces: [[i32; 7]; 2],
+}
+
+/// The error of the viewport arrays
+#[derive(Debug, Clone)]
+pub enum ViewportError {
+	GLCoreError(GLCoreError),
+
+	/// The viewport arrays need OpenGL 4.1 or `GL_ARB_viewport_array`
+	NotSupported,
+
+	/// The number of the viewports (the first one) exceeds `GL_MAX_VIEWPORTS` (the second one)
+	TooManyViewports(usize, usize),
+}
+
+/// A viewport of the `ViewportArray`, selected by `gl_ViewportIndex` in the geometry shader
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndexedViewport {
+	/// The rectangle `[x, y, width, height]`, by `glViewportIndexedf()`
+	pub rect: [f32; 4],
+
+	/// The depth range `[near, far]`, by `glDepthRangeIndexed()`
+	pub depth_range: [f64; 2],
+
+	/// Enable `GL_SCISSOR_TEST` of the viewport with the rectangle `(x, y, width, height)` by `glScissorIndexed()`, or disable it for `None`
+	pub scissor: Option<(i32, i32, u32, u32)>,
+}
+
+/// The viewports and the scissors of the indices, e.g. for the split screens or the shadow cascades rendered in one pass.
+/// Set it to a `Pipeline` to apply it before drawing and restore the viewports after, or call `apply()` to manage it by yourself.
+/// The scissors of the viewports override the scissor of `RenderState`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ViewportArray {
+	viewports: Vec<IndexedViewport>,
+}
+
+/// The snapshot of the viewports set by `ViewportArray::apply()`, the RAII system helps to restore them
+pub struct ViewportArrayGuard<'a> {
+	pub glcore: &'a GLCore,
+	viewports: Vec<([f32; 4], [f64; 2], bool, [i32; 4])>,
+}
+
+impl BlendFunc {
+	/// Create the factors and the equation
+	pub fn new(src: BlendFactor, dst: BlendFactor, equation: BlendEquation) -> Self {
+		Self {
+			src,
+			dst,
+			equation,
+		}
+	}
+}
+
+impl BlendState {
+	/// The alpha blending of the non-premultiplied colors
+	pub const ALPHA: Self = Self {
+		color: BlendFunc {src: BlendFactor::SrcAlpha, dst: BlendFactor::OneMinusSrcAlpha, equation: BlendEquation::Add},
+		alpha: None,
+	};
+
+	/// The alpha blending of the premultiplied colors
+	pub const PREMULTIPLIED: Self = Self {
+		color: BlendFunc {src: BlendFactor::One, dst: BlendFactor::OneMinusSrcAlpha, equation: BlendEquation::Add},
+		alpha: None,
+	};
+
+	/// Add the colors weighted by the alpha of the source
+	pub const ADDITIVE: Self = Self {
+		color: BlendFunc {src: BlendFactor::SrcAlpha, dst: BlendFactor::One, equation: BlendEquation::Add},
+		alpha: None,
+	};
+
+	/// Get the blending of the alpha channel
+	pub fn get_alpha(&self) -> BlendFunc {
+		self.alpha.unwrap_or(self.color)
+	}
+}
+
+impl StencilFace {
+	/// Always pass the test and write `reference` into the stencil of the drawn samples, e.g. to mark the region of a portal or the selected object
+	pub fn write(reference: i32) -> Self {
+		Self {
+			pass: StencilOp::Replace,
+			reference,
+			..Default::default()
+		}
+	}
+
+	/// Only draw the samples that pass the test of `func` against `reference`, the stencil is kept, e.g. `CompareFunc::Equal` to draw inside the marked region
+	pub fn test(func: CompareFunc, reference: i32) -> Self {
+		Self {
+			func,
+			reference,
+			..Default::default()
+		}
+	}
+}
+
+impl Default for StencilFace {
+	fn default() -> Self {
+		Self {
+			func: CompareFunc::Always,
+			reference: 0,
+			read_mask: !0,
+			fail: StencilOp::Keep,
+			depth_fail: StencilOp::Keep,
+			pass: StencilOp::Keep,
+			write_mask: !0,
+		}
+	}
+}
+
+impl StencilState {
+	/// Use the same stencil states for the front faces and the back faces
+	pub fn both(face: StencilFace) -> Self {
+		Self {
+			front: face,
+			back: face,
+		}
+	}
+}
+
+impl From<StencilFace> for StencilState {
+	fn from(face: StencilFace) -> Self {
+		Self::both(face)
+	}
+}
+
+impl Default for RenderState {
+	fn default() -> Self {
+		Self {
+			depth_test: false,
+			depth_write: true,
+			depth_func: CompareFunc::Less,
+			blend: None,
+			cull_face: None,
+			front_face: FrontFace::Ccw,
+			scissor: None,
+			color_mask: [true; 4],
+			polygon_mode: PolygonMode::Fill,
+			polygon_offset: None,
+			line_width: 1.0,
+			program_point_size: false,
+			stencil: None,
+		}
+	}
+}
+
+impl RenderState {
+	/// Set all of the states to OpenGL
+	pub fn apply(&self, glcore: &GLCore) -> Result<(), GLCoreError> {
+		self.set_states(glcore, None)
+	}
+
+	/// Set the states that differ from `current` to OpenGL, `current` must be the states of OpenGL, e.g. the last applied `RenderState`
+	pub fn apply_changes(&self, glcore: &GLCore, current: &RenderState) -> Result<(), GLCoreError> {
+		self.set_states(glcore, Some(current))
+	}
+
+	/// Set the states, only the ones that differ from `current` if it's given
+	fn set_states(&self, glcore: &GLCore, current: Option<&RenderState>) -> Result<(), GLCoreError> {
+		if current.is_none_or(|current| current.depth_test != self.depth_test) {
+			set_enabled(glcore, GL_DEPTH_TEST, self.depth_test)?;
+		}
+		if current.is_none_or(|current| current.depth_write != self.depth_write) {
+			glcore.glDepthMask(self.depth_write as u8)?;
+		}
+		if current.is_none_or(|current| current.depth_func != self.depth_func) {
+			glcore.glDepthFunc(self.depth_func as u32)?;
+		}
+		if current.is_none_or(|current| current.blend.is_some() != self.blend.is_some()) {
+			set_enabled(glcore, GL_BLEND, self.blend.is_some())?;
+		}
+		if let Some(blend) = self.blend.filter(|blend| current.is_none_or(|current| current.blend != Some(*blend))) {
+			let alpha = blend.get_alpha();
+			glcore.glBlendFuncSeparate(blend.color.src as u32, blend.color.dst as u32, alpha.src as u32, alpha.dst as u32)?;
+			glcore.glBlendEquationSeparate(blend.color.equation as u32, alpha.equation as u32)?;
+		}
+		if current.is_none_or(|current| current.cull_face.is_some() != self.cull_face.is_some()) {
+			set_enabled(glcore, GL_CULL_FACE, self.cull_face.is_some())?;
+		}
+		if let Some(cull_face) = self.cull_face.filter(|cull_face| current.is_none_or(|current| current.cull_face != Some(*cull_face))) {
+			glcore.glCullFace(cull_face as u32)?;
+		}
+		if current.is_none_or(|current| current.front_face != self.front_face) {
+			glcore.glFrontFace(self.front_face as u32)?;
+		}
+		if current.is_none_or(|current| current.scissor.is_some() != self.scissor.is_some()) {
+			set_enabled(glcore, GL_SCISSOR_TEST, self.scissor.is_some())?;
+		}
+		if let Some((x, y, width, height)) = self.scissor.filter(|scissor| current.is_none_or(|current| current.scissor != Some(*scissor))) {
+			glcore.glScissor(x, y, width as i32, height as i32)?;
+		}
+		if current.is_none_or(|current| current.color_mask != self.color_mask) {
+			let [r, g, b, a] = self.color_mask;
+			glcore.glColorMask(r as u8, g as u8, b as u8, a as u8)?;
+		}
+		if current.is_none_or(|current| current.polygon_mode != self.polygon_mode) {
+			glcore.glPolygonMode(GL_FRONT_AND_BACK, self.polygon_mode as u32)?;
+		}
+		if current.is_none_or(|current| current.polygon_offset.is_some() != self.polygon_offset.is_some() || current.polygon_mode != self.polygon_mode) {
+			let enabled = self.polygon_mode.get_polygon_offset_cap();
+			for cap in [GL_POLYGON_OFFSET_FILL, GL_POLYGON_OFFSET_LINE, GL_POLYGON_OFFSET_POINT] {
+				set_enabled(glcore, cap, self.polygon_offset.is_some() && cap == enabled)?;
+			}
+		}
+		if let Some((factor, units)) = self.polygon_offset.filter(|offset| current.is_none_or(|current| current.polygon_offset != Some(*offset))) {
+			glcore.glPolygonOffset(factor, units)?;
+		}
+		if current.is_none_or(|current| current.line_width != self.line_width) {
+			glcore.glLineWidth(self.line_width)?;
+		}
+		if current.is_none_or(|current| current.program_point_size != self.program_point_size) {
+			set_enabled(glcore, GL_PROGRAM_POINT_SIZE, self.program_point_size)?;
+		}
+		if current.is_none_or(|current| current.stencil.is_some() != self.stencil.is_some()) {
+			set_enabled(glcore, GL_STENCIL_TEST, self.stencil.is_some())?;
+		}
+		if let Some(stencil) = self.stencil.filter(|stencil| current.is_none_or(|current| current.stencil != Some(*stencil))) {
+			for (face, state) in [(GL_FRONT, stencil.front), (GL_BACK, stencil.back)] {
+				glcore.glStencilFuncSeparate(face, state.func as u32, state.reference, state.read_mask)?;
+				glcore.glStencilOpSeparate(face, state.fail as u32, state.depth_fail as u32, state.pass as u32)?;
+				glcore.glStencilMaskSeparate(face, state.write_mask)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl PolygonMode {
+	/// Get the capability of the polygon offset of the mode
+	fn get_polygon_offset_cap(&self) -> u32 {
+		match self {
+			Self::Fill => GL_POLYGON_OFFSET_FILL,
+			Self::Line => GL_POLYGON_OFFSET_LINE,
+			Self::Point => GL_POLYGON_OFFSET_POINT,
+		}
+	}
+}
+
+/// Enable or disable a capability
+fn set_enabled(glcore: &GLCore, cap: u32, enabled: bool) -> Result<(), GLCoreError> {
+	if enabled {
+		glcore.glEnable(cap)
+	} else {
+		glcore.glDisable(cap)
+	}
+}
+
+impl<'a> StateGuard<'a> {
+	/// Take the snapshot of the current states
+	pub fn new(glcore: &'a GLCore) -> Result<Self, GLCoreError> {
+		let get_integers = |pname: u32, values: &mut [i32]| glcore.glGetIntegerv(pname, values.as_mut_ptr());
+		let mut depth_write = 0u8;
+		let mut depth_func = [0i32];
+		let mut blend_factors = [0i32; 4];
+		let mut blend_equations = [0i32; 2];
+		let mut cull_face_mode = [0i32];
+		let mut front_face = [0i32];
+		let mut scissor_box = [0i32; 4];
+		let mut color_mask = [0u8; 4];
+		let mut polygon_mode = [0i32; 2];
+		let mut polygon_offset_values = [0f32; 2];
+		let mut line_width = 0f32;
+		glcore.glGetBooleanv(GL_DEPTH_WRITEMASK, &mut depth_write as *mut u8)?;
+		get_integers(GL_DEPTH_FUNC, &mut depth_func)?;
+		for (i, pname) in [GL_BLEND_SRC_RGB, GL_BLEND_DST_RGB, GL_BLEND_SRC_ALPHA, GL_BLEND_DST_ALPHA].into_iter().enumerate() {
+			get_integers(pname, &mut blend_factors[i..=i])?;
+		}
+		get_integers(GL_BLEND_EQUATION_RGB, &mut blend_equations[0..1])?;
+		get_integers(GL_BLEND_EQUATION_ALPHA, &mut blend_equations[1..2])?;
+		get_integers(GL_CULL_FACE_MODE, &mut cull_face_mode)?;
+		get_integers(GL_FRONT_FACE, &mut front_face)?;
+		get_integers(GL_SCISSOR_BOX, &mut scissor_box)?;
+		glcore.glGetBooleanv(GL_COLOR_WRITEMASK, color_mask.as_mut_ptr())?;
+		get_integers(GL_POLYGON_MODE, &mut polygon_mode)?;
+		glcore.glGetFloatv(GL_POLYGON_OFFSET_FACTOR, &mut polygon_offset_values[0] as *mut f32)?;
+		glcore.glGetFloatv(GL_POLYGON_OFFSET_UNITS, &mut polygon_offset_values[1] as *mut f32)?;
+		glcore.glGetFloatv(GL_LINE_WIDTH, &mut line_width as *mut f32)?;
+		let mut stencil_faces = [[0i32; 7]; 2];
+		let stencil_pnames = [
+			[GL_STENCIL_FUNC, GL_STENCIL_REF, GL_STENCIL_VALUE_MASK, GL_STENCIL_FAIL, GL_STENCIL_PASS_DEPTH_FAIL, GL_STENCIL_PASS_DEPTH_PASS, GL_STENCIL_WRITEMASK],
+			[GL_STENCIL_BACK_FUNC, GL_STENCIL_BACK_REF, GL_STENCIL_BACK_VALUE_MASK, GL_STENCIL_BACK_FAIL, GL_STENCIL_BACK_PASS_DEPTH_FAIL, GL_STENCIL_BACK_PASS_DEPTH_PASS, GL_STENCIL_BACK_WRITEMASK],
+		];
+		for (values, pnames) in stencil_faces.iter_mut().zip(stencil_pnames) {
+			for (i, pname) in pnames.into_iter().enumerate() {
+				get_integers(pname, &mut values[i..=i])?;
+			}
+		}
+		Ok(Self {
+			glcore,
+			depth_test: glcore.glIsEnabled(GL_DEPTH_TEST)? != 0,
+			depth_write,
+			depth_func: depth_func[0],
+			blend: glcore.glIsEnabled(GL_BLEND)? != 0,
+			blend_factors,
+			blend_equations,
+			cull_face: glcore.glIsEnabled(GL_CULL_FACE)? != 0,
+			cull_face_mode: cull_face_mode[0],
+			front_face: front_face[0],
+			scissor_test: glcore.glIsEnabled(GL_SCISSOR_TEST)? != 0,
+			scissor_box,
+			color_mask,
+			polygon_mode: polygon_mode[0],
+			polygon_offset: [
+				glcore.glIsEnabled(GL_POLYGON_OFFSET_FILL)? != 0,
+				glcore.glIsEnabled(GL_POLYGON_OFFSET_LINE)? != 0,
+				glcore.glIsEnabled(GL_POLYGON_OFFSET_POINT)? != 0,
+			],
+			polygon_offset_values,
+			line_width,
+			program_point_size: glcore.glIsEnabled(GL_PROGRAM_POINT_SIZE)? != 0,
+			stencil_test: glcore.glIsEnabled(GL_STENCIL_TEST)? != 0,
+			stencil_faces,
+		})
+	}
+
+	/// Restore the states of the snapshot now, the guard keeps the snapshot to restore again when it's dropped
+	pub fn reset(&self) -> Result<(), GLCoreError> {
+		let glcore = self.glcore;
+		set_enabled(glcore, GL_DEPTH_TEST, self.depth_test)?;
+		glcore.glDepthMask(self.depth_write)?;
+		glcore.glDepthFunc(self.depth_func as u32)?;
+		set_enabled(glcore, GL_BLEND, self.blend)?;
+		let [src_rgb, dst_rgb, src_alpha, dst_alpha] = self.blend_factors;
+		glcore.glBlendFuncSeparate(src_rgb as u32, dst_rgb as u32, src_alpha as u32, dst_alpha as u32)?;
+		glcore.glBlendEquationSeparate(self.blend_equations[0] as u32, self.blend_equations[1] as u32)?;
+		set_enabled(glcore, GL_CULL_FACE, self.cull_face)?;
+		glcore.glCullFace(self.cull_face_mode as u32)?;
+		glcore.glFrontFace(self.front_face as u32)?;
+		set_enabled(glcore, GL_SCISSOR_TEST, self.scissor_test)?;
+		let [x, y, width, height] = self.scissor_box;
+		glcore.glScissor(x, y, width, height)?;
+		let [r, g, b, a] = self.color_mask;
+		glcore.glColorMask(r, g, b, a)?;
+		glcore.glPolygonMode(GL_FRONT_AND_BACK, self.polygon_mode as u32)?;
+		for (cap, enabled) in [GL_POLYGON_OFFSET_FILL, GL_POLYGON_OFFSET_LINE, GL_POLYGON_OFFSET_POINT].into_iter().zip(self.polygon_offset) {
+			set_enabled(glcore, cap, enabled)?;
+		}
+		let [factor, units] = self.polygon_offset_values;
+		glcore.glPolygonOffset(factor, units)?;
+		glcore.glLineWidth(self.line_width)?;
+		set_enabled(glcore, GL_PROGRAM_POINT_SIZE, self.program_point_size)?;
+		set_enabled(glcore, GL_STENCIL_TEST, self.stencil_test)?;
+		for (face, values) in [GL_FRONT, GL_BACK].into_iter().zip(self.stencil_faces) {
+			let [func, reference, read_mask, fail, depth_fail, pass, write_mask] = values;
+			glcore.glStencilFuncSeparate(face, func as u32, reference, read_mask as u32)?;
+			glcore.glStencilOpSeparate(face, fail as u32, depth_fail as u32, pass as u32)?;
+			glcore.glStencilMaskSeparate(face, write_mask as u32)?;
+		}
+		Ok(())
+	}
+
+	/// Restore the states of the snapshot
+	pub fn restore(self) {} // Restore by owning it in the function and `drop()`
+}
+
+impl Drop for StateGuard<'_> {
+	fn drop(&mut self) {
+		self.reset().unwrap();
+	}
+}
+
+impl Debug for StateGuard<'_> {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.debug_struct("StateGuard")
+		.field("depth_test", &self.depth_test)
+		.field("depth_write", &self.depth_write)
+		.field("depth_func", &self.depth_func)
+		.field("blend", &self.blend)
+		.field("blend_factors", &self.blend_factors)
+		.field("blend_equations", &self.blend_equations)
+		.field("cull_face", &self.cull_face)
+		.field("cull_face_mode", &self.cull_face_mode)
+		.field("front_face", &self.front_face)
+		.field("scissor_test", &self.scissor_test)
+		.field("scissor_box", &self.scissor_box)
+		.field("color_mask", &self.color_mask)
+		.field("polygon_mode", &self.polygon_mode)
+		.field("polygon_offset", &self.polygon_offset)
+		.field("polygon_offset_values", &self.polygon_offset_values)
+		.field("line_width", &self.line_width)
+		.field("program_point_size", &self.program_point_size)
+		.field("stencil_test", &self.stencil_test)
+		.field("stencil_faces", &self.stencil_faces)
+		.finish()
+	}
+}
+
+impl From<GLCoreError> for ViewportError {
+	fn from(val: GLCoreError) -> Self {
+		Self::GLCoreError(val)
+	}
+}
+
+impl IndexedViewport {
+	/// Create a viewport of the rectangle with the default depth range and without the scissor
+	pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+		Self {
+			rect: [x, y, width, height],
+			depth_range: [0.0, 1.0],
+			scissor: None,
+		}
+	}
+
+	/// Enable the scissor test of the viewport with the rectangle
+	pub fn with_scissor(mut self, x: i32, y: i32, width: u32, height: u32) -> Self {
+		self.scissor = Some((x, y, width, height));
+		self
+	}
+
+	/// Set the depth range of the viewport
+	pub fn with_depth_range(mut self, near: f64, far: f64) -> Self {
+		self.depth_range = [near, far];
+		self
+	}
+}
+
+impl Default for IndexedViewport {
+	fn default() -> Self {
+		Self::new(0.0, 0.0, 0.0, 0.0)
+	}
+}
+
+impl ViewportArray {
+	/// Create an empty viewport array
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the viewport of the index, the array grows with the empty viewports if the index is out of it
+	pub fn set(&mut self, index: usize, viewport: IndexedViewport) {
+		if index >= self.viewports.len() {
+			self.viewports.resize(index + 1, IndexedViewport::default());
+		}
+		self.viewports[index] = viewport;
+	}
+
+	/// Get the viewport of the index
+	pub fn get(&self, index: usize) -> Option<&IndexedViewport> {
+		self.viewports.get(index)
+	}
+
+	/// Get all of the viewports
+	pub fn get_viewports(&self) -> &[IndexedViewport] {
+		&self.viewports
+	}
+
+	/// Get the number of the viewports
+	pub fn len(&self) -> usize {
+		self.viewports.len()
+	}
+
+	/// Check if there are no viewports
+	pub fn is_empty(&self) -> bool {
+		self.viewports.is_empty()
+	}
+
+	/// Get `GL_MAX_VIEWPORTS`, fails with `ViewportError::NotSupported` below OpenGL 4.1 without `GL_ARB_viewport_array`
+	pub fn get_max_viewports(glcore: &GLCore) -> Result<usize, ViewportError> {
+		if !has_viewport_array(glcore)? {
+			return Err(ViewportError::NotSupported);
+		}
+		let mut max_viewports: i32 = 0;
+		glcore.glGetIntegerv(GL_MAX_VIEWPORTS, &mut max_viewports as *mut _)?;
+		Ok(max_viewports as usize)
+	}
+
+	/// Check that the context supports the viewport arrays and the number of the viewports
+	pub fn validate(&self, glcore: &GLCore) -> Result<(), ViewportError> {
+		let max_viewports = Self::get_max_viewports(glcore)?;
+		if self.viewports.len() > max_viewports {
+			return Err(ViewportError::TooManyViewports(self.viewports.len(), max_viewports));
+		}
+		Ok(())
+	}
+
+	/// Set the viewports to OpenGL after validating them, the returned guard restores the viewports of the indices when it's dropped
+	pub fn apply<'a>(&self, glcore: &'a GLCore) -> Result<ViewportArrayGuard<'a>, ViewportError> {
+		self.validate(glcore)?;
+		let guard = ViewportArrayGuard::new(glcore, self.viewports.len())?;
+		for (index, viewport) in self.viewports.iter().enumerate() {
+			let index = index as u32;
+			let [x, y, width, height] = viewport.rect;
+			glcore.glViewportIndexedf(index, x, y, width, height)?;
+			glcore.glDepthRangeIndexed(index, viewport.depth_range[0], viewport.depth_range[1])?;
+			match viewport.scissor {
+				Some((x, y, width, height)) => {
+					glcore.glEnablei(GL_SCISSOR_TEST, index)?;
+					glcore.glScissorIndexed(index, x, y, width as i32, height as i32)?;
+				}
+				None => glcore.glDisablei(GL_SCISSOR_TEST, index)?,
+			}
+		}
+		Ok(guard)
+	}
+}
+
+impl From<Vec<IndexedViewport>> for ViewportArray {
+	fn from(viewports: Vec<IndexedViewport>) -> Self {
+		Self {
+			viewports,
+		}
+	}
+}
+
+impl<'a> ViewportArrayGuard<'a> {
+	/// Take the snapshot of the first `count` viewports
+	fn new(glcore: &'a GLCore, count: usize) -> Result<Self, GLCoreError> {
+		let mut viewports = Vec::with_capacity(count);
+		for index in 0..count as u32 {
+			let mut rect = [0f32; 4];
+			let mut depth_range = [0f64; 2];
+			let mut scissor_box = [0i32; 4];
+			glcore.glGetFloati_v(GL_VIEWPORT, index, rect.as_mut_ptr())?;
+			glcore.glGetDoublei_v(GL_DEPTH_RANGE, index, depth_range.as_mut_ptr())?;
+			glcore.glGetIntegeri_v(GL_SCISSOR_BOX, index, scissor_box.as_mut_ptr())?;
+			let scissor_test = glcore.glIsEnabledi(GL_SCISSOR_TEST, index)? != 0;
+			viewports.push((rect, depth_range, scissor_test, scissor_box));
+		}
+		Ok(Self {
+			glcore,
+			viewports,
+		})
+	}
+
+	/// Restore the viewports of the snapshot
+	pub fn restore(self) {} // Restore by owning it in the function and `drop()`
+}
+
+impl Drop for ViewportArrayGuard<'_> {
+	fn drop(&mut self) {
+		let glcore = self.glcore;
+		for (index, (rect, depth_range, scissor_test, scissor_box)) in self.viewports.iter().enumerate() {
+			let index = index as u32;
+			let [x, y, width, height] = *rect;
+			glcore.glViewportIndexedf(index, x, y, width, height).unwrap();
+			glcore.glDepthRangeIndexed(index, depth_range[0], depth_range[1]).unwrap();
+			let [x, y, width, height] = *scissor_box;
+			glcore.glScissorIndexed(index, x, y, width, height).unwrap();
+			if *scissor_test {
+				glcore.glEnablei(GL_SCISSOR_TEST, index).unwrap();
+			} else {
+				glcore.glDisablei(GL_SCISSOR_TEST, index).unwrap();
+			}
+		}
+	}
+}
+
+impl Debug for ViewportArrayGuard<'_> {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.debug_struct("ViewportArrayGuard")
+		.field("viewports", &self.viewports)
+		.finish()
+	}
+}
+
+/// Are the viewport arrays available, they are the core of OpenGL 4.1 or by `GL_ARB_viewport_array`
+fn has_viewport_array(glcore: &GLCore) -> Result<bool, GLCoreError> {
+	let mut major: i32 = 0;
+	let mut minor: i32 = 0;
+	glcore.glGetIntegerv(GL_MAJOR_VERSION, &mut major as *mut _)?;
+	glcore.glGetIntegerv(GL_MINOR_VERSION, &mut minor as *mut _)?;
+	if (major, minor) >= (4, 1) {
+		return Ok(true);
+	}
+	has_gl_extension(glcore, "GL_ARB_viewport_array")
+}
+
+impl Debug for BlendFactor {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Zero => write!(f, "Zero"),
+			Self::One => write!(f, "One"),
+			Self::SrcColor => write!(f, "SrcColor"),
+			Self::OneMinusSrcColor => write!(f, "OneMinusSrcColor"),
+			Self::DstColor => write!(f, "DstColor"),
+			Self::OneMinusDstColor => write!(f, "OneMinusDstColor"),
+			Self::SrcAlpha => write!(f, "SrcAlpha"),
+			Self::OneMinusSrcAlpha => write!(f, "OneMinusSrcAlpha"),
+			Self::DstAlpha => write!(f, "DstAlpha"),
+			Self::OneMinusDstAlpha => write!(f, "OneMinusDstAlpha"),
+			Self::ConstantColor => write!(f, "ConstantColor"),
+			Self::OneMinusConstantColor => write!(f, "OneMinusConstantColor"),
+			Self::ConstantAlpha => write!(f, "ConstantAlpha"),
+			Self::OneMinusConstantAlpha => write!(f, "OneMinusConstantAlpha"),
+			Self::SrcAlphaSaturate => write!(f, "SrcAlphaSaturate"),
+		}
+	}
+}
+
+impl Debug for BlendEquation {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Add => write!(f, "Add"),
+			Self::Subtract => write!(f, "Subtract"),
+			Self::ReverseSubtract => write!(f, "ReverseSubtract"),
+			Self::Min => write!(f, "Min"),
+			Self::Max => write!(f, "Max"),
+		}
+	}
+}
+
+impl Debug for CullFace {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Front => write!(f, "Front"),
+			Self::Back => write!(f, "Back"),
+			Self::FrontAndBack => write!(f, "FrontAndBack"),
+		}
+	}
+}
+
+impl Debug for FrontFace {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Ccw => write!(f, "Ccw"),
+			Self::Cw => write!(f, "Cw"),
+		}
+	}
+}
+
+impl Debug for PolygonMode {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Fill => write!(f, "Fill"),
+			Self::Line => write!(f, "Line"),
+			Self::Point => write!(f, "Point"),
+		}
+	}
+}
+
+impl Debug for StencilOp {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Keep => write!(f, "Keep"),
+			Self::Zero => write!(f, "Zero"),
+			Self::Replace => write!(f, "Replace"),
+			Self::Incr => write!(f, "Incr"),
+			Self::IncrWrap => write!(f, "IncrWrap"),
+			Self::Decr => write!(f, "Decr"),
+			Self::DecrWrap => write!(f, "DecrWrap"),
+			Self::Invert => write!(f, "Invert"),
+		}
+	}
+}
@@ -0,0 +1,60 @@
+use crate::prelude::*;
+use std::{
+	fmt::{self, Debug, Formatter},
+	rc::Rc,
+};
+
+/// The OpenGL fence sync object, signaled when the GPU finished all of the commands issued before the fence
+pub struct Fence {
+	pub glcore: Rc<GLCore>,
+	sync: GLsync,
+}
+
+impl Fence {
+	/// Insert a new fence into the command stream
+	pub fn new(glcore: Rc<GLCore>) -> Result<Self, GLCoreError> {
+		let sync = glcore.glFenceSync(GL_SYNC_GPU_COMMANDS_COMPLETE, 0)?;
+		Ok(Self {
+			glcore,
+			sync,
+		})
+	}
+
+	/// Get the internal sync object
+	pub fn get_sync(&self) -> GLsync {
+		self.sync
+	}
+
+	/// Check if the fence is signaled without blocking
+	pub fn is_signaled(&self) -> Result<bool, GLCoreError> {
+		self.wait(0)
+	}
+
+	/// Wait for the fence for at most `timeout` nanoseconds, returns `false` if timed out
+	pub fn wait(&self, timeout: u64) -> Result<bool, GLCoreError> {
+		match self.glcore.glClientWaitSync(self.sync, GL_SYNC_FLUSH_COMMANDS_BIT, timeout)? {
+			GL_ALREADY_SIGNALED | GL_CONDITION_SATISFIED => Ok(true),
+			_ => Ok(false),
+		}
+	}
+
+	/// Block until the fence is signaled
+	pub fn wait_forever(&self) -> Result<(), GLCoreError> {
+		while !self.wait(1_000_000_000)? {}
+		Ok(())
+	}
+}
+
+impl Drop for Fence {
+	fn drop(&mut self) {
+		self.glcore.glDeleteSync(self.sync).unwrap();
+	}
+}
+
+impl Debug for Fence {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.debug_struct("Fence")
+		.field("sync", &self.sync)
+		.finish()
+	}
+}
@@ -5,15 +5,17 @@
 use crate::prelude::*;
 use std::{
 	any::type_name,
+	cell::Cell,
 	ffi::{OsStr, c_void},
-	fmt::{self, Debug, Formatter},
+	fmt::{self, Debug, Display, Formatter},
 	marker::PhantomData,
 	mem::size_of_val,
-	path::Path,
+	path::{Path, PathBuf},
 	ptr::null,
 	rc::Rc,
 };
-use image::{ImageReader, Pixel, ImageBuffer, RgbImage, DynamicImage};
+use bitvec::vec::BitVec;
+use image::{ImageFormat, Pixel, ImageBuffer, Rgb, Rgba, RgbImage, DynamicImage, ColorType};
 
 /// The dimension of the texture represents the type of texture
 #[derive(Clone, Copy, PartialEq)]
@@ -22,6 +24,9 @@ pub enum TextureDimension {
 	Tex2d = GL_TEXTURE_2D as isize,
 	Tex3d = GL_TEXTURE_3D as isize,
 	TexCube = GL_TEXTURE_CUBE_MAP as isize,
+
+	/// **NOTE**: The multisample texture has no mipmaps and no sampler parameters, it's only used as a render target
+	Tex2dMultisample = GL_TEXTURE_2D_MULTISAMPLE as isize,
 }
 
 /// The binding target of the texture includes the 6 faces of a cubemap
@@ -37,6 +42,7 @@ pub enum TextureTarget {
 	TexCubeNegY = GL_TEXTURE_CUBE_MAP_NEGATIVE_Y as isize,
 	TexCubePosZ = GL_TEXTURE_CUBE_MAP_POSITIVE_Z as isize,
 	TexCubeNegZ = GL_TEXTURE_CUBE_MAP_NEGATIVE_Z as isize,
+	Tex2dMultisample = GL_TEXTURE_2D_MULTISAMPLE as isize,
 }
 
 /// The cubemap faces enum
@@ -50,6 +56,9 @@ pub enum CubeMapFaces {
 	TexCubeNegZ = TextureTarget::TexCubeNegZ as isize,
 }
 
+/// The default `GL_TEXTURE_MAX_LEVEL` of the textures, `set_mip_range()` accepts it to sample all of the levels
+pub const DEFAULT_MAX_MIP_LEVEL: u32 = 1000;
+
 /// The constant helps to conveniently iterate through the 6 faces of a cubemap
 pub const CUBE_FACE_TARGETS: [CubeMapFaces; 6] = [
 	CubeMapFaces::TexCubePosX,
@@ -60,11 +69,26 @@ pub const CUBE_FACE_TARGETS: [CubeMapFaces; 6] = [
 	CubeMapFaces::TexCubeNegZ,
 ];
 
+/// The S3TC formats come from `EXT_texture_compression_s3tc` and `EXT_texture_sRGB`, they are not in the core profile but are supported by almost every desktop GPU
+const GL_COMPRESSED_RGB_S3TC_DXT1_EXT: u32 = 0x83F0;
+const GL_COMPRESSED_RGBA_S3TC_DXT1_EXT: u32 = 0x83F1;
+const GL_COMPRESSED_RGBA_S3TC_DXT3_EXT: u32 = 0x83F2;
+const GL_COMPRESSED_RGBA_S3TC_DXT5_EXT: u32 = 0x83F3;
+const GL_COMPRESSED_SRGB_S3TC_DXT1_EXT: u32 = 0x8C4C;
+const GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT: u32 = 0x8C4D;
+const GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT: u32 = 0x8C4E;
+const GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT: u32 = 0x8C4F;
+
 /// The **internal format** of the texture indicates how the pixels are stored in the GPU texture
 #[derive(Clone, Copy, PartialEq)]
 pub enum TextureFormat {
 	Depth = GL_DEPTH_COMPONENT as isize,
 	DepthStencil = GL_DEPTH_STENCIL as isize,
+	DepthComponent16 = GL_DEPTH_COMPONENT16 as isize,
+	DepthComponent24 = GL_DEPTH_COMPONENT24 as isize,
+	DepthComponent32F = GL_DEPTH_COMPONENT32F as isize,
+	Depth24Stencil8 = GL_DEPTH24_STENCIL8 as isize,
+	Depth32FStencil8 = GL_DEPTH32F_STENCIL8 as isize,
 	Red = GL_RED as isize,
 	Rg = GL_RG as isize,
 	Rgb = GL_RGB as isize,
@@ -84,16 +108,20 @@ pub enum TextureFormat {
 	Rgb8Snorm = GL_RGB8_SNORM as isize,
 	Rgb10 = GL_RGB10 as isize,
 	Rgb12 = GL_RGB12 as isize,
+	Rgb16 = GL_RGB16 as isize,
 	Rgb16Snorm = GL_RGB16_SNORM as isize,
 	Rgba2 = GL_RGBA2 as isize,
 	Rgba4 = GL_RGBA4 as isize,
 	Rgb5a1 = GL_RGB5_A1 as isize,
 	Rgba8 = GL_RGBA8 as isize,
 	Rgba8Snorm = GL_RGBA8_SNORM as isize,
+	Srgb8 = GL_SRGB8 as isize,
+	Srgb8Alpha8 = GL_SRGB8_ALPHA8 as isize,
 	Rgb10a2 = GL_RGB10_A2 as isize,
 	Rgb10a2ui = GL_RGB10_A2UI as isize,
 	Rgba12 = GL_RGBA12 as isize,
 	Rgba16 = GL_RGBA16 as isize,
+	Rgba16Snorm = GL_RGBA16_SNORM as isize,
 	R32f = GL_R32F as isize,
 	Rg32f = GL_RG32F as isize,
 	Rgb32f = GL_RGB32F as isize,
@@ -124,6 +152,50 @@ pub enum TextureFormat {
 	Rgba16ui = GL_RGBA16UI as isize,
 	Rgba32i = GL_RGBA32I as isize,
 	Rgba32ui = GL_RGBA32UI as isize,
+
+	/// BC1 (DXT1) without alpha
+	Bc1Rgb = GL_COMPRESSED_RGB_S3TC_DXT1_EXT as isize,
+
+	/// BC1 (DXT1) with 1-bit alpha
+	Bc1Rgba = GL_COMPRESSED_RGBA_S3TC_DXT1_EXT as isize,
+
+	/// BC2 (DXT3)
+	Bc2 = GL_COMPRESSED_RGBA_S3TC_DXT3_EXT as isize,
+
+	/// BC3 (DXT5)
+	Bc3 = GL_COMPRESSED_RGBA_S3TC_DXT5_EXT as isize,
+
+	/// BC4 (RGTC1)
+	Bc4 = GL_COMPRESSED_RED_RGTC1 as isize,
+	Bc4Snorm = GL_COMPRESSED_SIGNED_RED_RGTC1 as isize,
+
+	/// BC5 (RGTC2)
+	Bc5 = GL_COMPRESSED_RG_RGTC2 as isize,
+	Bc5Snorm = GL_COMPRESSED_SIGNED_RG_RGTC2 as isize,
+
+	/// BC6H (BPTC float)
+	Bc6hUfloat = GL_COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT as isize,
+	Bc6hSfloat = GL_COMPRESSED_RGB_BPTC_SIGNED_FLOAT as isize,
+
+	/// BC7 (BPTC)
+	Bc7 = GL_COMPRESSED_RGBA_BPTC_UNORM as isize,
+
+	/// The sRGB versions of the block compressed formats
+	Bc1Srgb = GL_COMPRESSED_SRGB_S3TC_DXT1_EXT as isize,
+	Bc1SrgbAlpha = GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT as isize,
+	Bc2Srgb = GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT as isize,
+	Bc3Srgb = GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT as isize,
+	Bc7Srgb = GL_COMPRESSED_SRGB_ALPHA_BPTC_UNORM as isize,
+}
+
+/// The sized depth formats for the depth textures, e.g. the shadow maps
+#[derive(Clone, Copy, PartialEq)]
+pub enum DepthFormat {
+	DepthComponent16 = GL_DEPTH_COMPONENT16 as isize,
+	DepthComponent24 = GL_DEPTH_COMPONENT24 as isize,
+	DepthComponent32F = GL_DEPTH_COMPONENT32F as isize,
+	Depth24Stencil8 = GL_DEPTH24_STENCIL8 as isize,
+	Depth32FStencil8 = GL_DEPTH32F_STENCIL8 as isize,
 }
 
 /// The wrapping rules of the textures
@@ -158,6 +230,41 @@ pub enum SamplerMagFilter {
 	Linear = GL_LINEAR as isize,
 }
 
+/// What to do with the 1- and 2-channel images when creating the textures from them
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum GrayscalePolicy {
+	/// Keep the channels as R/RG, the luminance samples as red and the alpha samples as green
+	#[default]
+	KeepRed,
+
+	/// Expand the pixels to RGB/RGBA on the CPU before uploading, costs more memory
+	ExpandToRgb,
+
+	/// Allocate R/RG and apply the RGBA swizzle, so the texture samples as `(L, L, L, 1)` or `(L, L, L, A)`
+	SwizzleLuminance,
+}
+
+/// The access of the shader to the image bound by `glBindImageTexture()`
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImageAccess {
+	ReadOnly = GL_READ_ONLY as isize,
+	WriteOnly = GL_WRITE_ONLY as isize,
+	ReadWrite = GL_READ_WRITE as isize,
+}
+
+/// The compare function for the depth textures used by the shadow samplers, and for the depth test of `RenderState`
+#[derive(Clone, Copy, PartialEq)]
+pub enum CompareFunc {
+	Never = GL_NEVER as isize,
+	Less = GL_LESS as isize,
+	Equal = GL_EQUAL as isize,
+	LEqual = GL_LEQUAL as isize,
+	Greater = GL_GREATER as isize,
+	NotEqual = GL_NOTEQUAL as isize,
+	GEqual = GL_GEQUAL as isize,
+	Always = GL_ALWAYS as isize,
+}
+
 /// The channel type of a pixel
 #[derive(Clone, Copy, PartialEq)]
 pub enum ChannelType {
@@ -201,6 +308,12 @@ pub enum ComponentType {
 	U32_8888Rev = GL_UNSIGNED_INT_8_8_8_8_REV as isize,
 	U32_10_10_10_2 = GL_UNSIGNED_INT_10_10_10_2 as isize,
 	U32_2_10_10_10Rev = GL_UNSIGNED_INT_2_10_10_10_REV as isize,
+
+	/// The 24-bit depth and the 8-bit stencil packed in 32 bits, for the `DepthStencil` channel type
+	U32_24_8 = GL_UNSIGNED_INT_24_8 as isize,
+
+	/// The 32-bit float depth and the 8-bit stencil packed in 64 bits, for the `DepthStencil` channel type
+	F32_24_8Rev = GL_FLOAT_32_UNSIGNED_INT_24_8_REV as isize,
 }
 
 /// Get the size for each pixel
@@ -217,7 +330,9 @@ pub fn size_of_pixel(channel_type: ChannelType, component_type: ComponentType) -
 		ComponentType::U32_8888 |
 		ComponentType::U32_8888Rev |
 		ComponentType::U32_10_10_10_2 |
-		ComponentType::U32_2_10_10_10Rev => return 4,
+		ComponentType::U32_2_10_10_10Rev |
+		ComponentType::U32_24_8 => return 4,
+		ComponentType::F32_24_8Rev => return 8,
 		ComponentType::U8 |
 		ComponentType::I8 => 1,
 		ComponentType::U16 |
@@ -277,16 +392,71 @@ where
 	name: u32,
 	dim: TextureDimension,
 	format: TextureFormat,
+	width: Cell<u32>,
+	height: Cell<u32>,
+	depth: Cell<u32>,
+	samples: u32,
+	fixed_sample_locations: bool,
+	has_mipmap: Cell<bool>,
+	mag_filter: SamplerMagFilter,
+	min_filter: SamplerFilter,
+	base_level: u32,
+	max_level: u32,
+	min_lod: f32,
+	max_lod: f32,
+	lod_bias: f32,
+	compare_func: Option<CompareFunc>,
+	immutable_levels: Option<u32>,
+	bytes_of_texture: Cell<usize>,
+	bytes_of_face: Cell<usize>,
+	pixel_buffer: Option<PixelBuffer<B, BP>>,
+	streaming_buffers: Vec<(PixelBuffer<B, BP>, Option<Fence>)>,
+	streaming_index: usize,
+	grayscale_policy: GrayscalePolicy,
+	tracked_bytes: Cell<usize>,
+	_pixel_type: PhantomData<BP>,
+}
+
+/// The estimated GPU memory of a texture, the padding and the alignment of the driver are ignored
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureMemoryReport {
+	/// The storage of all of the mip levels, the cube map faces and the samples
+	pub texture_bytes: usize,
+
+	/// The PBO and the streaming PBOs
+	pub pixel_buffer_bytes: usize,
+}
+
+/// The pending asynchronous readback of a texture, owns the PBO the pixels are packed into and the fence to know when the packing is done
+#[derive(Debug)]
+pub struct ReadbackHandle {
+	buffer: Buffer,
+	fence: Fence,
+}
+
+/// The builder of the texture, use `Texture::builder_1d()`, `Texture::builder_2d()`, `Texture::builder_3d()` or `Texture::builder_cube()` to create one
+pub struct TextureBuilder<'a, B, BP>
+where
+	B: BufferVec<BP>,
+	BP: BufferVecItem {
+	glcore: Rc<GLCore>,
+	dim: TextureDimension,
+	format: TextureFormat,
 	width: u32,
 	height: u32,
 	depth: u32,
+	wrapping_s: TextureWrapping,
+	wrapping_t: TextureWrapping,
+	wrapping_r: TextureWrapping,
 	has_mipmap: bool,
 	mag_filter: SamplerMagFilter,
 	min_filter: SamplerFilter,
-	bytes_of_texture: usize,
-	bytes_of_face: usize,
-	pixel_buffer: Option<PixelBuffer<B, BP>>,
-	_pixel_type: PhantomData<BP>,
+	buffering: bool,
+	pixel_type: Option<(ChannelType, ComponentType)>,
+	initial_data: Option<*const c_void>,
+	initial_data_len: Option<usize>,
+	_data: PhantomData<&'a [u8]>,
+	_texture: PhantomData<Texture<B, BP>>,
 }
 
 /// The binding state of the texture, utilizing the RAII rules to manage the binding state
@@ -295,6 +465,48 @@ pub struct TextureBind<'a> {
 	target: TextureTarget,
 }
 
+/// The pixel storage modes set by `glPixelStorei()` for a pixel transfer, utilizing the RAII rules to restore the previous values
+pub(crate) struct PixelStore<'a> {
+	glcore: &'a GLCore,
+	saved: Vec<(u32, i32)>,
+}
+
+/// The binding state of the texture to an image unit for the image load/store operations, utilizing the RAII rules to manage the binding state
+pub struct ImageBind<'a> {
+	pub texture: &'a dyn GenericTexture,
+	unit: u32,
+}
+
+/// The sampler object, overrides the sampling parameters of the texture bound to the same texture unit
+pub struct Sampler {
+	pub glcore: Rc<GLCore>,
+	name: u32,
+	wrapping_s: TextureWrapping,
+	wrapping_t: TextureWrapping,
+	wrapping_r: TextureWrapping,
+	mag_filter: SamplerMagFilter,
+	min_filter: SamplerFilter,
+	max_anisotropy: f32,
+	min_lod: f32,
+	max_lod: f32,
+	lod_bias: f32,
+	compare_func: Option<CompareFunc>,
+}
+
+/// The binding state of the sampler to a texture unit, utilizing the RAII rules to manage the binding state
+pub struct SamplerBind<'a> {
+	pub sampler: &'a Sampler,
+	unit: u32,
+}
+
+/// The texture unit allocator, keeps the fixed units reserved for the engine-level textures and hands out the transient units for the material textures
+#[derive(Debug, Clone)]
+pub struct TextureUnits {
+	max_units: u32,
+	reserved: BitVec,
+	allocated: BitVec,
+}
+
 /// The error for loading an image from a file, decoding the byte stream of the image
 #[derive(Debug)]
 pub enum LoadImageError {
@@ -302,6 +514,36 @@ pub enum LoadImageError {
 	TurboJpegError(turbojpeg::Error),
 	ImageError(image::ImageError),
 	UnsupportedImageType(String),
+
+	/// The faces of the cube map are mismatched or the layout is wrong, the message tells which face differs
+	InvalidCubeMap(String),
+
+	/// The slice (the first one) of the volume mismatches the slice 0, the message (the second one) tells how
+	InvalidVolume(usize, String),
+
+	/// The file isn't a valid DDS file
+	DdsInvalidHeader(String),
+
+	/// The DDS file (the first one) uses a pixel format (the second one) that couldn't be mapped to a `TextureFormat`
+	DdsUnknownFormat(String, String),
+
+	/// The DDS file is shorter than what its header describes
+	DdsTruncated(String),
+
+	/// The file isn't a valid KTX2 file
+	Ktx2InvalidHeader(String),
+
+	/// The KTX2 file (the first one) uses a `VkFormat` (the second one) that couldn't be mapped to a `TextureFormat`
+	Ktx2UnknownFormat(String, u32),
+
+	/// The KTX2 file is shorter than what its header describes
+	Ktx2Truncated(String),
+
+	/// The KTX2 file (the first one) stores a Basis Universal payload (the second one), transcoding unsupported
+	Ktx2TranscodingUnsupported(String, String),
+
+	/// The KTX2 file (the first one) uses a supercompression scheme (the second one) that couldn't be decoded
+	Ktx2UnsupportedSupercompression(String, String),
 }
 
 impl From<std::io::Error> for LoadImageError {
@@ -326,6 +568,48 @@ impl From<image::ImageError> for LoadImageError {
 pub enum TextureError {
 	LoadImageError(LoadImageError),
 	GLCoreError(GLCoreError),
+
+	/// The requested sample count (the first one) exceeds `GL_MAX_SAMPLES` (the second one), or is zero
+	InvalidSampleCount(u32, u32),
+
+	/// The operation couldn't be done on this type of texture
+	UnsupportedOperation(String),
+
+	/// The mip level or the level count (the first one) exceeds the number of levels of the texture (the second one)
+	InvalidMipLevel(u32, u32),
+
+	/// The base mip level (the first one) is above the max mip level (the second one)
+	InvalidMipRange(u32, u32),
+
+	/// The size (the second one) of the mip level (the first one) isn't the expected size (the third one)
+	InvalidLevelSize(u32, [u32; 3], [u32; 3]),
+
+	/// The immutable storage needs a sized internal format
+	UnsizedFormat(TextureFormat),
+
+	/// The depth compare mode needs a depth format, the format of the texture (the first one) isn't
+	NotDepthFormat(TextureFormat),
+
+	/// The size (the second one) isn't valid for the dimension of the texture (the first one)
+	InvalidSize(TextureDimension, [u32; 3]),
+
+	/// The size of the initial data in bytes (the first one) is smaller than the texture needs (the second one)
+	InitialDataTooSmall(usize, usize),
+
+	/// The size of the pixel buffer in bytes (the first one) is smaller than the mip level needs (the second one)
+	PixelBufferTooSmall(usize, usize),
+
+	/// The internal format (the first one) couldn't accept the pixels of the channel type (the second one) and the component type (the third one)
+	IncompatiblePixelType(TextureFormat, ChannelType, ComponentType),
+
+	/// The texture unit (the first one) exceeds `GL_MAX_COMBINED_TEXTURE_IMAGE_UNITS` (the second one)
+	InvalidTextureUnit(u32, u32),
+
+	/// The texture unit is already reserved or allocated
+	TextureUnitInUse(u32),
+
+	/// The format couldn't be used for the image load/store operations
+	InvalidImageFormat(TextureFormat),
 }
 
 impl From<LoadImageError> for TextureError {
@@ -359,22 +643,262 @@ impl From<image::ImageError> for TextureError {
 	}
 }
 
+impl From<DepthFormat> for TextureFormat {
+	fn from(val: DepthFormat) -> Self {
+		match val {
+			DepthFormat::DepthComponent16 => Self::DepthComponent16,
+			DepthFormat::DepthComponent24 => Self::DepthComponent24,
+			DepthFormat::DepthComponent32F => Self::DepthComponent32F,
+			DepthFormat::Depth24Stencil8 => Self::Depth24Stencil8,
+			DepthFormat::Depth32FStencil8 => Self::Depth32FStencil8,
+		}
+	}
+}
+
+impl TextureDimension {
+	/// Get the binding target of the texture of this dimension, the cube map binds to `GL_TEXTURE_CUBE_MAP`
+	pub fn get_target(&self) -> TextureTarget {
+		match self {
+			Self::Tex1d => TextureTarget::Tex1d,
+			Self::Tex2d => TextureTarget::Tex2d,
+			Self::Tex3d => TextureTarget::Tex3d,
+			Self::TexCube => TextureTarget::TexCube,
+			Self::Tex2dMultisample => TextureTarget::Tex2dMultisample,
+		}
+	}
+}
+
 impl TextureFormat {
-	/// Get how many bits that composed of a pixel. The implementation is just to ask anything from OpenGL
-	pub fn bits_of_pixel(&self, glcore: &GLCore, target: TextureTarget) -> Result<usize, TextureError> {
-		let target = target as u32;
-		let mut data: i32 = 0;
-		let mut size: usize = 0;
-		glcore.glGetTexLevelParameteriv(target, 0, GL_TEXTURE_DEPTH_SIZE, &mut data as *mut _)?;	size += data as usize;
-		glcore.glGetTexLevelParameteriv(target, 0, GL_TEXTURE_STENCIL_SIZE, &mut data as *mut _)?;	size += data as usize;
-		glcore.glGetTexLevelParameteriv(target, 0, GL_TEXTURE_RED_SIZE, &mut data as *mut _)?;		size += data as usize;
-		glcore.glGetTexLevelParameteriv(target, 0, GL_TEXTURE_GREEN_SIZE, &mut data as *mut _)?;	size += data as usize;
-		glcore.glGetTexLevelParameteriv(target, 0, GL_TEXTURE_BLUE_SIZE, &mut data as *mut _)?;		size += data as usize;
-		glcore.glGetTexLevelParameteriv(target, 0, GL_TEXTURE_ALPHA_SIZE, &mut data as *mut _)?;	size += data as usize;
-		Ok(size)
-	}
-
-	/// Create a `TextureFormat` from the channel type and the component type, returns `None` if the combination couldn't have its corresponding format
+	/// Get the size in bytes of each 4x4 block if the format is a block compressed format, returns `None` for the uncompressed formats
+	pub fn compressed_block_bytes(&self) -> Option<usize> {
+		match self {
+			Self::Bc1Rgb |
+			Self::Bc1Rgba |
+			Self::Bc1Srgb |
+			Self::Bc1SrgbAlpha |
+			Self::Bc4 |
+			Self::Bc4Snorm => Some(8),
+			Self::Bc2 |
+			Self::Bc2Srgb |
+			Self::Bc3 |
+			Self::Bc3Srgb |
+			Self::Bc5 |
+			Self::Bc5Snorm |
+			Self::Bc6hUfloat |
+			Self::Bc6hSfloat |
+			Self::Bc7 |
+			Self::Bc7Srgb => Some(16),
+			_ => None,
+		}
+	}
+
+	/// Is this format a block compressed format
+	pub fn is_compressed(&self) -> bool {
+		self.compressed_block_bytes().is_some()
+	}
+
+	/// Is this format a sized format, the unsized formats let the driver choose the size of the components
+	pub fn is_sized(&self) -> bool {
+		!matches!(self, Self::Depth | Self::DepthStencil | Self::Red | Self::Rg | Self::Rgb | Self::Rgba)
+	}
+
+	/// Is this format a depth format, including the depth-stencil formats
+	pub fn is_depth(&self) -> bool {
+		matches!(self, Self::Depth | Self::DepthComponent16 | Self::DepthComponent24 | Self::DepthComponent32F) || self.is_depth_stencil()
+	}
+
+	/// Is this format a depth-stencil format
+	pub fn is_depth_stencil(&self) -> bool {
+		matches!(self, Self::DepthStencil | Self::Depth24Stencil8 | Self::Depth32FStencil8)
+	}
+
+	/// Is this format an sRGB format, the shaders read the linear colors from it and write the colors encoded by `GL_FRAMEBUFFER_SRGB` to it
+	pub fn is_srgb(&self) -> bool {
+		matches!(self, Self::Srgb8 | Self::Srgb8Alpha8 | Self::Bc1Srgb | Self::Bc1SrgbAlpha | Self::Bc2Srgb | Self::Bc3Srgb | Self::Bc7Srgb)
+	}
+
+	/// Is this format an integer format, the integer formats could only be uploaded with the `*Integer` channel types
+	pub fn is_integer(&self) -> bool {
+		matches!(self,
+			Self::R8i | Self::R8ui | Self::R16i | Self::R16ui | Self::R32i | Self::R32ui |
+			Self::Rg8i | Self::Rg8ui | Self::Rg16i | Self::Rg16ui | Self::Rg32i | Self::Rg32ui |
+			Self::Rgb8i | Self::Rgb8ui | Self::Rgb16i | Self::Rgb16ui | Self::Rgb32i | Self::Rgb32ui |
+			Self::Rgba8i | Self::Rgba8ui | Self::Rgba16i | Self::Rgba16ui | Self::Rgba32i | Self::Rgba32ui |
+			Self::Rgb10a2ui
+		)
+	}
+
+	/// Is this format usable by the image load/store operations, see the table of `glBindImageTexture()`
+	pub fn is_image_format(&self) -> bool {
+		matches!(self,
+			Self::Rgba32f | Self::Rg32f | Self::R32f | Self::R11fg11fb10f |
+			Self::Rgba32ui | Self::Rgba16ui | Self::Rgb10a2ui | Self::Rgba8ui | Self::Rg32ui | Self::Rg16ui | Self::Rg8ui | Self::R32ui | Self::R16ui | Self::R8ui |
+			Self::Rgba32i | Self::Rgba16i | Self::Rgba8i | Self::Rg32i | Self::Rg16i | Self::Rg8i | Self::R32i | Self::R16i | Self::R8i |
+			Self::Rgba16 | Self::Rgb10a2 | Self::Rgba8 | Self::Rg16 | Self::Rg8 | Self::Red16 | Self::Red8 |
+			Self::Rgba16Snorm | Self::Rgba8Snorm | Self::Rg16Snorm | Self::Rg8Snorm | Self::Red16Snorm | Self::Red8Snorm
+		)
+	}
+
+	/// Check if the pixels of the channel type and the component type could be uploaded to or downloaded from a texture of this format.
+	/// The integer formats need the `*Integer` channel types, the depth formats need the depth channel types, and the packed component types need the matching channel count.
+	pub fn accepts(&self, channel_type: ChannelType, component_type: ComponentType) -> bool {
+		let channels_valid = match channel_type {
+			ChannelType::Red |
+			ChannelType::Rg |
+			ChannelType::Rgb |
+			ChannelType::Bgr |
+			ChannelType::Rgba |
+			ChannelType::Bgra => !self.is_integer() && !self.is_depth(),
+			ChannelType::RedInteger |
+			ChannelType::RgInteger |
+			ChannelType::RgbInteger |
+			ChannelType::BgrInteger |
+			ChannelType::RgbaInteger |
+			ChannelType::BgraInteger => self.is_integer() && !matches!(component_type, ComponentType::F16 | ComponentType::F32),
+			ChannelType::Depth => self.is_depth(),
+			ChannelType::DepthStencil => self.is_depth_stencil(),
+			ChannelType::StencilIndex => false,
+		};
+		let packed_valid = match component_type {
+			ComponentType::U8_332 |
+			ComponentType::U8_233Rev |
+			ComponentType::U16_565 |
+			ComponentType::U16_565Rev => matches!(channel_type, ChannelType::Rgb | ChannelType::Bgr | ChannelType::RgbInteger | ChannelType::BgrInteger),
+			ComponentType::U16_4444 |
+			ComponentType::U16_4444Rev |
+			ComponentType::U16_5551 |
+			ComponentType::U16_1555Rev |
+			ComponentType::U32_8888 |
+			ComponentType::U32_8888Rev |
+			ComponentType::U32_10_10_10_2 |
+			ComponentType::U32_2_10_10_10Rev => matches!(channel_type, ChannelType::Rgba | ChannelType::Bgra | ChannelType::RgbaInteger | ChannelType::BgraInteger),
+			ComponentType::U32_24_8 |
+			ComponentType::F32_24_8Rev => channel_type == ChannelType::DepthStencil,
+			_ => channel_type != ChannelType::DepthStencil,
+		};
+		channels_valid && packed_valid
+	}
+
+	/// Check the channel type and the component type with `accepts()`, returns a descriptive error if the format couldn't accept them
+	pub fn check_pixel_type(&self, channel_type: ChannelType, component_type: ComponentType) -> Result<(), TextureError> {
+		if self.accepts(channel_type, component_type) {
+			Ok(())
+		} else {
+			Err(TextureError::IncompatiblePixelType(*self, channel_type, component_type))
+		}
+	}
+
+	/// Get a channel type and a component type that the format accepts, used to allocate the texture without uploading any pixels
+	fn allocation_pixel_type(&self) -> (ChannelType, ComponentType) {
+		match self {
+			Self::Depth32FStencil8 => (ChannelType::DepthStencil, ComponentType::F32_24_8Rev),
+			_ if self.is_depth_stencil() => (ChannelType::DepthStencil, ComponentType::U32_24_8),
+			_ if self.is_depth() => (ChannelType::Depth, ComponentType::F32),
+			_ if self.is_integer() => (ChannelType::RgbaInteger, ComponentType::U8),
+			_ => (ChannelType::Rgba, ComponentType::U8),
+		}
+	}
+
+	/// Get how many bits that composed of a pixel, without asking OpenGL, so it's usable before the texture is allocated.
+	///
+	/// The sizes of the unsized formats are approximations, the driver chooses the actual size, e.g. `Depth` is counted as 32 bits and `Rgba` as 32 bits.
+	/// The odd-sized formats, e.g. `Rgb4`, `Rgb5`, `Rgb12`, are counted by the sum of their components, the driver may pad them.
+	/// The packed formats `R11fg11fb10f` and `Rgb9e5` are 32 bits, the components share the bits of a 32-bit word.
+	/// The `Depth32FStencil8` is 64 bits as it's packed by `F32_24_8Rev`, 24 of the bits are unused.
+	/// The block compressed formats are counted by the average bits of the pixels of a 4x4 block.
+	pub fn bits_of_pixel(&self) -> usize {
+		match self {
+			Self::Depth => 32,
+			Self::DepthStencil => 32,
+			Self::DepthComponent16 => 16,
+			Self::DepthComponent24 => 24,
+			Self::DepthComponent32F => 32,
+			Self::Depth24Stencil8 => 32,
+			Self::Depth32FStencil8 => 64,
+			Self::Red => 8,
+			Self::Rg => 16,
+			Self::Rgb => 24,
+			Self::Rgba => 32,
+			Self::Red8 => 8,
+			Self::Red8Snorm => 8,
+			Self::Red16 => 16,
+			Self::Red16Snorm => 16,
+			Self::Rg8 => 16,
+			Self::Rg8Snorm => 16,
+			Self::Rg16 => 32,
+			Self::Rg16Snorm => 32,
+			Self::R3g3b2 => 8,
+			Self::Rgb4 => 12,
+			Self::Rgb5 => 15,
+			Self::Rgb8 => 24,
+			Self::Rgb8Snorm => 24,
+			Self::Rgb10 => 30,
+			Self::Rgb12 => 36,
+			Self::Rgb16 => 48,
+			Self::Rgb16Snorm => 48,
+			Self::Rgba2 => 8,
+			Self::Rgba4 => 16,
+			Self::Rgb5a1 => 16,
+			Self::Rgba8 => 32,
+			Self::Rgba8Snorm => 32,
+			Self::Srgb8 => 24,
+			Self::Srgb8Alpha8 => 32,
+			Self::Rgb10a2 => 32,
+			Self::Rgb10a2ui => 32,
+			Self::Rgba12 => 48,
+			Self::Rgba16 => 64,
+			Self::Rgba16Snorm => 64,
+			Self::R32f => 32,
+			Self::Rg32f => 64,
+			Self::Rgb32f => 96,
+			Self::Rgba32f => 128,
+			Self::R11fg11fb10f => 32,
+			Self::Rgb9e5 => 32,
+			Self::R8i => 8,
+			Self::R8ui => 8,
+			Self::R16i => 16,
+			Self::R16ui => 16,
+			Self::R32i => 32,
+			Self::R32ui => 32,
+			Self::Rg8i => 16,
+			Self::Rg8ui => 16,
+			Self::Rg16i => 32,
+			Self::Rg16ui => 32,
+			Self::Rg32i => 64,
+			Self::Rg32ui => 64,
+			Self::Rgb8i => 24,
+			Self::Rgb8ui => 24,
+			Self::Rgb16i => 48,
+			Self::Rgb16ui => 48,
+			Self::Rgb32i => 96,
+			Self::Rgb32ui => 96,
+			Self::Rgba8i => 32,
+			Self::Rgba8ui => 32,
+			Self::Rgba16i => 64,
+			Self::Rgba16ui => 64,
+			Self::Rgba32i => 128,
+			Self::Rgba32ui => 128,
+			Self::Bc1Rgb => 4,
+			Self::Bc1Rgba => 4,
+			Self::Bc2 => 8,
+			Self::Bc3 => 8,
+			Self::Bc4 => 4,
+			Self::Bc4Snorm => 4,
+			Self::Bc5 => 8,
+			Self::Bc5Snorm => 8,
+			Self::Bc6hUfloat => 8,
+			Self::Bc6hSfloat => 8,
+			Self::Bc7 => 8,
+			Self::Bc1Srgb => 4,
+			Self::Bc1SrgbAlpha => 4,
+			Self::Bc2Srgb => 8,
+			Self::Bc3Srgb => 8,
+			Self::Bc7Srgb => 8,
+		}
+	}
+
+	/// Create a `TextureFormat` from the channel type and the component type, returns `None` if the combination couldn't have its corresponding format.
+	/// The plain channel types select the normalized formats, the `*Integer` channel types select the integer formats.
 	pub fn from_channel_type_and_component_type(channel_type: ChannelType, component_type: ComponentType) -> Option<Self> {
 		match component_type {
 			ComponentType::U8_332 => Some(Self::R3g3b2),
@@ -382,58 +906,88 @@ impl TextureFormat {
 			ComponentType::U16_5551 => Some(Self::Rgb5a1),
 			ComponentType::U32_8888 => Some(Self::Rgba8),
 			ComponentType::U32_10_10_10_2 => Some(Self::Rgb10a2),
-			ComponentType::I8 => match channel_type {
-				ChannelType::Red =>  Some(Self::R8i),
-				ChannelType::Rg =>   Some(Self::Rg8i),
-				ChannelType::Rgb =>  Some(Self::Rgb8i),
-				ChannelType::Rgba => Some(Self::Rgba8i),
-				_ => None,
-			}
+			ComponentType::U32_24_8 => Some(Self::Depth24Stencil8),
+			ComponentType::F32_24_8Rev => Some(Self::Depth32FStencil8),
 			ComponentType::U8 => match channel_type {
-				ChannelType::Red =>  Some(Self::R8ui),
-				ChannelType::Rg =>   Some(Self::Rg8ui),
-				ChannelType::Rgb =>  Some(Self::Rgb8ui),
-				ChannelType::Rgba => Some(Self::Rgba8ui),
+				ChannelType::Red => Some(Self::Red8),
+				ChannelType::Rg => Some(Self::Rg8),
+				ChannelType::Rgb | ChannelType::Bgr => Some(Self::Rgb8),
+				ChannelType::Rgba | ChannelType::Bgra => Some(Self::Rgba8),
+				ChannelType::RedInteger => Some(Self::R8ui),
+				ChannelType::RgInteger => Some(Self::Rg8ui),
+				ChannelType::RgbInteger | ChannelType::BgrInteger => Some(Self::Rgb8ui),
+				ChannelType::RgbaInteger | ChannelType::BgraInteger => Some(Self::Rgba8ui),
 				_ => None,
 			}
-			ComponentType::I16 => match channel_type {
-				ChannelType::Red =>  Some(Self::R16i),
-				ChannelType::Rg =>   Some(Self::Rg16i),
-				ChannelType::Rgb =>  Some(Self::Rgb16i),
-				ChannelType::Rgba => Some(Self::Rgba16i),
+			ComponentType::I8 => match channel_type {
+				ChannelType::Red => Some(Self::Red8Snorm),
+				ChannelType::Rg => Some(Self::Rg8Snorm),
+				ChannelType::Rgb | ChannelType::Bgr => Some(Self::Rgb8Snorm),
+				ChannelType::Rgba | ChannelType::Bgra => Some(Self::Rgba8Snorm),
+				ChannelType::RedInteger => Some(Self::R8i),
+				ChannelType::RgInteger => Some(Self::Rg8i),
+				ChannelType::RgbInteger | ChannelType::BgrInteger => Some(Self::Rgb8i),
+				ChannelType::RgbaInteger | ChannelType::BgraInteger => Some(Self::Rgba8i),
 				_ => None,
 			}
 			ComponentType::U16 => match channel_type {
-				ChannelType::Red =>  Some(Self::R16ui),
-				ChannelType::Rg =>   Some(Self::Rg16ui),
-				ChannelType::Rgb =>  Some(Self::Rgb16ui),
-				ChannelType::Rgba => Some(Self::Rgba16ui),
+				ChannelType::Red => Some(Self::Red16),
+				ChannelType::Rg => Some(Self::Rg16),
+				ChannelType::Rgb | ChannelType::Bgr => Some(Self::Rgb16),
+				ChannelType::Rgba | ChannelType::Bgra => Some(Self::Rgba16),
+				ChannelType::RedInteger => Some(Self::R16ui),
+				ChannelType::RgInteger => Some(Self::Rg16ui),
+				ChannelType::RgbInteger | ChannelType::BgrInteger => Some(Self::Rgb16ui),
+				ChannelType::RgbaInteger | ChannelType::BgraInteger => Some(Self::Rgba16ui),
+				ChannelType::Depth => Some(Self::DepthComponent16),
 				_ => None,
 			}
-			ComponentType::I32 => match channel_type {
-				ChannelType::Red =>  Some(Self::R32i),
-				ChannelType::Rg =>   Some(Self::Rg32i),
-				ChannelType::Rgb =>  Some(Self::Rgb32i),
-				ChannelType::Rgba => Some(Self::Rgba32i),
+			ComponentType::I16 => match channel_type {
+				ChannelType::Red => Some(Self::Red16Snorm),
+				ChannelType::Rg => Some(Self::Rg16Snorm),
+				ChannelType::Rgb | ChannelType::Bgr => Some(Self::Rgb16Snorm),
+				ChannelType::Rgba | ChannelType::Bgra => Some(Self::Rgba16Snorm),
+				ChannelType::RedInteger => Some(Self::R16i),
+				ChannelType::RgInteger => Some(Self::Rg16i),
+				ChannelType::RgbInteger | ChannelType::BgrInteger => Some(Self::Rgb16i),
+				ChannelType::RgbaInteger | ChannelType::BgraInteger => Some(Self::Rgba16i),
 				_ => None,
 			}
 			ComponentType::U32 => match channel_type {
-				ChannelType::Red =>  Some(Self::R32ui),
-				ChannelType::Rg =>   Some(Self::Rg32ui),
-				ChannelType::Rgb =>  Some(Self::Rgb32ui),
-				ChannelType::Rgba => Some(Self::Rgba32ui),
+				ChannelType::RedInteger => Some(Self::R32ui),
+				ChannelType::RgInteger => Some(Self::Rg32ui),
+				ChannelType::RgbInteger | ChannelType::BgrInteger => Some(Self::Rgb32ui),
+				ChannelType::RgbaInteger | ChannelType::BgraInteger => Some(Self::Rgba32ui),
+				ChannelType::Depth => Some(Self::DepthComponent24),
+				_ => None,
+			}
+			ComponentType::I32 => match channel_type {
+				ChannelType::RedInteger => Some(Self::R32i),
+				ChannelType::RgInteger => Some(Self::Rg32i),
+				ChannelType::RgbInteger | ChannelType::BgrInteger => Some(Self::Rgb32i),
+				ChannelType::RgbaInteger | ChannelType::BgraInteger => Some(Self::Rgba32i),
 				_ => None,
 			}
 			ComponentType::F32 => match channel_type {
-				ChannelType::Red =>  Some(Self::R32f),
-				ChannelType::Rg =>   Some(Self::Rg32f),
-				ChannelType::Rgb =>  Some(Self::Rgb32f),
-				ChannelType::Rgba => Some(Self::Rgba32f),
+				ChannelType::Red => Some(Self::R32f),
+				ChannelType::Rg => Some(Self::Rg32f),
+				ChannelType::Rgb | ChannelType::Bgr => Some(Self::Rgb32f),
+				ChannelType::Rgba | ChannelType::Bgra => Some(Self::Rgba32f),
+				ChannelType::Depth => Some(Self::DepthComponent32F),
 				_ => None,
 			}
 			_ => None
 		}
 	}
+
+	/// Create a `TextureFormat` like `from_channel_type_and_component_type()`, but 8-bit RGB/RGBA data gets the sRGB formats for color maps
+	pub fn from_channel_type_and_component_type_srgb(channel_type: ChannelType, component_type: ComponentType) -> Option<Self> {
+		match (channel_type, component_type) {
+			(ChannelType::Rgb, ComponentType::U8) | (ChannelType::Bgr, ComponentType::U8) => Some(Self::Srgb8),
+			(ChannelType::Rgba, ComponentType::U8) | (ChannelType::Bgra, ComponentType::U8) => Some(Self::Srgb8Alpha8),
+			_ => Self::from_channel_type_and_component_type(channel_type, component_type),
+		}
+	}
 }
 
 impl ComponentType {
@@ -488,15 +1042,447 @@ pub trait GenericPixelBuffer: Debug {
 	/// Get the component type
 	fn get_component_type(&self) -> ComponentType;
 
+	/// Get the size of a row in bytes, the rows are tightly packed
+	fn get_pitch(&self) -> usize;
+
 	/// Get the size of the buffer
 	fn size_in_bytes(&self) -> usize {
 		self.get_buffer().size()
 	}
 
-	/// Create a `BufferBind` to use the RAII system to manage the binding state
+	/// Create a `BufferBind` to use the RAII system to manage the binding state, the buffer is bound to `GL_PIXEL_UNPACK_BUFFER` for uploading
 	fn bind<'a>(&'a self) -> Result<BufferBind<'a>, TextureError> {
 		Ok(self.get_buffer().bind()?)
 	}
+
+	/// Bind to a specific target, `PixelUnpackBuffer` for uploading to the texture or `PixelPackBuffer` for reading back from the texture
+	fn bind_to<'a>(&'a self, target: BufferTarget) -> Result<BufferBind<'a>, TextureError> {
+		Ok(self.get_buffer().bind_to(target)?)
+	}
+}
+
+/// The format of the encoded image bytes, helps `from_memory()` to choose the decoder without guessing from the magic bytes
+pub type ImageFormatHint = ImageFormat;
+
+/// Call `$body` with the `ImageBuffer` inside of a `DynamicImage`, the variants that the textures couldn't store are reported with their discriminant
+macro_rules! dispatch_dynamic_image {
+	($img:expr, |$buf:ident| $body:expr) => {
+		match $img {
+			DynamicImage::ImageLuma8($buf) => $body,
+			DynamicImage::ImageLumaA8($buf) => $body,
+			DynamicImage::ImageRgb8($buf) => $body,
+			DynamicImage::ImageRgba8($buf) => $body,
+			DynamicImage::ImageLuma16($buf) => $body,
+			DynamicImage::ImageLumaA16($buf) => $body,
+			DynamicImage::ImageRgb16($buf) => $body,
+			DynamicImage::ImageRgba16($buf) => $body,
+			DynamicImage::ImageRgb32F($buf) => $body,
+			DynamicImage::ImageRgba32F($buf) => $body,
+			other => Err(LoadImageError::UnsupportedImageType(format!("Unsupported DynamicImage variant {:?} of the color type {:?}", std::mem::discriminant(other), other.color())))?,
+		}
+	};
+}
+
+/// Decode the image from the encoded bytes, the JPEG images are decoded by `turbojpeg`. Without the hint, the format is detected by the magic bytes.
+fn decode_dynamic_image(bytes: &[u8], hint: Option<ImageFormatHint>, source: &str) -> Result<DynamicImage, LoadImageError> {
+	let format = match hint {
+		Some(format) => format,
+		None => image::guess_format(bytes).map_err(|_| LoadImageError::UnsupportedImageType(format!("Unknown image format of {source}")))?,
+	};
+	if format == ImageFormat::Jpeg {
+		let img: RgbImage = turbojpeg::decompress_image(bytes)?;
+		Ok(DynamicImage::ImageRgb8(img))
+	} else {
+		image::load_from_memory_with_format(bytes, format).map_err(|e| LoadImageError::UnsupportedImageType(format!("Could not decode {source} as a {format:?} image: {e}")))
+	}
+}
+
+/// Read the whole file, the I/O error carries the path of the file
+fn read_image_file(path: &Path) -> Result<Vec<u8>, LoadImageError> {
+	std::fs::read(path).map_err(|e| LoadImageError::IOError(std::io::Error::new(e.kind(), format!("Could not read {path:?}: {e}"))))
+}
+
+/// Load an image file, the extension is the hint of the image format
+fn load_dynamic_image(path: &Path) -> Result<DynamicImage, LoadImageError> {
+	let bytes = read_image_file(path)?;
+	decode_dynamic_image(&bytes, ImageFormat::from_path(path).ok(), &format!("{path:?}"))
+}
+
+/// Get the size in bytes of a tightly packed 2D image, `pixel_type` is `None` for the block compressed formats. `None` if the size overflows
+fn bytes_of_image(format: TextureFormat, pixel_type: Option<(ChannelType, ComponentType)>, width: u32, height: u32) -> Option<usize> {
+	match (format.compressed_block_bytes(), pixel_type) {
+		(Some(block_bytes), _) => (width.div_ceil(4) as usize).checked_mul(height.div_ceil(4) as usize)?.checked_mul(block_bytes),
+		(None, Some((channel_type, component_type))) => (width as usize).checked_mul(height as usize)?.checked_mul(size_of_pixel(channel_type, component_type)),
+		(None, None) => Some(0),
+	}
+}
+
+/// The parsed DDS (DirectDraw Surface) file, holds the pixel data of every face and every mip level
+#[derive(Debug, Clone)]
+pub struct DdsImage {
+	/// The internal format for the texture
+	pub format: TextureFormat,
+
+	/// The channel type and the component type of the uncompressed formats, `None` for the block compressed formats
+	pub pixel_type: Option<(ChannelType, ComponentType)>,
+
+	/// The size of the top mip level
+	pub width: u32,
+	pub height: u32,
+
+	/// How many mip levels are stored in the file
+	pub mip_levels: u32,
+
+	/// Is the file a cube map, the faces are in the order of `CUBE_FACE_TARGETS`
+	pub is_cube_map: bool,
+
+	/// The color channels were pre-multiplied by the alpha channel (DXT2, DXT4, or the DX10 header says so)
+	pub premultiplied_alpha: bool,
+
+	/// The data of each face, each face contains the data of each mip level
+	pub faces: Vec<Vec<Vec<u8>>>,
+}
+
+impl DdsImage {
+	const MAGIC: u32 = 0x20534444;
+	const HEADER_SIZE: u32 = 124;
+	const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+	const DDPF_ALPHAPIXELS: u32 = 0x1;
+	const DDPF_FOURCC: u32 = 0x4;
+	const DDPF_RGB: u32 = 0x40;
+	const DDSCAPS2_CUBEMAP: u32 = 0x200;
+	const DDSCAPS2_VOLUME: u32 = 0x200000;
+	const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+	const DDS_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+	const DDS_ALPHA_MODE_PREMULTIPLIED: u32 = 2;
+
+	const fn fourcc(code: &[u8; 4]) -> u32 {
+		u32::from_le_bytes(*code)
+	}
+
+	/// Map the legacy FourCC code to the texture format, returns the format and whether the alpha is pre-multiplied
+	fn format_from_fourcc(fourcc: u32) -> Option<(TextureFormat, Option<(ChannelType, ComponentType)>, bool)> {
+		const DXT1: u32 = DdsImage::fourcc(b"DXT1");
+		const DXT2: u32 = DdsImage::fourcc(b"DXT2");
+		const DXT3: u32 = DdsImage::fourcc(b"DXT3");
+		const DXT4: u32 = DdsImage::fourcc(b"DXT4");
+		const DXT5: u32 = DdsImage::fourcc(b"DXT5");
+		const ATI1: u32 = DdsImage::fourcc(b"ATI1");
+		const BC4U: u32 = DdsImage::fourcc(b"BC4U");
+		const BC4S: u32 = DdsImage::fourcc(b"BC4S");
+		const ATI2: u32 = DdsImage::fourcc(b"ATI2");
+		const BC5U: u32 = DdsImage::fourcc(b"BC5U");
+		const BC5S: u32 = DdsImage::fourcc(b"BC5S");
+		const D3DFMT_A32B32G32R32F: u32 = 116;
+		match fourcc {
+			DXT1 => Some((TextureFormat::Bc1Rgba, None, false)),
+			DXT2 => Some((TextureFormat::Bc2, None, true)),
+			DXT3 => Some((TextureFormat::Bc2, None, false)),
+			DXT4 => Some((TextureFormat::Bc3, None, true)),
+			DXT5 => Some((TextureFormat::Bc3, None, false)),
+			ATI1 | BC4U => Some((TextureFormat::Bc4, None, false)),
+			BC4S => Some((TextureFormat::Bc4Snorm, None, false)),
+			ATI2 | BC5U => Some((TextureFormat::Bc5, None, false)),
+			BC5S => Some((TextureFormat::Bc5Snorm, None, false)),
+			D3DFMT_A32B32G32R32F => Some((TextureFormat::Rgba32f, Some((ChannelType::Rgba, ComponentType::F32)), false)),
+			_ => None,
+		}
+	}
+
+	/// Map the `DXGI_FORMAT` of the DX10 header to the texture format
+	fn format_from_dxgi(dxgi_format: u32) -> Option<(TextureFormat, Option<(ChannelType, ComponentType)>)> {
+		match dxgi_format {
+			2 => Some((TextureFormat::Rgba32f, Some((ChannelType::Rgba, ComponentType::F32)))),
+			28 => Some((TextureFormat::Rgba8, Some((ChannelType::Rgba, ComponentType::U8)))),
+			29 => Some((TextureFormat::Srgb8Alpha8, Some((ChannelType::Rgba, ComponentType::U8)))),
+			87 => Some((TextureFormat::Rgba8, Some((ChannelType::Bgra, ComponentType::U8)))),
+			91 => Some((TextureFormat::Srgb8Alpha8, Some((ChannelType::Bgra, ComponentType::U8)))),
+			71 => Some((TextureFormat::Bc1Rgba, None)),
+			72 => Some((TextureFormat::Bc1SrgbAlpha, None)),
+			74 => Some((TextureFormat::Bc2, None)),
+			75 => Some((TextureFormat::Bc2Srgb, None)),
+			77 => Some((TextureFormat::Bc3, None)),
+			78 => Some((TextureFormat::Bc3Srgb, None)),
+			80 => Some((TextureFormat::Bc4, None)),
+			81 => Some((TextureFormat::Bc4Snorm, None)),
+			83 => Some((TextureFormat::Bc5, None)),
+			84 => Some((TextureFormat::Bc5Snorm, None)),
+			95 => Some((TextureFormat::Bc6hUfloat, None)),
+			96 => Some((TextureFormat::Bc6hSfloat, None)),
+			98 => Some((TextureFormat::Bc7, None)),
+			99 => Some((TextureFormat::Bc7Srgb, None)),
+			_ => None,
+		}
+	}
+
+	/// Parse a DDS file from its bytes, `file_name` is used for the error messages
+	pub fn parse(bytes: &[u8], file_name: &str) -> Result<Self, LoadImageError> {
+		let read_u32 = |offset: usize| -> Result<u32, LoadImageError> {
+			match bytes.get(offset..offset + 4) {
+				Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+				None => Err(LoadImageError::DdsTruncated(file_name.to_owned())),
+			}
+		};
+		if read_u32(0)? != Self::MAGIC || read_u32(4)? != Self::HEADER_SIZE {
+			return Err(LoadImageError::DdsInvalidHeader(file_name.to_owned()));
+		}
+		let flags = read_u32(8)?;
+		let height = read_u32(12)?;
+		let width = read_u32(16)?;
+		let mip_levels = if flags & Self::DDSD_MIPMAPCOUNT != 0 {read_u32(28)?.max(1)} else {1};
+		let pf_flags = read_u32(80)?;
+		let fourcc = read_u32(84)?;
+		let caps2 = read_u32(112)?;
+		if width == 0 || height == 0 || caps2 & Self::DDSCAPS2_VOLUME != 0 {
+			return Err(LoadImageError::UnsupportedImageType(format!("Only the 2D textures and the cube maps are supported in the DDS file `{file_name}`")));
+		}
+		// The mip chain ends at 1x1, a longer chain is a corrupted header
+		if mip_levels > 32 - width.max(height).leading_zeros() {
+			return Err(LoadImageError::DdsInvalidHeader(file_name.to_owned()));
+		}
+
+		let mut data_offset = 128;
+		let mut is_cube_map = caps2 & Self::DDSCAPS2_CUBEMAP != 0;
+		let (format, pixel_type, premultiplied_alpha) = if pf_flags & Self::DDPF_FOURCC != 0 && fourcc == Self::fourcc(b"DX10") {
+			let dxgi_format = read_u32(128)?;
+			let resource_dimension = read_u32(132)?;
+			let misc_flag = read_u32(136)?;
+			let array_size = read_u32(140)?;
+			let misc_flags2 = read_u32(144)?;
+			data_offset = 148;
+			if resource_dimension != Self::DDS_RESOURCE_DIMENSION_TEXTURE2D || array_size > 1 {
+				return Err(LoadImageError::UnsupportedImageType(format!("Only the 2D textures and the cube maps are supported in the DDS file `{file_name}`")));
+			}
+			is_cube_map = misc_flag & Self::DDS_RESOURCE_MISC_TEXTURECUBE != 0;
+			let (format, pixel_type) = Self::format_from_dxgi(dxgi_format).ok_or_else(|| LoadImageError::DdsUnknownFormat(file_name.to_owned(), format!("DXGI_FORMAT {dxgi_format}")))?;
+			(format, pixel_type, misc_flags2 & 0x7 == Self::DDS_ALPHA_MODE_PREMULTIPLIED)
+		} else if pf_flags & Self::DDPF_FOURCC != 0 {
+			Self::format_from_fourcc(fourcc).ok_or_else(|| {
+				let code: String = fourcc.to_le_bytes().iter().map(|&c| if c.is_ascii_graphic() {c as char} else {'?'}).collect();
+				LoadImageError::DdsUnknownFormat(file_name.to_owned(), format!("FourCC `{code}`"))
+			})?
+		} else if pf_flags & Self::DDPF_RGB != 0 {
+			let bit_count = read_u32(88)?;
+			let masks = [read_u32(92)?, read_u32(96)?, read_u32(100)?, if pf_flags & Self::DDPF_ALPHAPIXELS != 0 {read_u32(104)?} else {0}];
+			match (bit_count, masks) {
+				(32, [0xFF, 0xFF00, 0xFF0000, 0xFF000000]) => (TextureFormat::Rgba8, Some((ChannelType::Rgba, ComponentType::U8)), false),
+				(32, [0xFF0000, 0xFF00, 0xFF, 0xFF000000]) => (TextureFormat::Rgba8, Some((ChannelType::Bgra, ComponentType::U8)), false),
+				_ => return Err(LoadImageError::DdsUnknownFormat(file_name.to_owned(), format!("{bit_count}-bit RGB with masks {masks:08X?}"))),
+			}
+		} else {
+			return Err(LoadImageError::DdsUnknownFormat(file_name.to_owned(), format!("pixel format flags 0x{pf_flags:X}")));
+		};
+
+		let num_faces = if is_cube_map {6} else {1};
+		let mut offset = data_offset;
+		let mut faces = Vec::with_capacity(num_faces);
+		for _ in 0..num_faces {
+			let mut levels = Vec::with_capacity(mip_levels as usize);
+			for level in 0..mip_levels {
+				let level_width = width.checked_shr(level).unwrap_or(0).max(1);
+				let level_height = height.checked_shr(level).unwrap_or(0).max(1);
+				let end = bytes_of_image(format, pixel_type, level_width, level_height).and_then(|size| offset.checked_add(size));
+				let data = end.and_then(|end| bytes.get(offset..end)).ok_or_else(|| LoadImageError::DdsTruncated(file_name.to_owned()))?;
+				levels.push(data.to_vec());
+				offset += data.len();
+			}
+			faces.push(levels);
+		}
+		Ok(Self {
+			format,
+			pixel_type,
+			width,
+			height,
+			mip_levels,
+			is_cube_map,
+			premultiplied_alpha,
+			faces,
+		})
+	}
+
+	/// Load and parse a DDS file
+	pub fn from_file(path: &Path) -> Result<Self, LoadImageError> {
+		let bytes = read_image_file(path)?;
+		Self::parse(&bytes, &path.to_string_lossy())
+	}
+}
+
+/// The parsed KTX2 file, holds the pixel data of every mip level
+#[derive(Debug, Clone)]
+pub struct Ktx2Image {
+	/// The internal format for the texture
+	pub format: TextureFormat,
+
+	/// The channel type and the component type of the uncompressed formats, `None` for the block compressed formats
+	pub pixel_type: Option<(ChannelType, ComponentType)>,
+
+	/// The dimension of the texture, could be `Tex1d`, `Tex2d`, `Tex3d` or `TexCube`
+	pub dim: TextureDimension,
+
+	/// The size of the top mip level, the unused dimensions are 1
+	pub width: u32,
+	pub height: u32,
+	pub depth: u32,
+
+	/// How many mip levels are stored in the file. The file asks for the mipmaps to be generated if `mip_levels` is 1 and `generate_mipmap` is true
+	pub mip_levels: u32,
+	pub generate_mipmap: bool,
+
+	/// The data of each mip level, each level contains every face (in the order of `CUBE_FACE_TARGETS`) and every depth slice
+	pub levels: Vec<Vec<u8>>,
+}
+
+impl Ktx2Image {
+	const IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+	const SUPERCOMPRESSION_NONE: u32 = 0;
+	const SUPERCOMPRESSION_BASIS_LZ: u32 = 1;
+	const SUPERCOMPRESSION_ZSTD: u32 = 2;
+	const SUPERCOMPRESSION_ZLIB: u32 = 3;
+	const KHR_DF_MODEL_UASTC: u8 = 166;
+
+	/// Map the `VkFormat` to the texture format
+	fn format_from_vk_format(vk_format: u32) -> Option<(TextureFormat, Option<(ChannelType, ComponentType)>)> {
+		match vk_format {
+			9 => Some((TextureFormat::Red8, Some((ChannelType::Red, ComponentType::U8)))),
+			16 => Some((TextureFormat::Rg8, Some((ChannelType::Rg, ComponentType::U8)))),
+			23 => Some((TextureFormat::Rgb8, Some((ChannelType::Rgb, ComponentType::U8)))),
+			29 => Some((TextureFormat::Srgb8, Some((ChannelType::Rgb, ComponentType::U8)))),
+			37 => Some((TextureFormat::Rgba8, Some((ChannelType::Rgba, ComponentType::U8)))),
+			43 => Some((TextureFormat::Srgb8Alpha8, Some((ChannelType::Rgba, ComponentType::U8)))),
+			44 => Some((TextureFormat::Rgba8, Some((ChannelType::Bgra, ComponentType::U8)))),
+			50 => Some((TextureFormat::Srgb8Alpha8, Some((ChannelType::Bgra, ComponentType::U8)))),
+			70 => Some((TextureFormat::Red16, Some((ChannelType::Red, ComponentType::U16)))),
+			77 => Some((TextureFormat::Rg16, Some((ChannelType::Rg, ComponentType::U16)))),
+			91 => Some((TextureFormat::Rgba16, Some((ChannelType::Rgba, ComponentType::U16)))),
+			98 => Some((TextureFormat::R32ui, Some((ChannelType::RedInteger, ComponentType::U32)))),
+			100 => Some((TextureFormat::R32f, Some((ChannelType::Red, ComponentType::F32)))),
+			103 => Some((TextureFormat::Rg32f, Some((ChannelType::Rg, ComponentType::F32)))),
+			106 => Some((TextureFormat::Rgb32f, Some((ChannelType::Rgb, ComponentType::F32)))),
+			109 => Some((TextureFormat::Rgba32f, Some((ChannelType::Rgba, ComponentType::F32)))),
+			131 => Some((TextureFormat::Bc1Rgb, None)),
+			132 => Some((TextureFormat::Bc1Srgb, None)),
+			133 => Some((TextureFormat::Bc1Rgba, None)),
+			134 => Some((TextureFormat::Bc1SrgbAlpha, None)),
+			135 => Some((TextureFormat::Bc2, None)),
+			136 => Some((TextureFormat::Bc2Srgb, None)),
+			137 => Some((TextureFormat::Bc3, None)),
+			138 => Some((TextureFormat::Bc3Srgb, None)),
+			139 => Some((TextureFormat::Bc4, None)),
+			140 => Some((TextureFormat::Bc4Snorm, None)),
+			141 => Some((TextureFormat::Bc5, None)),
+			142 => Some((TextureFormat::Bc5Snorm, None)),
+			143 => Some((TextureFormat::Bc6hUfloat, None)),
+			144 => Some((TextureFormat::Bc6hSfloat, None)),
+			145 => Some((TextureFormat::Bc7, None)),
+			146 => Some((TextureFormat::Bc7Srgb, None)),
+			_ => None,
+		}
+	}
+
+	#[cfg(feature = "zstd")]
+	fn decode_zstd(data: &[u8], uncompressed_length: usize, _file_name: &str) -> Result<Vec<u8>, LoadImageError> {
+		Ok(zstd::bulk::decompress(data, uncompressed_length)?)
+	}
+
+	#[cfg(not(feature = "zstd"))]
+	fn decode_zstd(_data: &[u8], _uncompressed_length: usize, file_name: &str) -> Result<Vec<u8>, LoadImageError> {
+		Err(LoadImageError::Ktx2UnsupportedSupercompression(file_name.to_owned(), "Zstandard (the `zstd` feature is disabled)".to_owned()))
+	}
+
+	/// Parse a KTX2 file from its bytes, `file_name` is used for the error messages
+	pub fn parse(bytes: &[u8], file_name: &str) -> Result<Self, LoadImageError> {
+		let truncated = || LoadImageError::Ktx2Truncated(file_name.to_owned());
+		let read_u32 = |offset: usize| -> Result<u32, LoadImageError> {
+			let b = bytes.get(offset..offset + 4).ok_or_else(truncated)?;
+			Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+		};
+		let read_u64 = |offset: usize| -> Result<u64, LoadImageError> {
+			Ok((read_u32(offset)? as u64) | ((read_u32(offset + 4)? as u64) << 32))
+		};
+		if bytes.get(0..12) != Some(&Self::IDENTIFIER[..]) {
+			return Err(LoadImageError::Ktx2InvalidHeader(file_name.to_owned()));
+		}
+		let vk_format = read_u32(12)?;
+		let width = read_u32(20)?;
+		let height = read_u32(24)?;
+		let depth = read_u32(28)?;
+		let layer_count = read_u32(32)?;
+		let face_count = read_u32(36)?;
+		let level_count = read_u32(40)?;
+		let supercompression = read_u32(44)?;
+		let dfd_offset = read_u32(48)? as usize;
+
+		if vk_format == 0 {
+			let payload = if supercompression == Self::SUPERCOMPRESSION_BASIS_LZ {
+				"BasisLZ/ETC1S"
+			} else if dfd_offset.checked_add(12).and_then(|offset| bytes.get(offset)) == Some(&Self::KHR_DF_MODEL_UASTC) {
+				"UASTC"
+			} else {
+				"VK_FORMAT_UNDEFINED"
+			};
+			return Err(LoadImageError::Ktx2TranscodingUnsupported(file_name.to_owned(), payload.to_owned()));
+		}
+		let (format, pixel_type) = Self::format_from_vk_format(vk_format).ok_or_else(|| LoadImageError::Ktx2UnknownFormat(file_name.to_owned(), vk_format))?;
+		if layer_count > 1 {
+			return Err(LoadImageError::UnsupportedImageType(format!("The array textures aren't supported in the KTX2 file `{file_name}`")));
+		}
+		let dim = match (width, height, depth, face_count) {
+			(0, _, _, _) => return Err(LoadImageError::Ktx2InvalidHeader(file_name.to_owned())),
+			(_, _, 0, 6) => TextureDimension::TexCube,
+			(_, 0, 0, 1) => TextureDimension::Tex1d,
+			(_, _, 0, 1) => TextureDimension::Tex2d,
+			(_, _, _, 1) => TextureDimension::Tex3d,
+			_ => return Err(LoadImageError::Ktx2InvalidHeader(file_name.to_owned())),
+		};
+		let (height, depth) = (height.max(1), depth.max(1));
+		let mip_levels = level_count.max(1);
+		// The mip chain ends at 1x1, a longer chain is a corrupted header
+		if mip_levels > 32 - width.max(height).max(depth).leading_zeros() {
+			return Err(LoadImageError::Ktx2InvalidHeader(file_name.to_owned()));
+		}
+		let to_usize = |value: u64| usize::try_from(value).map_err(|_| truncated());
+
+		let mut levels = Vec::with_capacity(mip_levels as usize);
+		for level in 0..mip_levels {
+			let index = 80 + level as usize * 24;
+			let offset = to_usize(read_u64(index)?)?;
+			let length = to_usize(read_u64(index + 8)?)?;
+			let uncompressed_length = to_usize(read_u64(index + 16)?)?;
+			let data = offset.checked_add(length).and_then(|end| bytes.get(offset..end)).ok_or_else(truncated)?;
+			let bytes_of_level = bytes_of_image(format, pixel_type, (width >> level).max(1), (height >> level).max(1))
+				.and_then(|size| size.checked_mul((depth >> level).max(1) as usize))
+				.and_then(|size| size.checked_mul(face_count as usize))
+				.ok_or_else(|| LoadImageError::Ktx2InvalidHeader(file_name.to_owned()))?;
+			let data = match supercompression {
+				Self::SUPERCOMPRESSION_NONE => data.to_vec(),
+				// The decompression buffer is bounded by the size of the level, not by the length claimed by the file
+				Self::SUPERCOMPRESSION_ZSTD => Self::decode_zstd(data, uncompressed_length.min(bytes_of_level), file_name)?,
+				Self::SUPERCOMPRESSION_ZLIB => return Err(LoadImageError::Ktx2UnsupportedSupercompression(file_name.to_owned(), "ZLIB".to_owned())),
+				other => return Err(LoadImageError::Ktx2UnsupportedSupercompression(file_name.to_owned(), format!("scheme {other}"))),
+			};
+			if data.len() < bytes_of_level {
+				return Err(truncated());
+			}
+			levels.push(data);
+		}
+		Ok(Self {
+			format,
+			pixel_type,
+			dim,
+			width,
+			height,
+			depth,
+			mip_levels,
+			generate_mipmap: level_count == 0,
+			levels,
+		})
+	}
+
+	/// Load and parse a KTX2 file
+	pub fn from_file(path: &Path) -> Result<Self, LoadImageError> {
+		let bytes = read_image_file(path)?;
+		Self::parse(&bytes, &path.to_string_lossy())
+	}
 }
 
 impl<B, BP> PixelBuffer<B, BP>
@@ -514,7 +1500,7 @@ where
 			initial_data: Option<*const c_void>,
 		) -> Result<Self, TextureError> {
 		let pixel_size = size_of_pixel(channel_type, component_type);
-		let pitch = ((width as usize * pixel_size - 1) / 4 + 1) * 4;
+		let pitch = width as usize * pixel_size;
 		let pitch_wh = pitch * height as usize;
 		let buffer = match initial_data {
 			Some(initial_data) => Buffer::new(glcore.clone(), BufferTarget::PixelUnpackBuffer, size_in_bytes, BufferUsage::StreamDraw, initial_data)?,
@@ -538,7 +1524,7 @@ where
 		})
 	}
 
-	/// Create from an `ImageBuffer`
+	/// Create from an `ImageBuffer`, the rows of the image are tightly packed as the pixel buffer needs
 	pub fn from_image<P: Pixel>(glcore: Rc<GLCore>, img: &ImageBuffer<P, Vec<P::Subpixel>>) -> Result<Self, TextureError> {
 		let container = img.as_raw();
 		let mut channel_type = ChannelType::Rgb;
@@ -547,31 +1533,19 @@ where
 		Self::new(glcore, img.width(), img.height(), 1, size_of_val(&container[..]), channel_type, component_type, Some(container.as_ptr() as *const c_void))
 	}
 
+	/// Create from a decoded `DynamicImage`
+	pub fn from_dynamic_image(glcore: Rc<GLCore>, img: &DynamicImage) -> Result<Self, TextureError> {
+		dispatch_dynamic_image!(img, |img| Self::from_image(glcore, img))
+	}
+
 	/// Create from a file
 	pub fn from_file(glcore: Rc<GLCore>, path: &Path) -> Result<Self, TextureError> {
-		let ext = path.extension().map_or_else(String::new, |ext| OsStr::to_str(ext).unwrap().to_lowercase());
-		match &ext[..] {
-			"jpg" | "jpeg" => {
-				let image_data = std::fs::read(path)?;
-				let img: RgbImage = turbojpeg::decompress_image(&image_data)?;
-				Ok(Self::from_image(glcore, &img)?)
-			}
-			_ => {
-				match ImageReader::open(path)?.decode()? {
-					DynamicImage::ImageLuma8(img) => Ok(Self::from_image(glcore, &img)?),
-					DynamicImage::ImageLumaA8(img) => Ok(Self::from_image(glcore, &img)?),
-					DynamicImage::ImageRgb8(img) => Ok(Self::from_image(glcore, &img)?),
-					DynamicImage::ImageRgba8(img) => Ok(Self::from_image(glcore, &img)?),
-					DynamicImage::ImageLuma16(img) => Ok(Self::from_image(glcore, &img)?),
-					DynamicImage::ImageLumaA16(img) => Ok(Self::from_image(glcore, &img)?),
-					DynamicImage::ImageRgb16(img) => Ok(Self::from_image(glcore, &img)?),
-					DynamicImage::ImageRgba16(img) => Ok(Self::from_image(glcore, &img)?),
-					DynamicImage::ImageRgb32F(img) => Ok(Self::from_image(glcore, &img)?),
-					DynamicImage::ImageRgba32F(img) => Ok(Self::from_image(glcore, &img)?),
-					_ => Err(LoadImageError::UnsupportedImageType(format!("Unsupported image type when loading pixel buffer from {path:?}")))?,
-				}
-			}
-		}
+		Self::from_dynamic_image(glcore, &load_dynamic_image(path)?)
+	}
+
+	/// Create from the encoded bytes of an image file, the format is detected by the magic bytes if there's no `hint`
+	pub fn from_memory(glcore: Rc<GLCore>, bytes: &[u8], hint: Option<ImageFormatHint>) -> Result<Self, TextureError> {
+		Self::from_dynamic_image(glcore, &decode_dynamic_image(bytes, hint, "<memory>")?)
 	}
 }
 
@@ -590,6 +1564,10 @@ where
 	fn get_component_type(&self) -> ComponentType {
 		self.component_type
 	}
+
+	fn get_pitch(&self) -> usize {
+		self.pitch
+	}
 }
 
 /// The `GenericTexture` trait helps the `Texture` struct to be able to turn into an object
@@ -612,6 +1590,9 @@ pub trait GenericTexture: Debug {
 	/// Get depth
 	fn get_depth(&self) -> u32;
 
+	/// Get the number of samples of a multisample texture, returns 0 for the non-multisample textures
+	fn get_samples(&self) -> u32;
+
 	/// Get the texture internal format
 	fn get_format(&self) -> TextureFormat;
 
@@ -624,6 +1605,22 @@ pub trait GenericTexture: Debug {
 	/// Get if have mipmap
 	fn has_mipmap(&self) -> bool;
 
+	/// Get how many mip levels the texture has, a full mip chain if the texture has mipmaps
+	fn level_count(&self) -> u32 {
+		if self.has_mipmap() && self.get_dim() != TextureDimension::Tex2dMultisample {
+			32 - self.get_width().max(self.get_height()).max(self.get_depth()).max(1).leading_zeros()
+		} else {
+			1
+		}
+	}
+
+	/// Is the texture allocated by `glTexStorage*()`, the immutable storage could only be updated by `glTexSubImage*()`
+	fn is_immutable(&self) -> bool;
+
+	/// Re-specify the storage of the texture with the new size through a shared reference, e.g. the render targets held by a `Framebuffer`.
+	/// The pixels are unspecified after resizing. The textures with PBOs must be resized by `Texture::resize()` instead.
+	fn resize_storage(&self, width: u32, height: u32, depth: u32) -> Result<(), TextureError>;
+
 	/// Get the pixel buffer
 	fn get_pixel_buffer(&self) -> Option<&dyn GenericPixelBuffer>;
 
@@ -633,10 +1630,15 @@ pub trait GenericTexture: Debug {
 	/// Discard the PBO if not necessarily need it
 	fn drop_pixel_buffer(&mut self);
 
-	/// Map the pixel buffer for the specified access
+	/// Map the pixel buffer for the specified access, it's bound to `GL_PIXEL_PACK_BUFFER` for reading the pixels packed by `pack_pixel_buffer()`, otherwise `GL_PIXEL_UNPACK_BUFFER`
 	fn map_buffer<'a>(&'a mut self, access: MapAccess) -> Result<Option<(BufferBind<'a>, BufferMapping<'a>, *mut c_void)>, TextureError> {
 		if let Some(b) = self.get_pixel_buffer().as_ref() {
-			let bind = b.bind()?;
+			let target = if access == MapAccess::ReadOnly {
+				BufferTarget::PixelPackBuffer
+			} else {
+				BufferTarget::PixelUnpackBuffer
+			};
+			let bind = b.bind_to(target)?;
 			let (mapping, address) = bind.map(access)?;
 			Ok(Some((bind, mapping, address)))
 		} else {
@@ -644,63 +1646,96 @@ pub trait GenericTexture: Debug {
 		}
 	}
 
-	/// Bind the texture, using the RAII system to manage the binding state
+	/// Bind the texture, using the RAII system to manage the binding state. The cube maps are bound to `GL_TEXTURE_CUBE_MAP` for sampling
 	fn bind<'a>(&'a self) -> Result<TextureBind<'a>, TextureError>;
 
-	/// Bind a cubemap face, using the RAII system to manage the binding state
+	/// Bind a cubemap for the image operations of the face, e.g. `glTexImage2D()` or `glFramebufferTexture2D()`, using the RAII system to manage the binding state
 	fn bind_face<'a>(&'a self, face: CubeMapFaces) -> Result<TextureBind<'a>, TextureError>;
 
-	/// Retrieve the pixels from the texture to the specified data pointer regardless of is currently using a PBO or not
+	/// Get the size in bytes of the pixels of a mip level in the specified pixel format, including all of the 6 faces of a cube map.
+	/// The rows are tightly packed, the pixel transfers of the textures set `GL_PACK_ALIGNMENT` and `GL_UNPACK_ALIGNMENT` to 1.
+	fn bytes_of_level(&self, level: u32, buffer_channel_type: ChannelType, buffer_component_type: ComponentType) -> usize {
+		let width = (self.get_width() >> level).max(1) as usize;
+		let height = (self.get_height() >> level).max(1) as usize;
+		let depth = (self.get_depth() >> level).max(1) as usize;
+		let pitch = width * size_of_pixel(buffer_channel_type, buffer_component_type);
+		let faces = if self.get_dim() == TextureDimension::TexCube {6} else {1};
+		pitch * height * depth * faces
+	}
+
+	/// Retrieve the pixels of the mip level 0 from the texture to the specified data pointer regardless of is currently using a PBO or not
 	///
 	/// # Safety
 	///
 	/// When binding a pixel pack buffer, the pointer `data` refers to the offset of the buffer in bytes.
 	/// When not bound to any pixel pack buffers, the pointer `data` is the pointer to your image buffer in the system memory.
 	unsafe fn download_texture(&self, data: *mut c_void, buffer_channel_type: ChannelType, buffer_component_type: ComponentType) -> Result<(), TextureError> {
-		let glcore = self.get_glcore();
+		unsafe {self.download_level(0, data, buffer_channel_type, buffer_component_type)}
+	}
+
+	/// Retrieve the pixels of a mip level from the texture to the specified data pointer regardless of is currently using a PBO or not.
+	/// Use `bytes_of_level()` to know how many bytes will be written.
+	///
+	/// # Safety
+	///
+	/// When binding a pixel pack buffer, the pointer `data` refers to the offset of the buffer in bytes.
+	/// When not bound to any pixel pack buffers, the pointer `data` is the pointer to your image buffer in the system memory.
+	unsafe fn download_level(&self, level: u32, data: *mut c_void, buffer_channel_type: ChannelType, buffer_component_type: ComponentType) -> Result<(), TextureError> {
+		self.get_format().check_pixel_type(buffer_channel_type, buffer_component_type)?;
+		let glcore = self.get_glcore();
 		let pointer = data as *mut u8;
+		let level_count = self.level_count();
+		if level >= level_count {
+			return Err(TextureError::InvalidMipLevel(level, level_count));
+		}
+		let pixel_store = PixelStore::pack(glcore, 1, 0)?;
 		match self.get_dim() {
-			TextureDimension::Tex1d => {
-				let bind_tex = self.bind()?;
-				glcore.glGetTexImage(TextureTarget::Tex1d as u32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *mut c_void)?;
-				bind_tex.unbind();
-			}
-			TextureDimension::Tex2d => {
-				let bind_tex = self.bind()?;
-				glcore.glGetTexImage(TextureTarget::Tex2d as u32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *mut c_void)?;
-				bind_tex.unbind();
-			}
-			TextureDimension::Tex3d => {
-				let bind_tex = self.bind()?;
-				glcore.glGetTexImage(TextureTarget::Tex3d as u32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *mut c_void)?;
-				bind_tex.unbind();
+			TextureDimension::Tex2dMultisample => {
+				return Err(TextureError::UnsupportedOperation("Could not download pixels from a multisample texture, resolve it to a single-sample texture first.".to_owned()));
 			}
 			TextureDimension::TexCube => {
+				let bytes_of_face = self.bytes_of_level(level, buffer_channel_type, buffer_component_type) / 6;
 				for (i, target) in CUBE_FACE_TARGETS.iter().enumerate() {
 					let target = *target;
 					let bind_tex = self.bind_face(target)?;
-					let pointer = pointer.wrapping_add(i * self.get_bytes_of_face());
-					glcore.glGetTexImage(target as u32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *mut c_void)?;
+					let pointer = pointer.wrapping_add(i * bytes_of_face);
+					glcore.glGetTexImage(target as u32, level as i32, buffer_channel_type as u32, buffer_component_type as u32, pointer as *mut c_void)?;
 					bind_tex.unbind();
 				}
 			}
+			dim => {
+				let bind_tex = self.bind()?;
+				glcore.glGetTexImage(dim.get_target() as u32, level as i32, buffer_channel_type as u32, buffer_component_type as u32, pointer as *mut c_void)?;
+				bind_tex.unbind();
+			}
 		}
+		pixel_store.restore();
 		Ok(())
 	}
 
-	/// Load the texture with the specified data pointer regardless of is currently using a PBO or not
+	/// Load the texture with the specified data pointer regardless of is currently using a PBO or not.
+	/// The rows of the data are tightly packed, use `bytes_of_level()` to know how many bytes will be read.
 	///
 	/// # Safety
 	///
 	/// When binding a pixel unpack buffer, the pointer `data` refers to the offset of the buffer in bytes.
 	/// When not bound to any pixel unpack buffers, the pointer `data` is the pointer to your image buffer in the system memory.
 	unsafe fn upload_texture(&self, data: *const c_void, buffer_channel_type: ChannelType, buffer_component_type: ComponentType, regen_mipmap: bool) -> Result<(), TextureError> {
+		self.get_format().check_pixel_type(buffer_channel_type, buffer_component_type)?;
 		let glcore = self.get_glcore();
 		let pointer = data as *const u8;
+		let _pixel_store = PixelStore::unpack(glcore, 1, 0)?;
 		match self.get_dim() {
+			TextureDimension::Tex2dMultisample => {
+				Err(TextureError::UnsupportedOperation("Could not upload pixels to a multisample texture.".to_owned()))
+			}
 			TextureDimension::Tex1d => {
 				let bind_tex = self.bind()?;
-				glcore.glTexImage1D(TextureTarget::Tex1d as u32, 0, self.get_format() as i32, self.get_width() as i32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
+				if self.is_immutable() {
+					glcore.glTexSubImage1D(TextureTarget::Tex1d as u32, 0, 0, self.get_width() as i32, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
+				} else {
+					glcore.glTexImage1D(TextureTarget::Tex1d as u32, 0, self.get_format() as i32, self.get_width() as i32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
+				}
 				if regen_mipmap && self.has_mipmap() {
 					glcore.glGenerateMipmap(TextureTarget::Tex1d as u32)?;
 				}
@@ -709,7 +1744,11 @@ pub trait GenericTexture: Debug {
 			}
 			TextureDimension::Tex2d => {
 				let bind_tex = self.bind()?;
-				glcore.glTexImage2D(TextureTarget::Tex2d as u32, 0, self.get_format() as i32, self.get_width() as i32, self.get_height() as i32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
+				if self.is_immutable() {
+					glcore.glTexSubImage2D(TextureTarget::Tex2d as u32, 0, 0, 0, self.get_width() as i32, self.get_height() as i32, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
+				} else {
+					glcore.glTexImage2D(TextureTarget::Tex2d as u32, 0, self.get_format() as i32, self.get_width() as i32, self.get_height() as i32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
+				}
 				if regen_mipmap && self.has_mipmap() {
 					glcore.glGenerateMipmap(TextureTarget::Tex2d as u32)?;
 				}
@@ -718,7 +1757,11 @@ pub trait GenericTexture: Debug {
 			}
 			TextureDimension::Tex3d => {
 				let bind_tex = self.bind()?;
-				glcore.glTexImage3D(TextureTarget::Tex3d as u32, 0, self.get_format() as i32, self.get_width() as i32, self.get_height() as i32, self.get_depth() as i32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
+				if self.is_immutable() {
+					glcore.glTexSubImage3D(TextureTarget::Tex3d as u32, 0, 0, 0, 0, self.get_width() as i32, self.get_height() as i32, self.get_depth() as i32, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
+				} else {
+					glcore.glTexImage3D(TextureTarget::Tex3d as u32, 0, self.get_format() as i32, self.get_width() as i32, self.get_height() as i32, self.get_depth() as i32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
+				}
 				if regen_mipmap && self.has_mipmap() {
 					glcore.glGenerateMipmap(TextureTarget::Tex3d as u32)?;
 				}
@@ -726,39 +1769,75 @@ pub trait GenericTexture: Debug {
 				Ok(())
 			}
 			TextureDimension::TexCube => {
+				let bytes_of_face = self.bytes_of_level(0, buffer_channel_type, buffer_component_type) / 6;
+				// A null pointer is only the offset of a bound pixel unpack buffer, otherwise every face gets the null pointer to be allocated without data
+				let offset_faces = !pointer.is_null() || is_pixel_unpack_buffer_bound(glcore)?;
 				for (i, target) in CUBE_FACE_TARGETS.iter().enumerate() {
 					let target = *target;
 					let bind_tex = self.bind_face(target)?;
-					let pointer = pointer.wrapping_add(i * self.get_bytes_of_face());
-					glcore.glTexImage2D(target as u32, 0, self.get_format() as i32, self.get_width() as i32, self.get_height() as i32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
-					if regen_mipmap && self.has_mipmap() {
-						glcore.glGenerateMipmap(target as u32)?;
+					let pointer = if offset_faces {pointer.wrapping_add(i * bytes_of_face)} else {pointer};
+					if self.is_immutable() {
+						glcore.glTexSubImage2D(target as u32, 0, 0, 0, self.get_width() as i32, self.get_height() as i32, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
+					} else {
+						glcore.glTexImage2D(target as u32, 0, self.get_format() as i32, self.get_width() as i32, self.get_height() as i32, 0, buffer_channel_type as u32, buffer_component_type as u32, pointer as *const c_void)?;
 					}
 					bind_tex.unbind();
 				}
+				if regen_mipmap && self.has_mipmap() {
+					// The mipmaps of a cube map are generated for all of the faces at once, the face targets are invalid for `glGenerateMipmap()`
+					let bind_tex = self.bind()?;
+					glcore.glGenerateMipmap(TextureTarget::TexCube as u32)?;
+					bind_tex.unbind();
+				}
 				Ok(())
 			}
 		}
 	}
 
-	/// Read the pixels from the texture to the pixel buffer
-	fn pack_pixel_buffer(&self) -> Result<(), TextureError> {
+	/// Read the pixels from the texture to the pixel buffer, read from the mip `level` if specified, otherwise the level 0
+	fn pack_pixel_buffer(&self, level: Option<u32>) -> Result<(), TextureError> {
 		let pixel_buffer = self.get_pixel_buffer().unwrap();
 		let buffer_channel_type = pixel_buffer.get_channel_type();
 		let buffer_component_type = pixel_buffer.get_component_type();
-		let bind_pbo = pixel_buffer.bind()?;
-		unsafe {self.download_texture(std::ptr::null_mut::<c_void>(), buffer_channel_type, buffer_component_type)?};
+		let level = level.unwrap_or(0);
+		let bytes_of_level = self.bytes_of_level(level, buffer_channel_type, buffer_component_type);
+		if pixel_buffer.size_in_bytes() < bytes_of_level {
+			return Err(TextureError::PixelBufferTooSmall(pixel_buffer.size_in_bytes(), bytes_of_level));
+		}
+		let bind_pbo = pixel_buffer.bind_to(BufferTarget::PixelPackBuffer)?;
+		unsafe {self.download_level(level, std::ptr::null_mut::<c_void>(), buffer_channel_type, buffer_component_type)?};
 		bind_pbo.unbind();
 		Ok(())
 	}
 
-	/// Apply the change to the pixel buffer of the texture
-	fn unpack_pixel_buffer(&self, regen_mipmap: bool) -> Result<(), TextureError> {
+	/// Upload the pixels of a mip level, `size` must be the size of the level and the rows are tightly packed. The cube maps need the `face` to upload to.
+	/// Uploading a level above 0 marks the texture as having mipmaps, use `set_mip_range()` if the mip chain is partial.
+	///
+	/// # Safety
+	///
+	/// When binding a pixel unpack buffer, the pointer `data` refers to the offset of the buffer in bytes.
+	/// When not bound to any pixel unpack buffers, the pointer `data` is the pointer to your image buffer in the system memory.
+	unsafe fn upload_level(&self, level: u32, face: Option<CubeMapFaces>, size: [u32; 3], data: *const c_void, buffer_channel_type: ChannelType, buffer_component_type: ComponentType) -> Result<(), TextureError>;
+
+	/// Apply the change to the pixel buffer of the texture, upload it to the mip `level` if specified, the pixel buffer contains all of the 6 faces for a cube map.
+	fn unpack_pixel_buffer(&self, level: Option<u32>, regen_mipmap: bool) -> Result<(), TextureError> {
 		let pixel_buffer = self.get_pixel_buffer().unwrap();
 		let buffer_channel_type = pixel_buffer.get_channel_type();
 		let buffer_component_type = pixel_buffer.get_component_type();
-		let bind_pbo = pixel_buffer.bind()?;
-		unsafe {self.upload_texture(std::ptr::null(), buffer_channel_type, buffer_component_type, regen_mipmap)?};
+		let bind_pbo = pixel_buffer.bind_to(BufferTarget::PixelUnpackBuffer)?;
+		if let Some(level) = level {
+			let size = [(self.get_width() >> level).max(1), (self.get_height() >> level).max(1), (self.get_depth() >> level).max(1)];
+			if self.get_dim() == TextureDimension::TexCube {
+				let bytes_of_face = self.bytes_of_level(level, buffer_channel_type, buffer_component_type) / 6;
+				for (i, face) in CUBE_FACE_TARGETS.iter().enumerate() {
+					unsafe {self.upload_level(level, Some(*face), size, std::ptr::null::<u8>().wrapping_add(i * bytes_of_face) as *const c_void, buffer_channel_type, buffer_component_type)?};
+				}
+			} else {
+				unsafe {self.upload_level(level, None, size, std::ptr::null(), buffer_channel_type, buffer_component_type)?};
+			}
+		} else {
+			unsafe {self.upload_texture(std::ptr::null(), buffer_channel_type, buffer_component_type, regen_mipmap)?};
+		}
 		bind_pbo.unbind();
 		Ok(())
 	}
@@ -812,8 +1891,17 @@ where
 				*depth = 1;
 				*size_mod = 6;
 			}
+			TextureDimension::Tex2dMultisample => {
+				target = TextureTarget::Tex2dMultisample;
+				*depth = 1;
+				*size_mod = 1;
+			}
 		}
 		glcore.glBindTexture(target as u32, name)?;
+		if dim == TextureDimension::Tex2dMultisample {
+			// The multisample textures don't accept any sampler parameters
+			return Ok(target);
+		}
 		match dim {
 			TextureDimension::Tex1d => {
 				glcore.glTexParameteri(target as u32, GL_TEXTURE_WRAP_S, wrapping_s as i32)?;
@@ -834,6 +1922,16 @@ where
 		Ok(target)
 	}
 
+	/// Calculate the size in bytes of a face of the texture, the rows are aligned to 4 bytes
+	fn calc_bytes_of_face(format: TextureFormat, width: u32, height: u32, depth: u32) -> usize {
+		if let Some(block_bytes) = format.compressed_block_bytes() {
+			width.div_ceil(4) as usize * height.div_ceil(4) as usize * block_bytes * depth as usize
+		} else {
+			let pitch = (width as usize * format.bits_of_pixel()).div_ceil(32) * 4;
+			pitch * height as usize * depth as usize
+		}
+	}
+
 	/// Create an unallocated texture for further initialization
 	fn new_unallocates(
 			glcore: Rc<GLCore>,
@@ -852,27 +1950,41 @@ where
 		let mut name: u32 = 0;
 		glcore.glGenTextures(1, &mut name as *mut _)?;
 		let mut size_mod = 1;
-		let target = Self::set_texture_params(glcore.clone(), name, dim, width, &mut height, &mut depth, &mut size_mod, wrapping_s, wrapping_t, wrapping_r, mag_filter, min_filter)?;
-		let pixel_bits = format.bits_of_pixel(glcore.as_ref(), target)?;
-		let pitch = ((pixel_bits - 1) / 32 + 1) * 4;
-		let bytes_of_face = pitch * height as usize * depth as usize;
+		Self::set_texture_params(glcore.clone(), name, dim, width, &mut height, &mut depth, &mut size_mod, wrapping_s, wrapping_t, wrapping_r, mag_filter, min_filter)?;
+		let bytes_of_face = Self::calc_bytes_of_face(format, width, height, depth);
 		let bytes_of_texture = bytes_of_face * size_mod;
-		Ok(Self {
+		let ret = Self {
 			glcore,
 			name,
 			dim,
 			format,
-			width,
-			height,
-			depth,
-			has_mipmap,
+			width: Cell::new(width),
+			height: Cell::new(height),
+			depth: Cell::new(depth),
+			samples: 0,
+			fixed_sample_locations: true,
+			has_mipmap: Cell::new(has_mipmap),
 			mag_filter,
 			min_filter,
-			bytes_of_texture,
-			bytes_of_face,
+			base_level: 0,
+			max_level: DEFAULT_MAX_MIP_LEVEL,
+			min_lod: -1000.0,
+			max_lod: 1000.0,
+			lod_bias: 0.0,
+			compare_func: None,
+			immutable_levels: None,
+			bytes_of_texture: Cell::new(bytes_of_texture),
+			bytes_of_face: Cell::new(bytes_of_face),
 			pixel_buffer: None,
+			streaming_buffers: Vec::new(),
+			streaming_index: 0,
+			grayscale_policy: GrayscalePolicy::KeepRed,
+			tracked_bytes: Cell::new(0),
 			_pixel_type: PhantomData,
-		})
+		};
+		MemoryTracker::track_new(MemoryCategory::Textures, 0);
+		ret.retrack();
+		Ok(ret)
 	}
 
 	/// Create from a pixel buffer
@@ -891,21 +2003,62 @@ where
 			min_filter: SamplerFilter,
 			pixel_buffer: PixelBuffer<B, BP>,
 		) -> Result<Self, TextureError> {
-		let ret = Self::new_unallocates(glcore, dim, format, width, height, depth, wrapping_s, wrapping_t, wrapping_r, has_mipmap, mag_filter, min_filter)?;
-		unsafe {ret.upload_texture(null(), pixel_buffer.get_channel_type(), pixel_buffer.get_component_type(), has_mipmap)?};
+		let mut ret = Self::new_unallocates(glcore, dim, format, width, height, depth, wrapping_s, wrapping_t, wrapping_r, has_mipmap, mag_filter, min_filter)?;
+		ret.pixel_buffer = Some(pixel_buffer);
+		ret.unpack_pixel_buffer(None, has_mipmap)?;
 		Ok(ret)
 	}
 
-	/// Create without pixel buffer
-	fn new(glcore: Rc<GLCore>,
-			dim: TextureDimension,
+	/// Create a builder for the texture of the specified dimension
+	fn builder<'a>(glcore: Rc<GLCore>, dim: TextureDimension, format: TextureFormat, width: u32, height: u32, depth: u32) -> TextureBuilder<'a, B, BP> {
+		TextureBuilder {
+			glcore,
+			dim,
+			format,
+			width,
+			height,
+			depth,
+			wrapping_s: TextureWrapping::ClampToEdge,
+			wrapping_t: TextureWrapping::ClampToEdge,
+			wrapping_r: TextureWrapping::ClampToEdge,
+			has_mipmap: false,
+			mag_filter: SamplerMagFilter::Linear,
+			min_filter: SamplerFilter::Linear,
+			buffering: false,
+			pixel_type: None,
+			initial_data: None,
+			initial_data_len: None,
+			_data: PhantomData,
+			_texture: PhantomData,
+		}
+	}
+
+	/// Create a builder for a 1D texture
+	pub fn builder_1d<'a>(glcore: Rc<GLCore>, format: TextureFormat, width: u32) -> TextureBuilder<'a, B, BP> {
+		Self::builder(glcore, TextureDimension::Tex1d, format, width, 1, 1)
+	}
+
+	/// Create a builder for a 2D texture
+	pub fn builder_2d<'a>(glcore: Rc<GLCore>, format: TextureFormat, width: u32, height: u32) -> TextureBuilder<'a, B, BP> {
+		Self::builder(glcore, TextureDimension::Tex2d, format, width, height, 1)
+	}
+
+	/// Create a builder for a 3D texture
+	pub fn builder_3d<'a>(glcore: Rc<GLCore>, format: TextureFormat, width: u32, height: u32, depth: u32) -> TextureBuilder<'a, B, BP> {
+		Self::builder(glcore, TextureDimension::Tex3d, format, width, height, depth)
+	}
+
+	/// Create a builder for a cube map texture
+	pub fn builder_cube<'a>(glcore: Rc<GLCore>, format: TextureFormat, size: u32) -> TextureBuilder<'a, B, BP> {
+		Self::builder(glcore, TextureDimension::TexCube, format, size, size, 1)
+	}
+
+	/// Create a 1D texture
+	pub fn new_1d(
+			glcore: Rc<GLCore>,
 			format: TextureFormat,
 			width: u32,
-			height: u32,
-			depth: u32,
 			wrapping_s: TextureWrapping,
-			wrapping_t: TextureWrapping,
-			wrapping_r: TextureWrapping,
 			has_mipmap: bool,
 			mag_filter: SamplerMagFilter,
 			min_filter: SamplerFilter,
@@ -914,244 +2067,1262 @@ where
 			buffer_component_type: ComponentType,
 			initial_data: Option<*const c_void>,
 		) -> Result<Self, TextureError> {
-		let mut ret = Self::new_unallocates(glcore, dim, format, width, height, depth, wrapping_s, wrapping_t, wrapping_r, has_mipmap, mag_filter, min_filter)?;
-		if buffering {
-			ret.create_pixel_buffer(buffer_channel_type, buffer_component_type, initial_data)?;
-		} else if let Some(data_pointer) = initial_data {
-			unsafe {ret.upload_texture(data_pointer, buffer_channel_type, buffer_component_type, has_mipmap)?};
-		} else {
-			let empty_data = vec![0u8; ret.bytes_of_texture];
-			unsafe {ret.upload_texture(empty_data.as_ptr() as *const c_void, buffer_channel_type, buffer_component_type, has_mipmap)?};
-		}
-		Ok(ret)
-	}
-
-	/// Create an 1D texture
-	pub fn new_1d(
-	        glcore: Rc<GLCore>,
-	        format: TextureFormat,
-	        width: u32,
-	        wrapping_s: TextureWrapping,
-	        has_mipmap: bool,
-	        mag_filter: SamplerMagFilter,
-			min_filter: SamplerFilter,
-			buffering: bool,
-			buffer_channel_type: ChannelType,
-			buffer_component_type: ComponentType,
-			initial_data: Option<*const c_void>,
-		) -> Result<Self, TextureError> {
-		Self::new(glcore, TextureDimension::Tex1d, format, width, 1, 1, wrapping_s, TextureWrapping::Repeat, TextureWrapping::Repeat, has_mipmap, mag_filter, min_filter, buffering, buffer_channel_type, buffer_component_type, initial_data)
+		Self::builder_1d(glcore, format, width).wrap(wrapping_s)
+			.filters(mag_filter, min_filter)
+			.mipmaps(has_mipmap)
+			.pixel_format(buffer_channel_type, buffer_component_type)
+			.pixel_buffer(buffering)
+			.initial_data_ptr(initial_data)
+			.build()
 	}
 
-	/// Create an 2D texture
+	/// Create a 2D texture
 	pub fn new_2d(
-	        glcore: Rc<GLCore>,
-	        format: TextureFormat,
-	        width: u32,
-	        height: u32,
-	        wrapping_s: TextureWrapping,
-	        wrapping_t: TextureWrapping,
-	        has_mipmap: bool,
-	        mag_filter: SamplerMagFilter,
+			glcore: Rc<GLCore>,
+			format: TextureFormat,
+			width: u32,
+			height: u32,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
 			min_filter: SamplerFilter,
 			buffering: bool,
 			buffer_channel_type: ChannelType,
 			buffer_component_type: ComponentType,
 			initial_data: Option<*const c_void>,
 		) -> Result<Self, TextureError> {
-		Self::new(glcore, TextureDimension::Tex2d, format, width, height, 1, wrapping_s, wrapping_t, TextureWrapping::Repeat, has_mipmap, mag_filter, min_filter, buffering, buffer_channel_type, buffer_component_type, initial_data)
+		Self::builder_2d(glcore, format, width, height).wrap_each(wrapping_s, wrapping_t, TextureWrapping::Repeat)
+			.filters(mag_filter, min_filter)
+			.mipmaps(has_mipmap)
+			.pixel_format(buffer_channel_type, buffer_component_type)
+			.pixel_buffer(buffering)
+			.initial_data_ptr(initial_data)
+			.build()
 	}
 
-	/// Create an 3D texture
+	/// Create a 3D texture
 	pub fn new_3d(
-	        glcore: Rc<GLCore>,
-	        format: TextureFormat,
-	        width: u32,
-	        height: u32,
-	        depth: u32,
-	        wrapping_s: TextureWrapping,
-	        wrapping_t: TextureWrapping,
-	        wrapping_r: TextureWrapping,
-	        has_mipmap: bool,
-	        mag_filter: SamplerMagFilter,
+			glcore: Rc<GLCore>,
+			format: TextureFormat,
+			width: u32,
+			height: u32,
+			depth: u32,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			wrapping_r: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
 			min_filter: SamplerFilter,
 			buffering: bool,
 			buffer_channel_type: ChannelType,
 			buffer_component_type: ComponentType,
 			initial_data: Option<*const c_void>,
 		) -> Result<Self, TextureError> {
-		Self::new(glcore, TextureDimension::Tex3d, format, width, height, depth, wrapping_s, wrapping_t, wrapping_r, has_mipmap, mag_filter, min_filter, buffering, buffer_channel_type, buffer_component_type, initial_data)
+		Self::builder_3d(glcore, format, width, height, depth).wrap_each(wrapping_s, wrapping_t, wrapping_r)
+			.filters(mag_filter, min_filter)
+			.mipmaps(has_mipmap)
+			.pixel_format(buffer_channel_type, buffer_component_type)
+			.pixel_buffer(buffering)
+			.initial_data_ptr(initial_data)
+			.build()
 	}
 
-	/// Create an cube map texture
+	/// Create a cube map texture
 	pub fn new_cube(
-	        glcore: Rc<GLCore>,
-	        format: TextureFormat,
-	        size: u32,
-	        has_mipmap: bool,
-	        mag_filter: SamplerMagFilter,
+			glcore: Rc<GLCore>,
+			format: TextureFormat,
+			size: u32,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
 			min_filter: SamplerFilter,
 			buffering: bool,
 			buffer_channel_type: ChannelType,
 			buffer_component_type: ComponentType,
 			initial_data: Option<*const c_void>,
 		) -> Result<Self, TextureError> {
-		Self::new(glcore, TextureDimension::TexCube, format, size, size, 1, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, has_mipmap, mag_filter, min_filter, buffering, buffer_channel_type, buffer_component_type, initial_data)
+		Self::builder_cube(glcore, format, size)
+			.filters(mag_filter, min_filter)
+			.mipmaps(has_mipmap)
+			.pixel_format(buffer_channel_type, buffer_component_type)
+			.pixel_buffer(buffering)
+			.initial_data_ptr(initial_data)
+			.build()
 	}
 
-	/// Create a texture from an image
-	pub fn from_image<P: Pixel>(
+	/// Create a texture with immutable storage allocated by `glTexStorage*()`
+	fn new_storage(
 			glcore: Rc<GLCore>,
 			dim: TextureDimension,
-			img: &ImageBuffer<P, Vec<P::Subpixel>>,
+			format: TextureFormat,
+			width: u32,
+			height: u32,
+			depth: u32,
+			levels: u32,
 			wrapping_s: TextureWrapping,
 			wrapping_t: TextureWrapping,
-			has_mipmap: bool,
+			wrapping_r: TextureWrapping,
 			mag_filter: SamplerMagFilter,
 			min_filter: SamplerFilter,
 		) -> Result<Self, TextureError> {
-		let mut buffer_channel_type = ChannelType::Rgb;
-		let mut buffer_component_type = ComponentType::U8;
-		get_channel_type_and_component_type_from_image_pixel::<P>(&mut buffer_channel_type, &mut buffer_component_type).unwrap();
-		let format = TextureFormat::from_channel_type_and_component_type(buffer_channel_type, buffer_component_type).unwrap();
-		let pixel_buffer = PixelBuffer::from_image(glcore.clone(), img)?;
+		if !format.is_sized() {
+			return Err(TextureError::UnsizedFormat(format));
+		}
+		let full_levels = 32 - width.max(height).max(depth).max(1).leading_zeros();
+		if levels == 0 || levels > full_levels {
+			return Err(TextureError::InvalidMipLevel(levels, full_levels));
+		}
+		let mut ret = Self::new_unallocates(glcore, dim, format, width, height, depth, wrapping_s, wrapping_t, wrapping_r, levels > 1, mag_filter, min_filter)?;
+		ret.immutable_levels = Some(levels);
+		ret.retrack();
+		let target = dim.get_target();
+		let bind_tex = TextureBind::new(&ret, target)?;
 		match dim {
-			TextureDimension::Tex1d => {
-				assert_eq!(img.height(), 1);
-				Self::new_from_pixel_buffer(glcore, dim, format, img.width(), 1, 1, wrapping_s, wrapping_t, TextureWrapping::Repeat, has_mipmap, mag_filter, min_filter, pixel_buffer)
-			}
-			TextureDimension::Tex2d => {
-				Self::new_from_pixel_buffer(glcore, dim, format, img.width(), img.height(), 1, wrapping_s, wrapping_t, TextureWrapping::Repeat, has_mipmap, mag_filter, min_filter, pixel_buffer)
-			}
-			TextureDimension::TexCube => {
-				assert_eq!(img.width() * 6, img.height());
-				Self::new_from_pixel_buffer(glcore, dim, format, img.width(), img.width(), 1, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, has_mipmap, mag_filter, min_filter, pixel_buffer)
-			}
-			other => panic!("Could not create a {other:?} texture from a `ImageBuffer`")
+			TextureDimension::Tex1d => ret.glcore.glTexStorage1D(target as u32, levels as i32, format as u32, ret.width.get() as i32)?,
+			TextureDimension::Tex3d => ret.glcore.glTexStorage3D(target as u32, levels as i32, format as u32, ret.width.get() as i32, ret.height.get() as i32, ret.depth.get() as i32)?,
+			_ => ret.glcore.glTexStorage2D(target as u32, levels as i32, format as u32, ret.width.get() as i32, ret.height.get() as i32)?,
 		}
+		bind_tex.unbind();
+		Ok(ret)
 	}
 
-	/// Create a texture from a file
-	pub fn from_file(
+	/// Create a 1D texture with immutable storage of `levels` mip levels
+	pub fn new_1d_storage(
 			glcore: Rc<GLCore>,
-			path: &Path,
-			dim: TextureDimension,
+			format: TextureFormat,
+			width: u32,
+			levels: u32,
 			wrapping_s: TextureWrapping,
-			wrapping_t: TextureWrapping,
-			has_mipmap: bool,
 			mag_filter: SamplerMagFilter,
 			min_filter: SamplerFilter,
 		) -> Result<Self, TextureError> {
-		let ext = path.extension().map_or_else(String::new, |ext| OsStr::to_str(ext).unwrap().to_lowercase());
-		match &ext[..] {
-			"jpg" | "jpeg" => {
-				let image_data = std::fs::read(path)?;
-				let img: RgbImage = turbojpeg::decompress_image(&image_data)?;
-				Ok(Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)?)
-			}
-			_ => {
-				match ImageReader::open(path)?.decode()? {
-					DynamicImage::ImageLuma8(img) => Ok(Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)?),
-					DynamicImage::ImageLumaA8(img) => Ok(Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)?),
-					DynamicImage::ImageRgb8(img) => Ok(Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)?),
-					DynamicImage::ImageRgba8(img) => Ok(Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)?),
-					DynamicImage::ImageLuma16(img) => Ok(Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)?),
-					DynamicImage::ImageLumaA16(img) => Ok(Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)?),
-					DynamicImage::ImageRgb16(img) => Ok(Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)?),
-					DynamicImage::ImageRgba16(img) => Ok(Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)?),
-					DynamicImage::ImageRgb32F(img) => Ok(Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)?),
-					DynamicImage::ImageRgba32F(img) => Ok(Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)?),
-					_ => Err(LoadImageError::UnsupportedImageType(format!("Unsupported image type when loading texture from {path:?}")))?,
-				}
-			}
-		}
-	}
-}
-
-impl<B, BP> GenericTexture for Texture<B, BP>
-where
-	B: BufferVec<BP>,
-	BP: BufferVecItem {
-	fn get_glcore(&self) -> &GLCore {
-		&self.glcore
-	}
-
-	fn get_name(&self) -> u32 {
-		self.name
+		Self::new_storage(glcore, TextureDimension::Tex1d, format, width, 1, 1, levels, wrapping_s, TextureWrapping::Repeat, TextureWrapping::Repeat, mag_filter, min_filter)
 	}
 
-	fn get_dim(&self) -> TextureDimension {
-		self.dim
+	/// Create a 2D texture with immutable storage of `levels` mip levels
+	pub fn new_2d_storage(
+			glcore: Rc<GLCore>,
+			format: TextureFormat,
+			width: u32,
+			height: u32,
+			levels: u32,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		Self::new_storage(glcore, TextureDimension::Tex2d, format, width, height, 1, levels, wrapping_s, wrapping_t, TextureWrapping::Repeat, mag_filter, min_filter)
 	}
 
-	fn get_width(&self) -> u32 {
-		self.width
+	/// Create a 3D texture with immutable storage of `levels` mip levels
+	pub fn new_3d_storage(
+			glcore: Rc<GLCore>,
+			format: TextureFormat,
+			width: u32,
+			height: u32,
+			depth: u32,
+			levels: u32,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			wrapping_r: TextureWrapping,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		Self::new_storage(glcore, TextureDimension::Tex3d, format, width, height, depth, levels, wrapping_s, wrapping_t, wrapping_r, mag_filter, min_filter)
 	}
 
-	fn get_height(&self) -> u32 {
-		self.height
+	/// Create a cube map texture with immutable storage of `levels` mip levels
+	pub fn new_cube_storage(
+			glcore: Rc<GLCore>,
+			format: TextureFormat,
+			size: u32,
+			levels: u32,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		Self::new_storage(glcore, TextureDimension::TexCube, format, size, size, 1, levels, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, mag_filter, min_filter)
 	}
 
-	fn get_depth(&self) -> u32 {
-		self.depth
+	/// Create a 2D depth texture for the shadow maps, clamped to a white border so that the samples outside of the map are never in shadow, with the nearest filtering.
+	/// If `compare` is given, the compare mode is enabled for the `sampler2DShadow` samplers.
+	pub fn new_depth_2d(glcore: Rc<GLCore>, width: u32, height: u32, format: DepthFormat, compare: Option<CompareFunc>) -> Result<Self, TextureError> {
+		let mut ret = Self::builder_2d(glcore, format.into(), width, height)
+			.wrap(TextureWrapping::ClampToBorder)
+			.filters(SamplerMagFilter::Nearest, SamplerFilter::Nearest)
+			.build()?;
+		let border_color = [1.0f32; 4];
+		let bind_tex = TextureBind::new(&ret, TextureTarget::Tex2d)?;
+		ret.glcore.glTexParameterfv(TextureTarget::Tex2d as u32, GL_TEXTURE_BORDER_COLOR, border_color.as_ptr())?;
+		bind_tex.unbind();
+		ret.set_depth_compare(compare)?;
+		Ok(ret)
 	}
 
-	fn get_format(&self) -> TextureFormat {
-		self.format
+	/// Create a 2D multisample texture for MSAA rendering. The sample count is validated against `GL_MAX_SAMPLES`.
+	///
+	/// The multisample texture has no mipmaps and no sampler filters, and its pixels couldn't be uploaded or downloaded directly.
+	pub fn new_2d_multisample(
+			glcore: Rc<GLCore>,
+			format: TextureFormat,
+			width: u32,
+			height: u32,
+			samples: u32,
+			fixed_sample_locations: bool,
+		) -> Result<Self, TextureError> {
+		let mut max_samples: i32 = 0;
+		glcore.glGetIntegerv(GL_MAX_SAMPLES, &mut max_samples as *mut _)?;
+		let max_samples = max_samples.max(0) as u32;
+		if samples == 0 || samples > max_samples {
+			return Err(TextureError::InvalidSampleCount(samples, max_samples));
+		}
+		let mut ret = Self::new_unallocates(glcore, TextureDimension::Tex2dMultisample, format, width, height, 1, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, false, SamplerMagFilter::Nearest, SamplerFilter::Nearest)?;
+		ret.samples = samples;
+		ret.fixed_sample_locations = fixed_sample_locations;
+		ret.retrack();
+		let bind_tex = ret.bind()?;
+		ret.glcore.glTexImage2DMultisample(TextureTarget::Tex2dMultisample as u32, samples as i32, format as u32, width as i32, height as i32, fixed_sample_locations as u8)?;
+		bind_tex.unbind();
+		Ok(ret)
 	}
 
-	fn get_bytes_of_face(&self) -> usize {
-		self.bytes_of_face
+	/// Create a ring of `n_buffers` PBOs for streaming the pixels to the texture every frame without synchronizing with the previous uploads
+	pub fn enable_streaming(&mut self, n_buffers: usize, buffer_channel_type: ChannelType, buffer_component_type: ComponentType) -> Result<(), TextureError> {
+		if n_buffers == 0 {
+			return Err(TextureError::UnsupportedOperation("Could not stream pixels without any PBO in the ring.".to_owned()));
+		}
+		if self.dim == TextureDimension::Tex2dMultisample {
+			return Err(TextureError::UnsupportedOperation("Could not stream pixels to a multisample texture.".to_owned()));
+		}
+		if self.format.is_compressed() {
+			return Err(TextureError::UnsupportedOperation("Could not stream pixels to a compressed texture.".to_owned()));
+		}
+		let size_in_bytes = self.bytes_of_level(0, buffer_channel_type, buffer_component_type);
+		let mut streaming_buffers = Vec::with_capacity(n_buffers);
+		for _ in 0..n_buffers {
+			streaming_buffers.push((PixelBuffer::new(self.glcore.clone(), self.width.get(), self.height.get(), self.depth.get(), size_in_bytes, buffer_channel_type, buffer_component_type, None)?, None));
+		}
+		self.streaming_buffers = streaming_buffers;
+		self.streaming_index = 0;
+		Ok(())
 	}
 
-	fn get_bytes_of_texture(&self) -> usize {
-		self.bytes_of_texture
+	/// Discard the PBO ring created by `enable_streaming()`
+	pub fn disable_streaming(&mut self) {
+		self.streaming_buffers.clear();
+		self.streaming_index = 0;
 	}
 
-	fn has_mipmap(&self) -> bool {
-		self.has_mipmap
+	/// Is the texture streaming with a PBO ring
+	pub fn is_streaming(&self) -> bool {
+		!self.streaming_buffers.is_empty()
 	}
 
-	fn get_pixel_buffer(&self) -> Option<&dyn GenericPixelBuffer> {
-		if let Some(pixel_buffer) = &self.pixel_buffer {
-			Some(pixel_buffer)
-		} else {
-			None
+	/// Map the least-recently-used PBO of the streaming ring for writing, waits for its fence if the GPU is still reading it.
+	/// Unmap it and call `submit()` to upload the pixels.
+	pub fn map_write_next<'a>(&'a mut self) -> Result<(BufferBind<'a>, BufferMapping<'a>, *mut c_void), TextureError> {
+		if self.streaming_buffers.is_empty() {
+			return Err(TextureError::UnsupportedOperation("The texture isn't streaming, call `enable_streaming()` first.".to_owned()));
+		}
+		let (pixel_buffer, fence) = &mut self.streaming_buffers[self.streaming_index];
+		if let Some(fence) = fence.take() {
+			fence.wait_forever()?;
 		}
+		let bind = pixel_buffer.bind()?;
+		let (mapping, address) = bind.map(MapAccess::WriteOnly)?;
+		Ok((bind, mapping, address))
 	}
 
-	fn create_pixel_buffer(&mut self, buffer_channel_type: ChannelType, buffer_component_type: ComponentType, initial_data: Option<*const c_void>) -> Result<(), TextureError> {
-		self.pixel_buffer = Some(PixelBuffer::new(self.glcore.clone(), self.width, self.height, self.depth, self.bytes_of_texture, buffer_channel_type, buffer_component_type, initial_data)?);
+	/// Upload the pixels from the PBO mapped by `map_write_next()`, fence it and move on to the next PBO of the ring
+	pub fn submit(&mut self) -> Result<(), TextureError> {
+		if self.streaming_buffers.is_empty() {
+			return Err(TextureError::UnsupportedOperation("The texture isn't streaming, call `enable_streaming()` first.".to_owned()));
+		}
+		let index = self.streaming_index;
+		let (pixel_buffer, _) = &self.streaming_buffers[index];
+		let bind_pbo = pixel_buffer.bind()?;
+		unsafe {self.upload_texture(null(), pixel_buffer.get_channel_type(), pixel_buffer.get_component_type(), true)?};
+		bind_pbo.unbind();
+		self.streaming_buffers[index].1 = Some(Fence::new(self.glcore.clone())?);
+		self.streaming_index = (index + 1) % self.streaming_buffers.len();
 		Ok(())
 	}
 
-	fn drop_pixel_buffer(&mut self) {
-		self.pixel_buffer = None
+	/// Start packing the pixels of the texture into a dedicated PBO and fence it, returns immediately without waiting for the GPU
+	pub fn begin_async_readback(&self, buffer_channel_type: ChannelType, buffer_component_type: ComponentType) -> Result<ReadbackHandle, TextureError> {
+		let size_in_bytes = self.bytes_of_level(0, buffer_channel_type, buffer_component_type);
+		let buffer = Buffer::new(self.glcore.clone(), BufferTarget::PixelPackBuffer, size_in_bytes, BufferUsage::StreamRead, null())?;
+		let bind_pbo = buffer.bind()?;
+		unsafe {self.download_texture(std::ptr::null_mut::<c_void>(), buffer_channel_type, buffer_component_type)?};
+		bind_pbo.unbind();
+		let fence = Fence::new(self.glcore.clone())?;
+		Ok(ReadbackHandle {
+			buffer,
+			fence,
+		})
 	}
 
-	fn bind<'a>(&'a self) -> Result<TextureBind<'a>, TextureError> {
-		match self.get_dim() {
-			TextureDimension::Tex1d => TextureBind::new(self, TextureTarget::Tex1d),
-			TextureDimension::Tex2d => TextureBind::new(self, TextureTarget::Tex2d),
-			TextureDimension::Tex3d => TextureBind::new(self, TextureTarget::Tex3d),
-			TextureDimension::TexCube => panic!("Please use `bind_face()` to bind a cube map."),
-		}
+	/// Create a texture from an image, set `assume_srgb` for color maps so that 8-bit RGB/RGBA images get sRGB storage.
+	/// The 1- and 2-channel images, e.g. heightmaps, sample as red-only, use `from_image_with_grayscale_policy()` for the other options.
+	pub fn from_image<P: Pixel>(
+			glcore: Rc<GLCore>,
+			dim: TextureDimension,
+			img: &ImageBuffer<P, Vec<P::Subpixel>>,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+			assume_srgb: bool,
+		) -> Result<Self, TextureError> {
+		Self::from_image_with_grayscale_policy(glcore, dim, img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter, assume_srgb, GrayscalePolicy::KeepRed)
 	}
 
-	fn bind_face<'a>(&'a self, face: CubeMapFaces) -> Result<TextureBind<'a>, TextureError> {
-		match self.get_dim() {
-			TextureDimension::TexCube => {
-				match face {
-					CubeMapFaces::TexCubePosX => TextureBind::new(self, TextureTarget::TexCubePosX),
-					CubeMapFaces::TexCubeNegX => TextureBind::new(self, TextureTarget::TexCubeNegX),
-					CubeMapFaces::TexCubePosY => TextureBind::new(self, TextureTarget::TexCubePosY),
-					CubeMapFaces::TexCubeNegY => TextureBind::new(self, TextureTarget::TexCubeNegY),
-					CubeMapFaces::TexCubePosZ => TextureBind::new(self, TextureTarget::TexCubePosZ),
-					CubeMapFaces::TexCubeNegZ => TextureBind::new(self, TextureTarget::TexCubeNegZ),
-				}
-			}
-			_ => panic!("Please use `bind()` to bind an non-cube-map texture."),
-		}
+	/// Create a texture from an image like `from_image()`, `grayscale_policy` decides how the 1- and 2-channel images sample, see `GrayscalePolicy`
+	pub fn from_image_with_grayscale_policy<P: Pixel>(
+			glcore: Rc<GLCore>,
+			dim: TextureDimension,
+			img: &ImageBuffer<P, Vec<P::Subpixel>>,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+			assume_srgb: bool,
+			grayscale_policy: GrayscalePolicy,
+		) -> Result<Self, TextureError> {
+		if P::CHANNEL_COUNT <= 2 && grayscale_policy == GrayscalePolicy::ExpandToRgb {
+			let mut ret = if P::CHANNEL_COUNT == 1 {
+				let img: ImageBuffer<Rgb<P::Subpixel>, Vec<P::Subpixel>> = ImageBuffer::from_fn(img.width(), img.height(), |x, y| img.get_pixel(x, y).to_rgb());
+				Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter, assume_srgb)?
+			} else {
+				let img: ImageBuffer<Rgba<P::Subpixel>, Vec<P::Subpixel>> = ImageBuffer::from_fn(img.width(), img.height(), |x, y| img.get_pixel(x, y).to_rgba());
+				Self::from_image(glcore, dim, &img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter, assume_srgb)?
+			};
+			ret.grayscale_policy = grayscale_policy;
+			return Ok(ret);
+		}
+		let mut buffer_channel_type = ChannelType::Rgb;
+		let mut buffer_component_type = ComponentType::U8;
+		get_channel_type_and_component_type_from_image_pixel::<P>(&mut buffer_channel_type, &mut buffer_component_type).unwrap();
+		let format = if assume_srgb {
+			TextureFormat::from_channel_type_and_component_type_srgb(buffer_channel_type, buffer_component_type).unwrap()
+		} else {
+			TextureFormat::from_channel_type_and_component_type(buffer_channel_type, buffer_component_type).unwrap()
+		};
+		let pixel_buffer = PixelBuffer::from_image(glcore.clone(), img)?;
+		let mut ret = match dim {
+			TextureDimension::Tex1d => {
+				assert_eq!(img.height(), 1);
+				Self::new_from_pixel_buffer(glcore, dim, format, img.width(), 1, 1, wrapping_s, wrapping_t, TextureWrapping::Repeat, has_mipmap, mag_filter, min_filter, pixel_buffer)?
+			}
+			TextureDimension::Tex2d => {
+				Self::new_from_pixel_buffer(glcore, dim, format, img.width(), img.height(), 1, wrapping_s, wrapping_t, TextureWrapping::Repeat, has_mipmap, mag_filter, min_filter, pixel_buffer)?
+			}
+			TextureDimension::TexCube => {
+				assert_eq!(img.width() * 6, img.height());
+				Self::new_from_pixel_buffer(glcore, dim, format, img.width(), img.width(), 1, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, has_mipmap, mag_filter, min_filter, pixel_buffer)?
+			}
+			other => panic!("Could not create a {other:?} texture from a `ImageBuffer`")
+		};
+		if P::CHANNEL_COUNT <= 2 && grayscale_policy == GrayscalePolicy::SwizzleLuminance {
+			let swizzle: [i32; 4] = if P::CHANNEL_COUNT == 1 {
+				[GL_RED as i32, GL_RED as i32, GL_RED as i32, GL_ONE as i32]
+			} else {
+				[GL_RED as i32, GL_RED as i32, GL_RED as i32, GL_GREEN as i32]
+			};
+			let bind_tex = ret.bind()?;
+			ret.glcore.glTexParameteriv(ret.dim.get_target() as u32, GL_TEXTURE_SWIZZLE_RGBA, swizzle.as_ptr())?;
+			bind_tex.unbind();
+		}
+		ret.grayscale_policy = grayscale_policy;
+		Ok(ret)
+	}
+
+	/// Get the policy of the 1- and 2-channel images the texture was created from
+	pub fn get_grayscale_policy(&self) -> GrayscalePolicy {
+		self.grayscale_policy
+	}
+
+	/// Create a texture from a file, `assume_srgb` only applies to plain images since DDS/KTX2 files carry their own format
+	pub fn from_file(
+			glcore: Rc<GLCore>,
+			path: &Path,
+			dim: TextureDimension,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+			assume_srgb: bool,
+		) -> Result<Self, TextureError> {
+		Self::from_file_with_grayscale_policy(glcore, path, dim, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter, assume_srgb, GrayscalePolicy::KeepRed)
+	}
+
+	/// Create a texture from a file like `from_file()`, `grayscale_policy` only applies to plain images like `assume_srgb`
+	pub fn from_file_with_grayscale_policy(
+			glcore: Rc<GLCore>,
+			path: &Path,
+			dim: TextureDimension,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+			assume_srgb: bool,
+			grayscale_policy: GrayscalePolicy,
+		) -> Result<Self, TextureError> {
+		let ext = path.extension().map_or_else(String::new, |ext| OsStr::to_str(ext).unwrap().to_lowercase());
+		match &ext[..] {
+			"dds" => {
+				let dds = DdsImage::from_file(path)?;
+				Self::from_dds(glcore, &dds, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)
+			}
+			"ktx2" => Self::from_ktx2_file(glcore, path, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter),
+			_ => Self::from_dynamic_image_with_grayscale_policy(glcore, dim, &load_dynamic_image(path)?, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter, assume_srgb, grayscale_policy),
+		}
+	}
+
+	/// Create a texture from the encoded bytes of an image file, e.g. extracted from an archive. The format is detected by the magic bytes if there's no `hint`.
+	/// The DDS and KTX2 files are recognized too, `assume_srgb` only applies to plain images.
+	pub fn from_memory(
+			glcore: Rc<GLCore>,
+			bytes: &[u8],
+			hint: Option<ImageFormatHint>,
+			dim: TextureDimension,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+			assume_srgb: bool,
+		) -> Result<Self, TextureError> {
+		Self::from_memory_with_grayscale_policy(glcore, bytes, hint, dim, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter, assume_srgb, GrayscalePolicy::KeepRed)
+	}
+
+	/// Create a texture from the encoded bytes like `from_memory()`, `grayscale_policy` only applies to plain images like `assume_srgb`
+	pub fn from_memory_with_grayscale_policy(
+			glcore: Rc<GLCore>,
+			bytes: &[u8],
+			hint: Option<ImageFormatHint>,
+			dim: TextureDimension,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+			assume_srgb: bool,
+			grayscale_policy: GrayscalePolicy,
+		) -> Result<Self, TextureError> {
+		if hint == Some(ImageFormat::Dds) || bytes.starts_with(b"DDS ") {
+			let dds = DdsImage::parse(bytes, "<memory>")?;
+			Self::from_dds(glcore, &dds, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)
+		} else if bytes.starts_with(&Ktx2Image::IDENTIFIER) {
+			Self::from_ktx2_bytes(glcore, bytes, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)
+		} else {
+			Self::from_dynamic_image_with_grayscale_policy(glcore, dim, &decode_dynamic_image(bytes, hint, "<memory>")?, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter, assume_srgb, grayscale_policy)
+		}
+	}
+
+	/// Create a texture from a decoded `DynamicImage`, e.g. an image that your code already holds
+	pub fn from_dynamic_image(
+			glcore: Rc<GLCore>,
+			dim: TextureDimension,
+			img: &DynamicImage,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+			assume_srgb: bool,
+		) -> Result<Self, TextureError> {
+		Self::from_dynamic_image_with_grayscale_policy(glcore, dim, img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter, assume_srgb, GrayscalePolicy::KeepRed)
+	}
+
+	/// Create a texture from a decoded `DynamicImage` like `from_dynamic_image()` with the `GrayscalePolicy` of the 1- and 2-channel images
+	pub fn from_dynamic_image_with_grayscale_policy(
+			glcore: Rc<GLCore>,
+			dim: TextureDimension,
+			img: &DynamicImage,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+			assume_srgb: bool,
+			grayscale_policy: GrayscalePolicy,
+		) -> Result<Self, TextureError> {
+		dispatch_dynamic_image!(img, |img| Self::from_image_with_grayscale_policy(glcore, dim, img, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter, assume_srgb, grayscale_policy))
+	}
+
+	/// Create a cube map from six images in the order of `CUBE_FACE_TARGETS` (+X, -X, +Y, -Y, +Z, -Z), all faces must be square and have the same size
+	pub fn cube_from_images<P: Pixel>(
+			glcore: Rc<GLCore>,
+			images: &[ImageBuffer<P, Vec<P::Subpixel>>; 6],
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		let size = images[0].width();
+		for (i, img) in images.iter().enumerate() {
+			if img.width() != img.height() {
+				return Err(LoadImageError::InvalidCubeMap(format!("Face {i} ({:?}) is {}x{}, the faces of a cube map must be square", CUBE_FACE_TARGETS[i], img.width(), img.height())).into());
+			}
+			if img.width() != size {
+				return Err(LoadImageError::InvalidCubeMap(format!("Face {i} ({:?}) is {}x{}, but face 0 is {size}x{size}", CUBE_FACE_TARGETS[i], img.width(), img.height())).into());
+			}
+		}
+		let mut buffer_channel_type = ChannelType::Rgb;
+		let mut buffer_component_type = ComponentType::U8;
+		get_channel_type_and_component_type_from_image_pixel::<P>(&mut buffer_channel_type, &mut buffer_component_type)?;
+		let format = TextureFormat::from_channel_type_and_component_type(buffer_channel_type, buffer_component_type).ok_or_else(|| LoadImageError::UnsupportedImageType(format!("No texture format for the pixel type {buffer_channel_type:?} {buffer_component_type:?}")))?;
+		let ret = Self::new_unallocates(glcore, TextureDimension::TexCube, format, size, size, 1, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, has_mipmap, mag_filter, min_filter)?;
+		let bind_tex = TextureBind::new(&ret, TextureTarget::TexCube)?;
+		for (img, target) in images.iter().zip(CUBE_FACE_TARGETS.iter()) {
+			let container = img.as_raw();
+			let data = unsafe {std::slice::from_raw_parts(container.as_ptr() as *const u8, size_of_val(&container[..]))};
+			ret.upload_image_level(*target as u32, 0, size, size, 1, Some((buffer_channel_type, buffer_component_type)), data)?;
+		}
+		if has_mipmap {
+			ret.glcore.glGenerateMipmap(TextureTarget::TexCube as u32)?;
+		}
+		bind_tex.unbind();
+		Ok(ret)
+	}
+
+	/// Create a cube map from six image files in the order of `CUBE_FACE_TARGETS` (+X, -X, +Y, -Y, +Z, -Z), all faces must have the same size and the same pixel type
+	pub fn cube_from_files(
+			glcore: Rc<GLCore>,
+			paths: &[&Path; 6],
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		let mut faces = Vec::with_capacity(6);
+		for path in paths.iter() {
+			faces.push(load_dynamic_image(path)?);
+		}
+		for (i, face) in faces.iter().enumerate() {
+			if face.color() != faces[0].color() {
+				return Err(LoadImageError::InvalidCubeMap(format!("Face {i} ({:?}) {:?} is {:?}, but face 0 {:?} is {:?}", CUBE_FACE_TARGETS[i], paths[i], face.color(), paths[0], faces[0].color())).into());
+			}
+		}
+		let faces: [DynamicImage; 6] = faces.try_into().unwrap();
+		match faces[0].color() {
+			ColorType::L8 => Self::cube_from_images(glcore, &faces.map(DynamicImage::into_luma8), has_mipmap, mag_filter, min_filter),
+			ColorType::La8 => Self::cube_from_images(glcore, &faces.map(DynamicImage::into_luma_alpha8), has_mipmap, mag_filter, min_filter),
+			ColorType::Rgb8 => Self::cube_from_images(glcore, &faces.map(DynamicImage::into_rgb8), has_mipmap, mag_filter, min_filter),
+			ColorType::Rgba8 => Self::cube_from_images(glcore, &faces.map(DynamicImage::into_rgba8), has_mipmap, mag_filter, min_filter),
+			ColorType::L16 => Self::cube_from_images(glcore, &faces.map(DynamicImage::into_luma16), has_mipmap, mag_filter, min_filter),
+			ColorType::La16 => Self::cube_from_images(glcore, &faces.map(DynamicImage::into_luma_alpha16), has_mipmap, mag_filter, min_filter),
+			ColorType::Rgb16 => Self::cube_from_images(glcore, &faces.map(DynamicImage::into_rgb16), has_mipmap, mag_filter, min_filter),
+			ColorType::Rgba16 => Self::cube_from_images(glcore, &faces.map(DynamicImage::into_rgba16), has_mipmap, mag_filter, min_filter),
+			ColorType::Rgb32F => Self::cube_from_images(glcore, &faces.map(DynamicImage::into_rgb32f), has_mipmap, mag_filter, min_filter),
+			ColorType::Rgba32F => Self::cube_from_images(glcore, &faces.map(DynamicImage::into_rgba32f), has_mipmap, mag_filter, min_filter),
+			_ => Err(LoadImageError::UnsupportedImageType(format!("Unsupported image type when loading cube map from {:?}", paths[0])))?,
+		}
+	}
+
+	/// Create a cube map from a single image of the horizontal cross layout, the image is a 4x3 grid of the faces:
+	/// ```text
+	///       +Y
+	///   -X  +Z  +X  -Z
+	///       -Y
+	/// ```
+	pub fn cube_from_cross_image<P: Pixel + 'static>(
+			glcore: Rc<GLCore>,
+			img: &ImageBuffer<P, Vec<P::Subpixel>>,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		/// The grid cell (column, row) of each face in the order of `CUBE_FACE_TARGETS`
+		const CROSS_CELLS: [(u32, u32); 6] = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+		let size = img.width() / 4;
+		if size == 0 || img.width() != size * 4 || img.height() != size * 3 {
+			return Err(LoadImageError::InvalidCubeMap(format!("The image is {}x{}, the horizontal cross layout needs a 4:3 image", img.width(), img.height())).into());
+		}
+		let mut buffer_channel_type = ChannelType::Rgb;
+		let mut buffer_component_type = ComponentType::U8;
+		get_channel_type_and_component_type_from_image_pixel::<P>(&mut buffer_channel_type, &mut buffer_component_type)?;
+		let format = TextureFormat::from_channel_type_and_component_type(buffer_channel_type, buffer_component_type).ok_or_else(|| LoadImageError::UnsupportedImageType(format!("No texture format for the pixel type {buffer_channel_type:?} {buffer_component_type:?}")))?;
+		let ret = Self::new_unallocates(glcore, TextureDimension::TexCube, format, size, size, 1, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, has_mipmap, mag_filter, min_filter)?;
+		let container = img.as_raw();
+		let data = unsafe {std::slice::from_raw_parts(container.as_ptr() as *const u8, size_of_val(&container[..]))};
+		let bind_tex = TextureBind::new(&ret, TextureTarget::TexCube)?;
+		for ((col, row), target) in CROSS_CELLS.iter().zip(CUBE_FACE_TARGETS.iter()) {
+			// Upload the face from its cell in place, the rows of the face are the rows of the whole image
+			let pixel_store = PixelStore::new(ret.glcore.as_ref(), &[(GL_UNPACK_ROW_LENGTH, img.width() as i32), (GL_UNPACK_SKIP_PIXELS, (col * size) as i32), (GL_UNPACK_SKIP_ROWS, (row * size) as i32)])?;
+			ret.upload_image_level(*target as u32, 0, size, size, 1, Some((buffer_channel_type, buffer_component_type)), data)?;
+			pixel_store.restore();
+		}
+		if has_mipmap {
+			ret.glcore.glGenerateMipmap(TextureTarget::TexCube as u32)?;
+		}
+		bind_tex.unbind();
+		Ok(ret)
+	}
+
+	/// Create a 3D texture from a stack of 2D slices, all slices must have the same size, the depth of the texture is the number of the slices.
+	/// If the volume is larger than `DEFAULT_VOLUME_UPLOAD_THRESHOLD` bytes, the slices are uploaded one by one.
+	pub fn volume_from_images<P: Pixel>(
+			glcore: Rc<GLCore>,
+			slices: &[ImageBuffer<P, Vec<P::Subpixel>>],
+			wrapping: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		Self::volume_from_images_with_threshold(glcore, slices, wrapping, has_mipmap, mag_filter, min_filter, DEFAULT_VOLUME_UPLOAD_THRESHOLD)
+	}
+
+	/// Create a 3D texture from a stack of 2D slices, the volume is packed into one staging buffer and uploaded at once if it's not larger than `threshold` bytes, otherwise the slices are uploaded one by one by `glTexSubImage3D()`
+	pub fn volume_from_images_with_threshold<P: Pixel>(
+			glcore: Rc<GLCore>,
+			slices: &[ImageBuffer<P, Vec<P::Subpixel>>],
+			wrapping: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+			threshold: usize,
+		) -> Result<Self, TextureError> {
+		let Some(first) = slices.first() else {
+			return Err(LoadImageError::InvalidVolume(0, "The volume has no slices".to_string()).into());
+		};
+		let (width, height) = first.dimensions();
+		for (i, slice) in slices.iter().enumerate() {
+			if slice.dimensions() != (width, height) {
+				return Err(LoadImageError::InvalidVolume(i, format!("Slice {i} is {}x{}, but slice 0 is {width}x{height}", slice.width(), slice.height())).into());
+			}
+		}
+		let depth = slices.len() as u32;
+		let mut buffer_channel_type = ChannelType::Rgb;
+		let mut buffer_component_type = ComponentType::U8;
+		get_channel_type_and_component_type_from_image_pixel::<P>(&mut buffer_channel_type, &mut buffer_component_type)?;
+		let format = TextureFormat::from_channel_type_and_component_type(buffer_channel_type, buffer_component_type).ok_or_else(|| LoadImageError::UnsupportedImageType(format!("No texture format for the pixel type {buffer_channel_type:?} {buffer_component_type:?}")))?;
+		let ret = Self::new_unallocates(glcore, TextureDimension::Tex3d, format, width, height, depth, wrapping, wrapping, wrapping, has_mipmap, mag_filter, min_filter)?;
+		let bind_tex = TextureBind::new(&ret, TextureTarget::Tex3d)?;
+		fn slice_bytes<P: Pixel>(slice: &ImageBuffer<P, Vec<P::Subpixel>>) -> &[u8] {
+			let container = slice.as_raw();
+			unsafe {std::slice::from_raw_parts(container.as_ptr() as *const u8, size_of_val(&container[..]))}
+		}
+		let bytes_of_volume: usize = slices.iter().map(|slice| slice_bytes(slice).len()).sum();
+		if bytes_of_volume <= threshold {
+			let mut data = Vec::with_capacity(bytes_of_volume);
+			for slice in slices.iter() {
+				data.extend_from_slice(slice_bytes(slice));
+			}
+			ret.upload_image_level(TextureTarget::Tex3d as u32, 0, width, height, depth, Some((buffer_channel_type, buffer_component_type)), &data)?;
+		} else {
+			let glcore = ret.glcore.as_ref();
+			let (w, h, d) = (width as i32, height as i32, depth as i32);
+			glcore.glTexImage3D(TextureTarget::Tex3d as u32, 0, format as i32, w, h, d, 0, buffer_channel_type as u32, buffer_component_type as u32, null())?;
+			let pixel_store = PixelStore::new(glcore, &[(GL_UNPACK_ALIGNMENT, 1)])?;
+			for (z, slice) in slices.iter().enumerate() {
+				let data = slice_bytes(slice);
+				glcore.glTexSubImage3D(TextureTarget::Tex3d as u32, 0, 0, 0, z as i32, w, h, 1, buffer_channel_type as u32, buffer_component_type as u32, data.as_ptr() as *const c_void)?;
+			}
+			pixel_store.restore();
+		}
+		if has_mipmap {
+			ret.glcore.glGenerateMipmap(TextureTarget::Tex3d as u32)?;
+		}
+		bind_tex.unbind();
+		Ok(ret)
+	}
+
+	/// Create a 3D texture from a stack of 2D image files, all slices must have the same size and the same pixel type
+	pub fn volume_from_files(
+			glcore: Rc<GLCore>,
+			paths: &[PathBuf],
+			wrapping: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		let mut slices = Vec::with_capacity(paths.len());
+		for path in paths.iter() {
+			slices.push(load_dynamic_image(path)?);
+		}
+		let Some(first) = slices.first() else {
+			return Err(LoadImageError::InvalidVolume(0, "The volume has no slices".to_string()).into());
+		};
+		let color = first.color();
+		for (i, slice) in slices.iter().enumerate() {
+			if slice.color() != color {
+				return Err(LoadImageError::InvalidVolume(i, format!("Slice {i} {:?} is {:?}, but slice 0 {:?} is {color:?}", paths[i], slice.color(), paths[0])).into());
+			}
+		}
+		match color {
+			ColorType::L8 => Self::volume_from_images(glcore, &slices.into_iter().map(DynamicImage::into_luma8).collect::<Vec<_>>(), wrapping, has_mipmap, mag_filter, min_filter),
+			ColorType::La8 => Self::volume_from_images(glcore, &slices.into_iter().map(DynamicImage::into_luma_alpha8).collect::<Vec<_>>(), wrapping, has_mipmap, mag_filter, min_filter),
+			ColorType::Rgb8 => Self::volume_from_images(glcore, &slices.into_iter().map(DynamicImage::into_rgb8).collect::<Vec<_>>(), wrapping, has_mipmap, mag_filter, min_filter),
+			ColorType::Rgba8 => Self::volume_from_images(glcore, &slices.into_iter().map(DynamicImage::into_rgba8).collect::<Vec<_>>(), wrapping, has_mipmap, mag_filter, min_filter),
+			ColorType::L16 => Self::volume_from_images(glcore, &slices.into_iter().map(DynamicImage::into_luma16).collect::<Vec<_>>(), wrapping, has_mipmap, mag_filter, min_filter),
+			ColorType::La16 => Self::volume_from_images(glcore, &slices.into_iter().map(DynamicImage::into_luma_alpha16).collect::<Vec<_>>(), wrapping, has_mipmap, mag_filter, min_filter),
+			ColorType::Rgb16 => Self::volume_from_images(glcore, &slices.into_iter().map(DynamicImage::into_rgb16).collect::<Vec<_>>(), wrapping, has_mipmap, mag_filter, min_filter),
+			ColorType::Rgba16 => Self::volume_from_images(glcore, &slices.into_iter().map(DynamicImage::into_rgba16).collect::<Vec<_>>(), wrapping, has_mipmap, mag_filter, min_filter),
+			ColorType::Rgb32F => Self::volume_from_images(glcore, &slices.into_iter().map(DynamicImage::into_rgb32f).collect::<Vec<_>>(), wrapping, has_mipmap, mag_filter, min_filter),
+			ColorType::Rgba32F => Self::volume_from_images(glcore, &slices.into_iter().map(DynamicImage::into_rgba32f).collect::<Vec<_>>(), wrapping, has_mipmap, mag_filter, min_filter),
+			_ => Err(LoadImageError::UnsupportedImageType(format!("Unsupported image type {color:?} when loading volume from {:?}", paths[0])))?,
+		}
+	}
+
+	/// Create a texture from a parsed DDS file, every stored mip level and every cube face is uploaded.
+	/// If the file has no mip chain, `has_mipmap` decides whether to generate the mipmaps for the uncompressed formats.
+	pub fn from_dds(
+			glcore: Rc<GLCore>,
+			dds: &DdsImage,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		let (dim, target) = if dds.is_cube_map {
+			(TextureDimension::TexCube, TextureTarget::TexCube)
+		} else {
+			(TextureDimension::Tex2d, TextureTarget::Tex2d)
+		};
+		let generate_mipmap = has_mipmap && dds.mip_levels == 1 && !dds.format.is_compressed();
+		let mut ret = Self::new_unallocates(glcore, dim, dds.format, dds.width, dds.height, 1, wrapping_s, wrapping_t, TextureWrapping::Repeat, dds.mip_levels > 1 || generate_mipmap, mag_filter, min_filter)?;
+		if !generate_mipmap {
+			ret.set_mip_range(0, dds.mip_levels - 1)?;
+		}
+		let glcore = ret.glcore.as_ref();
+		let bind_tex = TextureBind::new(&ret, target)?;
+		for (i, face) in dds.faces.iter().enumerate() {
+			let face_target = if dds.is_cube_map {
+				CUBE_FACE_TARGETS[i] as u32
+			} else {
+				target as u32
+			};
+			for (level, data) in face.iter().enumerate() {
+				let level = level as u32;
+				ret.upload_image_level(face_target, level, (dds.width >> level).max(1), (dds.height >> level).max(1), 1, dds.pixel_type, data)?;
+			}
+		}
+		if generate_mipmap {
+			glcore.glGenerateMipmap(target as u32)?;
+		}
+		bind_tex.unbind();
+		Ok(ret)
+	}
+
+	/// Create a texture from a parsed KTX2 file, every stored mip level and every cube face is uploaded.
+	/// If the file has no mip chain, the mipmaps are generated for the uncompressed formats if `has_mipmap` is true or the file asks for it.
+	pub fn from_ktx2(
+			glcore: Rc<GLCore>,
+			ktx2: &Ktx2Image,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		let target = match ktx2.dim {
+			TextureDimension::Tex1d => TextureTarget::Tex1d,
+			TextureDimension::Tex2d => TextureTarget::Tex2d,
+			TextureDimension::Tex3d => TextureTarget::Tex3d,
+			TextureDimension::TexCube => TextureTarget::TexCube,
+			TextureDimension::Tex2dMultisample => return Err(TextureError::UnsupportedOperation("KTX2 files couldn't be loaded as multisample textures".to_owned())),
+		};
+		let generate_mipmap = (has_mipmap || ktx2.generate_mipmap) && ktx2.mip_levels == 1 && !ktx2.format.is_compressed();
+		let mut ret = Self::new_unallocates(glcore, ktx2.dim, ktx2.format, ktx2.width, ktx2.height, ktx2.depth, wrapping_s, wrapping_t, TextureWrapping::Repeat, ktx2.mip_levels > 1 || generate_mipmap, mag_filter, min_filter)?;
+		if !generate_mipmap {
+			ret.set_mip_range(0, ktx2.mip_levels - 1)?;
+		}
+		let glcore = ret.glcore.as_ref();
+		let bind_tex = TextureBind::new(&ret, target)?;
+		for (level, data) in ktx2.levels.iter().enumerate() {
+			let level = level as u32;
+			let width = (ktx2.width >> level).max(1);
+			let height = (ktx2.height >> level).max(1);
+			let depth = (ktx2.depth >> level).max(1);
+			if ktx2.dim == TextureDimension::TexCube {
+				let bytes_of_face = data.len() / 6;
+				for (i, face_target) in CUBE_FACE_TARGETS.iter().enumerate() {
+					ret.upload_image_level(*face_target as u32, level, width, height, 1, ktx2.pixel_type, &data[i * bytes_of_face..(i + 1) * bytes_of_face])?;
+				}
+			} else {
+				ret.upload_image_level(target as u32, level, width, height, depth, ktx2.pixel_type, data)?;
+			}
+		}
+		if generate_mipmap {
+			glcore.glGenerateMipmap(target as u32)?;
+		}
+		bind_tex.unbind();
+		Ok(ret)
+	}
+
+	/// Create a texture from the bytes of a KTX2 file
+	pub fn from_ktx2_bytes(
+			glcore: Rc<GLCore>,
+			bytes: &[u8],
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		let ktx2 = Ktx2Image::parse(bytes, "<memory>")?;
+		Self::from_ktx2(glcore, &ktx2, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)
+	}
+
+	/// Create a texture from a KTX2 file
+	pub fn from_ktx2_file(
+			glcore: Rc<GLCore>,
+			path: &Path,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		let ktx2 = Ktx2Image::from_file(path)?;
+		Self::from_ktx2(glcore, &ktx2, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)
+	}
+
+	/// Create a texture view with `glTextureView()` that shares the storage of this texture, reinterpreting the format or slicing the mip levels and the cube map faces (the layers).
+	/// The source texture must use immutable storage and the `format` must be in the same view class. The storage is kept alive by OpenGL until all of the views are deleted,
+	/// so the view could outlive this texture. Dropping the view only deletes the view name. The wrapping modes of the view are `ClampToEdge`.
+	pub fn view(&self, dim: TextureDimension, format: TextureFormat, min_level: u32, num_levels: u32, min_layer: u32, num_layers: u32) -> Result<Self, TextureError> {
+		let Some(levels) = self.immutable_levels else {
+			return Err(TextureError::UnsupportedOperation("The texture views need the source texture to use immutable storage, create it with `new_*_storage()`.".to_owned()));
+		};
+		if num_levels == 0 || min_level.checked_add(num_levels).is_none_or(|level_end| level_end > levels) {
+			return Err(TextureError::InvalidMipLevel(min_level.saturating_add(num_levels), levels));
+		}
+		let layers = if self.dim == TextureDimension::TexCube {6} else {1};
+		let dim_valid = match (self.dim, dim) {
+			(TextureDimension::TexCube, TextureDimension::TexCube) => num_layers == 6,
+			(TextureDimension::TexCube, TextureDimension::Tex2d) => num_layers == 1,
+			(source, view) => source == view && num_layers == 1,
+		};
+		if !dim_valid || min_layer.checked_add(num_layers).is_none_or(|layer_end| layer_end > layers) {
+			return Err(TextureError::UnsupportedOperation(format!("Could not create a {dim:?} view of the layers {min_layer}..{} from a {:?} texture.", min_layer.saturating_add(num_layers), self.dim)));
+		}
+		let glcore = self.glcore.clone();
+		let mut name: u32 = 0;
+		glcore.glGenTextures(1, &mut name as *mut _)?;
+		glcore.glTextureView(name, dim.get_target() as u32, self.name, format as u32, min_level, num_levels, min_layer, num_layers)?;
+		let width = (self.width.get() >> min_level).max(1);
+		let mut height = (self.height.get() >> min_level).max(1);
+		let mut depth = (self.depth.get() >> min_level).max(1);
+		let mut size_mod = 1;
+		let target = Self::set_texture_params(glcore.clone(), name, dim, width, &mut height, &mut depth, &mut size_mod, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, self.mag_filter, self.min_filter)?;
+		glcore.glBindTexture(target as u32, 0)?;
+		let bytes_of_face = Self::calc_bytes_of_face(format, width, height, depth);
+		let ret = Self {
+			glcore,
+			name,
+			dim,
+			format,
+			width: Cell::new(width),
+			height: Cell::new(height),
+			depth: Cell::new(depth),
+			samples: self.samples,
+			fixed_sample_locations: self.fixed_sample_locations,
+			has_mipmap: Cell::new(num_levels > 1),
+			mag_filter: self.mag_filter,
+			min_filter: self.min_filter,
+			base_level: 0,
+			max_level: DEFAULT_MAX_MIP_LEVEL,
+			min_lod: -1000.0,
+			max_lod: 1000.0,
+			lod_bias: 0.0,
+			compare_func: None,
+			immutable_levels: Some(num_levels),
+			bytes_of_texture: Cell::new(bytes_of_face * size_mod),
+			bytes_of_face: Cell::new(bytes_of_face),
+			pixel_buffer: None,
+			streaming_buffers: Vec::new(),
+			streaming_index: 0,
+			grayscale_policy: GrayscalePolicy::KeepRed,
+			tracked_bytes: Cell::new(0),
+			_pixel_type: PhantomData,
+		};
+		MemoryTracker::track_new(MemoryCategory::Textures, 0);
+		ret.retrack();
+		Ok(ret)
+	}
+
+	/// Get the estimated GPU memory of the texture, summing up the mip chain, the cube map faces, the samples and the PBOs.
+	/// A texture view is counted like a texture though it shares the storage of the original texture.
+	pub fn gpu_bytes(&self) -> TextureMemoryReport {
+		let levels = self.immutable_levels.unwrap_or_else(|| self.level_count());
+		let (width, height, depth) = (self.width.get(), self.height.get(), self.depth.get());
+		let size_mod = if self.dim == TextureDimension::TexCube {6} else {1};
+		let texture_bytes: usize = (0..levels).map(|level| Self::calc_bytes_of_face(self.format, (width >> level).max(1), (height >> level).max(1), (depth >> level).max(1))).sum();
+		let pixel_buffer_bytes = self.pixel_buffer.iter().chain(self.streaming_buffers.iter().map(|(b, _)| b)).map(|b| b.size_in_bytes()).sum();
+		TextureMemoryReport {
+			texture_bytes: texture_bytes * size_mod * self.samples.max(1) as usize,
+			pixel_buffer_bytes,
+		}
+	}
+
+	/// Update the `MemoryTracker` with the current size of the texture storage
+	fn retrack(&self) {
+		let bytes = self.gpu_bytes().texture_bytes;
+		MemoryTracker::track_resize(MemoryCategory::Textures, self.tracked_bytes.replace(bytes), bytes);
+	}
+
+	/// Re-specify the level 0 of the storage without data by the null pointers, including every face of a cube map. The mip chain is allocated by `glGenerateMipmap()` if the texture has mipmaps
+	fn allocate_storage(&self) -> Result<(), TextureError> {
+		let glcore = self.glcore.as_ref();
+		let (buffer_channel_type, buffer_component_type) = self.format.allocation_pixel_type();
+		let (channel_type, component_type) = (buffer_channel_type as u32, buffer_component_type as u32);
+		let (format, width, height, depth) = (self.format as i32, self.width.get() as i32, self.height.get() as i32, self.depth.get() as i32);
+		let bind_tex = self.bind()?;
+		match self.dim {
+			TextureDimension::Tex1d => glcore.glTexImage1D(TextureTarget::Tex1d as u32, 0, format, width, 0, channel_type, component_type, null())?,
+			TextureDimension::Tex2d => glcore.glTexImage2D(TextureTarget::Tex2d as u32, 0, format, width, height, 0, channel_type, component_type, null())?,
+			TextureDimension::Tex3d => glcore.glTexImage3D(TextureTarget::Tex3d as u32, 0, format, width, height, depth, 0, channel_type, component_type, null())?,
+			TextureDimension::TexCube => {
+				for face in CUBE_FACE_TARGETS {
+					glcore.glTexImage2D(face as u32, 0, format, width, height, 0, channel_type, component_type, null())?;
+				}
+			}
+			TextureDimension::Tex2dMultisample => glcore.glTexImage2DMultisample(TextureTarget::Tex2dMultisample as u32, self.samples as i32, self.format as u32, width, height, self.fixed_sample_locations as u8)?,
+		}
+		if self.has_mipmap.get() && self.dim != TextureDimension::Tex2dMultisample {
+			glcore.glGenerateMipmap(self.dim.get_target() as u32)?;
+		}
+		bind_tex.unbind();
+		Ok(())
+	}
+
+	/// Regenerate the mip chain from the level 0, e.g. after rendering into the texture through a `Framebuffer`, then the texture is marked as having mipmaps
+	pub fn generate_mipmap(&self) -> Result<(), TextureError> {
+		if self.dim == TextureDimension::Tex2dMultisample {
+			return Err(TextureError::UnsupportedOperation("Could not generate mipmaps for a multisample texture.".to_owned()));
+		}
+		let bind_tex = self.bind()?;
+		self.glcore.glGenerateMipmap(self.dim.get_target() as u32)?;
+		bind_tex.unbind();
+		self.has_mipmap.set(true);
+		self.retrack();
+		Ok(())
+	}
+
+	/// Bind a mip level of the texture to an image unit for the image load/store operations, e.g. the `image2D` uniforms of the compute shaders.
+	/// If `layered` is `false`, only the `layer` of an array, a cube map or a 3D texture is bound.
+	/// The `format` is how the shader reads and writes the pixels, it must be an image format and have the same size of pixel as the texture format.
+	pub fn bind_image_unit<'a>(&'a self, unit: u32, level: u32, layered: bool, layer: u32, access: ImageAccess, format: TextureFormat) -> Result<ImageBind<'a>, TextureError> {
+		if !format.is_image_format() {
+			return Err(TextureError::InvalidImageFormat(format));
+		}
+		let levels = self.level_count();
+		if level >= levels {
+			return Err(TextureError::InvalidMipLevel(level, levels));
+		}
+		ImageBind::new(self, unit, level, layered, layer, access, format)
+	}
+
+	/// Re-specify the storage of the texture with the new size, keeping the name, the format and the sampling parameters, so the `Rc` holders of the texture stay valid.
+	/// The pixels are unspecified after resizing, the PBOs are reallocated to match the new size. The textures with immutable storage couldn't be resized.
+	pub fn resize(&mut self, width: u32, height: u32, depth: u32) -> Result<(), TextureError> {
+		let pixel_type = self.pixel_buffer.as_ref().map(|b| (b.get_channel_type(), b.get_component_type()));
+		let streaming = self.streaming_buffers.first().map(|(b, _)| (self.streaming_buffers.len(), b.get_channel_type(), b.get_component_type()));
+		self.pixel_buffer = None;
+		self.disable_streaming();
+		let result = self.resize_storage(width, height, depth);
+		if let Some((buffer_channel_type, buffer_component_type)) = pixel_type {
+			self.create_pixel_buffer(buffer_channel_type, buffer_component_type, None)?;
+		}
+		if let Some((n_buffers, buffer_channel_type, buffer_component_type)) = streaming {
+			self.enable_streaming(n_buffers, buffer_channel_type, buffer_component_type)?;
+		}
+		result
+	}
+
+	/// Set `GL_TEXTURE_BASE_LEVEL` and `GL_TEXTURE_MAX_LEVEL` to sample only a part of the mip chain.
+	/// GL allows `max` to be beyond the levels of the texture, but it's likely a mistake unless it's `DEFAULT_MAX_MIP_LEVEL`, so it's only checked by the debug builds.
+	pub fn set_mip_range(&mut self, base: u32, max: u32) -> Result<(), TextureError> {
+		if base > max {
+			return Err(TextureError::InvalidMipRange(base, max));
+		}
+		debug_assert!(max < self.level_count() || max == DEFAULT_MAX_MIP_LEVEL, "The max mip level {max} is beyond the allocated {} levels of the texture {}.", self.level_count(), self.name);
+		let target = self.dim.get_target() as u32;
+		let bind_tex = TextureBind::new(&*self, self.dim.get_target())?;
+		self.glcore.glTexParameteri(target, GL_TEXTURE_BASE_LEVEL, base as i32)?;
+		self.glcore.glTexParameteri(target, GL_TEXTURE_MAX_LEVEL, max as i32)?;
+		bind_tex.unbind();
+		self.base_level = base;
+		self.max_level = max;
+		Ok(())
+	}
+
+	/// Set `GL_TEXTURE_MIN_LOD` and `GL_TEXTURE_MAX_LOD` to clamp the level of detail
+	pub fn set_lod_range(&mut self, min: f32, max: f32) -> Result<(), TextureError> {
+		let target = self.dim.get_target() as u32;
+		let bind_tex = TextureBind::new(&*self, self.dim.get_target())?;
+		self.glcore.glTexParameterf(target, GL_TEXTURE_MIN_LOD, min)?;
+		self.glcore.glTexParameterf(target, GL_TEXTURE_MAX_LOD, max)?;
+		bind_tex.unbind();
+		self.min_lod = min;
+		self.max_lod = max;
+		Ok(())
+	}
+
+	/// Set `GL_TEXTURE_LOD_BIAS` to bias the level of detail
+	pub fn set_lod_bias(&mut self, bias: f32) -> Result<(), TextureError> {
+		let target = self.dim.get_target() as u32;
+		let bind_tex = TextureBind::new(&*self, self.dim.get_target())?;
+		self.glcore.glTexParameterf(target, GL_TEXTURE_LOD_BIAS, bias)?;
+		bind_tex.unbind();
+		self.lod_bias = bias;
+		Ok(())
+	}
+
+	/// Set the depth compare function for sampling the depth texture by the shadow samplers, e.g. `sampler2DShadow`, `None` restores `GL_NONE` of `GL_TEXTURE_COMPARE_MODE`.
+	/// Fails with `TextureError::NotDepthFormat` if the format isn't a depth format.
+	///
+	/// ```ignore
+	/// let mut shadow_map = Texture::<BufferVecStatic<f32>, f32>::new_2d(glcore.clone(), TextureFormat::Depth, 1024, 1024, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, false, SamplerMagFilter::Linear, SamplerFilter::Linear, false, ChannelType::Depth, ComponentType::F32, None)?;
+	/// shadow_map.set_depth_compare(Some(CompareFunc::LEqual))?; // The linear filter blends the results of the 2x2 compares, the hardware PCF
+	/// material.others.insert("shadow_map".to_owned(), MaterialComponent::Texture(Rc::new(shadow_map))); // `uniform sampler2DShadow shadow_map;` of the shader
+	/// ```
+	pub fn set_depth_compare(&mut self, func: Option<CompareFunc>) -> Result<(), TextureError> {
+		if func.is_some() && !self.format.is_depth() {
+			return Err(TextureError::NotDepthFormat(self.format));
+		}
+		let target = self.dim.get_target() as u32;
+		let bind_tex = TextureBind::new(&*self, self.dim.get_target())?;
+		if let Some(func) = func {
+			self.glcore.glTexParameteri(target, GL_TEXTURE_COMPARE_MODE, GL_COMPARE_REF_TO_TEXTURE as i32)?;
+			self.glcore.glTexParameteri(target, GL_TEXTURE_COMPARE_FUNC, func as i32)?;
+		} else {
+			self.glcore.glTexParameteri(target, GL_TEXTURE_COMPARE_MODE, GL_NONE as i32)?;
+		}
+		bind_tex.unbind();
+		self.compare_func = func;
+		Ok(())
+	}
+
+	/// Get the depth compare function, `None` if the compare mode is disabled
+	pub fn get_depth_compare(&self) -> Option<CompareFunc> {
+		self.compare_func
+	}
+
+	/// Get the base mip level and the max mip level
+	pub fn get_mip_range(&self) -> (u32, u32) {
+		(self.base_level, self.max_level)
+	}
+
+	/// Get the min and max level of detail
+	pub fn get_lod_range(&self) -> (f32, f32) {
+		(self.min_lod, self.max_lod)
+	}
+
+	/// Get the level of detail bias
+	pub fn get_lod_bias(&self) -> f32 {
+		self.lod_bias
+	}
+
+	/// Upload the image of a mip level to the bound texture, the block compressed formats (`pixel_type` is `None`) are uploaded by `glCompressedTexImage*()`.
+	/// The rows of the image are tightly packed, `data` must hold the whole image, e.g. all of the blocks of the compressed level.
+	fn upload_image_level(&self, target: u32, level: u32, width: u32, height: u32, depth: u32, pixel_type: Option<(ChannelType, ComponentType)>, data: &[u8]) -> Result<(), TextureError> {
+		let expected = bytes_of_image(self.format, pixel_type, width, height).and_then(|bytes| bytes.checked_mul(depth.max(1) as usize));
+		let image_size = match expected.map(i32::try_from) {
+			Some(Ok(image_size)) if data.len() >= image_size as usize => image_size,
+			_ => return Err(TextureError::InitialDataTooSmall(data.len(), expected.unwrap_or(usize::MAX))),
+		};
+		let glcore = self.glcore.as_ref();
+		let (level, width, height, depth) = (level as i32, width as i32, height as i32, depth as i32);
+		let pointer = data.as_ptr() as *const c_void;
+		let pixel_store = PixelStore::new(glcore, &[(GL_UNPACK_ALIGNMENT, 1)])?;
+		match (self.dim, pixel_type) {
+			(TextureDimension::Tex1d, None) => glcore.glCompressedTexImage1D(target, level, self.format as u32, width, 0, image_size, pointer)?,
+			(TextureDimension::Tex1d, Some((channel_type, component_type))) => glcore.glTexImage1D(target, level, self.format as i32, width, 0, channel_type as u32, component_type as u32, pointer)?,
+			(TextureDimension::Tex3d, None) => glcore.glCompressedTexImage3D(target, level, self.format as u32, width, height, depth, 0, image_size, pointer)?,
+			(TextureDimension::Tex3d, Some((channel_type, component_type))) => glcore.glTexImage3D(target, level, self.format as i32, width, height, depth, 0, channel_type as u32, component_type as u32, pointer)?,
+			(_, None) => glcore.glCompressedTexImage2D(target, level, self.format as u32, width, height, 0, image_size, pointer)?,
+			(_, Some((channel_type, component_type))) => glcore.glTexImage2D(target, level, self.format as i32, width, height, 0, channel_type as u32, component_type as u32, pointer)?,
+		}
+		pixel_store.restore();
+		Ok(())
+	}
+
+	/// Create a texture from a DDS file
+	pub fn from_dds_file(
+			glcore: Rc<GLCore>,
+			path: &Path,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			has_mipmap: bool,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		let dds = DdsImage::from_file(path)?;
+		Self::from_dds(glcore, &dds, wrapping_s, wrapping_t, has_mipmap, mag_filter, min_filter)
+	}
+}
+
+impl<B, BP> GenericTexture for Texture<B, BP>
+where
+	B: BufferVec<BP>,
+	BP: BufferVecItem {
+	fn get_glcore(&self) -> &GLCore {
+		&self.glcore
+	}
+
+	fn get_name(&self) -> u32 {
+		self.name
+	}
+
+	fn get_dim(&self) -> TextureDimension {
+		self.dim
+	}
+
+	fn get_width(&self) -> u32 {
+		self.width.get()
+	}
+
+	fn get_height(&self) -> u32 {
+		self.height.get()
+	}
+
+	fn get_depth(&self) -> u32 {
+		self.depth.get()
+	}
+
+	fn get_samples(&self) -> u32 {
+		self.samples
+	}
+
+	fn get_format(&self) -> TextureFormat {
+		self.format
+	}
+
+	fn get_bytes_of_face(&self) -> usize {
+		self.bytes_of_face.get()
+	}
+
+	fn get_bytes_of_texture(&self) -> usize {
+		self.bytes_of_texture.get()
+	}
+
+	fn has_mipmap(&self) -> bool {
+		self.has_mipmap.get()
+	}
+
+	fn level_count(&self) -> u32 {
+		if let Some(levels) = self.immutable_levels {
+			levels
+		} else if self.has_mipmap.get() && self.dim != TextureDimension::Tex2dMultisample {
+			32 - self.width.get().max(self.height.get()).max(self.depth.get()).max(1).leading_zeros()
+		} else {
+			1
+		}
+	}
+
+	fn is_immutable(&self) -> bool {
+		self.immutable_levels.is_some()
+	}
+
+	fn resize_storage(&self, width: u32, height: u32, depth: u32) -> Result<(), TextureError> {
+		if self.immutable_levels.is_some() {
+			return Err(TextureError::UnsupportedOperation("Could not resize a texture with immutable storage, create a new one instead.".to_owned()));
+		}
+		if self.format.is_compressed() {
+			return Err(TextureError::UnsupportedOperation("Could not resize a compressed texture, load it again instead.".to_owned()));
+		}
+		let (height, depth) = match self.dim {
+			TextureDimension::Tex1d => (1, 1),
+			TextureDimension::Tex2d | TextureDimension::Tex2dMultisample => (height, 1),
+			TextureDimension::Tex3d => (height, depth),
+			TextureDimension::TexCube => (width, 1),
+		};
+		if width == 0 || height == 0 || depth == 0 {
+			return Err(TextureError::InvalidSize(self.dim, [width, height, depth]));
+		}
+		if self.pixel_buffer.is_some() || self.is_streaming() {
+			return Err(TextureError::UnsupportedOperation("Could not resize the storage of a texture with PBOs in place, use `Texture::resize()` instead.".to_owned()));
+		}
+		self.width.set(width);
+		self.height.set(height);
+		self.depth.set(depth);
+		let size_mod = if self.dim == TextureDimension::TexCube {6} else {1};
+		let bytes_of_face = Self::calc_bytes_of_face(self.format, width, height, depth);
+		self.bytes_of_face.set(bytes_of_face);
+		self.bytes_of_texture.set(bytes_of_face * size_mod);
+		self.retrack();
+		self.allocate_storage()
+	}
+
+	fn get_pixel_buffer(&self) -> Option<&dyn GenericPixelBuffer> {
+		if let Some(pixel_buffer) = &self.pixel_buffer {
+			Some(pixel_buffer)
+		} else {
+			None
+		}
+	}
+
+	fn create_pixel_buffer(&mut self, buffer_channel_type: ChannelType, buffer_component_type: ComponentType, initial_data: Option<*const c_void>) -> Result<(), TextureError> {
+		self.format.check_pixel_type(buffer_channel_type, buffer_component_type)?;
+		let size_in_bytes = self.bytes_of_level(0, buffer_channel_type, buffer_component_type);
+		self.pixel_buffer = Some(PixelBuffer::new(self.glcore.clone(), self.width.get(), self.height.get(), self.depth.get(), size_in_bytes, buffer_channel_type, buffer_component_type, initial_data)?);
+		Ok(())
+	}
+
+	fn drop_pixel_buffer(&mut self) {
+		self.pixel_buffer = None
+	}
+
+	unsafe fn upload_level(&self, level: u32, face: Option<CubeMapFaces>, size: [u32; 3], data: *const c_void, buffer_channel_type: ChannelType, buffer_component_type: ComponentType) -> Result<(), TextureError> {
+		let full_levels = self.immutable_levels.unwrap_or(32 - self.width.get().max(self.height.get()).max(self.depth.get()).max(1).leading_zeros());
+		if level >= full_levels {
+			return Err(TextureError::InvalidMipLevel(level, full_levels));
+		}
+		let expected = [(self.width.get() >> level).max(1), (self.height.get() >> level).max(1), (self.depth.get() >> level).max(1)];
+		if size != expected {
+			return Err(TextureError::InvalidLevelSize(level, size, expected));
+		}
+		let target = match (self.dim, face) {
+			(TextureDimension::Tex2dMultisample, _) => return Err(TextureError::UnsupportedOperation("Could not upload pixels to a multisample texture.".to_owned())),
+			(TextureDimension::TexCube, Some(face)) => face as u32,
+			(TextureDimension::TexCube, None) => return Err(TextureError::UnsupportedOperation("Please specify the face to upload a level of a cube map.".to_owned())),
+			(_, Some(_)) => return Err(TextureError::UnsupportedOperation("Only the cube maps have faces to upload.".to_owned())),
+			(dim, None) => dim.get_target() as u32,
+		};
+		self.format.check_pixel_type(buffer_channel_type, buffer_component_type)?;
+		let glcore = self.glcore.as_ref();
+		let (level, width, height, depth) = (level as i32, size[0] as i32, size[1] as i32, size[2] as i32);
+		let bind_tex = TextureBind::new(self, self.dim.get_target())?;
+		let pixel_store = PixelStore::unpack(glcore, 1, 0)?;
+		match (self.dim, self.is_immutable()) {
+			(TextureDimension::Tex1d, false) => glcore.glTexImage1D(target, level, self.format as i32, width, 0, buffer_channel_type as u32, buffer_component_type as u32, data)?,
+			(TextureDimension::Tex1d, true) => glcore.glTexSubImage1D(target, level, 0, width, buffer_channel_type as u32, buffer_component_type as u32, data)?,
+			(TextureDimension::Tex3d, false) => glcore.glTexImage3D(target, level, self.format as i32, width, height, depth, 0, buffer_channel_type as u32, buffer_component_type as u32, data)?,
+			(TextureDimension::Tex3d, true) => glcore.glTexSubImage3D(target, level, 0, 0, 0, width, height, depth, buffer_channel_type as u32, buffer_component_type as u32, data)?,
+			(_, false) => glcore.glTexImage2D(target, level, self.format as i32, width, height, 0, buffer_channel_type as u32, buffer_component_type as u32, data)?,
+			(_, true) => glcore.glTexSubImage2D(target, level, 0, 0, width, height, buffer_channel_type as u32, buffer_component_type as u32, data)?,
+		}
+		pixel_store.restore();
+		bind_tex.unbind();
+		if level > 0 {
+			self.has_mipmap.set(true);
+			self.retrack();
+		}
+		Ok(())
+	}
+
+	fn bind<'a>(&'a self) -> Result<TextureBind<'a>, TextureError> {
+		match self.get_dim() {
+			TextureDimension::Tex1d => TextureBind::new(self, TextureTarget::Tex1d),
+			TextureDimension::Tex2d => TextureBind::new(self, TextureTarget::Tex2d),
+			TextureDimension::Tex3d => TextureBind::new(self, TextureTarget::Tex3d),
+			TextureDimension::TexCube => TextureBind::new(self, TextureTarget::TexCube),
+			TextureDimension::Tex2dMultisample => TextureBind::new(self, TextureTarget::Tex2dMultisample),
+		}
+	}
+
+	fn bind_face<'a>(&'a self, _face: CubeMapFaces) -> Result<TextureBind<'a>, TextureError> {
+		match self.get_dim() {
+			// The cube map is always bound to `GL_TEXTURE_CUBE_MAP`, the face target is only for the image operations
+			TextureDimension::TexCube => TextureBind::new(self, TextureTarget::TexCube),
+			_ => panic!("Please use `bind()` to bind an non-cube-map texture."),
+		}
 	}
 }
 
@@ -1161,6 +3332,129 @@ where
 	BP: BufferVecItem {
 	fn drop(&mut self) {
 		self.glcore.glDeleteTextures(1, &self.name as *const u32).unwrap();
+		MemoryTracker::track_delete(MemoryCategory::Textures, self.tracked_bytes.get());
+	}
+}
+
+impl ReadbackHandle {
+	/// Check if the GPU finished packing the pixels, taking the pixels won't stall if it did
+	pub fn is_ready(&self) -> Result<bool, GLCoreError> {
+		self.fence.is_signaled()
+	}
+
+	/// Get the size of the pixels in bytes
+	pub fn size(&self) -> usize {
+		self.buffer.size()
+	}
+
+	/// Copy out the pixels right away, the mapping stalls until the packing is done if `is_ready()` hasn't returned `true`
+	pub fn take(self) -> Result<Vec<u8>, GLCoreError> {
+		let bind = self.buffer.bind()?;
+		let (mapping, address) = bind.map(MapAccess::ReadOnly)?;
+		let pixels = unsafe {std::slice::from_raw_parts(address as *const u8, self.buffer.size())}.to_vec();
+		mapping.unmap();
+		bind.unbind();
+		Ok(pixels)
+	}
+
+	/// Wait for the fence, then copy out the pixels
+	pub fn take_blocking(self) -> Result<Vec<u8>, GLCoreError> {
+		self.fence.wait_forever()?;
+		self.take()
+	}
+}
+
+impl<'a, B, BP> TextureBuilder<'a, B, BP>
+where
+	B: BufferVec<BP>,
+	BP: BufferVecItem {
+	/// Set the wrapping mode of all of the axes
+	pub fn wrap(self, wrapping: TextureWrapping) -> Self {
+		self.wrap_each(wrapping, wrapping, wrapping)
+	}
+
+	/// Set the wrapping mode of the S, T, R axes separately
+	pub fn wrap_each(mut self, wrapping_s: TextureWrapping, wrapping_t: TextureWrapping, wrapping_r: TextureWrapping) -> Self {
+		self.wrapping_s = wrapping_s;
+		self.wrapping_t = wrapping_t;
+		self.wrapping_r = wrapping_r;
+		self
+	}
+
+	/// Set the magnification filter and the minification filter
+	pub fn filters(mut self, mag_filter: SamplerMagFilter, min_filter: SamplerFilter) -> Self {
+		self.mag_filter = mag_filter;
+		self.min_filter = min_filter;
+		self
+	}
+
+	/// Generate the mipmaps after uploading the pixels
+	pub fn mipmaps(mut self, has_mipmap: bool) -> Self {
+		self.has_mipmap = has_mipmap;
+		self
+	}
+
+	/// Set the channel type and the component type of the pixels to upload, by default it's a pixel type that the format accepts
+	pub fn pixel_format(mut self, buffer_channel_type: ChannelType, buffer_component_type: ComponentType) -> Self {
+		self.pixel_type = Some((buffer_channel_type, buffer_component_type));
+		self
+	}
+
+	/// Set the initial pixels of the texture, the rows are tightly packed and the cube map faces are in the order of `CUBE_FACE_TARGETS`
+	pub fn initial_data<T: Copy>(mut self, data: &'a [T], buffer_channel_type: ChannelType, buffer_component_type: ComponentType) -> Self {
+		self.initial_data = Some(data.as_ptr() as *const c_void);
+		self.initial_data_len = Some(size_of_val(data));
+		self.pixel_format(buffer_channel_type, buffer_component_type)
+	}
+
+	/// Set the initial pixels from a raw pointer, the size couldn't be validated
+	fn initial_data_ptr(mut self, initial_data: Option<*const c_void>) -> Self {
+		self.initial_data = initial_data;
+		self.initial_data_len = None;
+		self
+	}
+
+	/// Create a PBO for the texture to update the pixels asynchronously
+	pub fn pixel_buffer(mut self, buffering: bool) -> Self {
+		self.buffering = buffering;
+		self
+	}
+
+	/// Validate the parameters and create the texture
+	pub fn build(self) -> Result<Texture<B, BP>, TextureError> {
+		let (width, height, depth) = (self.width, self.height, self.depth);
+		let size_valid = width > 0 && height > 0 && depth > 0 && match self.dim {
+			TextureDimension::Tex1d => height == 1 && depth == 1,
+			TextureDimension::Tex2d => depth == 1,
+			TextureDimension::Tex3d => true,
+			TextureDimension::TexCube => width == height && depth == 1,
+			TextureDimension::Tex2dMultisample => return Err(TextureError::UnsupportedOperation("Please use `Texture::new_2d_multisample()` to create a multisample texture.".to_owned())),
+		};
+		if !size_valid {
+			return Err(TextureError::InvalidSize(self.dim, [width, height, depth]));
+		}
+		let (buffer_channel_type, buffer_component_type) = self.pixel_type.unwrap_or_else(|| self.format.allocation_pixel_type());
+		self.format.check_pixel_type(buffer_channel_type, buffer_component_type)?;
+		if let Some(data_len) = self.initial_data_len {
+			let pitch = width as usize * size_of_pixel(buffer_channel_type, buffer_component_type);
+			let bytes_of_face = pitch * height as usize;
+			let faces = if self.dim == TextureDimension::TexCube {6} else {1};
+			let expected = bytes_of_face * depth as usize * faces;
+			if data_len < expected {
+				return Err(TextureError::InitialDataTooSmall(data_len, expected));
+			}
+		}
+		let mut ret = Texture::<B, BP>::new_unallocates(self.glcore, self.dim, self.format, width, height, depth, self.wrapping_s, self.wrapping_t, self.wrapping_r, self.has_mipmap, self.mag_filter, self.min_filter)?;
+		if self.buffering {
+			ret.create_pixel_buffer(buffer_channel_type, buffer_component_type, self.initial_data)?;
+			ret.unpack_pixel_buffer(None, self.has_mipmap)?;
+		} else if let Some(data_pointer) = self.initial_data {
+			unsafe {ret.upload_texture(data_pointer, buffer_channel_type, buffer_component_type, self.has_mipmap)?};
+		} else {
+			let empty_data = vec![0u8; ret.bytes_of_level(0, buffer_channel_type, buffer_component_type)];
+			unsafe {ret.upload_texture(empty_data.as_ptr() as *const c_void, buffer_channel_type, buffer_component_type, self.has_mipmap)?};
+		}
+		Ok(ret)
 	}
 }
 
@@ -1184,6 +3478,307 @@ impl Drop for TextureBind<'_> {
 	}
 }
 
+/// Is a buffer bound to `GL_PIXEL_UNPACK_BUFFER`, the data pointers of the uploads are the offsets of the buffer if so
+fn is_pixel_unpack_buffer_bound(glcore: &GLCore) -> Result<bool, GLCoreError> {
+	let mut binding: i32 = 0;
+	glcore.glGetIntegerv(GL_PIXEL_UNPACK_BUFFER_BINDING, &mut binding as *mut _)?;
+	Ok(binding != 0)
+}
+
+impl<'a> PixelStore<'a> {
+	/// Set the pixel storage modes, the previous values are restored when dropped.
+	/// If it fails halfway, the modes that were already set are restored before returning the error.
+	pub(crate) fn new(glcore: &'a GLCore, modes: &[(u32, i32)]) -> Result<Self, GLCoreError> {
+		let mut ret = Self {
+			glcore,
+			saved: Vec::with_capacity(modes.len()),
+		};
+		for (pname, value) in modes.iter() {
+			let mut prev: i32 = 0;
+			glcore.glGetIntegerv(*pname, &mut prev as *mut _)?;
+			ret.saved.push((*pname, prev));
+			glcore.glPixelStorei(*pname, *value)?;
+		}
+		Ok(ret)
+	}
+
+	/// Set the alignment of the rows and the row length for uploading, the `row_length` in pixels is 0 if the rows are as long as the width of the image
+	pub(crate) fn unpack(glcore: &'a GLCore, alignment: u32, row_length: u32) -> Result<Self, GLCoreError> {
+		Self::new(glcore, &[(GL_UNPACK_ALIGNMENT, alignment as i32), (GL_UNPACK_ROW_LENGTH, row_length as i32)])
+	}
+
+	/// Set the alignment of the rows and the row length for downloading, the `row_length` in pixels is 0 if the rows are as long as the width of the image
+	pub(crate) fn pack(glcore: &'a GLCore, alignment: u32, row_length: u32) -> Result<Self, GLCoreError> {
+		Self::new(glcore, &[(GL_PACK_ALIGNMENT, alignment as i32), (GL_PACK_ROW_LENGTH, row_length as i32)])
+	}
+
+	/// Explicitly restore the previous values.
+	pub(crate) fn restore(self) {}
+}
+
+impl Drop for PixelStore<'_> {
+	fn drop(&mut self) {
+		for (pname, value) in self.saved.iter().rev() {
+			self.glcore.glPixelStorei(*pname, *value).unwrap();
+		}
+	}
+}
+
+impl<'a> ImageBind<'a> {
+	/// Create a binding state of the texture to the image unit, utilizing the RAII rules to manage the binding state
+	fn new(texture: &'a dyn GenericTexture, unit: u32, level: u32, layered: bool, layer: u32, access: ImageAccess, format: TextureFormat) -> Result<Self, TextureError> {
+		texture.get_glcore().glBindImageTexture(unit, texture.get_name(), level as i32, layered as u8, layer as i32, access as u32, format as u32)?;
+		Ok(Self {
+			texture,
+			unit,
+		})
+	}
+
+	/// Get the image unit
+	pub fn get_unit(&self) -> u32 {
+		self.unit
+	}
+
+	/// Explicitly unbind the image.
+	pub fn unbind(self) {}
+}
+
+impl Drop for ImageBind<'_> {
+	fn drop(&mut self) {
+		self.texture.get_glcore().glBindImageTexture(self.unit, 0, 0, 0, 0, GL_READ_ONLY, GL_R8).unwrap();
+	}
+}
+
+impl TextureUnits {
+	/// Create the allocator, querying `GL_MAX_COMBINED_TEXTURE_IMAGE_UNITS` once
+	pub fn new(glcore: &GLCore) -> Result<Self, TextureError> {
+		let mut max_units: i32 = 0;
+		glcore.glGetIntegerv(GL_MAX_COMBINED_TEXTURE_IMAGE_UNITS, &mut max_units as *mut _)?;
+		Ok(Self::with_max_units(max_units.max(0) as u32))
+	}
+
+	/// Create the allocator with a known number of texture units
+	pub fn with_max_units(max_units: u32) -> Self {
+		Self {
+			max_units,
+			reserved: BitVec::repeat(false, max_units as usize),
+			allocated: BitVec::repeat(false, max_units as usize),
+		}
+	}
+
+	/// Get the number of the texture units
+	pub fn max_units(&self) -> u32 {
+		self.max_units
+	}
+
+	/// Reserve a fixed unit for an engine-level texture, e.g. a shadow map
+	pub fn reserve(&mut self, unit: u32) -> Result<(), TextureError> {
+		if unit >= self.max_units {
+			return Err(TextureError::InvalidTextureUnit(unit, self.max_units));
+		}
+		if !self.is_free(unit) {
+			return Err(TextureError::TextureUnitInUse(unit));
+		}
+		self.reserved.set(unit as usize, true);
+		Ok(())
+	}
+
+	/// Give back a reserved unit
+	pub fn unreserve(&mut self, unit: u32) {
+		if unit < self.max_units {
+			self.reserved.set(unit as usize, false);
+		}
+	}
+
+	/// Check if the unit is reserved
+	pub fn is_reserved(&self, unit: u32) -> bool {
+		unit < self.max_units && self.reserved[unit as usize]
+	}
+
+	/// Check if the unit is neither reserved nor allocated
+	pub fn is_free(&self, unit: u32) -> bool {
+		unit < self.max_units && !self.reserved[unit as usize] && !self.allocated[unit as usize]
+	}
+
+	/// Get the number of the free units
+	pub fn num_free(&self) -> u32 {
+		(0..self.max_units).filter(|&unit| self.is_free(unit)).count() as u32
+	}
+
+	/// Allocate the lowest free unit as a transient unit, returns `None` if all of the units are in use
+	pub fn allocate(&mut self) -> Option<u32> {
+		let unit = (0..self.max_units).find(|&unit| self.is_free(unit))?;
+		self.allocated.set(unit as usize, true);
+		Some(unit)
+	}
+
+	/// Release a transient unit
+	pub fn release(&mut self, unit: u32) {
+		if unit < self.max_units {
+			self.allocated.set(unit as usize, false);
+		}
+	}
+
+	/// Release all of the transient units, the reserved units are kept
+	pub fn release_all(&mut self) {
+		self.allocated.fill(false);
+	}
+}
+
+impl Sampler {
+	/// Create a new sampler object
+	pub fn new(
+			glcore: Rc<GLCore>,
+			wrapping_s: TextureWrapping,
+			wrapping_t: TextureWrapping,
+			wrapping_r: TextureWrapping,
+			mag_filter: SamplerMagFilter,
+			min_filter: SamplerFilter,
+		) -> Result<Self, TextureError> {
+		let mut name: u32 = 0;
+		glcore.glGenSamplers(1, &mut name as *mut _)?;
+		let mut ret = Self {
+			glcore,
+			name,
+			wrapping_s,
+			wrapping_t,
+			wrapping_r,
+			mag_filter,
+			min_filter,
+			max_anisotropy: 1.0,
+			min_lod: -1000.0,
+			max_lod: 1000.0,
+			lod_bias: 0.0,
+			compare_func: None,
+		};
+		ret.set_wrapping(wrapping_s, wrapping_t, wrapping_r)?;
+		ret.set_filter(mag_filter, min_filter)?;
+		Ok(ret)
+	}
+
+	/// Get the internal name of the sampler
+	pub fn get_name(&self) -> u32 {
+		self.name
+	}
+
+	/// Set the wrapping mode of the S, T, R coordinates
+	pub fn set_wrapping(&mut self, wrapping_s: TextureWrapping, wrapping_t: TextureWrapping, wrapping_r: TextureWrapping) -> Result<(), TextureError> {
+		self.glcore.glSamplerParameteri(self.name, GL_TEXTURE_WRAP_S, wrapping_s as i32)?;
+		self.glcore.glSamplerParameteri(self.name, GL_TEXTURE_WRAP_T, wrapping_t as i32)?;
+		self.glcore.glSamplerParameteri(self.name, GL_TEXTURE_WRAP_R, wrapping_r as i32)?;
+		self.wrapping_s = wrapping_s;
+		self.wrapping_t = wrapping_t;
+		self.wrapping_r = wrapping_r;
+		Ok(())
+	}
+
+	/// Set the magnification filter and the minification filter
+	pub fn set_filter(&mut self, mag_filter: SamplerMagFilter, min_filter: SamplerFilter) -> Result<(), TextureError> {
+		self.glcore.glSamplerParameteri(self.name, GL_TEXTURE_MAG_FILTER, mag_filter as i32)?;
+		self.glcore.glSamplerParameteri(self.name, GL_TEXTURE_MIN_FILTER, min_filter as i32)?;
+		self.mag_filter = mag_filter;
+		self.min_filter = min_filter;
+		Ok(())
+	}
+
+	/// Set the max anisotropy, 1.0 disables the anisotropic filtering
+	pub fn set_max_anisotropy(&mut self, max_anisotropy: f32) -> Result<(), TextureError> {
+		self.glcore.glSamplerParameterf(self.name, GL_TEXTURE_MAX_ANISOTROPY, max_anisotropy)?;
+		self.max_anisotropy = max_anisotropy;
+		Ok(())
+	}
+
+	/// Set `GL_TEXTURE_MIN_LOD` and `GL_TEXTURE_MAX_LOD` to clamp the level of detail
+	pub fn set_lod_range(&mut self, min: f32, max: f32) -> Result<(), TextureError> {
+		self.glcore.glSamplerParameterf(self.name, GL_TEXTURE_MIN_LOD, min)?;
+		self.glcore.glSamplerParameterf(self.name, GL_TEXTURE_MAX_LOD, max)?;
+		self.min_lod = min;
+		self.max_lod = max;
+		Ok(())
+	}
+
+	/// Set `GL_TEXTURE_LOD_BIAS` to bias the level of detail
+	pub fn set_lod_bias(&mut self, bias: f32) -> Result<(), TextureError> {
+		self.glcore.glSamplerParameterf(self.name, GL_TEXTURE_LOD_BIAS, bias)?;
+		self.lod_bias = bias;
+		Ok(())
+	}
+
+	/// Set the depth compare function for the shadow samplers, `None` disables the compare mode
+	pub fn set_compare(&mut self, func: Option<CompareFunc>) -> Result<(), TextureError> {
+		if let Some(func) = func {
+			self.glcore.glSamplerParameteri(self.name, GL_TEXTURE_COMPARE_MODE, GL_COMPARE_REF_TO_TEXTURE as i32)?;
+			self.glcore.glSamplerParameteri(self.name, GL_TEXTURE_COMPARE_FUNC, func as i32)?;
+		} else {
+			self.glcore.glSamplerParameteri(self.name, GL_TEXTURE_COMPARE_MODE, GL_NONE as i32)?;
+		}
+		self.compare_func = func;
+		Ok(())
+	}
+
+	/// Bind the sampler to the texture unit, utilizing the RAII rules to manage the binding state
+	pub fn bind_to_unit<'a>(&'a self, unit: u32) -> Result<SamplerBind<'a>, TextureError> {
+		SamplerBind::new(self, unit)
+	}
+}
+
+impl<'a> SamplerBind<'a> {
+	/// Create a binding state of the sampler to the texture unit
+	fn new(sampler: &'a Sampler, unit: u32) -> Result<Self, TextureError> {
+		sampler.glcore.glBindSampler(unit, sampler.name)?;
+		Ok(Self {
+			sampler,
+			unit,
+		})
+	}
+
+	/// Explicitly unbind the sampler.
+	pub fn unbind(self) {}
+}
+
+impl Drop for SamplerBind<'_> {
+	fn drop(&mut self) {
+		self.sampler.glcore.glBindSampler(self.unit, 0).unwrap();
+	}
+}
+
+impl Drop for Sampler {
+	fn drop(&mut self) {
+		self.glcore.glDeleteSamplers(1, &self.name as *const u32).unwrap();
+	}
+}
+
+impl TextureMemoryReport {
+	/// Get the total bytes
+	pub fn total(&self) -> usize {
+		self.texture_bytes + self.pixel_buffer_bytes
+	}
+}
+
+impl Display for TextureMemoryReport {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{} bytes (texture {} bytes, pixel buffers {} bytes)", self.total(), self.texture_bytes, self.pixel_buffer_bytes)
+	}
+}
+
+impl Debug for Sampler {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.debug_struct("Sampler")
+		.field("name", &self.name)
+		.field("wrapping_s", &self.wrapping_s)
+		.field("wrapping_t", &self.wrapping_t)
+		.field("wrapping_r", &self.wrapping_r)
+		.field("mag_filter", &self.mag_filter)
+		.field("min_filter", &self.min_filter)
+		.field("max_anisotropy", &self.max_anisotropy)
+		.field("min_lod", &self.min_lod)
+		.field("max_lod", &self.max_lod)
+		.field("lod_bias", &self.lod_bias)
+		.field("compare_func", &self.compare_func)
+		.finish()
+	}
+}
+
 impl<B, BP> Debug for Texture<B, BP>
 where
 	B: BufferVec<BP>,
@@ -1193,11 +3788,23 @@ where
 		.field("name", &self.name)
 		.field("dim", &self.dim)
 		.field("format", &self.format)
-		.field("width", &self.width)
-		.field("height", &self.height)
-		.field("depth", &self.depth)
-		.field("has_mipmap", &self.has_mipmap)
+		.field("width", &self.width.get())
+		.field("height", &self.height.get())
+		.field("depth", &self.depth.get())
+		.field("samples", &self.samples)
+		.field("fixed_sample_locations", &self.fixed_sample_locations)
+		.field("has_mipmap", &self.has_mipmap.get())
+		.field("base_level", &self.base_level)
+		.field("max_level", &self.max_level)
+		.field("min_lod", &self.min_lod)
+		.field("max_lod", &self.max_lod)
+		.field("lod_bias", &self.lod_bias)
+		.field("compare_func", &self.compare_func)
+		.field("immutable_levels", &self.immutable_levels)
 		.field("pixel_buffer", &self.pixel_buffer)
+		.field("streaming_buffers", &self.streaming_buffers)
+		.field("streaming_index", &self.streaming_index)
+		.field("grayscale_policy", &self.grayscale_policy)
 		.finish()
 	}
 }
@@ -1209,6 +3816,7 @@ impl Debug for TextureDimension {
 			Self::Tex2d => write!(f, "2D"),
 			Self::Tex3d => write!(f, "3D"),
 			Self::TexCube => write!(f, "CubeMap"),
+			Self::Tex2dMultisample => write!(f, "2D Multisample"),
 		}
 	}
 }
@@ -1218,6 +3826,11 @@ impl Debug for TextureFormat {
 		match self {
 			Self::Depth => write!(f, "DEPTH"),
 			Self::DepthStencil => write!(f, "DEPTH_STENCIL"),
+			Self::DepthComponent16 => write!(f, "DEPTH_COMPONENT16"),
+			Self::DepthComponent24 => write!(f, "DEPTH_COMPONENT24"),
+			Self::DepthComponent32F => write!(f, "DEPTH_COMPONENT32F"),
+			Self::Depth24Stencil8 => write!(f, "DEPTH24_STENCIL8"),
+			Self::Depth32FStencil8 => write!(f, "DEPTH32F_STENCIL8"),
 			Self::Red => write!(f, "RED"),
 			Self::Rg => write!(f, "RG"),
 			Self::Rgb => write!(f, "RGB"),
@@ -1237,16 +3850,20 @@ impl Debug for TextureFormat {
 			Self::Rgb8Snorm => write!(f, "RGB8_SNORM"),
 			Self::Rgb10 => write!(f, "RGB10"),
 			Self::Rgb12 => write!(f, "RGB12"),
+			Self::Rgb16 => write!(f, "RGB16"),
 			Self::Rgb16Snorm => write!(f, "RGB16_SNORM"),
 			Self::Rgba2 => write!(f, "RGBA2"),
 			Self::Rgba4 => write!(f, "RGBA4"),
 			Self::Rgb5a1 => write!(f, "RGB5_A1"),
 			Self::Rgba8 => write!(f, "RGBA8"),
 			Self::Rgba8Snorm => write!(f, "RGBA8_SNORM"),
+			Self::Srgb8 => write!(f, "SRGB8"),
+			Self::Srgb8Alpha8 => write!(f, "SRGB8_ALPHA8"),
 			Self::Rgb10a2 => write!(f, "RGB10_A2"),
 			Self::Rgb10a2ui => write!(f, "RGB10_A2UI"),
 			Self::Rgba12 => write!(f, "RGBA12"),
 			Self::Rgba16 => write!(f, "RGBA16"),
+			Self::Rgba16Snorm => write!(f, "RGBA16_SNORM"),
 			Self::R32f => write!(f, "R32F"),
 			Self::Rg32f => write!(f, "RG32F"),
 			Self::Rgb32f => write!(f, "RGB32F"),
@@ -1277,6 +3894,22 @@ impl Debug for TextureFormat {
 			Self::Rgba16ui => write!(f, "RGBA16UI"),
 			Self::Rgba32i => write!(f, "RGBA32I"),
 			Self::Rgba32ui => write!(f, "RGBA32UI"),
+			Self::Bc1Rgb => write!(f, "COMPRESSED_RGB_S3TC_DXT1"),
+			Self::Bc1Rgba => write!(f, "COMPRESSED_RGBA_S3TC_DXT1"),
+			Self::Bc2 => write!(f, "COMPRESSED_RGBA_S3TC_DXT3"),
+			Self::Bc3 => write!(f, "COMPRESSED_RGBA_S3TC_DXT5"),
+			Self::Bc4 => write!(f, "COMPRESSED_RED_RGTC1"),
+			Self::Bc4Snorm => write!(f, "COMPRESSED_SIGNED_RED_RGTC1"),
+			Self::Bc5 => write!(f, "COMPRESSED_RG_RGTC2"),
+			Self::Bc5Snorm => write!(f, "COMPRESSED_SIGNED_RG_RGTC2"),
+			Self::Bc6hUfloat => write!(f, "COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT"),
+			Self::Bc6hSfloat => write!(f, "COMPRESSED_RGB_BPTC_SIGNED_FLOAT"),
+			Self::Bc7 => write!(f, "COMPRESSED_RGBA_BPTC_UNORM"),
+			Self::Bc1Srgb => write!(f, "COMPRESSED_SRGB_S3TC_DXT1"),
+			Self::Bc1SrgbAlpha => write!(f, "COMPRESSED_SRGB_ALPHA_S3TC_DXT1"),
+			Self::Bc2Srgb => write!(f, "COMPRESSED_SRGB_ALPHA_S3TC_DXT3"),
+			Self::Bc3Srgb => write!(f, "COMPRESSED_SRGB_ALPHA_S3TC_DXT5"),
+			Self::Bc7Srgb => write!(f, "COMPRESSED_SRGB_ALPHA_BPTC_UNORM"),
 		}
 	}
 }
@@ -1294,6 +3927,87 @@ impl Debug for CubeMapFaces {
 	}
 }
 
+impl Debug for DepthFormat {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::DepthComponent16 => write!(f, "DEPTH_COMPONENT16"),
+			Self::DepthComponent24 => write!(f, "DEPTH_COMPONENT24"),
+			Self::DepthComponent32F => write!(f, "DEPTH_COMPONENT32F"),
+			Self::Depth24Stencil8 => write!(f, "DEPTH24_STENCIL8"),
+			Self::Depth32FStencil8 => write!(f, "DEPTH32F_STENCIL8"),
+		}
+	}
+}
+
+impl Debug for TextureWrapping {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::ClampToEdge => write!(f, "ClampToEdge"),
+			Self::ClampToBorder => write!(f, "ClampToBorder"),
+			Self::MirrorClampToEdge => write!(f, "MirrorClampToEdge"),
+			Self::Repeat => write!(f, "Repeat"),
+			Self::MirroredRepeat => write!(f, "MirroredRepeat"),
+		}
+	}
+}
+
+impl Debug for SamplerFilter {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Nearest => write!(f, "Nearest"),
+			Self::Linear => write!(f, "Linear"),
+			Self::NearestMipmapNearest => write!(f, "NearestMipmapNearest"),
+			Self::LinearMipmapNearest => write!(f, "LinearMipmapNearest"),
+			Self::NearestMipmapLinear => write!(f, "NearestMipmapLinear"),
+			Self::LinearMipmapLinear => write!(f, "LinearMipmapLinear"),
+		}
+	}
+}
+
+impl Debug for SamplerMagFilter {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Nearest => write!(f, "Nearest"),
+			Self::Linear => write!(f, "Linear"),
+		}
+	}
+}
+
+impl Debug for GrayscalePolicy {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::KeepRed => write!(f, "KeepRed"),
+			Self::ExpandToRgb => write!(f, "ExpandToRgb"),
+			Self::SwizzleLuminance => write!(f, "SwizzleLuminance"),
+		}
+	}
+}
+
+impl Debug for ImageAccess {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::ReadOnly => write!(f, "ReadOnly"),
+			Self::WriteOnly => write!(f, "WriteOnly"),
+			Self::ReadWrite => write!(f, "ReadWrite"),
+		}
+	}
+}
+
+impl Debug for CompareFunc {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Never => write!(f, "Never"),
+			Self::Less => write!(f, "Less"),
+			Self::Equal => write!(f, "Equal"),
+			Self::LEqual => write!(f, "LEqual"),
+			Self::Greater => write!(f, "Greater"),
+			Self::NotEqual => write!(f, "NotEqual"),
+			Self::GEqual => write!(f, "GEqual"),
+			Self::Always => write!(f, "Always"),
+		}
+	}
+}
+
 impl Debug for ChannelType {
 	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
 		match self {
@@ -1339,6 +4053,8 @@ impl Debug for ComponentType {
 			Self::U32_8888Rev => write!(f, "UNSIGNED_INT_8_8_8_8_REV"),
 			Self::U32_10_10_10_2 => write!(f, "UNSIGNED_INT_10_10_10_2"),
 			Self::U32_2_10_10_10Rev => write!(f, "UNSIGNED_INT_2_10_10_10_REV"),
+			Self::U32_24_8 => write!(f, "UNSIGNED_INT_24_8"),
+			Self::F32_24_8Rev => write!(f, "FLOAT_32_UNSIGNED_INT_24_8_REV"),
 		}
 	}
 }
@@ -0,0 +1,192 @@
+use crate::prelude::*;
+use std::{
+	fmt::{self, Debug, Formatter},
+	rc::Rc,
+};
+
+/// How the captured varyings are written into the transform feedback buffers
+#[derive(Clone, Copy, PartialEq)]
+pub enum TransformFeedbackBufferMode {
+	/// All of the varyings are interleaved into the buffer at the binding index 0
+	Interleaved = GL_INTERLEAVED_ATTRIBS as isize,
+
+	/// Each of the varyings is written into its own buffer at the binding index of its order
+	Separate = GL_SEPARATE_ATTRIBS as isize,
+}
+
+/// The primitive type to capture, must match the primitives that reach the transform feedback stage
+#[derive(Clone, Copy, PartialEq)]
+pub enum TransformFeedbackPrimitive {
+	Points = GL_POINTS as isize,
+	Lines = GL_LINES as isize,
+	Triangles = GL_TRIANGLES as isize,
+}
+
+/// The OpenGL transform feedback object, captures the outputs of the vertex shader or the geometry shader into the buffers
+pub struct TransformFeedback {
+	pub glcore: Rc<GLCore>,
+	name: u32,
+
+	/// Enable `GL_RASTERIZER_DISCARD` while capturing, to skip the rasterization when only the captured data is needed
+	pub rasterizer_discard: bool,
+}
+
+/// The binding state of the transform feedback object, the RAII system helps to unbind it
+#[derive(Debug)]
+pub struct TransformFeedbackBind<'a> {
+	pub transform_feedback: &'a TransformFeedback,
+}
+
+/// The capturing state between `glBeginTransformFeedback()` and `glEndTransformFeedback()`, the RAII system helps to end it
+#[derive(Debug)]
+pub struct TransformFeedbackActive<'a> {
+	pub transform_feedback: &'a TransformFeedback,
+}
+
+impl TransformFeedback {
+	/// Create a new transform feedback object
+	pub fn new(glcore: Rc<GLCore>, rasterizer_discard: bool) -> Result<Self, GLCoreError> {
+		let mut name: u32 = 0;
+		glcore.glGenTransformFeedbacks(1, &mut name as *mut u32)?;
+		Ok(Self {
+			glcore,
+			name,
+			rasterizer_discard,
+		})
+	}
+
+	/// Get the internal name
+	pub fn get_name(&self) -> u32 {
+		self.name
+	}
+
+	/// Create a `TransformFeedbackBind` to use the RAII system to manage the binding state.
+	pub fn bind<'a>(&'a self) -> Result<TransformFeedbackBind<'a>, GLCoreError> {
+		TransformFeedbackBind::new(self)
+	}
+}
+
+impl Drop for TransformFeedback {
+	fn drop(&mut self) {
+		self.glcore.glDeleteTransformFeedbacks(1, &self.name as *const u32).unwrap();
+	}
+}
+
+impl Debug for TransformFeedback {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.debug_struct("TransformFeedback")
+		.field("name", &self.name)
+		.field("rasterizer_discard", &self.rasterizer_discard)
+		.finish()
+	}
+}
+
+impl<'a> TransformFeedbackBind<'a> {
+	/// Bind the transform feedback object
+	fn new(transform_feedback: &'a TransformFeedback) -> Result<Self, GLCoreError> {
+		transform_feedback.glcore.glBindTransformFeedback(GL_TRANSFORM_FEEDBACK, transform_feedback.name)?;
+		Ok(Self {
+			transform_feedback,
+		})
+	}
+
+	/// Unbind the transform feedback object
+	pub fn unbind(self) {} // Unbind by owning it in the function and `drop()`
+
+	/// Set the buffer to capture into at the binding `index`, the binding is kept by the transform feedback object
+	pub fn bind_buffer(&self, index: u32, buffer: &Buffer) -> Result<(), GLCoreError> {
+		self.transform_feedback.glcore.glBindBufferBase(GL_TRANSFORM_FEEDBACK_BUFFER, index, buffer.get_name())
+	}
+
+	/// Set the range `[offset, offset + size)` in bytes of the buffer to capture into at the binding `index`
+	pub fn bind_buffer_range(&self, index: u32, buffer: &Buffer, offset: usize, size: usize) -> Result<(), GLCoreError> {
+		self.transform_feedback.glcore.glBindBufferRange(GL_TRANSFORM_FEEDBACK_BUFFER, index, buffer.get_name(), offset, size)
+	}
+
+	/// Begin capturing the `primitive`, the capture ends when the returned `TransformFeedbackActive` is dropped
+	pub fn begin(&self, primitive: TransformFeedbackPrimitive) -> Result<TransformFeedbackActive<'a>, GLCoreError> {
+		TransformFeedbackActive::new(self.transform_feedback, primitive)
+	}
+}
+
+impl Drop for TransformFeedbackBind<'_> {
+	fn drop(&mut self) {
+		self.transform_feedback.glcore.glBindTransformFeedback(GL_TRANSFORM_FEEDBACK, 0).unwrap();
+	}
+}
+
+impl<'a> TransformFeedbackActive<'a> {
+	/// Enable the rasterizer discard if needed and begin the transform feedback
+	fn new(transform_feedback: &'a TransformFeedback, primitive: TransformFeedbackPrimitive) -> Result<Self, GLCoreError> {
+		let glcore = &transform_feedback.glcore;
+		if transform_feedback.rasterizer_discard {
+			glcore.glEnable(GL_RASTERIZER_DISCARD)?;
+		}
+		glcore.glBeginTransformFeedback(primitive as u32)?;
+		Ok(Self {
+			transform_feedback,
+		})
+	}
+
+	/// Pause the capture, the draw calls are not captured until `resume()` is called
+	pub fn pause(&self) -> Result<(), GLCoreError> {
+		self.transform_feedback.glcore.glPauseTransformFeedback()
+	}
+
+	/// Resume the paused capture
+	pub fn resume(&self) -> Result<(), GLCoreError> {
+		self.transform_feedback.glcore.glResumeTransformFeedback()
+	}
+
+	/// End the capture
+	pub fn end(self) {} // End by owning it in the function and `drop()`
+}
+
+impl Drop for TransformFeedbackActive<'_> {
+	fn drop(&mut self) {
+		let glcore = &self.transform_feedback.glcore;
+		glcore.glEndTransformFeedback().unwrap();
+		if self.transform_feedback.rasterizer_discard {
+			glcore.glDisable(GL_RASTERIZER_DISCARD).unwrap();
+		}
+	}
+}
+
+impl From<PrimitiveMode> for TransformFeedbackPrimitive {
+	/// The primitive captured from drawing the mode without a geometry shader. The patches are assumed to be tessellated into triangles.
+	fn from(mode: PrimitiveMode) -> Self {
+		match mode {
+			PrimitiveMode::Points => Self::Points,
+			PrimitiveMode::LineStrip |
+			PrimitiveMode::LineLoop |
+			PrimitiveMode::Lines |
+			PrimitiveMode::LineStripAdjacency |
+			PrimitiveMode::LinesAdjacency => Self::Lines,
+			PrimitiveMode::TriangleStrip |
+			PrimitiveMode::TriangleFan |
+			PrimitiveMode::Triangles |
+			PrimitiveMode::TriangleStripAdjacency |
+			PrimitiveMode::TrianglesAdjacency |
+			PrimitiveMode::Patches => Self::Triangles,
+		}
+	}
+}
+
+impl Debug for TransformFeedbackBufferMode {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Interleaved => write!(f, "Interleaved"),
+			Self::Separate => write!(f, "Separate"),
+		}
+	}
+}
+
+impl Debug for TransformFeedbackPrimitive {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Points => write!(f, "Points"),
+			Self::Lines => write!(f, "Lines"),
+			Self::Triangles => write!(f, "Triangles"),
+		}
+	}
+}
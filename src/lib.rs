@@ -16,6 +16,27 @@ pub mod gltexture;
 /// The most basic OpenGL Framebuffer Object wrapping
 pub mod glframebuffer;
 
+/// The OpenGL fence sync object wrapping, helps to know when the GPU finished the commands without stalling
+pub mod glsync;
+
+/// The OpenGL transform feedback object wrapping, captures the shader outputs into the buffers
+pub mod gltransformfeedback;
+
+/// The fixed-function states of drawing, `RenderState` sets the depth test, the blending, the culling, the scissor test and the color mask, `StateGuard` restores them
+pub mod glstate;
+
+/// The OpenGL query object wrapping, counts the samples, the primitives or the time, and drives the conditional rendering
+pub mod glquery;
+
+/// The debug groups and markers of `KHR_debug` for the frame capture tools
+pub mod gldebug;
+
+/// The opt-in `MemoryTracker` to estimate the GPU memory allocated by the buffers, the textures and the renderbuffers
+pub mod memory;
+
+/// The `DrawStats` returned by the draw calls and the opt-in `FrameStats` collector for the frame-stats overlay
+pub mod stats;
+
 /// An upper layer wrapping for `Buffer`, the `BufferVec` allows editing the buffer items easier than just to use the `Buffer`
 pub mod buffervec;
 
@@ -43,6 +64,13 @@ pub mod prelude {
 	pub use crate::glcmdbuf::*;
 	pub use crate::gltexture::*;
 	pub use crate::glframebuffer::*;
+	pub use crate::glsync::*;
+	pub use crate::gltransformfeedback::*;
+	pub use crate::glstate::*;
+	pub use crate::glquery::*;
+	pub use crate::gldebug::*;
+	pub use crate::memory::*;
+	pub use crate::stats::*;
 	pub use crate::buffervec::*;
 	pub use crate::mesh::*;
 	pub use crate::material::*;
@@ -63,8 +91,10 @@ mod tests {
 	use std::{
 		ffi::c_void,
 		mem::size_of_val,
+		path::Path,
 		process::ExitCode,
 		rc::Rc,
+		sync::{Mutex, PoisonError},
 	};
 	use super::prelude::*;
 	use glfw::*;
@@ -120,7 +150,7 @@ mod tests {
 	}
 
 	impl Renderer {
-		fn new(glcore: Rc<GLCore>) -> Result<Self, AppError> {
+		fn new_quad_mesh(glcore: Rc<GLCore>) -> Result<StaticMesh<MyVertex, u8, UnusedType, UnusedType>, AppError> {
 			let vertices = [
 				MyVertex{position: Vec2::new(-1.0, -1.0)},
 				MyVertex{position: Vec2::new( 1.0, -1.0)},
@@ -137,7 +167,11 @@ mod tests {
 			let element_buffer = Buffer::new(glcore.clone(), BufferTarget::ElementArrayBuffer, size_of_val(&elements), BufferUsage::StaticDraw, elements.as_ptr() as *const c_void)?;
 			let mut element_buffer = BufferVecStatic::<u8>::new(element_buffer);
 			element_buffer.resize(6, 0u8)?;
-			let mesh = StaticMesh::<MyVertex, u8, UnusedType, UnusedType>::new(PrimitiveMode::Triangles, vertex_buffer, Some(element_buffer), None, None);
+			Ok(StaticMesh::<MyVertex, u8, UnusedType, UnusedType>::new(PrimitiveMode::Triangles, vertex_buffer, Some(element_buffer), None, None))
+		}
+
+		fn new(glcore: Rc<GLCore>) -> Result<Self, AppError> {
+			let mesh = Self::new_quad_mesh(glcore.clone())?;
 			let mesh = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
 			let mesh: Rc<dyn GenericMeshWithMaterial> = mesh;
 			let shader = Rc::new(Shader::new(glcore.clone(),
@@ -415,9 +449,42 @@ void main() {
 		}
 	}
 
+	static GL_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+	/// Run the test with the `GLCore` of a hidden window, returns `ExitCode::from(2)` if GLFW isn't available.
+	/// GLFW isn't thread-safe, the GL tests are serialized by `GL_TEST_LOCK`.
+	fn with_gl_context_hinted(title: &str, hints: impl IntoIterator<Item = WindowHint>, test: impl FnOnce(Rc<GLCore>)) -> ExitCode {
+		let _lock = GL_TEST_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+		let mut glfw = match glfw::init_no_callbacks() {
+			Ok(glfw) => glfw,
+			Err(e) => {
+				eprintln!("GLFW Initialize failed: {:?}", e);
+				return ExitCode::from(2)
+			}
+		};
+		glfw.window_hint(WindowHint::Visible(false));
+		for hint in hints {
+			glfw.window_hint(hint);
+		}
+		let Some((mut window, _events)) = glfw.create_window(64, 64, title, glfw::WindowMode::Windowed) else {
+			eprintln!("GLFW Create window failed");
+			return ExitCode::from(2)
+		};
+		window.make_current();
+		let glcore = Rc::new(GLCore::new(|proc_name|window.get_proc_address(proc_name)).unwrap());
+		test(glcore);
+		ExitCode::from(0)
+	}
+
+	/// Run the test with the `GLCore` of a hidden window of the default context version
+	fn with_gl_context(title: &str, test: impl FnOnce(Rc<GLCore>)) -> ExitCode {
+		with_gl_context_hinted(title, [], test)
+	}
+
 	#[test]
 	fn test_glfw() -> ExitCode {
 		const DEBUG_TIME: f64 = 10.0;
+		let _lock = GL_TEST_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
 		let mut test_app = match AppInstance::new() {
 			Ok(app) => app,
 			Err(e) => {
@@ -427,4 +494,4100 @@ void main() {
 		};
 		test_app.run(Some(DEBUG_TIME))
 	}
+
+	#[test]
+	fn test_cube_map_material() -> ExitCode {
+		with_gl_context("Cube Map Test", |glcore| {
+			let pixels = [255u8; 4 * 6];
+			let cube_map = Texture::<BufferVecStatic<u8>, u8>::builder_cube(glcore.clone(), TextureFormat::Rgba8, 1)
+				.initial_data(&pixels, ChannelType::Rgba, ComponentType::U8)
+				.build().unwrap();
+			let mut material = MaterialLegacy::default();
+			material.others.insert("environment".to_owned(), MaterialComponent::Texture(Rc::new(cube_map)));
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(material)));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+uniform samplerCube iEnvironment;
+out vec4 Color;
+
+void main()
+{
+	Color = texture(iEnvironment, vec3(1.0, 0.0, 0.0));
+}
+			")
+			).unwrap());
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			p_bind.draw(None).unwrap();
+			p_bind.unbind();
+		})
+	}
+
+	#[test]
+	fn test_texture_streaming() -> ExitCode {
+		with_gl_context("Texture Streaming Test", |glcore| {
+			const SIZE: u32 = 64;
+			const ITERATIONS: usize = 100;
+			let bytes = (SIZE * SIZE * 4) as usize;
+			let mut texture = Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).build().unwrap();
+			assert!(matches!(texture.enable_streaming(0, ChannelType::Rgba, ComponentType::U8), Err(TextureError::UnsupportedOperation(_))));
+			assert!(!texture.is_streaming());
+			texture.enable_streaming(3, ChannelType::Rgba, ComponentType::U8).unwrap();
+			for i in 0..ITERATIONS {
+				let (bind, mapping, address) = texture.map_write_next().unwrap();
+				let pixels = unsafe {std::slice::from_raw_parts_mut(address as *mut u8, bytes)};
+				for (j, pixel) in pixels.iter_mut().enumerate() {
+					*pixel = (i + j) as u8;
+				}
+				mapping.unmap();
+				bind.unbind();
+				texture.submit().unwrap();
+			}
+			let mut result = vec![0u8; bytes];
+			unsafe {texture.download_texture(result.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::U8).unwrap()};
+			for (j, pixel) in result.iter().enumerate() {
+				assert_eq!(*pixel, (ITERATIONS - 1 + j) as u8);
+			}
+		})
+	}
+
+	#[test]
+	fn test_async_readback() -> ExitCode {
+		with_gl_context("Async Readback Test", |glcore| {
+			const SIZE: u32 = 16;
+			let texture = Rc::new(Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).build().unwrap());
+			let shader = Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+layout (location = 0) in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap();
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.add_color_target("Color", texture.clone(), 0).unwrap();
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			glcore.glClearColor(1.0, 0.0, 1.0, 1.0).unwrap();
+			glcore.glClear(GL_COLOR_BUFFER_BIT).unwrap();
+			fb_bind.unbind();
+
+			let readback = texture.begin_async_readback(ChannelType::Rgba, ComponentType::U8).unwrap();
+			let pixels = readback.take_blocking().unwrap();
+			assert_eq!(pixels.len(), (SIZE * SIZE * 4) as usize);
+			for pixel in pixels.chunks(4) {
+				assert_eq!(pixel, [255, 0, 255, 255]);
+			}
+		})
+	}
+
+	#[test]
+	fn test_texture_resize() -> ExitCode {
+		with_gl_context("Texture Resize Test", |glcore| {
+			let mut texture = Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, 16, 16).pixel_buffer(true).build().unwrap();
+			let name = texture.get_name();
+			texture.resize(40, 24, 1).unwrap();
+			assert_eq!((texture.get_name(), texture.get_width(), texture.get_height(), texture.get_depth()), (name, 40, 24, 1));
+			assert_eq!(texture.get_bytes_of_texture(), 40 * 24 * 4);
+			assert_eq!(texture.get_pixel_buffer().unwrap().size_in_bytes(), 40 * 24 * 4);
+
+			// Every face of the cube map must be reallocated to the new size
+			let mut cube_map = Texture::<BufferVecStatic<u8>, u8>::builder_cube(glcore.clone(), TextureFormat::Rgba8, 8).build().unwrap();
+			cube_map.resize(32, 32, 1).unwrap();
+			assert_eq!((cube_map.get_width(), cube_map.get_height()), (32, 32));
+			assert_eq!(cube_map.get_bytes_of_texture(), 32 * 32 * 4 * 6);
+			let bind_tex = cube_map.bind().unwrap();
+			for face in CUBE_FACE_TARGETS {
+				let mut size = [0i32; 2];
+				glcore.glGetTexLevelParameteriv(face as u32, 0, GL_TEXTURE_WIDTH, &mut size[0] as *mut _).unwrap();
+				glcore.glGetTexLevelParameteriv(face as u32, 0, GL_TEXTURE_HEIGHT, &mut size[1] as *mut _).unwrap();
+				assert_eq!(size, [32, 32]);
+			}
+			bind_tex.unbind();
+			let mut pixels = vec![0u8; cube_map.bytes_of_level(0, ChannelType::Rgba, ComponentType::U8)];
+			unsafe {cube_map.download_texture(pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::U8).unwrap()};
+		})
+	}
+
+	#[test]
+	fn test_generate_mipmap() -> ExitCode {
+		with_gl_context("Generate Mipmap Test", |glcore| {
+			let pixels = [255u8, 0, 0, 255].repeat(16 * 16);
+			let texture = Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, 16, 16)
+				.initial_data(&pixels, ChannelType::Rgba, ComponentType::U8)
+				.build().unwrap();
+			assert_eq!(texture.level_count(), 1);
+			texture.generate_mipmap().unwrap();
+			assert!(texture.has_mipmap());
+			assert_eq!(texture.level_count(), 5);
+			let mut pixel = [0u8; 4];
+			unsafe {texture.download_level(4, pixel.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::U8).unwrap()};
+			assert_eq!(pixel, [255, 0, 0, 255]);
+
+			// The base level must not be above the max level, the GL default max level samples all of the levels
+			let mut texture = texture;
+			assert!(matches!(texture.set_mip_range(3, 2), Err(TextureError::InvalidMipRange(3, 2))));
+			texture.set_mip_range(1, 4).unwrap();
+			assert_eq!(texture.get_mip_range(), (1, 4));
+			texture.set_mip_range(0, DEFAULT_MAX_MIP_LEVEL).unwrap();
+
+			// The mips of all of the 6 faces are generated on the cube map target
+			let pixels = [0u8, 255, 0, 255].repeat(4 * 4 * 6);
+			let cube_map = Texture::<BufferVecStatic<u8>, u8>::builder_cube(glcore.clone(), TextureFormat::Rgba8, 4)
+				.initial_data(&pixels, ChannelType::Rgba, ComponentType::U8)
+				.build().unwrap();
+			cube_map.generate_mipmap().unwrap();
+			assert_eq!(cube_map.level_count(), 3);
+			let mut faces = vec![0u8; cube_map.bytes_of_level(2, ChannelType::Rgba, ComponentType::U8)];
+			unsafe {cube_map.download_level(2, faces.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::U8).unwrap()};
+			assert_eq!(faces, [0u8, 255, 0, 255].repeat(6));
+		})
+	}
+
+	#[test]
+	fn test_texture_view() -> ExitCode {
+		with_gl_context_hinted("Texture View Test", [WindowHint::ContextVersion(4, 3), WindowHint::OpenGlProfile(OpenGlProfileHint::Core)], |glcore| {
+			let cube_map = Texture::<BufferVecStatic<u8>, u8>::new_cube_storage(glcore.clone(), TextureFormat::Rgba8, 16, 5, SamplerMagFilter::Linear, SamplerFilter::Linear).unwrap();
+			let face = cube_map.view(TextureDimension::Tex2d, TextureFormat::Srgb8Alpha8, 1, 2, 3, 1).unwrap();
+			assert_eq!((face.get_dim(), face.get_format(), face.get_width(), face.get_height(), face.level_count()), (TextureDimension::Tex2d, TextureFormat::Srgb8Alpha8, 8, 8, 2));
+			let whole = cube_map.view(TextureDimension::TexCube, TextureFormat::Rgba8, 0, 5, 0, 6).unwrap();
+			assert_eq!((whole.get_dim(), whole.level_count()), (TextureDimension::TexCube, 5));
+
+			assert!(matches!(cube_map.view(TextureDimension::Tex2d, TextureFormat::Rgba8, 4, 2, 0, 1), Err(TextureError::InvalidMipLevel(6, 5))));
+			assert!(matches!(cube_map.view(TextureDimension::Tex2d, TextureFormat::Rgba8, u32::MAX, 2, 0, 1), Err(TextureError::InvalidMipLevel(u32::MAX, 5))));
+			assert!(matches!(cube_map.view(TextureDimension::Tex2d, TextureFormat::Rgba8, 0, 1, 6, 1), Err(TextureError::UnsupportedOperation(_))));
+			assert!(matches!(cube_map.view(TextureDimension::Tex2d, TextureFormat::Rgba8, 0, 1, u32::MAX, 1), Err(TextureError::UnsupportedOperation(_))));
+			let mutable = Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, 16, 16).build().unwrap();
+			assert!(matches!(mutable.view(TextureDimension::Tex2d, TextureFormat::Rgba8, 0, 1, 0, 1), Err(TextureError::UnsupportedOperation(_))));
+		})
+	}
+
+	#[test]
+	fn test_renderbuffer_attachments() -> ExitCode {
+		with_gl_context("Renderbuffer Attachments Test", |glcore| {
+			let shader = Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+layout (location = 0) in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap();
+			for samples in [0, 4] {
+				let color = Rc::new(Renderbuffer::new_multisample(glcore.clone(), TextureFormat::Rgba8, 32, 16, samples).unwrap());
+				let depth = Rc::new(Renderbuffer::new_multisample(glcore.clone(), TextureFormat::Depth24Stencil8, 32, 16, samples).unwrap());
+				assert_eq!(color.get_samples(), samples);
+				let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+				assert!(framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), color.clone()).is_none());
+				assert!(framebuffer.attach_renderbuffer(AttachmentPoint::DepthStencil, depth.clone()).is_none());
+				assert_eq!(framebuffer.get_renderbuffers().keys().copied().collect::<Vec<_>>(), [AttachmentPoint::Color(0), AttachmentPoint::DepthStencil]);
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				let mut viewport = [0i32; 4];
+				glcore.glGetIntegerv(GL_VIEWPORT, viewport.as_mut_ptr()).unwrap();
+				assert_eq!(viewport, [0, 0, 32, 16]);
+				fb_bind.unbind();
+
+				// Render into a quadrant without `setup()` resetting the viewport
+				framebuffer.set_auto_viewport(false);
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.set_viewport(16, 8, 16, 8).unwrap();
+				fb_bind.setup(&shader).unwrap();
+				glcore.glGetIntegerv(GL_VIEWPORT, viewport.as_mut_ptr()).unwrap();
+				assert_eq!(viewport, [16, 8, 16, 8]);
+				fb_bind.unbind();
+				framebuffer.set_auto_viewport(true);
+				assert!(framebuffer.detach_renderbuffer(AttachmentPoint::DepthStencil).is_some());
+			}
+		})
+	}
+
+	#[test]
+	fn test_msaa_resolve() -> ExitCode {
+		with_gl_context("MSAA Resolve Test", |glcore| {
+			const SIZE: u32 = 16;
+			let mut max_samples: i32 = 0;
+			glcore.glGetIntegerv(GL_MAX_SAMPLES, &mut max_samples as *mut _).unwrap();
+			match Framebuffer::new_msaa(glcore.clone(), SIZE, SIZE, max_samples as u32 + 1, TextureFormat::Rgba8, None) {
+				Err(FramebufferError::InvalidSampleCount(_, max)) => assert_eq!(max, max_samples as u32),
+				other => panic!("Expected an invalid sample count, got {other:?}"),
+			}
+			let msaa = Framebuffer::new_msaa(glcore.clone(), SIZE, SIZE, 4u32.min(max_samples as u32), TextureFormat::Rgba8, Some(DepthFormat::DepthComponent24)).unwrap();
+			assert_eq!(msaa.get_size(), [SIZE, SIZE]);
+			assert!(msaa.is_complete().unwrap());
+			assert!(!Framebuffer::new(glcore.clone()).unwrap().is_complete().unwrap());
+			let msaa_bind = msaa.bind().unwrap();
+			glcore.glClearColor(0.0, 1.0, 1.0, 1.0).unwrap();
+			glcore.glClear(GL_COLOR_BUFFER_BIT | GL_DEPTH_BUFFER_BIT).unwrap();
+			msaa_bind.unbind();
+
+			let texture = Rc::new(Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).build().unwrap());
+			let mut resolved = Framebuffer::new(glcore.clone()).unwrap();
+			resolved.attach_renderbuffer(AttachmentPoint::Color(1), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE / 2, SIZE / 2).unwrap()));
+			assert!(matches!(msaa.resolve_to(&resolved, BlitMask::COLOR, BlitFilter::Nearest, SizeMismatchPolicy::Error), Err(FramebufferError::SizeMismatch(_, _))));
+			resolved.detach_renderbuffer(AttachmentPoint::Color(1));
+			let shader = Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+layout (location = 0) in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap();
+			resolved.add_color_target("Color", texture.clone(), 0).unwrap();
+			let resolved_bind = resolved.bind().unwrap();
+			resolved_bind.setup(&shader).unwrap();
+			resolved_bind.unbind();
+			msaa.resolve_to(&resolved, BlitMask::COLOR, BlitFilter::Nearest, SizeMismatchPolicy::Error).unwrap();
+			let msaa_bind = msaa.bind().unwrap();
+			msaa_bind.invalidate(&[AttachmentPoint::Color(0), AttachmentPoint::Depth]).unwrap();
+			msaa_bind.invalidate_sub_region(&[AttachmentPoint::Color(0)], 0, 0, 1, 1).unwrap();
+			msaa_bind.unbind();
+			assert!(matches!(msaa.resolve_to(&resolved, BlitMask::COLOR | BlitMask::DEPTH, BlitFilter::Linear, SizeMismatchPolicy::Error), Err(FramebufferError::InvalidBlitFilter(_, BlitFilter::Linear))));
+
+			// Scale the magenta quarter of a plain framebuffer up to the bottom-left quarter of the resolved texture
+			let mut small = Framebuffer::new(glcore.clone()).unwrap();
+			small.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE / 4, SIZE / 4).unwrap()));
+			let small_bind = small.bind().unwrap();
+			small_bind.setup(&shader).unwrap();
+			glcore.glClearColor(1.0, 0.0, 1.0, 1.0).unwrap();
+			glcore.glClear(GL_COLOR_BUFFER_BIT).unwrap();
+			small_bind.unbind();
+			let half = (SIZE / 2) as i32;
+			small.blit_to(Some(&resolved), [0, 0, (SIZE / 4) as i32, (SIZE / 4) as i32], [0, 0, half, half], BlitMask::COLOR, BlitFilter::Linear, Some(0)).unwrap();
+
+			let pixels = texture.begin_async_readback(ChannelType::Rgba, ComponentType::U8).unwrap().take_blocking().unwrap();
+			for (i, pixel) in pixels.chunks(4).enumerate() {
+				let (x, y) = (i as u32 % SIZE, i as u32 / SIZE);
+				if x < SIZE / 2 && y < SIZE / 2 {
+					assert_eq!(pixel, [255, 0, 255, 255], "({x}, {y})");
+				} else {
+					assert_eq!(pixel, [0, 255, 255, 255], "({x}, {y})");
+				}
+			}
+		})
+	}
+
+	#[test]
+	fn test_read_pixels() -> ExitCode {
+		with_gl_context("Read Pixels Test", |glcore| {
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(1), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::R32f, SIZE, SIZE).unwrap()));
+			let shader = Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+layout (location = 0) in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap();
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			// Clear the whole color attachment 0 to red and the bottom row to blue
+			glcore.glClearBufferfv(GL_COLOR, 0, [1.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+			glcore.glEnable(GL_SCISSOR_TEST).unwrap();
+			glcore.glScissor(0, 0, SIZE as i32, 1).unwrap();
+			glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 1.0, 1.0].as_ptr()).unwrap();
+			glcore.glDisable(GL_SCISSOR_TEST).unwrap();
+			glcore.glClearBufferfv(GL_COLOR, 1, [0.25f32, 0.0, 0.0, 0.0].as_ptr()).unwrap();
+			fb_bind.unbind();
+
+			let pixels = framebuffer.read_pixels(0, 0, 0, SIZE, SIZE, ChannelType::Rgba, ComponentType::U8).unwrap();
+			assert_eq!(pixels.len(), (SIZE * SIZE * 4) as usize);
+			assert_eq!(pixels[0..4], [0, 0, 255, 255]);
+			assert_eq!(pixels[pixels.len() - 4..], [255, 0, 0, 255]);
+			let image = framebuffer.read_rgba_image(0, 0, 0, SIZE, SIZE).unwrap();
+			assert_eq!(image.get_pixel(0, SIZE - 1).0, [0, 0, 255, 255]);
+			assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, 1, 0).unwrap(), [0, 0, 255, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, 1, 1).unwrap(), [255, 0, 0, 255]);
+			assert_eq!(framebuffer.pick_pixel_f32(1, 2, 2).unwrap(), 0.25);
+		})
+	}
+
+	#[test]
+	fn test_layered_attachments() -> ExitCode {
+		with_gl_context("Layered Attachments Test", |glcore| {
+			let shader = Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+layout (location = 0) in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap();
+			const SIZE: u32 = 4;
+			const DEPTH: u32 = 3;
+			let cube = Rc::new(Texture::<BufferVecStatic<u8>, u8>::builder_cube(glcore.clone(), TextureFormat::Rgba8, SIZE).build().unwrap());
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.add_color_target("Color", cube.clone(), 0).unwrap();
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			fb_bind.unbind();
+
+			// Clear only the layer 1 of the 3D texture
+			let volume = Rc::new(Texture::<BufferVecStatic<u8>, u8>::builder_3d(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE, DEPTH).build().unwrap());
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.add_color_target_with("Color", FramebufferTarget::layer(TextureTarget::Tex3d, 1), volume.clone()).unwrap();
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			glcore.glClearColor(0.0, 1.0, 0.0, 1.0).unwrap();
+			glcore.glClear(GL_COLOR_BUFFER_BIT).unwrap();
+			fb_bind.unbind();
+			let mut pixels = vec![0u8; volume.bytes_of_level(0, ChannelType::Rgba, ComponentType::U8)];
+			unsafe {volume.download_texture(pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::U8).unwrap()};
+			for (z, layer) in pixels.chunks((SIZE * SIZE * 4) as usize).enumerate() {
+				for pixel in layer.chunks(4) {
+					assert_eq!(pixel, if z == 1 {[0, 255, 0, 255]} else {[0, 0, 0, 0]}, "layer {z}");
+				}
+			}
+		})
+	}
+
+	#[test]
+	fn test_mip_level_attachments() -> ExitCode {
+		with_gl_context("Mip Level Attachments Test", |glcore| {
+			let shader = Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+layout (location = 0) in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap();
+			const SIZE: u32 = 8;
+			let texture = Rc::new(Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).mipmaps(true).build().unwrap());
+			let colors = [[1.0f32, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]];
+			for (level, color) in colors.iter().enumerate() {
+				let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+				framebuffer.add_color_target("Color", texture.clone(), level as u32).unwrap();
+				assert_eq!(framebuffer.get_size(), [SIZE >> level, SIZE >> level]);
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				let mut viewport = [0i32; 4];
+				glcore.glGetIntegerv(GL_VIEWPORT, viewport.as_mut_ptr()).unwrap();
+				assert_eq!(viewport, [0, 0, (SIZE >> level) as i32, (SIZE >> level) as i32]);
+				glcore.glClearColor(color[0], color[1], color[2], color[3]).unwrap();
+				glcore.glClear(GL_COLOR_BUFFER_BIT).unwrap();
+				fb_bind.unbind();
+			}
+			for (level, expected) in [[255, 0, 0, 255], [0, 255, 0, 255]].into_iter().enumerate() {
+				let mut pixels = vec![0u8; texture.bytes_of_level(level as u32, ChannelType::Rgba, ComponentType::U8)];
+				unsafe {texture.download_level(level as u32, pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::U8).unwrap()};
+				for pixel in pixels.chunks(4) {
+					assert_eq!(pixel, expected, "level {level}");
+				}
+			}
+
+			// The missing mip level is reported when adding the target
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			assert!(matches!(framebuffer.add_color_target("Color", texture.clone(), texture.level_count()), Err(FramebufferError::InvalidTargetTexture(_))));
+			assert!(framebuffer.get_draw_targets().is_empty());
+		})
+	}
+
+	#[test]
+	fn test_framebuffer_attachment_cache() -> ExitCode {
+		with_gl_context("Framebuffer Attachment Cache Test", |glcore| {
+			let shader = Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+layout (location = 0) in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap();
+			const SIZE: u32 = 8;
+			let texture = Rc::new(Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).build().unwrap());
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.add_color_target("Color", texture.clone(), 0).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Depth, Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::DepthComponent24, SIZE, SIZE).unwrap()));
+			let depth_object_type = |glcore: &GLCore| {
+				let mut object_type: i32 = 0;
+				glcore.glGetFramebufferAttachmentParameteriv(GL_DRAW_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, GL_FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE, &mut object_type as *mut _).unwrap();
+				object_type as u32
+			};
+			{
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				assert_eq!(depth_object_type(&glcore), GL_RENDERBUFFER);
+				assert_eq!(framebuffer.get_attachment_apply_count(), 1);
+
+				// The cached attachments are not applied again, so the detached texture stays detached until marked dirty
+				glcore.glFramebufferTexture2D(GL_DRAW_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, 0, 0).unwrap();
+				fb_bind.setup(&shader).unwrap();
+				assert_eq!(framebuffer.get_attachment_apply_count(), 1);
+				assert!(fb_bind.validate().is_err());
+				framebuffer.mark_dirty();
+				fb_bind.setup(&shader).unwrap();
+				assert_eq!(framebuffer.get_attachment_apply_count(), 2);
+				fb_bind.validate().unwrap();
+				fb_bind.unbind();
+			}
+
+			// The detached renderbuffer is removed from the framebuffer object by the next setup
+			framebuffer.detach_renderbuffer(AttachmentPoint::Depth);
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			assert_eq!(depth_object_type(&glcore), GL_NONE);
+			fb_bind.unbind();
+
+			assert!(matches!(framebuffer.add_color_target("Col\0or", texture.clone(), 0), Err(FramebufferError::InvalidTargetName(_))));
+
+			// The depth texture takes the depth attachment point, the color formats are rejected
+			assert!(matches!(framebuffer.set_depth_target(texture.clone()), Err(FramebufferError::InvalidTargetTexture(_))));
+			let depth = Rc::new(Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::DepthComponent24, SIZE, SIZE).build().unwrap());
+			assert!(framebuffer.set_depth_target(depth.clone()).unwrap().is_none());
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			assert_eq!(depth_object_type(&glcore), GL_TEXTURE);
+			fb_bind.unbind();
+			framebuffer.clear_targets();
+			assert!(framebuffer.get_draw_targets().is_empty() && framebuffer.get_depth_target().is_none());
+		})
+	}
+
+	#[test]
+	fn test_read_framebuffer_bind() -> ExitCode {
+		with_gl_context("Read Framebuffer Bind Test", |glcore| {
+			const SIZE: u32 = 4;
+			let framebuffer = Framebuffer::new_msaa(glcore.clone(), SIZE, SIZE, 0, TextureFormat::Rgba8, None).unwrap();
+			let fb_bind = framebuffer.bind_as(FramebufferBindTarget::Both).unwrap();
+			glcore.glClearColor(1.0, 1.0, 0.0, 1.0).unwrap();
+			glcore.glClear(GL_COLOR_BUFFER_BIT).unwrap();
+			fb_bind.unbind();
+
+			// Copy the read framebuffer into a texture by `glCopyTexSubImage2D()`
+			let texture = Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).build().unwrap();
+			let fb_bind = framebuffer.bind_as(FramebufferBindTarget::Read).unwrap();
+			assert_eq!(fb_bind.get_target(), FramebufferBindTarget::Read);
+			fb_bind.validate().unwrap();
+			let tex_bind = texture.bind().unwrap();
+			glcore.glCopyTexSubImage2D(GL_TEXTURE_2D, 0, 0, 0, 0, 0, SIZE as i32, SIZE as i32).unwrap();
+			tex_bind.unbind();
+			fb_bind.unbind();
+			let mut binding: i32 = -1;
+			glcore.glGetIntegerv(GL_READ_FRAMEBUFFER_BINDING, &mut binding as *mut _).unwrap();
+			assert_eq!(binding, 0);
+
+			let mut pixels = vec![0u8; texture.bytes_of_level(0, ChannelType::Rgba, ComponentType::U8)];
+			unsafe {texture.download_texture(pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::U8).unwrap()};
+			for pixel in pixels.chunks(4) {
+				assert_eq!(pixel, [255, 255, 0, 255]);
+			}
+		})
+	}
+
+	#[test]
+	fn test_resize_attachments() -> ExitCode {
+		with_gl_context("Resize Attachments Test", |glcore| {
+			let shader = Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+layout (location = 0) in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+out vec4 Occlusion;
+
+void main()
+{
+	Color = vec4(1.0);
+	Occlusion = vec4(1.0);
+}
+			")
+			).unwrap();
+			let color = Rc::new(Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, 16, 16).build().unwrap());
+			let occlusion = Rc::new(Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, 8, 8).build().unwrap());
+			let depth = Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::DepthComponent24, 16, 16).unwrap());
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.add_color_target("Color", color.clone(), 0).unwrap();
+			framebuffer.add_color_target_with("Occlusion", FramebufferTarget::new(TextureTarget::Tex2d).with_resize_scale(Some(0.5)), occlusion.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Depth, depth.clone());
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			fb_bind.unbind();
+
+			let color_name = color.get_name();
+			framebuffer.resize_attachments(40, 24).unwrap();
+			assert_eq!(color.get_name(), color_name);
+			assert_eq!((color.get_width(), color.get_height()), (40, 24));
+			assert_eq!((occlusion.get_width(), occlusion.get_height()), (20, 12));
+			assert_eq!((depth.get_width(), depth.get_height()), (40, 24));
+			assert_eq!(color.get_bytes_of_texture(), 40 * 24 * 4);
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			let mut viewport = [0i32; 4];
+			glcore.glGetIntegerv(GL_VIEWPORT, viewport.as_mut_ptr()).unwrap();
+			assert_eq!(viewport, [0, 0, 40, 24]);
+			fb_bind.unbind();
+
+			// Resizing a cube map face target reallocates all of the 6 faces
+			let cube_map = Rc::new(Texture::<BufferVecStatic<u8>, u8>::builder_cube(glcore.clone(), TextureFormat::Rgba8, 16).build().unwrap());
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.add_color_target_with("Color", FramebufferTarget::new(TextureTarget::TexCubeNegZ), cube_map.clone()).unwrap();
+			framebuffer.add_color_target("Occlusion", occlusion.clone(), 0).unwrap();
+			framebuffer.resize_attachments(32, 32).unwrap();
+			assert_eq!((cube_map.get_width(), cube_map.get_height()), (32, 32));
+			assert_eq!(cube_map.get_bytes_of_texture(), 32 * 32 * 4 * 6);
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			glcore.glClearColor(1.0, 0.0, 1.0, 1.0).unwrap();
+			glcore.glClear(GL_COLOR_BUFFER_BIT).unwrap();
+			fb_bind.unbind();
+			let mut pixels = vec![0u8; cube_map.bytes_of_level(0, ChannelType::Rgba, ComponentType::U8)];
+			unsafe {cube_map.download_texture(pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::U8).unwrap()};
+			assert!(pixels[pixels.len() - 32 * 32 * 4..].chunks(4).all(|pixel| pixel == [255, 0, 255, 255]));
+		})
+	}
+
+	#[test]
+	fn test_indexed_attachments() -> ExitCode {
+		with_gl_context("Indexed Attachments Test", |glcore| {
+			let shader = Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+layout (location = 0) in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+layout (location = 1) out vec4 Second;
+
+void main()
+{
+	Second = vec4(1.0);
+}
+			")
+			).unwrap();
+			const SIZE: u32 = 4;
+			let texture = Rc::new(Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).build().unwrap());
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.add_color_target(1u32, texture.clone(), 0).unwrap();
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			let mut draw_buffers = [0i32; 2];
+			glcore.glGetIntegerv(GL_DRAW_BUFFER0, &mut draw_buffers[0] as *mut _).unwrap();
+			glcore.glGetIntegerv(GL_DRAW_BUFFER1, &mut draw_buffers[1] as *mut _).unwrap();
+			assert_eq!(draw_buffers, [GL_NONE as i32, GL_COLOR_ATTACHMENT1 as i32]);
+			glcore.glClearBufferfv(GL_COLOR, 1, [0.0f32, 0.0, 1.0, 1.0].as_ptr()).unwrap();
+			fb_bind.unbind();
+			assert_eq!(framebuffer.pick_pixel(1, 0, 0).unwrap(), [0, 0, 255, 255]);
+
+			// The depth-only framebuffer has no color attachments at all
+			let mut depth_only = Framebuffer::new(glcore.clone()).unwrap();
+			depth_only.attach_renderbuffer(AttachmentPoint::Depth, Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::DepthComponent24, SIZE, SIZE).unwrap()));
+			let fb_bind = depth_only.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			let mut draw_buffer: i32 = -1;
+			glcore.glGetIntegerv(GL_DRAW_BUFFER, &mut draw_buffer as *mut _).unwrap();
+			assert_eq!(draw_buffer, GL_NONE as i32);
+			fb_bind.unbind();
+		})
+	}
+
+	#[test]
+	fn test_srgb_write() -> ExitCode {
+		with_gl_context("sRGB Write Test", |glcore| {
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Srgb8Alpha8, SIZE, SIZE).unwrap()));
+			assert!(framebuffer.get_srgb_write());
+			Framebuffer::set_default_srgb_write(&glcore, false).unwrap();
+
+			// The linear 0.5 is encoded to about 188 in sRGB
+			let fb_bind = framebuffer.bind().unwrap();
+			assert_ne!(glcore.glIsEnabled(GL_FRAMEBUFFER_SRGB).unwrap(), 0);
+			glcore.glClearBufferfv(GL_COLOR, 0, [0.5f32, 0.5, 0.5, 1.0].as_ptr()).unwrap();
+			fb_bind.unbind();
+			assert_eq!(glcore.glIsEnabled(GL_FRAMEBUFFER_SRGB).unwrap(), 0);
+			let pixel = framebuffer.pick_pixel(0, 0, 0).unwrap();
+			assert!((186..=190).contains(&pixel[0]), "{pixel:?}");
+
+			// The shaders that apply the gamma manually disable the encoding
+			framebuffer.set_srgb_write(false);
+			let fb_bind = framebuffer.bind().unwrap();
+			glcore.glClearBufferfv(GL_COLOR, 0, [0.5f32, 0.5, 0.5, 1.0].as_ptr()).unwrap();
+			fb_bind.unbind();
+			let pixel = framebuffer.pick_pixel(0, 0, 0).unwrap();
+			assert!((127..=128).contains(&pixel[0]), "{pixel:?}");
+		})
+	}
+
+	#[test]
+	fn test_mip_chain_target() -> ExitCode {
+		with_gl_context("Mip Chain Target Test", |glcore| {
+			// Draw a full screen triangle without vertex buffers, each pass averages 2x2 texels of the source level
+			let shader = Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+void main()
+{
+	gl_Position = vec4(float((gl_VertexID & 1) * 4 - 1), float((gl_VertexID >> 1) * 4 - 1), 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+uniform sampler2D Source;
+uniform int SourceLevel;
+out vec4 Color;
+
+void main()
+{
+	ivec2 coord = ivec2(gl_FragCoord.xy) * 2;
+	Color = (
+		texelFetch(Source, coord, SourceLevel) +
+		texelFetch(Source, coord + ivec2(1, 0), SourceLevel) +
+		texelFetch(Source, coord + ivec2(0, 1), SourceLevel) +
+		texelFetch(Source, coord + ivec2(1, 1), SourceLevel)) * 0.25;
+}
+			")
+			).unwrap();
+			const SIZE: u32 = 32;
+			const LEVELS: u32 = 4;
+			assert!(MipChainTarget::new(glcore.clone(), TextureFormat::Rgba32f, SIZE, SIZE, 7).is_err());
+			let chain = MipChainTarget::new(glcore.clone(), TextureFormat::Rgba32f, SIZE, SIZE, LEVELS).unwrap();
+			assert_eq!(chain.level_count(), LEVELS);
+			assert_eq!(chain.level_size(3), [4, 4]);
+			assert_eq!(chain.down_passes().map(|(level, _)| level).collect::<Vec<_>>(), [1, 2, 3]);
+			assert_eq!(chain.up_passes().map(|(level, _)| level).collect::<Vec<_>>(), [2, 1, 0]);
+
+			// The bright level 0, out of the range of the normalized formats
+			let fb_bind = chain.level_framebuffer(0).bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			glcore.glClearBufferfv(GL_COLOR, 0, [4.0f32, 2.0, 1.0, 1.0].as_ptr()).unwrap();
+			fb_bind.unbind();
+
+			let mut vao: u32 = 0;
+			glcore.glGenVertexArrays(1, &mut vao as *mut u32).unwrap();
+			glcore.glBindVertexArray(vao).unwrap();
+			let program = shader.use_program().unwrap();
+			program.set_uniform("Source", &0i32).unwrap();
+			let tex_bind = chain.get_texture().bind().unwrap();
+			for (level, framebuffer) in chain.down_passes() {
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				let mut viewport = [0i32; 4];
+				glcore.glGetIntegerv(GL_VIEWPORT, viewport.as_mut_ptr()).unwrap();
+				let [width, height] = chain.level_size(level);
+				assert_eq!(viewport, [0, 0, width as i32, height as i32]);
+				program.set_uniform("SourceLevel", &(level as i32 - 1)).unwrap();
+				glcore.glDrawArrays(GL_TRIANGLES, 0, 3).unwrap();
+				fb_bind.unbind();
+			}
+			tex_bind.unbind();
+			program.unuse();
+			glcore.glBindVertexArray(0).unwrap();
+			glcore.glDeleteVertexArrays(1, &vao as *const u32).unwrap();
+
+			let texture = chain.get_texture();
+			let mut pixels = vec![0f32; texture.bytes_of_level(LEVELS - 1, ChannelType::Rgba, ComponentType::F32) / 4];
+			unsafe {texture.download_level(LEVELS - 1, pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::F32).unwrap()};
+			for pixel in pixels.chunks(4) {
+				assert!((pixel[0] - 4.0).abs() < 1e-3 && (pixel[1] - 2.0).abs() < 1e-3, "{pixel:?}");
+			}
+		})
+	}
+
+	#[test]
+	fn test_draw_range() -> ExitCode {
+		with_gl_context("Draw Range Test", |glcore| {
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+			fb_bind.unbind();
+
+			// Only the first triangle of the quad covers the bottom-left corner
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			p_bind.draw_range(Some(&framebuffer), 0, 3, 0).unwrap();
+			assert!(matches!(p_bind.draw_range(Some(&framebuffer), 3, 6, 0), Err(PipelineError::InvalidDrawRange(3, 6, 6))));
+			assert!(matches!(p_bind.draw_range(Some(&framebuffer), usize::MAX, 2, 0), Err(PipelineError::InvalidDrawRange(i64::MAX, 2, 6))));
+			assert!(matches!(p_bind.draw_range(Some(&framebuffer), 1, usize::MAX, 0), Err(PipelineError::InvalidDrawRange(1, usize::MAX, 6))));
+			p_bind.unbind();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [255, 255, 255, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, SIZE as i32 - 1, SIZE as i32 - 1).unwrap(), [0, 0, 0, 255]);
+		})
+	}
+
+	#[test]
+	fn test_multi_stream_mesh() -> ExitCode {
+		with_gl_context("Multi Stream Mesh Test", |glcore| {
+			derive_vertex_type! {
+				pub struct ColorVertex {
+					color: Vec4,
+				}
+			}
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let colors = [ColorVertex {color: Vec4::new(1.0, 0.0, 0.0, 1.0)}; 4];
+			let color_buffer = Buffer::new(glcore.clone(), BufferTarget::ArrayBuffer, size_of_val(&colors), BufferUsage::StaticDraw, colors.as_ptr() as *const c_void).unwrap();
+			let mut color_buffer = BufferVecStatic::<ColorVertex>::new(color_buffer);
+			color_buffer.resize(colors.len(), ColorVertex::default()).unwrap();
+			let mut mesh = MultiStreamMesh::new(Renderer::new_quad_mesh(glcore.clone()).unwrap());
+			mesh.add_stream(VertexStream::new(color_buffer, 0));
+			assert_eq!(mesh.get_vertex_streams().len(), 1);
+			assert_eq!(mesh.get_vertex_streams()[0].get_vertex_count(), 4);
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+in vec4 color;
+out vec4 vColor;
+
+void main()
+{
+	vColor = color;
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+in vec4 vColor;
+out vec4 Color;
+
+void main()
+{
+	Color = vColor;
+}
+			")
+			).unwrap());
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			p_bind.draw(Some(&framebuffer)).unwrap();
+			p_bind.unbind();
+			assert_eq!(framebuffer.pick_pixel(0, 1, 1).unwrap(), [255, 0, 0, 255]);
+		})
+	}
+
+	#[test]
+	fn test_mesh_with_material_flush() -> ExitCode {
+		with_gl_context("Mesh Flush Test", |glcore| {
+			let instances = [MyVertex::default(); 4];
+			let instance_buffer = Buffer::new(glcore.clone(), BufferTarget::ArrayBuffer, size_of_val(&instances), BufferUsage::DynamicDraw, instances.as_ptr() as *const c_void).unwrap();
+			let mut instance_buffer = BufferVecStatic::<MyVertex>::new(instance_buffer);
+			instance_buffer.resize(instances.len(), MyVertex::default()).unwrap();
+			let mut instance_buffer = BufferVecDynamic::new(instance_buffer).unwrap();
+			instance_buffer[2] = MyVertex{position: Vec2::new(3.0, 4.0)};
+			let quad = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh = StaticMesh::<MyVertex, u8, MyVertex, UnusedType>::new(PrimitiveMode::Triangles, quad.vertex_buffer, quad.element_buffer, Some(instance_buffer), None);
+			let mut mesh: Box<dyn GenericMeshWithMaterial> = Box::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+
+			// The modified instance only reaches the GPU when the trait object forwards `flush()` to the mesh
+			mesh.flush().unwrap();
+			let bind = mesh.get_instance_buffer().unwrap().bind().unwrap();
+			let (map, addr) = bind.map(MapAccess::ReadOnly).unwrap();
+			let uploaded = unsafe {*(addr as *const MyVertex).add(2)};
+			map.unmap();
+			bind.unbind();
+			assert_eq!(uploaded.position, Vec2::new(3.0, 4.0));
+		})
+	}
+
+	#[test]
+	fn test_set_instances() -> ExitCode {
+		with_gl_context("Set Instances Test", |glcore| {
+			let quad = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mut mesh = StaticMesh::<MyVertex, u8, MyVertex, UnusedType>::new(PrimitiveMode::Triangles, quad.vertex_buffer, quad.element_buffer, None, None);
+			assert_eq!(mesh.instance_count(), 0);
+
+			// Creates the instance buffer, then grows it, then shrinks the instances without reallocation
+			for count in [3, 5, 2] {
+				let instances: Vec<MyVertex> = (0..count).map(|i| MyVertex{position: Vec2::new(i as f32, count as f32)}).collect();
+				mesh.set_instances(&instances).unwrap();
+				assert_eq!(mesh.instance_count(), count);
+				assert_eq!(mesh.get_instance_count(), count);
+				let bind = mesh.get_instance_buffer().unwrap().bind().unwrap();
+				let (map, addr) = bind.map(MapAccess::ReadOnly).unwrap();
+				let uploaded = unsafe {*(addr as *const MyVertex).add(count - 1)};
+				map.unmap();
+				bind.unbind();
+				assert_eq!(uploaded.position, Vec2::new((count - 1) as f32, count as f32));
+			}
+		})
+	}
+
+	#[test]
+	fn test_mesh_static_dynamic_conversion() -> ExitCode {
+		with_gl_context("Mesh Conversion Test", |glcore| {
+			let mut mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap().into_dynamic().unwrap();
+			assert_eq!(mesh.get_vertex_count(), 4);
+			assert_eq!(mesh.get_element_count(), 6);
+			mesh.vertex_buffer[0] = MyVertex{position: Vec2::new(0.5, 0.5)};
+
+			// The edit in the cache is flushed by the conversion back to the static mesh, the material is carried along
+			let mesh = MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())).into_static().unwrap();
+			assert_eq!(mesh.get_vertex_count(), 4);
+			assert_eq!(mesh.get_element_count(), 6);
+			let bind = mesh.get_vertex_buffer().bind().unwrap();
+			let (map, addr) = bind.map(MapAccess::ReadOnly).unwrap();
+			let uploaded = unsafe {*(addr as *const MyVertex)};
+			map.unmap();
+			bind.unbind();
+			assert_eq!(uploaded.position, Vec2::new(0.5, 0.5));
+			let mesh = mesh.into_dynamic().unwrap();
+			assert_eq!(mesh.get_vertex_count(), 4);
+		})
+	}
+
+	#[test]
+	fn test_memory_tracker() -> ExitCode {
+		with_gl_context("Memory Tracker Test", |glcore| {
+			MemoryTracker::enable();
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let report = mesh.gpu_bytes();
+			assert_eq!(report, MeshMemoryReport{vertex_bytes: 32, element_bytes: 6, instance_bytes: 0, command_bytes: 0});
+			assert_eq!(report.total(), 38);
+
+			// The 4x4 RGBA8 texture has 3 levels of 64, 16 and 4 bytes
+			let texture = Texture::<BufferVecStatic<u8>, u8>::new_2d_storage(glcore.clone(), TextureFormat::Rgba8, 4, 4, 3, TextureWrapping::Repeat, TextureWrapping::Repeat, SamplerMagFilter::Linear, SamplerFilter::LinearMipmapLinear).unwrap();
+			assert_eq!(texture.gpu_bytes(), TextureMemoryReport{texture_bytes: 84, pixel_buffer_bytes: 0});
+			let renderbuffer = Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, 4, 4).unwrap();
+			assert_eq!(renderbuffer.gpu_bytes(), 64);
+
+			let report = MemoryTracker::report().unwrap();
+			assert_eq!(report.buffers, MemoryUsage{count: 2, bytes: 38});
+			assert_eq!(report.textures, MemoryUsage{count: 1, bytes: 84});
+			assert_eq!(report.renderbuffers, MemoryUsage{count: 1, bytes: 64});
+			drop(texture);
+			renderbuffer.resize(8, 8).unwrap();
+			let report = MemoryTracker::report().unwrap();
+			assert_eq!(report.textures, MemoryUsage::default());
+			assert_eq!(report.renderbuffers, MemoryUsage{count: 1, bytes: 256});
+			MemoryTracker::disable();
+			assert!(MemoryTracker::report().is_none());
+		})
+	}
+
+	#[test]
+	fn test_mesh_append() -> ExitCode {
+		with_gl_context("Mesh Append Test", |glcore| {
+			let mut merged = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let quad = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let range = merged.append(&quad).unwrap();
+			assert_eq!(range, SubmeshRange{first_element: 6, element_count: 6, base_vertex: 4});
+			assert_eq!(merged.get_vertex_count(), 8);
+			assert_eq!(merged.get_element_count(), 12);
+			let elements = merged.element_buffer.as_ref().unwrap().get_slice_of_data(0, 12).unwrap();
+			assert_eq!(elements, [0, 1, 2, 1, 3, 2, 4, 5, 6, 5, 7, 6]);
+			let vertices = merged.vertex_buffer.get_slice_of_data(4, 4).unwrap();
+			assert_eq!(vertices[3].position, Vec2::new(1.0, 1.0));
+
+			let mut points = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			points.primitive = PrimitiveMode::Points;
+			assert!(matches!(merged.append(&points), Err(MeshError::PrimitiveMismatch(PrimitiveMode::Triangles, PrimitiveMode::Points))));
+		})
+	}
+
+	#[test]
+	fn test_mesh_buffer_targets() -> ExitCode {
+		with_gl_context("Mesh Buffer Targets Test", |glcore| {
+			// Create the buffers of the quad with the "wrong" targets
+			let vertices = [
+				MyVertex{position: Vec2::new(-1.0, -1.0)},
+				MyVertex{position: Vec2::new( 1.0, -1.0)},
+				MyVertex{position: Vec2::new(-1.0,  1.0)},
+				MyVertex{position: Vec2::new( 1.0,  1.0)},
+			];
+			let elements = [0u8, 1, 2, 1, 3, 2];
+			let vertex_buffer = Buffer::new(glcore.clone(), BufferTarget::CopyReadBuffer, size_of_val(&vertices), BufferUsage::StaticDraw, vertices.as_ptr() as *const c_void).unwrap();
+			let mut vertex_buffer = BufferVecStatic::<MyVertex>::new(vertex_buffer);
+			vertex_buffer.resize(4, MyVertex::default()).unwrap();
+			let element_buffer = Buffer::new(glcore.clone(), BufferTarget::ArrayBuffer, size_of_val(&elements), BufferUsage::StaticDraw, elements.as_ptr() as *const c_void).unwrap();
+			let mut element_buffer = BufferVecStatic::<u8>::new(element_buffer);
+			element_buffer.resize(6, 0u8).unwrap();
+			let mesh = StaticMesh::<MyVertex, u8, UnusedType, UnusedType>::new(PrimitiveMode::Triangles, vertex_buffer, Some(element_buffer), None, None);
+			assert_eq!(mesh.vertex_buffer.get_target(), BufferTarget::ArrayBuffer);
+			assert_eq!(mesh.element_buffer.as_ref().unwrap().get_target(), BufferTarget::ElementArrayBuffer);
+
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			p_bind.draw(Some(&framebuffer)).unwrap();
+			p_bind.unbind();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [255, 255, 255, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, SIZE as i32 - 1, SIZE as i32 - 1).unwrap(), [255, 255, 255, 255]);
+		})
+	}
+
+	#[test]
+	fn test_skinned_vertex() -> ExitCode {
+		with_gl_context("Skinned Vertex Test", |glcore| {
+			// The quad covers the left half, bone 1 moves it to the right half, bone 0 isn't weighted
+			let normal = Vec3::new(0.0, 0.0, 1.0);
+			let mut vertices = [
+				Vec2::new(-1.0, -1.0),
+				Vec2::new( 0.0, -1.0),
+				Vec2::new(-1.0,  1.0),
+				Vec2::new( 0.0,  1.0),
+			].map(|position| skinning::SkinnedVertex::new(Vec3::new(position.x, position.y, 0.0), normal, Vec2::zeros(), U16Vec4::new(0, 1, 0, 0), Vec4::new(0.0, 2.0, 0.0, 0.0)));
+			for vertex in vertices.iter_mut() {
+				assert!(vertex.normalize_weights());
+				assert_eq!(vertex.weights, Vec4::new(0.0, 1.0, 0.0, 0.0));
+			}
+			let mut bad = vertices[0];
+			bad.weights = Vec4::new(1.0, -1.0, 0.0, 0.0);
+			assert!(!bad.normalize_weights());
+			let elements = [0u8, 1, 2, 1, 3, 2];
+			let mesh = StaticMesh::<skinning::SkinnedVertex, u8, UnusedType, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, Some(&elements)).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(), Some(skinning::SKINNING_VERTEX_SHADER), None, Some(skinning::SKINNING_FRAGMENT_SHADER)).unwrap());
+			let bones = [Mat4::identity(), translation(&Vec3::new(1.0, 0.0, 0.0))];
+			let shader_use = shader.use_program().unwrap();
+			shader_use.set_bone_matrices("bones", &bones).unwrap();
+			assert!(matches!(shader_use.set_bone_matrices("bones", &[Mat4::identity(); skinning::SKINNING_SHADER_MAX_BONES + 1]), Err(ShaderError::UniformArrayTooSmall(_, 65))));
+			shader_use.set_uniform("view_projection", &Mat4::identity()).unwrap();
+			shader_use.set_uniform("color", &Vec4::new(1.0, 1.0, 1.0, 1.0)).unwrap();
+			shader_use.set_uniform("light_direction", &normal).unwrap();
+			shader_use.unuse();
+
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+			fb_bind.unbind();
+			let pipeline = Pipeline::<skinning::SkinnedVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			p_bind.draw(Some(&framebuffer)).unwrap();
+			p_bind.unbind();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [0, 0, 0, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, SIZE as i32 - 1, 0).unwrap(), [255, 255, 255, 255]);
+		})
+	}
+
+	#[test]
+	fn test_vertex_attrib_metadata() -> ExitCode {
+		derive_vertex_type! {
+			pub struct RenamedVertex {
+				/// Matched to `aPos`
+				#[attrib(name = "aPos")]
+				pos: Vec2,
+				#[attrib(location = 3, divisor = 1)]
+				#[allow(dead_code)]
+				tint: Vec4,
+			}
+		}
+		let fields = RenamedVertex::fields();
+		assert_eq!((fields[0].name, fields[0].attrib_name, fields[0].type_name, fields[0].location, fields[0].divisor), ("pos", "aPos", "vec2", None, None));
+		assert_eq!((fields[1].attrib_name, fields[1].type_name, fields[1].location, fields[1].divisor), ("tint", "vec4", Some(3), Some(1)));
+		assert_eq!(fields[1].offset, std::mem::offset_of!(RenamedVertex, tint));
+		assert_eq!(MyVertex::fields()[0].attrib_name, "position");
+
+		with_gl_context("Vertex Attrib Metadata Test", |glcore| {
+			// With the divisor 1, every vertex reads the tint of the first vertex
+			let magenta = Vec4::new(1.0, 0.0, 1.0, 1.0);
+			let green = Vec4::new(0.0, 1.0, 0.0, 1.0);
+			let vertices = [
+				RenamedVertex{pos: Vec2::new(-1.0, -1.0), tint: magenta},
+				RenamedVertex{pos: Vec2::new( 1.0, -1.0), tint: green},
+				RenamedVertex{pos: Vec2::new(-1.0,  1.0), tint: green},
+				RenamedVertex{pos: Vec2::new( 1.0,  1.0), tint: green},
+			];
+			let elements = [0u8, 1, 2, 1, 3, 2];
+			let mesh = StaticMesh::<RenamedVertex, u8, UnusedType, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, Some(&elements)).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 aPos;
+layout(location = 3) in vec4 aColor;
+
+out vec4 v_color;
+
+void main()
+{
+	v_color = aColor;
+	gl_Position = vec4(aPos, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+in vec4 v_color;
+
+out vec4 Color;
+
+void main()
+{
+	Color = v_color;
+}
+			")
+			).unwrap());
+
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let pipeline = Pipeline::<RenamedVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			p_bind.draw(Some(&framebuffer)).unwrap();
+			p_bind.unbind();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [255, 0, 255, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, SIZE as i32 - 1, SIZE as i32 - 1).unwrap(), [255, 0, 255, 255]);
+		})
+	}
+
+	#[test]
+	fn test_normalized_byte_color() -> ExitCode {
+		derive_vertex_type! {
+			pub struct ByteColorVertex {
+				position: Vec2,
+				#[attrib(normalized)]
+				color: U8Vec4,
+			}
+		}
+		assert!(ByteColorVertex::fields()[1].normalized);
+		assert!(!ByteColorVertex::fields()[0].normalized);
+
+		with_gl_context("Normalized Byte Color Test", |glcore| {
+			let color = U8Vec4::new(255, 128, 0, 255);
+			let vertices = [
+				Vec2::new(-1.0, -1.0),
+				Vec2::new( 1.0, -1.0),
+				Vec2::new(-1.0,  1.0),
+				Vec2::new( 1.0,  1.0),
+			].map(|position| ByteColorVertex{position, color});
+			let elements = [0u8, 1, 2, 1, 3, 2];
+			let mesh = StaticMesh::<ByteColorVertex, u8, UnusedType, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, Some(&elements)).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+in vec4 color;
+
+out vec4 v_color;
+
+void main()
+{
+	v_color = color;
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+in vec4 v_color;
+
+out vec4 Color;
+
+void main()
+{
+	Color = v_color;
+}
+			")
+			).unwrap());
+
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let pipeline = Pipeline::<ByteColorVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			p_bind.draw(Some(&framebuffer)).unwrap();
+			p_bind.unbind();
+			assert_eq!(framebuffer.pick_pixel(0, 1, 1).unwrap(), [255, 128, 0, 255]);
+		})
+	}
+
+	#[test]
+	fn test_instance_matrix() -> ExitCode {
+		derive_vertex_type! {
+			pub struct InstanceTransform {
+				transform: Mat4,
+			}
+		}
+		with_gl_context("Instance Matrix Test", |glcore| {
+			// The quad covers the bottom-left quarter, the instances move it to the bottom-right and the top-right quarters
+			let vertices = [
+				MyVertex{position: Vec2::new(-1.0, -1.0)},
+				MyVertex{position: Vec2::new( 0.0, -1.0)},
+				MyVertex{position: Vec2::new(-1.0,  0.0)},
+				MyVertex{position: Vec2::new( 0.0,  0.0)},
+			];
+			let elements = [0u8, 1, 2, 1, 3, 2];
+			let mut mesh = StaticMesh::<MyVertex, u8, InstanceTransform, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, Some(&elements)).unwrap();
+			mesh.set_instances(&[
+				InstanceTransform{transform: translation(&Vec3::new(1.0, 0.0, 0.0))},
+				InstanceTransform{transform: translation(&Vec3::new(1.0, 1.0, 0.0))},
+			]).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+in mat4 transform;
+
+void main()
+{
+	gl_Position = transform * vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+			fb_bind.unbind();
+			let pipeline = Pipeline::<MyVertex, InstanceTransform>::new(glcore.clone(), mesh, shader).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			p_bind.draw(Some(&framebuffer)).unwrap();
+			p_bind.unbind();
+			let far = SIZE as i32 - 1;
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [0, 0, 0, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, 0, far).unwrap(), [0, 0, 0, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, far, 0).unwrap(), [255, 255, 255, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, far, far).unwrap(), [255, 255, 255, 255]);
+		})
+	}
+
+	#[test]
+	fn test_stream_divisors() -> ExitCode {
+		derive_vertex_type! {
+			pub struct ParticleInstance {
+				offset: Vec2,
+				#[attrib(divisor = 0)]
+				jitter: Vec2,
+			}
+		}
+		with_gl_context("Stream Divisors Test", |glcore| {
+			let quad = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mut mesh = StaticMesh::<MyVertex, u8, ParticleInstance, UnusedType>::new(PrimitiveMode::Triangles, quad.vertex_buffer, quad.element_buffer, None, None);
+			mesh.set_instances(&[ParticleInstance::default(); 4]).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+in vec2 offset;
+in vec2 jitter;
+
+void main()
+{
+	gl_Position = vec4(position + offset + jitter, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+
+			// The member divisor overrides the stream divisor, which overrides the default divisor of the buffer
+			let pipeline = Pipeline::<MyVertex, ParticleInstance>::new_with_streams(glcore.clone(), mesh, shader.clone(), StreamConfig::VERTEX, StreamConfig::new(2)).unwrap();
+			assert_eq!(pipeline.get_instance_stream().default_divisor, 2);
+			let p_bind = pipeline.bind().unwrap();
+			for (name, divisor) in [("position", 0), ("offset", 2), ("jitter", 0)] {
+				let location = shader.get_attrib_location(name).unwrap();
+				assert!(location >= 0);
+				let mut value = -1i32;
+				glcore.glGetVertexAttribiv(location as u32, GL_VERTEX_ATTRIB_ARRAY_DIVISOR, &mut value as *mut i32).unwrap();
+				assert_eq!(value, divisor, "The divisor of `{name}`");
+			}
+			p_bind.unbind();
+		})
+	}
+
+	#[test]
+	fn test_draw_with_uniforms() -> ExitCode {
+		with_gl_context("Draw With Uniforms Test", |glcore| {
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+uniform vec4 color;
+
+out vec4 Color;
+
+void main()
+{
+	Color = color;
+}
+			")
+			).unwrap());
+
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh.clone(), shader.clone()).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			p_bind.draw_with(Some(&framebuffer), |program| program.set_uniform("color", &Vec4::new(0.0, 1.0, 0.0, 1.0))).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [0, 255, 0, 255]);
+			assert!(matches!(p_bind.draw_with(Some(&framebuffer), |program| program.set_uniform("missing", &1.0f32)), Err(PipelineError::ShaderError(ShaderError::UniformNotFound(_)))));
+			p_bind.unbind();
+
+			// The pipeline set passes the subset names to the callback
+			let meshset = Meshset {
+				subsets: [("quad".to_owned(), mesh)].into_iter().collect(),
+				lod_groups: Default::default(),
+			};
+			let pipelineset = Pipelineset::from_meshset::<MyVertex, UnusedType>(glcore.clone(), &meshset, &[shader]).unwrap();
+			let mut names = Vec::new();
+			pipelineset.draw_with(Some(&framebuffer), None, |name, program| {
+				names.push(name.to_owned());
+				program.set_uniform("color", &Vec4::new(1.0, 0.0, 0.0, 1.0))
+			}).unwrap();
+			assert_eq!(names, ["quad"]);
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [255, 0, 0, 255]);
+
+			// The pipelines of the different vertex types in one set
+			let cube_shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec3 position;
+
+void main()
+{
+	gl_Position = vec4(position.xy, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+uniform vec4 color;
+
+out vec4 Color;
+
+void main()
+{
+	Color = color;
+}
+			")
+			).unwrap());
+			let cube = Rc::new(Pipeline::<shapes::ShapeVertex, UnusedType>::new(glcore.clone(), Rc::new(shapes::cube(glcore.clone(), 2.0).unwrap()), cube_shader.clone()).unwrap());
+			let mut pipelineset = pipelineset;
+			pipelineset.insert("cube", cube.clone());
+			let mut names = Vec::new();
+			pipelineset.draw_with(Some(&framebuffer), None, |name, program| {
+				names.push(name.to_owned());
+				program.set_uniform("color", &if name == "cube" {Vec4::new(0.0, 0.0, 1.0, 1.0)} else {Vec4::new(0.0, 1.0, 0.0, 1.0)})
+			}).unwrap();
+			assert_eq!(names, ["cube", "quad"]);
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [0, 255, 0, 255]);
+
+			// A failed subset is skipped or stops the drawing by the policy
+			let failing = |name: &str, program: &ShaderUse| if name == "cube" {program.set_uniform("missing", &1.0f32)} else {program.set_uniform("color", &Vec4::new(1.0, 0.0, 0.0, 1.0))};
+			let (stats, skipped) = pipelineset.draw_with_policy(Some(&framebuffer), None, SetupErrorPolicy::SkipSubset, failing).unwrap();
+			assert_eq!(stats.draw_calls, 1);
+			assert!(matches!(skipped.as_slice(), [(name, ShaderError::UniformNotFound(_))] if name == "cube"));
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [255, 0, 0, 255]);
+			assert!(matches!(pipelineset.draw_with_policy(Some(&framebuffer), None, SetupErrorPolicy::Abort, failing), Err(PipelineError::ShaderError(ShaderError::UniformNotFound(_)))));
+
+			let pipelines: Vec<Rc<dyn GenericPipeline>> = vec![pipelineset.subsets["quad"][0].clone(), cube as Rc<dyn GenericPipeline>];
+			assert!(Rc::ptr_eq(pipelines[1].get_shader(), &cube_shader));
+			assert_eq!(pipelines[1].get_mesh().get_vertex_count(), 24);
+			for pipeline in pipelines.iter() {
+				pipeline.draw_dyn(Some(&framebuffer), true, &mut |program| program.set_uniform("color", &Vec4::new(1.0, 1.0, 0.0, 1.0))).unwrap();
+			}
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [255, 255, 0, 255]);
+		})
+	}
+
+	#[test]
+	fn test_draw_counted() -> ExitCode {
+		derive_vertex_type! {
+			pub struct InstanceOffset {
+				offset: Vec2,
+			}
+		}
+		with_gl_context("Draw Counted Test", |glcore| {
+			// The quad covers the bottom-left quarter, the second instance moves it to the top-right quarter
+			let vertices = [
+				MyVertex{position: Vec2::new(-1.0, -1.0)},
+				MyVertex{position: Vec2::new( 0.0, -1.0)},
+				MyVertex{position: Vec2::new(-1.0,  0.0)},
+				MyVertex{position: Vec2::new( 0.0,  0.0)},
+			];
+			let elements = [0u8, 1, 2, 1, 3, 2];
+			let mut mesh = StaticMesh::<MyVertex, u8, InstanceOffset, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, Some(&elements)).unwrap();
+			mesh.set_instances(&[InstanceOffset{offset: Vec2::new(0.0, 0.0)}, InstanceOffset{offset: Vec2::new(1.0, 1.0)}]).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+in vec2 offset;
+
+void main()
+{
+	gl_Position = vec4(position + offset, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let clear = || {
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+				fb_bind.unbind();
+			};
+			let white = [255, 255, 255, 255];
+			let black = [0, 0, 0, 255];
+			let far = SIZE as i32 - 1;
+			let pipeline = Pipeline::<MyVertex, InstanceOffset>::new(glcore.clone(), mesh, shader.clone()).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+
+			// Only the first triangle of the first instance
+			clear();
+			p_bind.draw_counted(Some(&framebuffer), 3, Some(1)).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), white);
+			assert_eq!(framebuffer.pick_pixel(0, 1, 1).unwrap(), black);
+			assert_eq!(framebuffer.pick_pixel(0, far, far).unwrap(), black);
+
+			// Both of the instances
+			clear();
+			assert_eq!(p_bind.draw_instanced(Some(&framebuffer), 2).unwrap(), DrawStats::new(1, 2, 6));
+			assert_eq!(framebuffer.pick_pixel(0, 1, 1).unwrap(), white);
+			assert_eq!(framebuffer.pick_pixel(0, far, far).unwrap(), white);
+
+			clear();
+			assert_eq!(p_bind.draw_counted(Some(&framebuffer), 0, None).unwrap(), DrawStats::default());
+			p_bind.draw_instanced(Some(&framebuffer), 0).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), black);
+			assert!(matches!(p_bind.draw_instanced(Some(&framebuffer), 3), Err(PipelineError::InvalidInstanceCount(3, 2))));
+			assert!(matches!(p_bind.draw_counted(Some(&framebuffer), 7, None), Err(PipelineError::InvalidDrawRange(0, 7, 6))));
+			p_bind.unbind();
+		})
+	}
+
+	#[test]
+	fn test_draw_base() -> ExitCode {
+		derive_vertex_type! {
+			pub struct InstanceOffset {
+				offset: Vec2,
+			}
+		}
+		with_gl_context("Draw Base Test", |glcore| {
+			// The quad covers the bottom-left quarter, the second instance moves it to the top-right quarter
+			let vertices = [
+				MyVertex{position: Vec2::new(-1.0, -1.0)},
+				MyVertex{position: Vec2::new( 0.0, -1.0)},
+				MyVertex{position: Vec2::new(-1.0,  0.0)},
+				MyVertex{position: Vec2::new( 0.0,  0.0)},
+			];
+			let elements = [0u8, 1, 2, 1, 3, 2];
+			let mut mesh = StaticMesh::<MyVertex, u8, InstanceOffset, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, Some(&elements)).unwrap();
+			mesh.set_instances(&[InstanceOffset{offset: Vec2::new(0.0, 0.0)}, InstanceOffset{offset: Vec2::new(1.0, 1.0)}]).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+in vec2 offset;
+
+void main()
+{
+	gl_Position = vec4(position + offset, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let clear = || {
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+				fb_bind.unbind();
+			};
+			let white = [255, 255, 255, 255];
+			let black = [0, 0, 0, 255];
+			let far = SIZE as i32 - 1;
+			let pipeline = Pipeline::<MyVertex, InstanceOffset>::new(glcore.clone(), mesh, shader.clone()).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+
+			// The second triangle of the first instance as a submesh
+			clear();
+			p_bind.draw_submesh(Some(&framebuffer), &SubmeshRange{first_element: 3, element_count: 3, base_vertex: 0}).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), black);
+			assert_eq!(framebuffer.pick_pixel(0, 1, 1).unwrap(), white);
+
+			// Only the second instance, if the base instance is supported
+			clear();
+			let mut version = [0i32; 2];
+			glcore.glGetIntegerv(GL_MAJOR_VERSION, &mut version[0] as *mut i32).unwrap();
+			glcore.glGetIntegerv(GL_MINOR_VERSION, &mut version[1] as *mut i32).unwrap();
+			if version >= [4, 2] {
+				assert_eq!(p_bind.draw_base(Some(&framebuffer), 0, 6, 0, 1, 1).unwrap(), DrawStats::new(1, 1, 6));
+				assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), black);
+				assert_eq!(framebuffer.pick_pixel(0, far, far).unwrap(), white);
+			} else {
+				assert!(matches!(p_bind.draw_base(Some(&framebuffer), 0, 6, 0, 1, 1), Err(PipelineError::BaseInstanceNotSupported(1))));
+			}
+			assert!(matches!(p_bind.draw_base(Some(&framebuffer), 0, 6, 0, 2, 1), Err(PipelineError::InvalidInstanceCount(3, 2))));
+			assert!(matches!(p_bind.draw_base(Some(&framebuffer), 0, 6, 0, usize::MAX, 1), Err(PipelineError::InvalidInstanceCount(usize::MAX, 2))));
+			p_bind.unbind();
+		})
+	}
+
+	#[test]
+	fn test_pipeline_rebuild() -> ExitCode {
+		derive_vertex_type! {
+			pub struct InstanceOffset {
+				offset: Vec2,
+			}
+		}
+		with_gl_context("Pipeline Rebuild Test", |glcore| {
+			// The quad covers the bottom-left quarter, the instances move it to the other quarters
+			let vertices = [
+				MyVertex{position: Vec2::new(-1.0, -1.0)},
+				MyVertex{position: Vec2::new( 0.0, -1.0)},
+				MyVertex{position: Vec2::new(-1.0,  0.0)},
+				MyVertex{position: Vec2::new( 0.0,  0.0)},
+			];
+			let elements = [0u8, 1, 2, 1, 3, 2];
+			let mut mesh = StaticMesh::<MyVertex, u8, InstanceOffset, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, Some(&elements)).unwrap();
+			mesh.set_instances(&[InstanceOffset{offset: Vec2::new(0.0, 0.0)}]).unwrap();
+			let mut mesh = Rc::new(mesh);
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+in vec2 offset;
+
+void main()
+{
+	gl_Position = vec4(position + offset, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let clear = || {
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+				fb_bind.unbind();
+			};
+			let white = [255, 255, 255, 255];
+			let black = [0, 0, 0, 255];
+			let far = SIZE as i32 - 1;
+			let mut pipeline = Pipeline::<MyVertex, InstanceOffset>::new(glcore.clone(), mesh.clone(), shader.clone()).unwrap();
+			clear();
+			pipeline.bind().unwrap().draw_instanced(Some(&framebuffer), 1).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), white);
+			assert_eq!(framebuffer.pick_pixel(0, far, 0).unwrap(), black);
+
+			// Growing the instances reallocates the instance buffer with a new name
+			let old_name = mesh.get_instance_buffer().unwrap().get_name();
+			pipeline.mesh = Rc::new(Renderer::new_quad_mesh(glcore.clone()).unwrap());
+			Rc::get_mut(&mut mesh).unwrap().set_instances(&[
+				InstanceOffset{offset: Vec2::new(0.0, 0.0)},
+				InstanceOffset{offset: Vec2::new(1.0, 0.0)},
+				InstanceOffset{offset: Vec2::new(0.0, 1.0)},
+			]).unwrap();
+			assert_ne!(mesh.get_instance_buffer().unwrap().get_name(), old_name);
+			pipeline.mesh = mesh.clone();
+
+			// The VAO is described again by `bind()` with the new instance buffer
+			clear();
+			pipeline.bind().unwrap().draw_instanced(Some(&framebuffer), 3).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), white);
+			assert_eq!(framebuffer.pick_pixel(0, far, 0).unwrap(), white);
+			assert_eq!(framebuffer.pick_pixel(0, 0, far).unwrap(), white);
+			assert_eq!(framebuffer.pick_pixel(0, far, far).unwrap(), black);
+
+			pipeline.rebuild().unwrap();
+			clear();
+			pipeline.bind().unwrap().draw_instanced(Some(&framebuffer), 3).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, far, 0).unwrap(), white);
+		})
+	}
+
+	#[test]
+	fn test_vertex_format_paths() -> ExitCode {
+		derive_vertex_type! {
+			pub struct InstanceTint {
+				offset: Vec2,
+				#[attrib(divisor = 2)]
+				tint: Vec4,
+			}
+		}
+		with_gl_context("Vertex Format Paths Test", |glcore| {
+			// The quad covers the bottom-left quarter, the instances move it to the other quarters, each tint is shared by 2 instances
+			let vertices = [
+				MyVertex{position: Vec2::new(-1.0, -1.0)},
+				MyVertex{position: Vec2::new( 0.0, -1.0)},
+				MyVertex{position: Vec2::new(-1.0,  0.0)},
+				MyVertex{position: Vec2::new( 0.0,  0.0)},
+			];
+			let elements = [0u8, 1, 2, 1, 3, 2];
+			let red = Vec4::new(1.0, 0.0, 0.0, 1.0);
+			let green = Vec4::new(0.0, 1.0, 0.0, 1.0);
+			let mut mesh = StaticMesh::<MyVertex, u8, InstanceTint, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, Some(&elements)).unwrap();
+			mesh.set_instances(&[
+				InstanceTint{offset: Vec2::new(0.0, 0.0), tint: red},
+				InstanceTint{offset: Vec2::new(1.0, 0.0), tint: green},
+				InstanceTint{offset: Vec2::new(0.0, 1.0), tint: green},
+				InstanceTint{offset: Vec2::new(1.0, 1.0), tint: red},
+			]).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(mesh);
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+in vec2 offset;
+in vec4 tint;
+
+out vec4 v_tint;
+
+void main()
+{
+	v_tint = tint;
+	gl_Position = vec4(position + offset, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+in vec4 v_tint;
+
+out vec4 Color;
+
+void main()
+{
+	Color = v_tint;
+}
+			")
+			).unwrap());
+
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let render = |pipeline: &Pipeline<MyVertex, InstanceTint>| {
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+				fb_bind.unbind();
+				pipeline.bind().unwrap().draw(Some(&framebuffer)).unwrap();
+				let mut pixels = Vec::new();
+				for y in 0..SIZE as i32 {
+					for x in 0..SIZE as i32 {
+						pixels.push(framebuffer.pick_pixel(0, x, y).unwrap());
+					}
+				}
+				pixels
+			};
+
+			let mut major: i32 = 0;
+			let mut minor: i32 = 0;
+			glcore.glGetIntegerv(GL_MAJOR_VERSION, &mut major as *mut _).unwrap();
+			glcore.glGetIntegerv(GL_MINOR_VERSION, &mut minor as *mut _).unwrap();
+			let supported = (major, minor) >= (4, 3);
+			let pipeline = Pipeline::<MyVertex, InstanceTint>::new(glcore.clone(), mesh.clone(), shader.clone()).unwrap();
+			assert_eq!(pipeline.is_separated(), supported);
+
+			let legacy = Pipeline::<MyVertex, InstanceTint>::new_with_format(glcore.clone(), mesh.clone(), shader.clone(), StreamConfig::VERTEX, StreamConfig::INSTANCE, false).unwrap();
+			assert!(!legacy.is_separated());
+			let expected = render(&legacy);
+			let far = SIZE as usize - 1;
+			assert_eq!(expected[0], [255, 0, 0, 255]);
+			assert_eq!(expected[far], [255, 0, 0, 255]);
+			assert_eq!(expected[far * SIZE as usize], [0, 255, 0, 255]);
+			assert_eq!(expected[far * SIZE as usize + far], [0, 255, 0, 255]);
+
+			let separated = Pipeline::<MyVertex, InstanceTint>::new_with_format(glcore.clone(), mesh.clone(), shader.clone(), StreamConfig::VERTEX, StreamConfig::INSTANCE, true);
+			if supported {
+				let separated = separated.unwrap();
+				assert!(separated.is_separated());
+				assert_eq!(render(&separated), expected);
+			} else {
+				assert!(matches!(separated, Err(PipelineError::SeparatedFormatNotSupported)));
+			}
+		})
+	}
+
+	#[test]
+	fn test_pipeline_attrib_errors() -> ExitCode {
+		derive_vertex_type! {
+			pub struct ExtraVertex {
+				position: Vec2,
+				unused: Vec4,
+			}
+		}
+		derive_vertex_type! {
+			#[repr(C)]
+			pub struct PaddedVertex {
+				flags: u8,
+				position: Vec2,
+			}
+		}
+		#[derive(Default, Debug, Clone, Copy)]
+		struct OverlappedVertex {
+			_position: Vec2,
+			_flags: u32,
+		}
+		impl VertexFields for OverlappedVertex {
+			fn fields() -> &'static [FieldDesc] {
+				const FIELDS: &[FieldDesc] = &[FieldDesc::new("position", "vec2", 0), FieldDesc::new("flags", "u32", 4)];
+				FIELDS
+			}
+		}
+		#[derive(Default, Debug, Clone, Copy)]
+		struct UnknownTypeVertex {
+			_position: Vec2,
+		}
+		impl VertexFields for UnknownTypeVertex {
+			fn fields() -> &'static [FieldDesc] {
+				const FIELDS: &[FieldDesc] = &[FieldDesc::new("position", "vec9", 0)];
+				FIELDS
+			}
+		}
+		with_gl_context("Pipeline Attrib Errors Test", |glcore| {
+			let new_shader = |position_type: &str| Rc::new(Shader::new(glcore.clone(),
+				Some(format!("
+#version 330\n
+
+in {position_type} position;
+
+void main()
+{{
+	gl_Position = vec4(position.xy, 0.0, 1.0);
+}}
+			").as_str()),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+
+			// The member without the active attrib is skipped with a warning
+			let vertices = [ExtraVertex::default(); 3];
+			let mesh = StaticMesh::<ExtraVertex, u8, UnusedType, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, None).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(mesh);
+			let pipeline = Pipeline::<ExtraVertex, UnusedType>::new(glcore.clone(), mesh.clone(), new_shader("vec2")).unwrap();
+			assert_eq!(pipeline.warnings(), vec![PipelineWarning::AttribNotActive("vec4 unused".to_string())]);
+			assert_eq!(pipeline.warnings()[0].to_string(), "Attrib `vec4 unused` is not active.");
+
+			// The size mismatch is an error instead of a panic
+			let result = Pipeline::<ExtraVertex, UnusedType>::new(glcore.clone(), mesh, new_shader("vec3"));
+			match result {
+				Err(PipelineError::AttribSizeMismatch(member, expected, got)) => {
+					assert_eq!(member, "position");
+					assert_eq!(expected, (3, 1));
+					assert_eq!(got, (2, 1));
+				}
+				other => panic!("Expected `AttribSizeMismatch`, got {other:?}"),
+			}
+			assert!(matches!(new_shader("vec2").get_attrib_location("posi\0tion"), Err(ShaderError::AttribNotFound(_))));
+
+			// The padding before `position` is respected, the quad covers the whole framebuffer
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let vertices = [Vec2::new(-1.0, -1.0), Vec2::new(1.0, -1.0), Vec2::new(-1.0, 1.0), Vec2::new(1.0, 1.0)].map(|position| PaddedVertex {flags: 0xff, position});
+			let mesh = StaticMesh::<PaddedVertex, u8, UnusedType, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::TriangleStrip, &vertices, None).unwrap();
+			let shader = new_shader("vec2");
+			let pipeline = Pipeline::<PaddedVertex, UnusedType>::new(glcore.clone(), Rc::new(mesh), shader.clone()).unwrap();
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+			fb_bind.unbind();
+			pipeline.bind().unwrap().draw(Some(&framebuffer)).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [255, 255, 255, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, SIZE as i32 - 1, SIZE as i32 - 1).unwrap(), [255, 255, 255, 255]);
+
+			// The overlapping members of a hand-written layout
+			let mesh = StaticMesh::<OverlappedVertex, u8, UnusedType, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &[OverlappedVertex::default(); 3], None).unwrap();
+			match Pipeline::<OverlappedVertex, UnusedType>::new(glcore.clone(), Rc::new(mesh), shader.clone()) {
+				Err(PipelineError::VertexLayoutPadding(member, expected, got)) => {
+					assert_eq!(member, "flags");
+					assert_eq!(expected, 8);
+					assert_eq!(got, 4);
+				}
+				other => panic!("Expected `VertexLayoutPadding`, got {other:?}"),
+			}
+
+			// The unsupported type names of a hand-written layout are errors instead of panics
+			let mesh = StaticMesh::<UnknownTypeVertex, u8, UnusedType, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &[UnknownTypeVertex::default(); 3], None).unwrap();
+			match Pipeline::<UnknownTypeVertex, UnusedType>::new(glcore.clone(), Rc::new(mesh), shader) {
+				Err(PipelineError::UnsupportedMemberType(member, type_name)) => {
+					assert_eq!(member, "position");
+					assert_eq!(type_name, "vec9");
+				}
+				other => panic!("Expected `UnsupportedMemberType`, got {other:?}"),
+			}
+		})
+	}
+
+	#[test]
+	fn test_render_state() -> ExitCode {
+		with_gl_context("Render State Test", |glcore| {
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+uniform vec4 color;
+
+out vec4 Color;
+
+void main()
+{
+	Color = color;
+}
+			")
+			).unwrap());
+			let clear = |color: [f32; 4]| {
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				glcore.glClearBufferfv(GL_COLOR, 0, color.as_ptr()).unwrap();
+				fb_bind.unbind();
+			};
+			let is_enabled = |cap: u32| glcore.glIsEnabled(cap).unwrap() != 0;
+			let white = [255, 255, 255, 255];
+			let black = [0, 0, 0, 255];
+			let far = SIZE as i32 - 1;
+
+			// The default states are the defaults of OpenGL, the guard restores the states changed by hand
+			glcore.glEnable(GL_DEPTH_TEST).unwrap();
+			let guard = StateGuard::new(&glcore).unwrap();
+			RenderState::default().apply(&glcore).unwrap();
+			assert!(!is_enabled(GL_DEPTH_TEST));
+			guard.restore();
+			assert!(is_enabled(GL_DEPTH_TEST));
+			glcore.glDisable(GL_DEPTH_TEST).unwrap();
+
+			// The blending is applied for the draw call only
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh.clone(), shader.clone()).unwrap();
+			pipeline.set_render_state(Some(RenderState {blend: Some(BlendState::ALPHA), ..Default::default()}));
+			clear([1.0, 0.0, 0.0, 1.0]);
+			pipeline.bind().unwrap().draw_with(Some(&framebuffer), |program| program.set_uniform("color", &Vec4::new(0.0, 0.0, 1.0, 0.5))).unwrap();
+			let [r, g, b, _] = framebuffer.pick_pixel(0, 0, 0).unwrap();
+			assert!((126..=129).contains(&r) && g == 0 && (126..=129).contains(&b), "Blended color: {r}, {g}, {b}");
+			assert!(!is_enabled(GL_BLEND));
+
+			// Only the bottom-left quarter passes the scissor test
+			pipeline.set_render_state(Some(RenderState {scissor: Some((0, 0, SIZE / 2, SIZE / 2)), ..Default::default()}));
+			clear([0.0, 0.0, 0.0, 1.0]);
+			pipeline.bind().unwrap().draw_with(Some(&framebuffer), |program| program.set_uniform("color", &Vec4::new(1.0, 1.0, 1.0, 1.0))).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), white);
+			assert_eq!(framebuffer.pick_pixel(0, far, far).unwrap(), black);
+			assert!(!is_enabled(GL_SCISSOR_TEST));
+
+			// Culling both of the faces draws nothing
+			pipeline.set_render_state(Some(RenderState {cull_face: Some(CullFace::FrontAndBack), ..Default::default()}));
+			pipeline.bind().unwrap().draw_with(Some(&framebuffer), |program| program.set_uniform("color", &Vec4::new(1.0, 1.0, 1.0, 1.0))).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, far, far).unwrap(), black);
+			assert!(!is_enabled(GL_CULL_FACE));
+
+			// The wireframe only draws the diagonal inside the quad, the polygon mode is restored to fill after drawing
+			let polygon_mode = || {
+				let mut modes = [0i32; 2];
+				glcore.glGetIntegerv(GL_POLYGON_MODE, modes.as_mut_ptr()).unwrap();
+				modes[0] as u32
+			};
+			pipeline.set_render_state(Some(RenderState {polygon_mode: PolygonMode::Line, ..Default::default()}));
+			clear([0.0, 0.0, 0.0, 1.0]);
+			pipeline.bind().unwrap().draw_with(Some(&framebuffer), |program| program.set_uniform("color", &Vec4::new(1.0, 1.0, 1.0, 1.0))).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 2, 1).unwrap(), white);
+			assert_eq!(framebuffer.pick_pixel(0, 1, 1).unwrap(), black);
+			assert_eq!(polygon_mode(), GL_FILL);
+
+			// The wireframe overlay draws the lines over the filled quad
+			pipeline.set_render_state(None);
+			clear([0.0, 0.0, 0.0, 1.0]);
+			pipeline.bind().unwrap().draw_wireframe_overlay(Some(&framebuffer), 1.0, |program, mode| {
+				let color = if mode == PolygonMode::Line {Vec4::new(0.0, 1.0, 0.0, 1.0)} else {Vec4::new(1.0, 0.0, 0.0, 1.0)};
+				program.set_uniform("color", &color)
+			}).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 2, 1).unwrap(), [0, 255, 0, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, 1, 1).unwrap(), [255, 0, 0, 255]);
+			assert_eq!(pipeline.get_render_state(), None);
+			assert_eq!(polygon_mode(), GL_FILL);
+			assert!(!is_enabled(GL_POLYGON_OFFSET_LINE));
+
+			// The pipeline set applies the states of its pipelines and restores them after drawing
+			let meshset = Meshset {
+				subsets: [("a".to_owned(), mesh.clone()), ("b".to_owned(), mesh)].into_iter().collect(),
+				lod_groups: Default::default(),
+			};
+			let pipelineset = Pipelineset::from_meshset::<MyVertex, UnusedType>(glcore.clone(), &meshset, &[shader.clone()]).unwrap();
+			pipelineset.set_render_state(Some(RenderState {scissor: Some((SIZE as i32 / 2, SIZE as i32 / 2, SIZE / 2, SIZE / 2)), ..Default::default()}));
+			clear([0.0, 0.0, 0.0, 1.0]);
+			pipelineset.draw_with(Some(&framebuffer), None, |_, program| program.set_uniform("color", &Vec4::new(1.0, 1.0, 1.0, 1.0))).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), black);
+			assert_eq!(framebuffer.pick_pixel(0, far, far).unwrap(), white);
+			assert!(!is_enabled(GL_SCISSOR_TEST));
+		})
+	}
+
+	#[test]
+	fn test_occlusion_query() -> ExitCode {
+		let aabb = Aabb::from_points([Vec3::new(1.0, -2.0, 0.5), Vec3::new(-1.0, 2.0, 0.0), Vec3::new(0.0, 0.0, 3.0)]).unwrap();
+		assert_eq!(aabb, Aabb::new(Vec3::new(-1.0, -2.0, 0.0), Vec3::new(1.0, 2.0, 3.0)));
+		assert_eq!(aabb.transform(&translation(&Vec3::new(1.0, 0.0, 0.0))), Aabb::new(Vec3::new(0.0, -2.0, 0.0), Vec3::new(2.0, 2.0, 3.0)));
+		assert_eq!((aabb.to_matrix() * Vec4::new(0.5, 0.5, 0.5, 1.0)).xyz(), aabb.max);
+		assert!(Aabb::from_points([]).is_none());
+
+		with_gl_context("Occlusion Query Test", |glcore| {
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			framebuffer.attach_renderbuffer(AttachmentPoint::DepthStencil, Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Depth24Stencil8, SIZE, SIZE).unwrap()));
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+uniform float depth;
+
+void main()
+{
+	gl_Position = vec4(position, depth, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+uniform vec4 color;
+
+out vec4 Color;
+
+void main()
+{
+	Color = color;
+}
+			")
+			).unwrap());
+
+			// The occluder in the middle of the depth range
+			let occluder = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh.clone(), shader.clone()).unwrap();
+			occluder.set_render_state(Some(RenderState {depth_test: true, ..Default::default()}));
+			let blue = Vec4::new(0.0, 0.0, 1.0, 1.0);
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+			glcore.glClearBufferfv(GL_DEPTH, 0, [1.0f32].as_ptr()).unwrap();
+			fb_bind.unbind();
+			occluder.bind().unwrap().draw_with(Some(&framebuffer), |program| {
+				program.set_uniform("depth", &0.0f32)?;
+				program.set_uniform("color", &blue)
+			}).unwrap();
+
+			// The expensive draw call is skipped when its bounding box is behind the occluder
+			let proxy = OcclusionProxy::new(glcore.clone()).unwrap();
+			let query = Query::new(glcore.clone(), QueryTarget::AnySamplesPassed).unwrap();
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			let behind = Aabb::new(Vec3::new(-0.5, -0.5, 0.5), Vec3::new(0.5, 0.5, 0.9));
+			let in_front = Aabb::new(Vec3::new(-0.5, -0.5, -0.9), Vec3::new(0.5, 0.5, -0.5));
+			let draw_conditional = |bounds: &Aabb| {
+				proxy.draw_query(Some(&framebuffer), &query, bounds, &Mat4::identity()).unwrap();
+				let visible = query.get_result().unwrap() != 0;
+				pipeline.bind().unwrap().draw_conditional(Some(&framebuffer), &query, ConditionalMode::Wait).unwrap();
+				visible
+			};
+
+			// The uniforms are kept by the program for `draw()`
+			let program = pipeline.shader.use_program().unwrap();
+			program.set_uniform("depth", &-1.0f32).unwrap();
+			program.set_uniform("color", &Vec4::new(1.0, 1.0, 1.0, 1.0)).unwrap();
+			program.unuse();
+			assert!(!draw_conditional(&behind));
+			assert_eq!(framebuffer.pick_pixel(0, 1, 1).unwrap(), [0, 0, 255, 255]);
+			assert!(draw_conditional(&in_front));
+			assert_eq!(framebuffer.pick_pixel(0, 1, 1).unwrap(), [255, 255, 255, 255]);
+		})
+	}
+
+	#[test]
+	fn test_draw_indirect_count() -> ExitCode {
+		with_gl_context("Draw Indirect Count Test", |glcore| {
+			// 10 commands of the quad, the compute shader writes the visible ones and their count
+			const NUM_COMMANDS: usize = 10;
+			let quad = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let commands = Buffer::new(glcore.clone(), BufferTarget::DrawIndirectBuffer, NUM_COMMANDS * size_of::<DrawElementsCommand>(), BufferUsage::DynamicCopy, std::ptr::null()).unwrap();
+			let mut commands = BufferVecStatic::<DrawElementsCommand>::from(commands);
+			commands.resize(NUM_COMMANDS, DrawElementsCommand::default()).unwrap();
+			let commands = BufferVecDynamic::new(commands).unwrap();
+			let mesh = StaticMesh::<MyVertex, u8, UnusedType, DrawElementsCommand>::new(PrimitiveMode::Triangles, quad.vertex_buffer, quad.element_buffer, None, Some(commands));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), Rc::new(mesh), shader).unwrap();
+			let count = 0u32;
+			let parameters = Buffer::new(glcore.clone(), BufferTarget::ParameterBuffer, size_of::<u32>(), BufferUsage::DynamicCopy, &count as *const u32 as *const c_void).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			assert!(matches!(p_bind.draw_indirect_count(None, &parameters, 0, NUM_COMMANDS + 1), Err(PipelineError::InvalidDrawCount(11, NUM_COMMANDS))));
+
+			let mut major: i32 = 0;
+			let mut minor: i32 = 0;
+			glcore.glGetIntegerv(GL_MAJOR_VERSION, &mut major as *mut _).unwrap();
+			glcore.glGetIntegerv(GL_MINOR_VERSION, &mut minor as *mut _).unwrap();
+			if (major, minor) < (4, 6) {
+				match p_bind.draw_indirect_count(None, &parameters, 0, NUM_COMMANDS) {
+					Err(PipelineError::IndirectCountNotSupported) | Ok(()) => return,
+					Err(e) => panic!("{e:?}"),
+				}
+			}
+
+			let culling = Shader::new_compute(glcore.clone(), "
+#version 430\n
+
+layout (local_size_x = 10) in;
+layout (std430, binding = 0) writeonly buffer Commands {uint commands[];};
+layout (std430, binding = 1) buffer Parameters {uint count;};
+
+void main()
+{
+	uint i = gl_GlobalInvocationID.x;
+	if (i % 3u == 0u && i < 9u) {
+		uint slot = atomicAdd(count, 1u);
+		commands[slot * 5u + 0u] = 6u;
+		commands[slot * 5u + 1u] = 1u;
+		commands[slot * 5u + 2u] = 0u;
+		commands[slot * 5u + 3u] = 0u;
+		commands[slot * 5u + 4u] = 0u;
+	}
+}
+		").unwrap();
+			let program = culling.use_program().unwrap();
+			glcore.glBindBufferBase(GL_SHADER_STORAGE_BUFFER, 0, pipeline.mesh.get_command_buffer().unwrap().get_name()).unwrap();
+			glcore.glBindBufferBase(GL_SHADER_STORAGE_BUFFER, 1, parameters.get_name()).unwrap();
+			program.dispatch_compute(1, 1, 1).unwrap();
+			glcore.glMemoryBarrier(GL_COMMAND_BARRIER_BIT).unwrap();
+			program.unuse();
+
+			// Only the 3 written commands of 2 triangles are drawn
+			let query = Query::new(glcore.clone(), QueryTarget::PrimitivesGenerated).unwrap();
+			let active = query.begin().unwrap();
+			p_bind.draw_indirect_count(None, &parameters, 0, NUM_COMMANDS).unwrap();
+			active.end();
+			assert_eq!(query.get_result().unwrap(), 6);
+			p_bind.unbind();
+		})
+	}
+
+	#[test]
+	fn test_half_float_attribs() -> ExitCode {
+		derive_vertex_type! {
+			pub struct UvVertex {
+				position: Vec2,
+				texcoord: Vec2,
+			}
+		}
+		derive_vertex_type! {
+			pub struct HalfUvVertex {
+				position: Vec2,
+				texcoord: F16Vec2,
+			}
+		}
+		with_gl_context("Half Float Attribs Test", |glcore| {
+			let v = Vec3::new(0.5, -2.0, 1024.0);
+			assert_eq!(Vec3::from(F16Vec3::from(v)), v);
+			assert_eq!(F16Vec4::from(Vec4::new(1.0, 0.0, 0.0, 0.0)).x, f16::ONE);
+
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+in vec2 texcoord;
+
+out vec2 v_texcoord;
+
+void main()
+{
+	v_texcoord = texcoord;
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+in vec2 v_texcoord;
+
+uniform sampler2D Source;
+
+out vec4 Color;
+
+void main()
+{
+	Color = texture(Source, v_texcoord);
+}
+			")
+			).unwrap());
+
+			// A gradient sampled linearly, so that the error of the texture coordinates shows up in the colors
+			const SIZE: u32 = 8;
+			let texture = Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).filters(SamplerMagFilter::Linear, SamplerFilter::Linear).build().unwrap();
+			let mut texels = Vec::new();
+			for y in 0..SIZE {
+				for x in 0..SIZE {
+					texels.extend_from_slice(&[(x * 32) as u8, (y * 32) as u8, ((x + y) * 16) as u8, 255]);
+				}
+			}
+			unsafe {texture.upload_texture(texels.as_ptr() as *const c_void, ChannelType::Rgba, ComponentType::U8, false).unwrap()};
+
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let corners = [(Vec2::new(-1.0, -1.0), Vec2::new(0.1, 0.2)), (Vec2::new(1.0, -1.0), Vec2::new(0.9, 0.2)), (Vec2::new(-1.0, 1.0), Vec2::new(0.1, 0.7)), (Vec2::new(1.0, 1.0), Vec2::new(0.9, 0.7))];
+			let render = |mesh: Rc<dyn GenericMeshWithMaterial>, draw: &dyn Fn(Rc<dyn GenericMeshWithMaterial>)| {
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+				fb_bind.unbind();
+				draw(mesh);
+				let mut pixels = Vec::new();
+				for y in 0..SIZE as i32 {
+					for x in 0..SIZE as i32 {
+						pixels.push(framebuffer.pick_pixel(0, x, y).unwrap());
+					}
+				}
+				pixels
+			};
+			let draw_full = |mesh: Rc<dyn GenericMeshWithMaterial>| {
+				let pipeline = Pipeline::<UvVertex, UnusedType>::new(glcore.clone(), mesh, shader.clone()).unwrap();
+				assert!(pipeline.warnings().is_empty());
+				let tex_bind = texture.bind().unwrap();
+				pipeline.bind().unwrap().draw_with(Some(&framebuffer), |program| program.set_uniform("Source", &0i32)).unwrap();
+				tex_bind.unbind();
+			};
+			let draw_half = |mesh: Rc<dyn GenericMeshWithMaterial>| {
+				let pipeline = Pipeline::<HalfUvVertex, UnusedType>::new(glcore.clone(), mesh, shader.clone()).unwrap();
+				assert!(pipeline.warnings().is_empty());
+				let tex_bind = texture.bind().unwrap();
+				pipeline.bind().unwrap().draw_with(Some(&framebuffer), |program| program.set_uniform("Source", &0i32)).unwrap();
+				tex_bind.unbind();
+			};
+
+			let vertices = corners.map(|(position, texcoord)| UvVertex {position, texcoord});
+			let mesh = StaticMesh::<UvVertex, u8, UnusedType, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::TriangleStrip, &vertices, None).unwrap();
+			let expected = render(Rc::new(mesh), &draw_full);
+
+			let vertices = corners.map(|(position, texcoord)| HalfUvVertex {position, texcoord: texcoord.into()});
+			let mesh = StaticMesh::<HalfUvVertex, u8, UnusedType, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::TriangleStrip, &vertices, None).unwrap();
+			let got = render(Rc::new(mesh), &draw_half);
+			assert_ne!(expected[0], expected[expected.len() - 1]);
+			for (expected, got) in expected.iter().zip(got.iter()) {
+				assert!(expected.iter().zip(got.iter()).all(|(e, g)| e.abs_diff(*g) <= 2), "{expected:?} {got:?}");
+			}
+		})
+	}
+
+	#[test]
+	fn test_draw_counts_by_len() -> ExitCode {
+		derive_vertex_type! {
+			pub struct InstanceOffset {
+				offset: Vec2,
+			}
+		}
+		with_gl_context("Draw Counts By Len Test", |glcore| {
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+in vec2 offset;
+
+void main()
+{
+	gl_Position = vec4(position * 0.1 + offset, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+
+			// Allocate 100 instances, then keep the first 10 of them
+			let quad = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mut mesh = StaticMesh::<MyVertex, u8, InstanceOffset, UnusedType>::new(PrimitiveMode::Triangles, quad.vertex_buffer, quad.element_buffer, None, None);
+			let instances: Vec<InstanceOffset> = (0..100).map(|i| InstanceOffset {offset: Vec2::new((i % 10) as f32 * 0.2 - 0.9, (i / 10) as f32 * 0.2 - 0.9)}).collect();
+			mesh.set_instances(&instances).unwrap();
+			let instance_buffer = mesh.instance_buffer.as_mut().unwrap();
+			instance_buffer.resize(10, InstanceOffset::default()).unwrap();
+			assert_eq!(instance_buffer.capacity(), 100);
+			assert_eq!(mesh.get_instance_count(), 10);
+
+			let pipeline = Pipeline::<MyVertex, InstanceOffset>::new(glcore.clone(), Rc::new(mesh), shader).unwrap();
+			let query = Query::new(glcore.clone(), QueryTarget::PrimitivesGenerated).unwrap();
+			let active = query.begin().unwrap();
+			let stats = pipeline.bind().unwrap().draw(None).unwrap();
+			active.end();
+			assert_eq!(query.get_result().unwrap(), 2 * 10);
+			assert_eq!(stats, DrawStats::new(1, 10, 6));
+		})
+	}
+
+	#[test]
+	fn test_debug_groups() -> ExitCode {
+		with_gl_context("Debug Groups Test", |glcore| {
+			let renderer = Renderer::new(glcore.clone()).unwrap();
+			renderer.pipeline.set_label(Some("Quad")).unwrap();
+			assert_eq!(renderer.pipeline.get_label(), Some("Quad".to_owned()));
+
+			// The scopes do nothing without `KHR_debug`
+			let group_depth = || {
+				let mut depth: i32 = 0;
+				glcore.glGetIntegerv(GL_DEBUG_GROUP_STACK_DEPTH, &mut depth as *mut _).unwrap();
+				depth
+			};
+			let mut major: i32 = 0;
+			let mut minor: i32 = 0;
+			glcore.glGetIntegerv(GL_MAJOR_VERSION, &mut major as *mut _).unwrap();
+			glcore.glGetIntegerv(GL_MINOR_VERSION, &mut minor as *mut _).unwrap();
+			if (major, minor) < (4, 3) && !has_gl_extension(&glcore, "GL_KHR_debug").unwrap() {
+				DebugScope::push(&glcore, "Frame").unwrap().pop();
+				insert_marker(&glcore, "Marker").unwrap();
+				return
+			}
+
+			let mut label = [0u8; 16];
+			let mut length: i32 = 0;
+			glcore.glGetObjectLabel(GL_VERTEX_ARRAY, renderer.pipeline.get_name(), label.len() as i32, &mut length as *mut _, label.as_mut_ptr() as *mut _).unwrap();
+			assert_eq!(&label[..length as usize], b"Quad");
+
+			// The groups pushed while drawing are balanced
+			let scope = DebugScope::push(&glcore, "Frame").unwrap();
+			assert_eq!(group_depth(), 2);
+			insert_marker(&glcore, "Before the quad").unwrap();
+			renderer.pipeline.bind().unwrap().draw(None).unwrap();
+			assert_eq!(group_depth(), 2);
+			scope.pop();
+			assert_eq!(group_depth(), 1);
+
+			DebugScope::set_internal_groups(false);
+			assert!(!DebugScope::is_internal_groups_enabled());
+			renderer.pipeline.bind().unwrap().draw(None).unwrap();
+			assert_eq!(group_depth(), 1);
+			DebugScope::set_internal_groups(true);
+		})
+	}
+
+	#[test]
+	fn test_model_matrix_instances() -> ExitCode {
+		use instancing::ModelInstance;
+		with_gl_context("Model Matrix Instances Test", |glcore| {
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+in mat4 model;
+
+void main()
+{
+	gl_Position = model * vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+			assert_eq!(shader.get_active_attribs().unwrap()["model"].get_size_and_rows(), (4, 4));
+
+			// Each of the 8 instances scales the quad to a pixel on the diagonal
+			const SIZE: u32 = 8;
+			let pixel = 2.0 / SIZE as f32;
+			let instances: Vec<ModelInstance> = (0..SIZE).map(|i| {
+				let center = -1.0 + (i as f32 + 0.5) * pixel;
+				translation(&Vec3::new(center, center, 0.0)) * scaling(&Vec3::new(pixel * 0.5, pixel * 0.5, 1.0))
+			}).map(ModelInstance::from).collect();
+			let quad = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mut mesh = StaticMesh::<MyVertex, u8, ModelInstance, UnusedType>::new(PrimitiveMode::Triangles, quad.vertex_buffer, quad.element_buffer, None, None);
+			mesh.set_instances(&instances).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(mesh);
+
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let check = |pipeline: &Pipeline<MyVertex, ModelInstance>| {
+				assert!(pipeline.warnings().is_empty(), "{:?}", pipeline.warnings());
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+				fb_bind.unbind();
+				pipeline.bind().unwrap().draw(Some(&framebuffer)).unwrap();
+				for y in 0..SIZE as i32 {
+					for x in 0..SIZE as i32 {
+						let expected = if x == y {[255, 255, 255, 255]} else {[0, 0, 0, 255]};
+						assert_eq!(framebuffer.pick_pixel(0, x, y).unwrap(), expected, "Pixel at {x}, {y}");
+					}
+				}
+			};
+
+			check(&Pipeline::<MyVertex, ModelInstance>::new_with_format(glcore.clone(), mesh.clone(), shader.clone(), StreamConfig::VERTEX, StreamConfig::INSTANCE, false).unwrap());
+			if let Ok(separated) = Pipeline::<MyVertex, ModelInstance>::new_with_format(glcore.clone(), mesh, shader.clone(), StreamConfig::VERTEX, StreamConfig::INSTANCE, true) {
+				check(&separated);
+			}
+		})
+	}
+
+	#[test]
+	fn test_pipeline_set_shader() -> ExitCode {
+		with_gl_context("Pipeline Set Shader Test", |glcore| {
+			let new_shader = |position: &str, color: &str| Rc::new(Shader::new(glcore.clone(),
+				Some(format!("
+#version 330\n
+
+{position} position;
+
+void main()
+{{
+	gl_Position = vec4(position.xy, 0.0, 1.0);
+}}
+			").as_str()),
+				None,
+				Some(format!("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{{
+	Color = {color};
+}}
+			").as_str())
+			).unwrap());
+
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let render = |pipeline: &Pipeline<MyVertex, UnusedType>| {
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&pipeline.shader).unwrap();
+				glcore.glClearBufferfv(GL_COLOR, 0, [0.0f32, 0.0, 0.0, 1.0].as_ptr()).unwrap();
+				fb_bind.unbind();
+				pipeline.bind().unwrap().draw(Some(&framebuffer)).unwrap();
+				framebuffer.pick_pixel(0, 1, 1).unwrap()
+			};
+
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(mesh);
+			let mut pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh, new_shader("layout (location = 0) in vec2", "vec4(1.0)")).unwrap();
+			assert_eq!(render(&pipeline), [255, 255, 255, 255]);
+
+			// The same attribs keep the description
+			assert!(!pipeline.set_shader(new_shader("layout (location = 0) in vec2", "vec4(1.0, 0.0, 0.0, 1.0)")).unwrap());
+			assert_eq!(render(&pipeline), [255, 0, 0, 255]);
+
+			// The attrib at another location is described again
+			assert!(pipeline.set_shader(new_shader("layout (location = 3) in vec2", "vec4(0.0, 1.0, 0.0, 1.0)")).unwrap());
+			assert_eq!(render(&pipeline), [0, 255, 0, 255]);
+
+			// The incompatible shader is rejected and the old one is kept
+			let old_shader = pipeline.shader.clone();
+			assert!(matches!(pipeline.set_shader(new_shader("in vec3", "vec4(0.0, 0.0, 1.0, 1.0)")), Err(PipelineError::AttribSizeMismatch(..))));
+			assert!(Rc::ptr_eq(&pipeline.shader, &old_shader));
+			assert_eq!(render(&pipeline), [0, 255, 0, 255]);
+		})
+	}
+
+	#[test]
+	fn test_draw_stats() -> ExitCode {
+		with_gl_context("Draw Stats Test", |glcore| {
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh.clone(), shader.clone()).unwrap();
+			let meshset = Meshset {
+				subsets: [("a".to_owned(), mesh.clone()), ("b".to_owned(), mesh)].into_iter().collect(),
+				lod_groups: Default::default(),
+			};
+			let pipelineset = Pipelineset::from_meshset::<MyVertex, UnusedType>(glcore.clone(), &meshset, &[shader]).unwrap();
+
+			// Nothing is collected before `enable()`
+			assert_eq!(FrameStats::report(), None);
+			assert_eq!(pipeline.bind().unwrap().draw(None).unwrap(), DrawStats::new(1, 1, 6));
+			assert_eq!(FrameStats::report(), None);
+
+			FrameStats::enable();
+			let p_bind = pipeline.bind().unwrap();
+			assert_eq!(p_bind.draw(None).unwrap(), DrawStats::new(1, 1, 6));
+			assert_eq!(p_bind.draw_range(None, 0, 3, 0).unwrap(), DrawStats::new(1, 1, 3));
+			p_bind.unbind();
+			assert_eq!(pipelineset.draw(None, None).unwrap(), DrawStats::new(2, 2, 12));
+
+			let frame = FrameStats::reset().unwrap();
+			assert_eq!(frame, DrawStats::new(4, 4, 21));
+			assert_eq!(frame.to_string(), "4 draw calls, 4 instances, 21 elements");
+			assert_eq!(FrameStats::report(), Some(DrawStats::default()));
+
+			let mut total = frame;
+			total += DrawStats::new(1, 2, 3);
+			assert_eq!(total, DrawStats::new(5, 6, 24));
+			FrameStats::disable();
+			assert!(!FrameStats::is_enabled());
+		})
+	}
+
+	#[test]
+	fn test_stencil_state() -> ExitCode {
+		with_gl_context("Stencil State Test", |glcore| {
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			framebuffer.attach_renderbuffer(AttachmentPoint::DepthStencil, Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Depth24Stencil8, SIZE, SIZE).unwrap()));
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let fragment_shader = "
+#version 330\n
+
+uniform vec4 color;
+
+out vec4 Color;
+
+void main()
+{
+	Color = color;
+}
+			";
+			let mask_shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position * 0.5 - 0.5, 0.0, 1.0);
+}
+			"),
+				None,
+				Some(fragment_shader)
+			).unwrap());
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some(fragment_shader)
+			).unwrap());
+			let clear = |stencil: i32| {
+				let fb_bind = framebuffer.bind().unwrap();
+				fb_bind.setup(&shader).unwrap();
+				fb_bind.clear_color(0, [0.0, 0.0, 0.0, 1.0]).unwrap();
+				fb_bind.clear_depth_stencil(1.0, stencil).unwrap();
+				fb_bind.unbind();
+			};
+			let white = [255, 255, 255, 255];
+			let black = [0, 0, 0, 255];
+			let far = SIZE as i32 - 1;
+
+			// Write 1s into the stencil of the bottom-left quarter without the colors
+			let mask = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh.clone(), mask_shader).unwrap();
+			mask.set_render_state(Some(RenderState {
+				color_mask: [false; 4],
+				stencil: Some(StencilFace::write(1).into()),
+				..Default::default()
+			}));
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			pipeline.set_render_state(Some(RenderState {
+				stencil: Some(StencilFace::test(CompareFunc::Equal, 1).into()),
+				..Default::default()
+			}));
+			clear(0);
+			mask.bind().unwrap().draw_with(Some(&framebuffer), |program| program.set_uniform("color", &Vec4::new(1.0, 0.0, 0.0, 1.0))).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), black);
+
+			// The fullscreen quad is only drawn where the stencil is 1
+			pipeline.bind().unwrap().draw_with(Some(&framebuffer), |program| program.set_uniform("color", &Vec4::new(1.0, 1.0, 1.0, 1.0))).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), white);
+			assert_eq!(framebuffer.pick_pixel(0, 1, 1).unwrap(), white);
+			assert_eq!(framebuffer.pick_pixel(0, far, far).unwrap(), black);
+			assert_eq!(framebuffer.pick_pixel(0, 0, far).unwrap(), black);
+			assert_eq!(glcore.glIsEnabled(GL_STENCIL_TEST).unwrap(), 0);
+			let mut write_mask = 0i32;
+			glcore.glGetIntegerv(GL_STENCIL_WRITEMASK, &mut write_mask as *mut _).unwrap();
+			assert_eq!(write_mask as u32 & 0xFF, 0xFF);
+
+			// Clearing the stencil to 1 lets the whole quad pass
+			clear(1);
+			pipeline.bind().unwrap().draw_with(Some(&framebuffer), |program| program.set_uniform("color", &Vec4::new(1.0, 1.0, 1.0, 1.0))).unwrap();
+			assert_eq!(framebuffer.pick_pixel(0, far, far).unwrap(), white);
+		})
+	}
+
+	#[test]
+	fn test_viewport_array() -> ExitCode {
+		with_gl_context("Viewport Array Test", |glcore| {
+			// The viewport arrays need OpenGL 4.1
+			let max_viewports = match ViewportArray::get_max_viewports(&glcore) {
+				Ok(max_viewports) => max_viewports,
+				Err(ViewportError::NotSupported) => {
+					assert!(matches!(ViewportArray::from(vec![IndexedViewport::default()]).apply(&glcore), Err(ViewportError::NotSupported)));
+					return
+				}
+				Err(e) => panic!("{e:?}"),
+			};
+
+			const SIZE: u32 = 4;
+			let mut framebuffer = Framebuffer::new(glcore.clone()).unwrap();
+			framebuffer.attach_renderbuffer(AttachmentPoint::Color(0), Rc::new(Renderbuffer::new(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).unwrap()));
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 410\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				Some("
+#version 410\n
+
+layout(triangles) in;
+layout(triangle_strip, max_vertices = 6) out;
+
+out vec4 v_color;
+
+void main()
+{
+	for (int viewport = 0; viewport < 2; viewport++)
+	{
+		for (int i = 0; i < 3; i++)
+		{
+			gl_Position = gl_in[i].gl_Position;
+			gl_ViewportIndex = viewport;
+			v_color = viewport == 0 ? vec4(1.0, 0.0, 0.0, 1.0) : vec4(0.0, 1.0, 0.0, 1.0);
+			EmitVertex();
+		}
+		EndPrimitive();
+	}
+}
+			"),
+				Some("
+#version 410\n
+
+in vec4 v_color;
+
+out vec4 Color;
+
+void main()
+{
+	Color = v_color;
+}
+			")
+			).unwrap());
+			let fb_bind = framebuffer.bind().unwrap();
+			fb_bind.setup(&shader).unwrap();
+			fb_bind.clear_color(0, [0.0, 0.0, 0.0, 1.0]).unwrap();
+			fb_bind.unbind();
+
+			// The left half is drawn by the viewport 0, the bottom row of the right half by the scissor of the viewport 1
+			let half = SIZE as f32 / 2.0;
+			let mut viewports = ViewportArray::new();
+			viewports.set(1, IndexedViewport::new(half, 0.0, half, SIZE as f32).with_scissor(SIZE as i32 / 2, 0, SIZE / 2, 1));
+			viewports.set(0, IndexedViewport::new(0.0, 0.0, half, SIZE as f32));
+			assert_eq!(viewports.len(), 2);
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			pipeline.set_viewport_array(Some(viewports)).unwrap();
+			pipeline.bind().unwrap().draw(Some(&framebuffer)).unwrap();
+			let far = SIZE as i32 - 1;
+			assert_eq!(framebuffer.pick_pixel(0, 0, 0).unwrap(), [255, 0, 0, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, 1, far).unwrap(), [255, 0, 0, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, far, 0).unwrap(), [0, 255, 0, 255]);
+			assert_eq!(framebuffer.pick_pixel(0, far, far).unwrap(), [0, 0, 0, 255]);
+
+			// The viewports are restored after drawing
+			let mut rect = [0f32; 4];
+			glcore.glGetFloati_v(GL_VIEWPORT, 1, rect.as_mut_ptr()).unwrap();
+			assert_eq!(rect, [0.0, 0.0, SIZE as f32, SIZE as f32]);
+			assert_eq!(glcore.glIsEnabledi(GL_SCISSOR_TEST, 1).unwrap(), 0);
+
+			let mut too_many = ViewportArray::new();
+			too_many.set(max_viewports, IndexedViewport::default());
+			match pipeline.set_viewport_array(Some(too_many)) {
+				Err(ViewportError::TooManyViewports(count, max)) => assert_eq!((count, max), (max_viewports + 1, max_viewports)),
+				other => panic!("Expected `TooManyViewports`, got {other:?}"),
+			}
+			assert!(pipeline.get_viewport_array().is_some());
+		})
+	}
+
+	#[test]
+	fn test_draw_order() -> ExitCode {
+		with_gl_context("Draw Order Test", |glcore| {
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let new_shader = || Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+uniform vec4 color;
+
+out vec4 Color;
+
+void main()
+{
+	Color = color;
+}
+			")
+			).unwrap());
+			let (shader1, shader2) = (new_shader(), new_shader());
+			let mut pipelineset = Pipelineset::default();
+			for (name, shader) in [("a", &shader1), ("b", &shader2), ("c", &shader1), ("d", &shader2)] {
+				pipelineset.insert(name, Rc::new(Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh.clone(), shader.clone()).unwrap()));
+			}
+			let draw_names = |pipelineset: &Pipelineset| {
+				let mut names = Vec::new();
+				let stats = pipelineset.draw_with(None, None, |name, program| {
+					names.push((name.to_owned(), program.shader.get_name()));
+					program.set_uniform("color", &Vec4::new(1.0, 1.0, 1.0, 1.0))
+				}).unwrap();
+				(names, stats)
+			};
+			let current_program = || {
+				let mut program: i32 = -1;
+				glcore.glGetIntegerv(GL_CURRENT_PROGRAM, &mut program as *mut _).unwrap();
+				program
+			};
+
+			let (by_name, stats) = draw_names(&pipelineset);
+			assert_eq!(by_name.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), ["a", "b", "c", "d"]);
+			assert_eq!((stats.draw_calls, stats.program_switches, stats.material_setups), (4, 0, 0));
+			assert_eq!(current_program(), 0);
+
+			// The pipelines of the same shader are drawn together, by the names within the group
+			pipelineset.set_draw_order(DrawOrder::StateSorted);
+			let (sorted, stats) = draw_names(&pipelineset);
+			let (first, second) = if shader1.get_name() < shader2.get_name() {(["a", "c"], ["b", "d"])} else {(["b", "d"], ["a", "c"])};
+			assert_eq!(sorted.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), [first, second].concat());
+			assert_eq!(sorted.windows(2).filter(|pair| pair[0].1 != pair[1].1).count(), 1);
+			// Each of the shaders is used once and sets up the shared material once
+			assert_eq!((stats.draw_calls, stats.program_switches, stats.material_setups), (4, 2, 2));
+			assert_eq!(current_program(), 0);
+
+			// Inserting a pipeline rebuilds the order
+			pipelineset.insert("e", Rc::new(Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh.clone(), shader1.clone()).unwrap()));
+			let (names, stats) = draw_names(&pipelineset);
+			assert_eq!(names.len(), 5);
+			assert_eq!((stats.draw_calls, stats.program_switches, stats.material_setups), (5, 2, 2));
+
+			// Replacing a pipeline of `subsets` directly keeps the count but still rebuilds the order
+			let replaced = Rc::new(Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh.clone(), shader2.clone()).unwrap());
+			pipelineset.subsets.get_mut("e").unwrap()[0] = replaced;
+			let (names, stats) = draw_names(&pipelineset);
+			assert_eq!(names.iter().filter(|(_, program)| *program == shader2.get_name()).count(), 3);
+			assert_eq!(names.windows(2).filter(|pair| pair[0].1 != pair[1].1).count(), 1);
+			assert_eq!((stats.draw_calls, stats.program_switches, stats.material_setups), (5, 2, 2));
+
+			// A nested use of the program in use doesn't unuse it
+			let outer = shader1.use_program().unwrap();
+			let inner = shader1.use_program().unwrap();
+			inner.unuse();
+			assert_eq!(current_program(), shader1.get_name() as i32);
+			outer.unuse();
+			assert_eq!(current_program(), 0);
+		})
+	}
+
+	#[test]
+	fn test_meshset_subsets() -> ExitCode {
+		with_gl_context("Meshset Subsets Test", |glcore| {
+			let new_mesh = || -> Rc<dyn GenericMeshWithMaterial> {Rc::new(MeshWithMaterial::new(Renderer::new_quad_mesh(glcore.clone()).unwrap(), Rc::new(MaterialLegacy::default())))};
+			let mut meshset = Meshset::default();
+			for name in ["Floor/a", "Floor/b", "Wall/a"] {
+				meshset.add_subset(name, new_mesh()).unwrap();
+			}
+			assert_eq!(meshset.add_subset("Wall/a", new_mesh()), Err(MeshsetError::DuplicateName("Wall/a".to_owned())));
+			assert_eq!(meshset.len(), 3);
+			let names = |pattern| meshset.subsets_matching(pattern).map(|(name, _)| name.to_owned()).collect::<Vec<_>>();
+			assert_eq!(names("Floor/"), ["Floor/a", "Floor/b"]);
+			assert_eq!(names("*/a"), ["Floor/a", "Wall/a"]);
+			assert_eq!(names("?all/*"), ["Wall/a"]);
+			assert_eq!(meshset.rename_subset("Wall/a", "Floor/b"), Err(MeshsetError::DuplicateName("Floor/b".to_owned())));
+			assert_eq!(meshset.rename_subset("Roof", "Floor/c"), Err(MeshsetError::SubsetNotFound("Roof".to_owned())));
+
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+			let mut pipelineset = Pipelineset::from_meshset::<MyVertex, UnusedType>(glcore.clone(), &meshset, &[shader.clone()]).unwrap();
+			let kept = pipelineset.subsets["Floor/a"][0].clone();
+
+			// Only the changed subsets get new pipelines
+			meshset.rename_subset("Wall/a", "Wall/c").unwrap();
+			assert!(meshset.remove_subset("Floor/b").is_some());
+			pipelineset.sync_with::<MyVertex, UnusedType>(glcore.clone(), &meshset, &[shader.clone()]).unwrap();
+			assert_eq!(pipelineset.subsets.keys().collect::<Vec<_>>(), ["Floor/a", "Wall/c"]);
+			assert!(Rc::ptr_eq(&pipelineset.subsets["Floor/a"][0], &kept));
+			meshset.subsets.insert("Floor/a".to_owned(), new_mesh());
+			pipelineset.sync_with::<MyVertex, UnusedType>(glcore.clone(), &meshset, &[shader]).unwrap();
+			assert!(!Rc::ptr_eq(&pipelineset.subsets["Floor/a"][0], &kept));
+			assert_eq!(pipelineset.draw(None, None).unwrap().draw_calls, 2);
+		})
+	}
+
+	#[test]
+	fn test_frustum() {
+		// The identity matrix makes the frustum the cube of the clip space
+		let cube = Frustum::from_matrix(&Mat4::identity());
+		assert!(cube.contains_point(&Vec3::new(0.5, -0.5, 0.9)));
+		assert!(!cube.contains_point(&Vec3::new(1.5, 0.0, 0.0)));
+		assert!(cube.intersects_aabb(&Aabb::new(Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, 0.5, 0.5))));
+		assert!(cube.intersects_aabb(&Aabb::new(Vec3::new(0.5, 0.5, 0.5), Vec3::new(3.0, 3.0, 3.0))));
+		assert!(cube.intersects_aabb(&Aabb::new(Vec3::new(-5.0, -5.0, -5.0), Vec3::new(5.0, 5.0, 5.0))));
+		assert!(!cube.intersects_aabb(&Aabb::new(Vec3::new(1.5, -0.5, -0.5), Vec3::new(2.0, 0.5, 0.5))));
+		assert!(!cube.intersects_aabb(&Aabb::new(Vec3::new(-0.5, -0.5, -3.0), Vec3::new(0.5, 0.5, -1.5))));
+
+		// The camera at the origin looking at -Z
+		let view_projection = perspective(1.0, std::f32::consts::FRAC_PI_2, 0.1, 100.0) * look_at(&Vec3::zeros(), &Vec3::new(0.0, 0.0, -1.0), &Vec3::y());
+		let frustum = Frustum::from_matrix(&view_projection);
+		let unit_at = |x: f32, y: f32, z: f32| Aabb::new(Vec3::new(x - 0.5, y - 0.5, z - 0.5), Vec3::new(x + 0.5, y + 0.5, z + 0.5));
+		assert!(frustum.intersects_aabb(&unit_at(0.0, 0.0, -10.0)));
+		assert!(frustum.intersects_aabb(&unit_at(10.0, 0.0, -10.0)));
+		assert!(!frustum.intersects_aabb(&unit_at(0.0, 0.0, 10.0)));
+		assert!(!frustum.intersects_aabb(&unit_at(20.0, 0.0, -10.0)));
+		assert!(!frustum.intersects_aabb(&unit_at(0.0, 0.0, -200.0)));
+		for plane in frustum.planes.iter() {
+			assert!((plane.xyz().norm() - 1.0).abs() < 1e-5);
+		}
+	}
+
+	#[test]
+	fn test_draw_culled() -> ExitCode {
+		with_gl_context("Draw Culled Test", |glcore| {
+			let new_mesh = || -> Rc<dyn GenericMeshWithMaterial> {Rc::new(MeshWithMaterial::new(Renderer::new_quad_mesh(glcore.clone()).unwrap(), Rc::new(MaterialLegacy::default())))};
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+uniform mat4 model;
+
+void main()
+{
+	gl_Position = model * vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+			let mut meshset = Meshset::default();
+			for name in ["far", "near", "edge"] {
+				meshset.add_subset(name, new_mesh()).unwrap();
+			}
+			let pipelineset = Pipelineset::from_meshset::<MyVertex, UnusedType>(glcore.clone(), &meshset, &[shader]).unwrap();
+			let transform = |name: &str| match name {
+				"far" => translation(&Vec3::new(5.0, 0.0, 0.0)),
+				"edge" => translation(&Vec3::new(1.5, 0.0, 0.0)),
+				_ => Mat4::identity(),
+			};
+
+			// The bounding boxes are computed from the `position` of the vertices and cached on the meshes
+			let pipeline = &pipelineset.subsets["near"][0];
+			assert_eq!(pipeline.get_mesh().get_bounds(), None);
+			assert_eq!(pipeline.get_bounds().unwrap(), Some(Aabb::new(Vec3::new(-1.0, -1.0, 0.0), Vec3::new(1.0, 1.0, 0.0))));
+			assert_eq!(pipeline.get_mesh().get_bounds(), pipeline.get_bounds().unwrap());
+
+			FrameStats::enable();
+			let stats = pipelineset.draw_culled_with(None, None, &Mat4::identity(), transform, |name, program| program.set_uniform("model", &transform(name))).unwrap();
+			assert_eq!((stats.draw_calls, stats.culled), (2, 1));
+			assert_eq!(FrameStats::reset().unwrap().culled, 1);
+			FrameStats::disable();
+			assert_eq!(pipelineset.draw_culled(None, &translation(&Vec3::new(0.0, 10.0, 0.0)), transform).unwrap().culled, 3);
+			assert!(stats.to_string().ends_with(", 1 culled"));
+		})
+	}
+
+	#[test]
+	fn test_draw_subsets() -> ExitCode {
+		with_gl_context("Draw Subsets Test", |glcore| {
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+			let transparent = MaterialLegacy {
+				diffuse: MaterialComponent::Color(Vec4::new(1.0, 1.0, 1.0, 0.5)),
+				..Default::default()
+			};
+			let mut pipelineset = Pipelineset::default();
+			for (name, material) in [("a", MaterialLegacy::default()), ("b", transparent), ("c", MaterialLegacy::default())] {
+				let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(Renderer::new_quad_mesh(glcore.clone()).unwrap(), Rc::new(material)));
+				pipelineset.insert(name, Rc::new(Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh, shader.clone()).unwrap()));
+			}
+
+			assert_eq!(pipelineset.draw(None, None).unwrap().draw_calls, 3);
+			assert_eq!(pipelineset.draw_subsets(None, &["a", "c"]).unwrap().draw_calls, 2);
+			match pipelineset.draw_subsets(None, &["a", "x", "y"]) {
+				Err(PipelineError::SubsetsNotFound(names)) => assert_eq!(names, ["x", "y"]),
+				other => panic!("Expected `SubsetsNotFound`, got {other:?}"),
+			}
+
+			// The transparent pass
+			let is_transparent = |_: &str, material: &dyn Material| matches!(material.get_diffuse(), Some(MaterialComponent::Color(color)) if color.w < 1.0);
+			assert_eq!(pipelineset.draw_filtered(None, is_transparent).unwrap().draw_calls, 1);
+			assert_eq!(pipelineset.draw_filtered(None, |name, material| !is_transparent(name, material)).unwrap().draw_calls, 2);
+
+			// The hidden subsets are skipped by all of the draws without being culled
+			pipelineset.set_visible("b", false);
+			assert!(!pipelineset.is_visible("b"));
+			let stats = pipelineset.draw(None, None).unwrap();
+			assert_eq!((stats.draw_calls, stats.culled), (2, 0));
+			assert_eq!(pipelineset.draw_subsets(None, &["b"]).unwrap().draw_calls, 0);
+			pipelineset.set_visible("b", true);
+			assert_eq!(pipelineset.draw_subsets(None, &["b"]).unwrap().draw_calls, 1);
+		})
+	}
+
+	#[test]
+	fn test_draw_with_shader() -> ExitCode {
+		with_gl_context("Draw With Shader Test", |glcore| {
+			let new_shader = |fragment: &str| Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some(fragment)
+			).unwrap());
+			let depth_prepass = new_shader("
+#version 330\n
+
+void main()
+{
+}
+		");
+			let forward = new_shader("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+		");
+			let mut meshset = Meshset::default();
+			for name in ["a", "b"] {
+				meshset.add_subset(name, Rc::new(MeshWithMaterial::new(Renderer::new_quad_mesh(glcore.clone()).unwrap(), Rc::new(MaterialLegacy::default())))).unwrap();
+			}
+			let pipelineset = Pipelineset::from_meshset::<MyVertex, UnusedType>(glcore.clone(), &meshset, &[depth_prepass.clone(), forward.clone()]).unwrap();
+			assert_eq!(pipelineset.get_shaders().len(), 2);
+			assert!(Rc::ptr_eq(&pipelineset.get_shaders()[1], &forward));
+			assert!(matches!(Pipelineset::from_meshset::<MyVertex, UnusedType>(glcore.clone(), &meshset, &[]), Err(PipelineError::NoShaders)));
+
+			// Each of the subsets is drawn once by each of the shaders
+			assert_eq!(pipelineset.draw(None, None).unwrap().draw_calls, 4);
+			assert_eq!(pipelineset.draw_with_shader(0, None, None).unwrap().draw_calls, 2);
+			assert_eq!(pipelineset.draw_with_shader(1, None, None).unwrap().draw_calls, 2);
+			assert!(matches!(pipelineset.draw_with_shader(2, None, None), Err(PipelineError::InvalidShaderIndex(2, 2))));
+		})
+	}
+
+	#[test]
+	fn test_bake_into_batch() -> ExitCode {
+		with_gl_context("Bake Into Batch Test", |glcore| {
+			let (red, blue) = (Rc::new(MaterialLegacy::default()), Rc::new(MaterialLegacy::default()));
+			let mut meshset = Meshset::default();
+			for (name, material) in [("a", &red), ("b", &blue), ("c", &red)] {
+				meshset.add_subset(name, Rc::new(MeshWithMaterial::new(Renderer::new_quad_mesh(glcore.clone()).unwrap(), material.clone()))).unwrap();
+			}
+			let vertices = [MyVertex{position: Vec2::new(0.0, 0.0)}, MyVertex{position: Vec2::new(1.0, 0.0)}, MyVertex{position: Vec2::new(0.0, 1.0)}];
+			meshset.add_subset("d", Rc::new(StaticMesh::<MyVertex, u16, UnusedType, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, None).unwrap())).unwrap();
+
+			let (batch, names) = meshset.bake_into_batch::<MyVertex, UnusedType>(glcore.clone()).unwrap();
+			assert_eq!(names, [("a".to_owned(), 0), ("b".to_owned(), 1), ("c".to_owned(), 2), ("d".to_owned(), 3)]);
+			assert_eq!((batch.vertex_buffer.len(), batch.element_buffer.as_ref().unwrap().len()), (15, 21));
+			let commands = batch.command_buffer.as_ref().unwrap();
+			let summary: Vec<(u32, u32, i32, u32)> = (0..4).map(|i| (commands[i].get_element_count(), commands[i].get_first_index(), commands[i].get_base_vertex(), commands[i].get_base_instance())).collect();
+			assert_eq!(summary, [(6, 0, 0, 0), (6, 6, 4, 1), (6, 12, 8, 0), (3, 18, 12, 3)]);
+			assert!((0..4).all(|i| commands[i].get_instance_count() == 1));
+
+			// The elements are kept, the commands rebase them
+			let elements = batch.element_buffer.as_ref().unwrap().get_slice_of_data(0, 21).unwrap();
+			assert_eq!(elements[6..12], [0, 1, 2, 1, 3, 2]);
+			assert_eq!(elements[18..], [0, 1, 2]);
+			assert!(matches!(meshset.bake_into_batch::<shapes::ShapeVertex, UnusedType>(glcore.clone()), Err(MeshError::VertexStrideMismatch(8, _))));
+
+			// The extra vertex streams couldn't be merged, the subset is rejected instead of losing them
+			let positions = [MyVertex::default(); 4];
+			let stream_buffer = Buffer::new(glcore.clone(), BufferTarget::ArrayBuffer, size_of_val(&positions), BufferUsage::StaticDraw, positions.as_ptr() as *const c_void).unwrap();
+			let mut stream_buffer = BufferVecStatic::<MyVertex>::new(stream_buffer);
+			stream_buffer.resize(positions.len(), MyVertex::default()).unwrap();
+			let mut multi_stream = MultiStreamMesh::new(Renderer::new_quad_mesh(glcore.clone()).unwrap());
+			multi_stream.add_stream(VertexStream::new(stream_buffer, 0));
+			meshset.add_subset("e", Rc::new(MeshWithMaterial::new(multi_stream, red.clone()))).unwrap();
+			assert!(matches!(meshset.bake_into_batch::<MyVertex, UnusedType>(glcore.clone()), Err(MeshError::UnmergedStreams(name)) if name == "e"));
+		})
+	}
+
+	#[test]
+	fn test_patch_vertices() -> ExitCode {
+		with_gl_context("Patch Vertices Test", |glcore| {
+			let mut mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			mesh.primitive = PrimitiveMode::Patches;
+			mesh.patch_vertices = Some(u32::MAX);
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			assert_eq!(mesh.get_patch_vertices(), Some(u32::MAX));
+			let shader = Rc::new(Shader::new(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+
+void main()
+{
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				Some("
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+			")
+			).unwrap());
+
+			// The patch size is validated against `GL_MAX_PATCH_VERTICES` before anything is drawn
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			assert!(matches!(p_bind.draw(None), Err(PipelineError::InvalidPatchVertices(u32::MAX, _))));
+			p_bind.unbind();
+		})
+	}
+
+	#[test]
+	fn test_transform_feedback() -> ExitCode {
+		with_gl_context("Transform Feedback Test", |glcore| {
+			let mesh = Renderer::new_quad_mesh(glcore.clone()).unwrap();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = Rc::new(MeshWithMaterial::new(mesh, Rc::new(MaterialLegacy::default())));
+			let shader = Rc::new(Shader::new_with_varyings(glcore.clone(),
+				Some("
+#version 330\n
+
+in vec2 position;
+out vec2 transformed;
+
+void main()
+{
+	transformed = position * 2.0 + 1.0;
+	gl_Position = vec4(position, 0.0, 1.0);
+}
+			"),
+				None,
+				None,
+				&["transformed"],
+				TransformFeedbackBufferMode::Interleaved,
+			).unwrap());
+
+			// Each of the 6 elements of the quad produces a captured vertex
+			let captured = Buffer::new(glcore.clone(), BufferTarget::TransformFeedbackBuffer, size_of::<[f32; 12]>(), BufferUsage::StreamRead, std::ptr::null()).unwrap();
+			let tf = TransformFeedback::new(glcore.clone(), true).unwrap();
+			let pipeline = Pipeline::<MyVertex, UnusedType>::new(glcore.clone(), mesh, shader).unwrap();
+			let p_bind = pipeline.bind().unwrap();
+			p_bind.draw_with_capture(&tf, &captured).unwrap();
+			p_bind.unbind();
+
+			let bind = captured.bind().unwrap();
+			let (mapping, address) = bind.map(MapAccess::ReadOnly).unwrap();
+			let data = unsafe {*(address as *const [f32; 12])};
+			mapping.unmap();
+			bind.unbind();
+			assert_eq!(data, [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0, 3.0, -1.0, 3.0, 3.0, -1.0, 3.0]);
+		})
+	}
+
+	#[test]
+	fn test_odd_width_rgb_upload() -> ExitCode {
+		with_gl_context("Odd Width RGB Upload Test", |glcore| {
+			// The rows of a 3x3 RGB image are 9 bytes long, not a multiple of the default alignment of 4
+			const SIZE: u32 = 3;
+			let img = image::RgbImage::from_fn(SIZE, SIZE, |x, y| image::Rgb([(x * 10 + y) as u8, (y * 10 + x + 100) as u8, (x + y + 200) as u8]));
+			let texture = Texture::<BufferVecStatic<u8>, u8>::from_image(glcore.clone(), TextureDimension::Tex2d, &img, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, false, SamplerMagFilter::Nearest, SamplerFilter::Nearest, false).unwrap();
+			let mut pixels = vec![0u8; texture.bytes_of_level(0, ChannelType::Rgb, ComponentType::U8)];
+			assert_eq!(pixels.len(), (SIZE * 3 * SIZE) as usize);
+			unsafe {texture.download_texture(pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgb, ComponentType::U8).unwrap()};
+			assert_eq!(&pixels, img.as_raw());
+
+			// The raw pointer paths take the tightly packed rows too
+			let texture = Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgb8, SIZE, SIZE)
+				.initial_data(img.as_raw(), ChannelType::Rgb, ComponentType::U8)
+				.build().unwrap();
+			unsafe {texture.download_texture(pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgb, ComponentType::U8).unwrap()};
+			assert_eq!(&pixels, img.as_raw());
+			let reversed: Vec<u8> = img.as_raw().iter().rev().copied().collect();
+			unsafe {texture.upload_level(0, None, [SIZE, SIZE, 1], reversed.as_ptr() as *const c_void, ChannelType::Rgb, ComponentType::U8).unwrap()};
+			unsafe {texture.download_texture(pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgb, ComponentType::U8).unwrap()};
+			assert_eq!(pixels, reversed);
+		})
+	}
+
+	#[test]
+	fn test_grayscale_policy() -> ExitCode {
+		with_gl_context("Grayscale Policy Test", |glcore| {
+			// The grayscale images keep the red-only storage by default, the policy is chosen by the `_with_grayscale_policy` variants
+			const SIZE: u32 = 3;
+			let gray = image::GrayImage::from_pixel(SIZE, SIZE, image::Luma([128]));
+			let texture = Texture::<BufferVecStatic<u8>, u8>::from_image(glcore.clone(), TextureDimension::Tex2d, &gray, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, false, SamplerMagFilter::Nearest, SamplerFilter::Nearest, false).unwrap();
+			assert_eq!(texture.get_grayscale_policy(), GrayscalePolicy::KeepRed);
+			let texture = Texture::<BufferVecStatic<u8>, u8>::from_image_with_grayscale_policy(glcore.clone(), TextureDimension::Tex2d, &gray, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, false, SamplerMagFilter::Nearest, SamplerFilter::Nearest, false, GrayscalePolicy::ExpandToRgb).unwrap();
+			assert_eq!((texture.get_grayscale_policy(), texture.get_format()), (GrayscalePolicy::ExpandToRgb, TextureFormat::Rgb8));
+		})
+	}
+
+	#[test]
+	fn test_volume_from_images() -> ExitCode {
+		with_gl_context("Volume From Images Test", |glcore| {
+			const WIDTH: u32 = 3;
+			const HEIGHT: u32 = 2;
+			let slices: Vec<image::RgbImage> = (0..4).map(|z| image::RgbImage::from_fn(WIDTH, HEIGHT, |x, y| image::Rgb([(x * 10 + y) as u8, (z * 10 + x) as u8, (z * 10 + y + 100) as u8]))).collect();
+			let pitch = WIDTH * 3;
+			// The threshold of 0 forces the slice by slice path
+			for threshold in [DEFAULT_VOLUME_UPLOAD_THRESHOLD, 0] {
+				let texture = Texture::<BufferVecStatic<u8>, u8>::volume_from_images_with_threshold(glcore.clone(), &slices, TextureWrapping::ClampToEdge, false, SamplerMagFilter::Nearest, SamplerFilter::Nearest, threshold).unwrap();
+				assert_eq!(texture.get_depth(), slices.len() as u32);
+				let mut pixels = vec![0u8; texture.bytes_of_level(0, ChannelType::Rgb, ComponentType::U8)];
+				unsafe {texture.download_texture(pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgb, ComponentType::U8).unwrap()};
+				for (z, slice) in slices.iter().enumerate() {
+					for y in 0..HEIGHT {
+						for x in 0..WIDTH {
+							let i = (z as u32 * HEIGHT * pitch + y * pitch + x * 3) as usize;
+							assert_eq!(pixels[i..i + 3], slice.get_pixel(x, y).0, "({x}, {y}, {z}) threshold {threshold}");
+						}
+					}
+				}
+			}
+
+			let mut mismatched = slices.clone();
+			mismatched[2] = image::RgbImage::new(WIDTH + 1, HEIGHT);
+			match Texture::<BufferVecStatic<u8>, u8>::volume_from_images(glcore.clone(), &mismatched, TextureWrapping::ClampToEdge, false, SamplerMagFilter::Nearest, SamplerFilter::Nearest) {
+				Err(TextureError::LoadImageError(LoadImageError::InvalidVolume(2, _))) => {}
+				other => panic!("Expected the slice 2 to be reported, got {:?}", other.err()),
+			}
+		})
+	}
+
+	#[test]
+	fn test_cube_from_images() -> ExitCode {
+		with_gl_context("Cube From Images Test", |glcore| {
+			const SIZE: u32 = 2;
+			let colors: [image::Rgba<u8>; 6] = std::array::from_fn(|i| image::Rgba([i as u8 * 40, 255 - i as u8 * 40, i as u8, 255]));
+			let expected: Vec<u8> = colors.iter().flat_map(|color| color.0.repeat((SIZE * SIZE) as usize)).collect();
+			let faces = colors.map(|color| image::RgbaImage::from_pixel(SIZE, SIZE, color));
+			let cube_map = Texture::<BufferVecStatic<u8>, u8>::cube_from_images(glcore.clone(), &faces, false, SamplerMagFilter::Nearest, SamplerFilter::Nearest).unwrap();
+			assert_eq!((cube_map.get_dim(), cube_map.get_width(), cube_map.get_height()), (TextureDimension::TexCube, SIZE, SIZE));
+			let mut pixels = vec![0u8; cube_map.bytes_of_level(0, ChannelType::Rgba, ComponentType::U8)];
+			unsafe {cube_map.download_texture(pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::U8).unwrap()};
+			assert_eq!(pixels, expected);
+
+			// The horizontal cross places +Y above +Z, -X +Z +X -Z in the middle row, and -Y below +Z
+			let cells = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+			let cross = image::RgbaImage::from_fn(SIZE * 4, SIZE * 3, |x, y| {
+				cells.iter().position(|cell| *cell == (x / SIZE, y / SIZE)).map_or(image::Rgba([0, 0, 0, 0]), |i| colors[i])
+			});
+			let cube_map = Texture::<BufferVecStatic<u8>, u8>::cube_from_cross_image(glcore.clone(), &cross, false, SamplerMagFilter::Nearest, SamplerFilter::Nearest).unwrap();
+			let mut pixels = vec![0u8; cube_map.bytes_of_level(0, ChannelType::Rgba, ComponentType::U8)];
+			unsafe {cube_map.download_texture(pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::U8).unwrap()};
+			assert_eq!(pixels, expected);
+
+			let mut mismatched = faces.clone();
+			mismatched[3] = image::RgbaImage::new(SIZE, SIZE + 1);
+			assert!(matches!(Texture::<BufferVecStatic<u8>, u8>::cube_from_images(glcore.clone(), &mismatched, false, SamplerMagFilter::Nearest, SamplerFilter::Nearest), Err(TextureError::LoadImageError(LoadImageError::InvalidCubeMap(_)))));
+			mismatched[3] = image::RgbaImage::new(SIZE + 1, SIZE + 1);
+			assert!(matches!(Texture::<BufferVecStatic<u8>, u8>::cube_from_images(glcore.clone(), &mismatched, false, SamplerMagFilter::Nearest, SamplerFilter::Nearest), Err(TextureError::LoadImageError(LoadImageError::InvalidCubeMap(_)))));
+			let not_cross = image::RgbaImage::new(SIZE * 3, SIZE * 4);
+			assert!(matches!(Texture::<BufferVecStatic<u8>, u8>::cube_from_cross_image(glcore.clone(), &not_cross, false, SamplerMagFilter::Nearest, SamplerFilter::Nearest), Err(TextureError::LoadImageError(LoadImageError::InvalidCubeMap(_)))));
+		})
+	}
+
+	#[test]
+	fn test_pack_pixel_buffer() -> ExitCode {
+		with_gl_context("Pack Pixel Buffer Test", |glcore| {
+			const SIZE: u32 = 8;
+			let pattern: Vec<u8> = (0..SIZE * SIZE * 4).map(|i| (i * 7) as u8).collect();
+			let mut texture = Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba8, SIZE, SIZE).initial_data(&pattern, ChannelType::Rgba, ComponentType::U8).build().unwrap();
+			texture.create_pixel_buffer(ChannelType::Rgba, ComponentType::U8, None).unwrap();
+			texture.pack_pixel_buffer(None).unwrap();
+			let (bind, mapping, address) = texture.map_buffer(MapAccess::ReadOnly).unwrap().unwrap();
+			assert_eq!(bind.get_target(), BufferTarget::PixelPackBuffer);
+			let pixels = unsafe {std::slice::from_raw_parts(address as *const u8, pattern.len())};
+			assert_eq!(pixels, &pattern[..]);
+			mapping.unmap();
+			bind.unbind();
+		})
+	}
+
+	#[test]
+	fn test_compute_image_store() -> ExitCode {
+		with_gl_context_hinted("Compute Image Store Test", [WindowHint::ContextVersion(4, 3), WindowHint::OpenGlProfile(OpenGlProfileHint::Core)], |glcore| {
+			const SIZE: u32 = 16;
+			let texture = Texture::<BufferVecStatic<u8>, u8>::builder_2d(glcore.clone(), TextureFormat::Rgba32f, SIZE, SIZE).filters(SamplerMagFilter::Nearest, SamplerFilter::Nearest).build().unwrap();
+			let shader = Shader::new_compute(glcore.clone(), "
+#version 430\n
+
+layout (local_size_x = 8, local_size_y = 8) in;
+layout (rgba32f) uniform writeonly image2D oImage;
+
+void main()
+{
+	ivec2 coord = ivec2(gl_GlobalInvocationID.xy);
+	vec2 gradient = vec2(coord) / vec2(imageSize(oImage) - 1);
+	imageStore(oImage, coord, vec4(gradient, 0.0, 1.0));
+}
+		").unwrap();
+			let program = shader.use_program().unwrap();
+			let image_bind = texture.bind_image_unit(0, 0, false, 0, ImageAccess::WriteOnly, TextureFormat::Rgba32f).unwrap();
+			program.set_uniform_image("oImage", image_bind.get_unit()).unwrap();
+			program.dispatch_compute(SIZE / 8, SIZE / 8, 1).unwrap();
+			glcore.glMemoryBarrier(GL_TEXTURE_FETCH_BARRIER_BIT | GL_TEXTURE_UPDATE_BARRIER_BIT).unwrap();
+			image_bind.unbind();
+			program.unuse();
+
+			let mut pixels = vec![0f32; (SIZE * SIZE * 4) as usize];
+			unsafe {texture.download_texture(pixels.as_mut_ptr() as *mut c_void, ChannelType::Rgba, ComponentType::F32).unwrap()};
+			for (i, pixel) in pixels.chunks(4).enumerate() {
+				let x = (i as u32 % SIZE) as f32 / (SIZE - 1) as f32;
+				let y = (i as u32 / SIZE) as f32 / (SIZE - 1) as f32;
+				assert!((pixel[0] - x).abs() < 1e-5 && (pixel[1] - y).abs() < 1e-5, "{i}: {pixel:?}");
+				assert_eq!(&pixel[2..], [0.0, 1.0]);
+			}
+			assert!(texture.bind_image_unit(0, 0, false, 0, ImageAccess::ReadOnly, TextureFormat::Rgb32f).is_err());
+		})
+	}
+
+	#[test]
+	fn test_depth_compare() -> ExitCode {
+		with_gl_context("Depth Compare Test", |glcore| {
+			let mut shadow_map = Texture::<BufferVecStatic<f32>, f32>::new_2d(glcore.clone(), TextureFormat::Depth, 4, 4, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, false, SamplerMagFilter::Linear, SamplerFilter::Linear, false, ChannelType::Depth, ComponentType::F32, None).unwrap();
+			shadow_map.set_depth_compare(Some(CompareFunc::LEqual)).unwrap();
+			assert_eq!(shadow_map.get_depth_compare(), Some(CompareFunc::LEqual));
+			shadow_map.set_depth_compare(None).unwrap();
+			assert_eq!(shadow_map.get_depth_compare(), None);
+
+			let mut color = Texture::<BufferVecStatic<u8>, u8>::new_2d(glcore.clone(), TextureFormat::Rgba8, 4, 4, TextureWrapping::ClampToEdge, TextureWrapping::ClampToEdge, false, SamplerMagFilter::Linear, SamplerFilter::Linear, false, ChannelType::Rgba, ComponentType::U8, None).unwrap();
+			assert!(matches!(color.set_depth_compare(Some(CompareFunc::LEqual)), Err(TextureError::NotDepthFormat(TextureFormat::Rgba8))));
+			assert_eq!(color.get_depth_compare(), None);
+		})
+	}
+
+	#[test]
+	fn test_dds_parse() {
+		let file = include_bytes!("../testdata/bc1_8x8_mips.dds");
+		let dds = DdsImage::parse(file, "bc1_8x8_mips.dds").unwrap();
+		assert_eq!(dds.format, TextureFormat::Bc1Rgba);
+		assert_eq!((dds.width, dds.height, dds.mip_levels), (8, 8, 4));
+		assert!(!dds.is_cube_map && !dds.premultiplied_alpha && dds.pixel_type.is_none());
+		assert_eq!(dds.faces[0].iter().map(|level| level.len()).collect::<Vec<_>>(), [32, 8, 8, 8]);
+		assert_eq!(dds.faces.concat().concat(), &file[128..]);
+
+		let file = include_bytes!("../testdata/rgba8_cube_dx10.dds");
+		let dds = DdsImage::parse(file, "rgba8_cube_dx10.dds").unwrap();
+		assert_eq!(dds.format, TextureFormat::Rgba8);
+		assert_eq!(dds.pixel_type, Some((ChannelType::Rgba, ComponentType::U8)));
+		assert!(dds.is_cube_map);
+		assert_eq!(dds.faces.len(), 6);
+		assert_eq!(dds.faces.concat().concat(), &file[148..]);
+
+		match DdsImage::parse(&file[..file.len() - 1], "truncated.dds") {
+			Err(LoadImageError::DdsTruncated(name)) => assert_eq!(name, "truncated.dds"),
+			other => panic!("Expected `DdsTruncated`, got {other:?}"),
+		}
+
+		// The mip count beyond the 1x1 level and the huge sizes are rejected instead of overflowing
+		let mut malformed = include_bytes!("../testdata/bc1_8x8_mips.dds").to_vec();
+		for mip_count in [5u32, 40, u32::MAX] {
+			malformed[28..32].copy_from_slice(&mip_count.to_le_bytes());
+			assert!(matches!(DdsImage::parse(&malformed, "malformed.dds"), Err(LoadImageError::DdsInvalidHeader(_))));
+		}
+		malformed[12..20].copy_from_slice(&[0xFF; 8]);
+		malformed[28..32].copy_from_slice(&32u32.to_le_bytes());
+		assert!(matches!(DdsImage::parse(&malformed, "malformed.dds"), Err(LoadImageError::DdsTruncated(_))));
+
+		// The I/O error tells which file could not be read
+		match DdsImage::from_file(Path::new("testdata/missing.dds")) {
+			Err(LoadImageError::IOError(err)) => assert!(err.to_string().contains("missing.dds")),
+			other => panic!("Expected `IOError`, got {other:?}"),
+		}
+	}
+
+	#[test]
+	fn test_ktx2_parse() {
+		let file = include_bytes!("../testdata/rgba8_4x2_mips.ktx2");
+		let ktx2 = Ktx2Image::parse(file, "rgba8_4x2_mips.ktx2").unwrap();
+		assert_eq!(ktx2.format, TextureFormat::Rgba8);
+		assert_eq!(ktx2.dim, TextureDimension::Tex2d);
+		assert_eq!((ktx2.width, ktx2.height, ktx2.depth, ktx2.mip_levels), (4, 2, 1, 2));
+		assert!(!ktx2.generate_mipmap);
+		assert_eq!(ktx2.levels[0], &file[140..172]);
+		assert_eq!(ktx2.levels[1], &file[132..140]);
+
+		let mut basis = file.to_vec();
+		basis[12..16].copy_from_slice(&0u32.to_le_bytes());
+		basis[44..48].copy_from_slice(&1u32.to_le_bytes());
+		match Ktx2Image::parse(&basis, "basis.ktx2") {
+			Err(LoadImageError::Ktx2TranscodingUnsupported(name, _)) => assert_eq!(name, "basis.ktx2"),
+			other => panic!("Expected `Ktx2TranscodingUnsupported`, got {other:?}"),
+		}
+
+		// The overflowing level index and the level count beyond the 1x1 level are rejected instead of panicking
+		let mut malformed = file.to_vec();
+		malformed[80..88].copy_from_slice(&u64::MAX.to_le_bytes());
+		assert!(matches!(Ktx2Image::parse(&malformed, "malformed.ktx2"), Err(LoadImageError::Ktx2Truncated(_))));
+		let mut malformed = file.to_vec();
+		malformed[88..96].copy_from_slice(&(u64::MAX - 100).to_le_bytes());
+		assert!(matches!(Ktx2Image::parse(&malformed, "malformed.ktx2"), Err(LoadImageError::Ktx2Truncated(_))));
+		let mut malformed = file.to_vec();
+		malformed[40..44].copy_from_slice(&40u32.to_le_bytes());
+		assert!(matches!(Ktx2Image::parse(&malformed, "malformed.ktx2"), Err(LoadImageError::Ktx2InvalidHeader(_))));
+		assert!(matches!(Ktx2Image::parse(&file[..100], "truncated.ktx2"), Err(LoadImageError::Ktx2Truncated(_))));
+	}
+
+	#[test]
+	fn test_texture_format_from_pixel_type() {
+		use ChannelType as C;
+		use ComponentType as T;
+		use TextureFormat as F;
+		let channel_types = [C::Red, C::Rg, C::Rgb, C::Bgr, C::Rgba, C::Bgra, C::RedInteger, C::RgInteger, C::RgbInteger, C::BgrInteger, C::RgbaInteger, C::BgraInteger, C::StencilIndex, C::Depth, C::DepthStencil];
+		let component_types = [T::U8, T::I8, T::U16, T::I16, T::U32, T::I32, T::F16, T::F32, T::U8_332, T::U8_233Rev, T::U16_565, T::U16_565Rev, T::U16_4444, T::U16_4444Rev, T::U16_5551, T::U16_1555Rev, T::U32_8888, T::U32_8888Rev, T::U32_10_10_10_2, T::U32_2_10_10_10Rev, T::U32_24_8, T::F32_24_8Rev];
+		let packed = [
+			(T::U8_332, F::R3g3b2),
+			(T::U16_4444, F::Rgba4),
+			(T::U16_5551, F::Rgb5a1),
+			(T::U32_8888, F::Rgba8),
+			(T::U32_10_10_10_2, F::Rgb10a2),
+			(T::U32_24_8, F::Depth24Stencil8),
+			(T::F32_24_8Rev, F::Depth32FStencil8),
+		];
+		let depth = [
+			(T::U16, F::DepthComponent16),
+			(T::U32, F::DepthComponent24),
+			(T::F32, F::DepthComponent32F),
+		];
+		// Each row is the formats of `Red`, `Rg`, `Rgb`/`Bgr`, `Rgba`/`Bgra`, then the same order for the `*Integer` channel types
+		let table = [
+			(T::U8,  [Some(F::Red8),      Some(F::Rg8),      Some(F::Rgb8),       Some(F::Rgba8),       Some(F::R8ui),  Some(F::Rg8ui),  Some(F::Rgb8ui),  Some(F::Rgba8ui)]),
+			(T::I8,  [Some(F::Red8Snorm), Some(F::Rg8Snorm), Some(F::Rgb8Snorm),  Some(F::Rgba8Snorm),  Some(F::R8i),   Some(F::Rg8i),   Some(F::Rgb8i),   Some(F::Rgba8i)]),
+			(T::U16, [Some(F::Red16),     Some(F::Rg16),     Some(F::Rgb16),      Some(F::Rgba16),      Some(F::R16ui), Some(F::Rg16ui), Some(F::Rgb16ui), Some(F::Rgba16ui)]),
+			(T::I16, [Some(F::Red16Snorm),Some(F::Rg16Snorm),Some(F::Rgb16Snorm), Some(F::Rgba16Snorm), Some(F::R16i),  Some(F::Rg16i),  Some(F::Rgb16i),  Some(F::Rgba16i)]),
+			(T::U32, [None,               None,              None,                None,                 Some(F::R32ui), Some(F::Rg32ui), Some(F::Rgb32ui), Some(F::Rgba32ui)]),
+			(T::I32, [None,               None,              None,                None,                 Some(F::R32i),  Some(F::Rg32i),  Some(F::Rgb32i),  Some(F::Rgba32i)]),
+			(T::F32, [Some(F::R32f),      Some(F::Rg32f),    Some(F::Rgb32f),     Some(F::Rgba32f),     None,           None,            None,             None]),
+		];
+		for channel_type in channel_types {
+			let column = match channel_type {
+				C::Red => Some(0),
+				C::Rg => Some(1),
+				C::Rgb | C::Bgr => Some(2),
+				C::Rgba | C::Bgra => Some(3),
+				C::RedInteger => Some(4),
+				C::RgInteger => Some(5),
+				C::RgbInteger | C::BgrInteger => Some(6),
+				C::RgbaInteger | C::BgraInteger => Some(7),
+				_ => None,
+			};
+			for component_type in component_types {
+				let expected = if let Some((_, format)) = packed.iter().find(|(t, _)| *t == component_type) {
+					Some(*format)
+				} else if channel_type == C::Depth {
+					depth.iter().find(|(t, _)| *t == component_type).map(|(_, format)| *format)
+				} else if let (Some((_, row)), Some(column)) = (table.iter().find(|(t, _)| *t == component_type), column) {
+					row[column]
+				} else {
+					None
+				};
+				assert_eq!(TextureFormat::from_channel_type_and_component_type(channel_type, component_type), expected, "{channel_type:?} {component_type:?}");
+			}
+		}
+	}
+
+	#[test]
+	fn test_texture_format_accepts() {
+		use ChannelType as C;
+		use ComponentType as T;
+		use TextureFormat as F;
+		let cases = [
+			(F::Rgba8, C::Rgba, T::U8, true),
+			(F::Rgba8, C::Bgra, T::U32_8888Rev, true),
+			(F::Rgba8, C::RgbaInteger, T::U8, false),
+			(F::Rgba8, C::Rgb, T::U32_8888, false),
+			(F::Rgba8, C::Depth, T::F32, false),
+			(F::Rgb8, C::Rgb, T::U16_565, true),
+			(F::Rgb8, C::Rgba, T::U16_565, false),
+			(F::Srgb8Alpha8, C::Rgba, T::U8, true),
+			(F::Bc7, C::Rgba, T::U8, true),
+			(F::R32ui, C::RedInteger, T::U32, true),
+			(F::R32ui, C::Red, T::U32, false),
+			(F::R32ui, C::RedInteger, T::F32, false),
+			(F::Rgb10a2ui, C::RgbaInteger, T::U32_2_10_10_10Rev, true),
+			(F::R32f, C::Red, T::F32, true),
+			(F::Depth, C::Depth, T::F32, true),
+			(F::Depth, C::Red, T::F32, false),
+			(F::DepthComponent16, C::Depth, T::U16, true),
+			(F::DepthComponent24, C::Depth, T::U32, true),
+			(F::DepthComponent32F, C::Depth, T::F32, true),
+			(F::DepthComponent24, C::DepthStencil, T::U32_24_8, false),
+			(F::Depth24Stencil8, C::DepthStencil, T::U32_24_8, true),
+			(F::Depth24Stencil8, C::DepthStencil, T::U32, false),
+			(F::Depth32FStencil8, C::DepthStencil, T::F32_24_8Rev, true),
+			(F::Rgba8, C::Rgba, T::U32_24_8, false),
+			(F::Rgba8, C::StencilIndex, T::U8, false),
+		];
+		for (format, channel_type, component_type, expected) in cases {
+			assert_eq!(format.accepts(channel_type, component_type), expected, "{format:?} {channel_type:?} {component_type:?}");
+		}
+
+		// Every format chosen for a pixel type must accept the pixel type
+		let channel_types = [C::Red, C::Rg, C::Rgb, C::Bgr, C::Rgba, C::Bgra, C::RedInteger, C::RgInteger, C::RgbInteger, C::BgrInteger, C::RgbaInteger, C::BgraInteger];
+		let component_types = [T::U8, T::I8, T::U16, T::I16, T::U32, T::I32, T::F32];
+		for channel_type in channel_types {
+			for component_type in component_types {
+				if let Some(format) = TextureFormat::from_channel_type_and_component_type(channel_type, component_type) {
+					assert!(format.accepts(channel_type, component_type), "{format:?} {channel_type:?} {component_type:?}");
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn test_texture_format_bits_of_pixel() {
+		use ChannelType as C;
+		use ComponentType as T;
+		use TextureFormat as F;
+		let cases = [
+			(F::Depth, 32),
+			(F::Rgba, 32),
+			(F::R3g3b2, 8),
+			(F::Rgb5a1, 16),
+			(F::Rgb10a2, 32),
+			(F::R11fg11fb10f, 32),
+			(F::Rgb9e5, 32),
+			(F::Srgb8, 24),
+			(F::Rgba32f, 128),
+			(F::Bc1Rgb, 4),
+			(F::Bc7, 8),
+		];
+		for (format, expected) in cases {
+			assert_eq!(format.bits_of_pixel(), expected, "{format:?}");
+		}
+
+		// The formats chosen for the plain pixel types have the same size as the pixels
+		let channel_types = [C::Red, C::Rg, C::Rgb, C::Bgr, C::Rgba, C::Bgra, C::RedInteger, C::RgInteger, C::RgbInteger, C::BgrInteger, C::RgbaInteger, C::BgraInteger];
+		let component_types = [T::U8, T::I8, T::U16, T::I16, T::U32, T::I32, T::F32];
+		for channel_type in channel_types {
+			for component_type in component_types {
+				if let Some(format) = TextureFormat::from_channel_type_and_component_type(channel_type, component_type) {
+					assert_eq!(format.bits_of_pixel(), size_of_pixel(channel_type, component_type) * 8, "{format:?} {channel_type:?} {component_type:?}");
+				}
+			}
+		}
+
+		// The block compressed formats are counted by the 4x4 blocks
+		for format in [F::Bc1Rgba, F::Bc2, F::Bc3, F::Bc4Snorm, F::Bc5, F::Bc6hSfloat, F::Bc7Srgb] {
+			assert_eq!(format.bits_of_pixel() * 16, format.compressed_block_bytes().unwrap() * 8, "{format:?}");
+		}
+	}
+
+	#[test]
+	fn test_texture_units() {
+		let mut units = TextureUnits::with_max_units(8);
+		units.reserve(7).unwrap();
+		assert!(units.reserve(7).is_err());
+		assert!(units.reserve(8).is_err());
+		let allocated: Vec<u32> = std::iter::from_fn(|| units.allocate()).collect();
+		assert_eq!(allocated, (0..7).collect::<Vec<u32>>());
+		assert!(units.reserve(3).is_err());
+		units.release(3);
+		assert_eq!(units.allocate(), Some(3));
+		units.release_all();
+		assert_eq!(units.num_free(), 7);
+		assert!(units.is_reserved(7));
+		units.unreserve(7);
+		assert_eq!(units.num_free(), 8);
+	}
+
+	#[test]
+	fn test_shape_geometry() {
+		let shapes = [
+			("quad", shapes::ShapeGeometry::quad(2.0, 1.0), 4, 6),
+			("cube", shapes::ShapeGeometry::cube(1.0), 24, 36),
+			("plane", shapes::ShapeGeometry::plane(2.0, 2.0, 4), 25, 96),
+			("uv_sphere", shapes::ShapeGeometry::uv_sphere(1.0, 8, 4), 45, 144),
+			("cylinder", shapes::ShapeGeometry::cylinder(1.0, 2.0, 8), 38, 96),
+			("torus", shapes::ShapeGeometry::torus(1.0, 0.25, 8, 6), 63, 288),
+		];
+		for (name, shape, num_vertices, num_indices) in shapes.iter() {
+			assert_eq!((shape.vertices.len(), shape.indices.len()), (*num_vertices, *num_indices), "{name}");
+			assert_eq!(shape.primitive, PrimitiveMode::Triangles);
+			for vertex in shape.vertices.iter() {
+				assert!((vertex.normal.norm() - 1.0).abs() < 1e-5, "{name}: {vertex:?}");
+				assert!((0.0..=1.0).contains(&vertex.texcoord.x) && (0.0..=1.0).contains(&vertex.texcoord.y), "{name}: {vertex:?}");
+			}
+
+			// Every triangle is counter-clockwise seen from the side its normals point to
+			for triangle in shape.indices.chunks(3) {
+				let [a, b, c] = [0, 1, 2].map(|i| shape.vertices[triangle[i] as usize]);
+				let face_normal = (b.position - a.position).cross(&(c.position - a.position));
+				assert!(face_normal.norm() > 1e-6, "{name}: degenerated {triangle:?}");
+				assert!(face_normal.dot(&(a.normal + b.normal + c.normal)) > 0.0, "{name}: {triangle:?}");
+			}
+		}
+
+		// The seam of the sphere has the duplicated positions with both ends of the texture coordinates
+		let sphere = shapes::ShapeGeometry::uv_sphere(2.0, 8, 4);
+		let row_len = 9;
+		let first = sphere.vertices[row_len];
+		let last = sphere.vertices[row_len * 2 - 1];
+		assert!((first.position - last.position).norm() < 1e-5);
+		assert_eq!((first.texcoord.x, last.texcoord.x), (0.0, 1.0));
+		assert!(sphere.vertices.iter().all(|vertex| (vertex.position.norm() - 2.0).abs() < 1e-5));
+	}
+
+	#[test]
+	fn test_weld_vertices() {
+		let plane = shapes::ShapeGeometry::plane(2.0, 2.0, 4);
+		let unwelded: Vec<shapes::ShapeVertex> = plane.indices.iter().map(|index| plane.vertices[*index as usize]).collect();
+		let (welded, indices, stats) = weld_vertices(&unwelded, None);
+		assert_eq!((welded.len(), indices.len()), (25, 96));
+		assert_eq!(stats.to_string(), "96 -> 25 vertices");
+		assert!(indices.iter().zip(unwelded.iter()).all(|(index, vertex)| welded[*index as usize] == *vertex));
+
+		// The jittered positions are only welded with the epsilon, the normals must still match exactly
+		let jittered: Vec<shapes::ShapeVertex> = unwelded.iter().enumerate().map(|(i, vertex)| {
+			let mut vertex = *vertex;
+			vertex.position.x += i as f32 * 1e-6;
+			vertex
+		}).collect();
+		assert_eq!(weld_vertices(&jittered, None).0.len(), 96);
+		assert_eq!(weld_vertices(&jittered, Some(1e-3)).0.len(), 25);
+		let cube = shapes::ShapeGeometry::cube(1.0);
+		assert_eq!(weld_vertices(&cube.vertices, Some(1e-3)).2, WeldStats {vertices_before: 24, vertices_after: 24});
+	}
+
+	#[test]
+	fn test_obj_parse() {
+		let text = "mtllib scene.mtl\n\
+			v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+			vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+			o Floor\nusemtl stone\nf 1/1 2/2 3/3 4/4 # a quad\n\
+			g Wall\nusemtl brick\nf -4/-4 -3/-3 -2/-2\nusemtl plaster\nf -4 -2 -1\n\
+			vn 0 0 -1\ng Back\nf 1//1 3//1 2//1\n";
+		let smooth = ObjModel::parse(text, "test.obj", &ObjLoadOptions::default()).unwrap();
+		assert_eq!(smooth.material_libs, ["scene.mtl"]);
+		let names: Vec<(&str, Option<&str>)> = smooth.groups.iter().map(|group| (group.name.as_str(), group.material.as_deref())).collect();
+		assert_eq!(names, [("Floor", Some("stone")), ("Floor/Wall", Some("brick")), ("Floor/Wall", Some("plaster")), ("Floor/Back", Some("plaster"))]);
+		let floor = &smooth.groups[0];
+		assert_eq!((floor.vertices.len(), floor.indices.len()), (4, 6));
+		assert!(floor.vertices.iter().all(|vertex| (vertex.normal - Vec3::z()).norm() < 1e-5));
+		assert_eq!(floor.vertices[2].texcoord, Vec2::new(1.0, 1.0));
+		let back = &smooth.groups[3];
+		assert_eq!((back.vertices.len(), back.indices.len()), (3, 3));
+		assert!(back.vertices.iter().all(|vertex| vertex.normal == Vec3::new(0.0, 0.0, -1.0)));
+
+		let flat = ObjModel::parse(text, "test.obj", &ObjLoadOptions {normals: ObjNormals::Flat}).unwrap();
+		assert_eq!((flat.groups[0].vertices.len(), flat.groups[0].indices.len()), (6, 6));
+		assert_eq!(flat.groups[3].vertices.len(), 3);
+
+		for (bad, line) in [("v 0 0 0\nf 1 2 3\n", 2), ("v 0 0\n", 1), ("v 0 0 0\nv 1 0 0\nf 1 2\n", 3)] {
+			match ObjModel::parse(bad, "bad.obj", &ObjLoadOptions::default()) {
+				Err(MeshLoadError::ObjParseError(name, error_line, _)) => assert_eq!((name.as_str(), error_line), ("bad.obj", line)),
+				other => panic!("Expected a parse error for {bad:?}, got {other:?}"),
+			}
+		}
+	}
+
+	#[test]
+	fn test_obj_mtl() -> ExitCode {
+		let mtl = MtlLibrary::parse("newmtl red\nKd 1 0 0\nKs 0.5\nNs 32\nd 0.5\nmap_Kd -bm 1 missing.png\n", "test.mtl").unwrap();
+		let red = mtl.get("red").unwrap();
+		assert_eq!((red.diffuse, red.specular, red.specular_power, red.dissolve), (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.5, 0.5, 0.5), 32.0, 0.5));
+		assert_eq!(red.diffuse_map.as_deref(), Some("missing.png"));
+		assert!(matches!(MtlLibrary::parse("Kd 1 1 1\n", "bad.mtl"), Err(MeshLoadError::ObjParseError(_, 1, _))));
+
+		with_gl_context("OBJ MTL Test", |glcore| {
+			let dir = std::env::temp_dir().join(format!("globject_test_obj_mtl_{}", std::process::id()));
+			std::fs::create_dir_all(&dir).unwrap();
+			std::fs::write(dir.join("scene.mtl"), "newmtl red\nKd 1 0 0\nmap_Kd missing.png\n").unwrap();
+			std::fs::write(dir.join("scene.obj"), "mtllib scene.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\ng Red\nusemtl red\nf 1 2 3\ng Plain\nusemtl nothing\nf 1 2 3\n").unwrap();
+			let model = ObjModel::from_file(&dir.join("scene.obj"), &ObjLoadOptions::default()).unwrap();
+			let mut warnings = Vec::new();
+			let materials = model.load_materials(&glcore, &dir, &mut warnings).unwrap();
+			assert_eq!(warnings, [
+				ObjLoadWarning::MissingTexture("red".to_owned(), dir.join("missing.png")),
+				ObjLoadWarning::MissingMaterial("nothing".to_owned(), "Plain".to_owned()),
+			]);
+			assert!(matches!(materials["red"].diffuse, MaterialComponent::Color(color) if color == Vec4::new(1.0, 0.0, 0.0, 1.0)));
+
+			let (meshset, materials, mesh_warnings) = Meshset::from_obj_with_mtl(glcore.clone(), &dir.join("scene.obj")).unwrap();
+			assert_eq!(materials.len(), 1);
+			assert_eq!(mesh_warnings, warnings);
+			assert!(meshset.subsets["Red"].get_material().is_some());
+			assert!(meshset.subsets["Plain"].get_material().is_none());
+			std::fs::remove_dir_all(&dir).unwrap();
+		})
+	}
+
+	#[test]
+	fn test_meshset_cache() -> ExitCode {
+		with_gl_context("Meshset Cache Test", |glcore| {
+			let dir = std::env::temp_dir().join(format!("globject_test_meshset_cache_{}", std::process::id()));
+			std::fs::create_dir_all(&dir).unwrap();
+			image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255])).save(dir.join("red.png")).unwrap();
+
+			let mut textures = TextureCache::new();
+			let mut red = MtlMaterial::new("red");
+			red.diffuse_map = Some("red.png".to_owned());
+			let material = Rc::new(red.to_material(&glcore, &dir, &mut textures, &mut Vec::new()).unwrap());
+			let mut meshset = Meshset::default();
+			for name in ["a", "b"] {
+				meshset.add_subset(name, Rc::new(MeshWithMaterial::new(Renderer::new_quad_mesh(glcore.clone()).unwrap(), material.clone()))).unwrap();
+			}
+			meshset.add_subset("plain", Rc::new(Renderer::new_quad_mesh(glcore.clone()).unwrap())).unwrap();
+			let path = dir.join("scene.meshset");
+			meshset.save_to_file(&path, &textures).unwrap();
+
+			// The textures are loaded again by their files, once for the shared material
+			let mut loaded_textures = TextureCache::new();
+			let loaded = Meshset::load_from_file::<MyVertex>(glcore.clone(), &path, &mut loaded_textures).unwrap();
+			assert_eq!(loaded.subsets.keys().collect::<Vec<_>>(), ["a", "b", "plain"]);
+			assert_eq!(loaded_textures.len(), 1);
+			assert!(loaded.subsets["plain"].get_material().is_none());
+			for name in ["a", "b"] {
+				let mesh = &loaded.subsets[name];
+				assert_eq!((mesh.get_primitive(), mesh.get_vertex_count(), mesh.get_element_count(), mesh.get_element_type()), (PrimitiveMode::Triangles, 4, 6, ElementType::U8));
+				assert!(matches!(mesh.get_material().unwrap().get_diffuse(), Some(MaterialComponent::Texture(_))));
+			}
+			assert!(matches!(Meshset::load_from_file::<shapes::ShapeVertex>(glcore.clone(), &path, &mut loaded_textures), Err(MeshsetCacheError::VertexStrideMismatch(..))));
+
+			// The truncated or corrupted caches fail the loading instead of the allocations or the draws
+			let mut bytes = std::fs::read(&path).unwrap();
+			std::fs::write(&path, &bytes[..bytes.len() - 16]).unwrap();
+			assert!(matches!(Meshset::load_from_file::<MyVertex>(glcore.clone(), &path, &mut loaded_textures), Err(MeshsetCacheError::DecodeError(_))));
+			let huge_length = [&MESHSET_CACHE_MAGIC[..], &MESHSET_CACHE_VERSION.to_le_bytes(), &u64::MAX.to_le_bytes()].concat();
+			std::fs::write(&path, &huge_length).unwrap();
+			assert!(matches!(Meshset::load_from_file::<MyVertex>(glcore.clone(), &path, &mut loaded_textures), Err(MeshsetCacheError::DecodeError(_))));
+			let vertices = [MyVertex::default(); 3];
+			let mut broken = Meshset::default();
+			broken.add_subset("broken", Rc::new(StaticMesh::<MyVertex, u8, UnusedType, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, Some(&[0u8, 1, 3][..])).unwrap())).unwrap();
+			broken.save_to_file(&path, &textures).unwrap();
+			assert!(matches!(Meshset::load_from_file::<MyVertex>(glcore.clone(), &path, &mut loaded_textures), Err(MeshsetCacheError::InvalidData(_))));
+
+			// The caches of the other versions or the other files are rejected
+			bytes[8] = 99;
+			std::fs::write(&path, &bytes).unwrap();
+			assert!(matches!(Meshset::load_from_file::<MyVertex>(glcore.clone(), &path, &mut loaded_textures), Err(MeshsetCacheError::VersionMismatch(99, MESHSET_CACHE_VERSION))));
+			std::fs::write(&path, b"Not a mesh set cache file").unwrap();
+			assert!(matches!(Meshset::load_from_file::<MyVertex>(glcore.clone(), &path, &mut loaded_textures), Err(MeshsetCacheError::InvalidMagic)));
+			std::fs::remove_dir_all(&dir).unwrap();
+		})
+	}
+
+	#[test]
+	#[cfg(feature = "gltf")]
+	fn test_gltf_parse() {
+		let text = r#"{
+			"asset": {"version": "2.0"},
+			"scene": 0,
+			"scenes": [{"nodes": [0, 1]}],
+			"nodes": [
+				{"name": "Mirror", "mesh": 0, "translation": [1, 0, 0], "scale": [-1, 1, 1]},
+				{"mesh": 0, "translation": [0, 0, 2]}
+			],
+			"meshes": [{"name": "Triangle", "primitives": [{"attributes": {"POSITION": 0}, "indices": 1}]}],
+			"buffers": [{"byteLength": 44, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA="}],
+			"bufferViews": [{"buffer": 0, "byteLength": 36}, {"buffer": 0, "byteOffset": 36, "byteLength": 6}],
+			"accessors": [
+				{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]},
+				{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}
+			]
+		}"#;
+		let model = GltfModel::parse(text.as_bytes(), None, "test.gltf").unwrap();
+		let names: Vec<&str> = model.primitives.iter().map(|primitive| primitive.name.as_str()).collect();
+		assert_eq!(names, ["Mirror/Triangle/0", "node1/Triangle/0"]);
+		for primitive in model.primitives.iter() {
+			// Without normals, the triangles are unwelded with the flat normals, the mirrored one is rewound to keep facing +Z
+			assert!(primitive.indices.is_none());
+			assert_eq!(primitive.vertices.len(), 3);
+			assert!(primitive.vertices.iter().all(|vertex| (vertex.normal - Vec3::z()).norm() < 1e-5));
+		}
+		let mirrored: Vec<Vec3> = model.primitives[0].vertices.iter().map(|vertex| vertex.position).collect();
+		assert_eq!(mirrored, [Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 0.0)]);
+		assert!(model.primitives[1].vertices.iter().all(|vertex| vertex.position.z == 2.0));
+
+		// A node that is its own grandchild must be rejected instead of recursing forever
+		let cyclic = r#"{
+			"asset": {"version": "2.0"},
+			"scene": 0,
+			"scenes": [{"nodes": [0]}],
+			"nodes": [{"children": [1]}, {"children": [0]}]
+		}"#;
+		assert!(matches!(GltfModel::parse(cyclic.as_bytes(), None, "cyclic.gltf"), Err(MeshLoadError::InvalidModel(_, _))));
+
+		// The cycle is rejected even if no scene reaches it, `get_global_transforms()` walks every node
+		let unreachable = r#"{
+			"asset": {"version": "2.0"},
+			"nodes": [{}, {"children": [2]}, {"children": [1]}]
+		}"#;
+		assert!(matches!(GltfModel::parse(unreachable.as_bytes(), None, "unreachable.gltf"), Err(MeshLoadError::InvalidModel(_, _))));
+	}
+
+	#[test]
+	#[cfg(feature = "gltf")]
+	fn test_gltf_scene() {
+		let text = r#"{
+			"asset": {"version": "2.0"},
+			"scene": 0,
+			"scenes": [{"nodes": [0]}],
+			"nodes": [
+				{"name": "Parent", "translation": [1, 0, 0], "rotation": [0, 0, 0.70710678, 0.70710678], "children": [1]},
+				{"name": "Child", "mesh": 0, "translation": [0, 2, 0]},
+				{"name": "Camera", "camera": 0}
+			],
+			"cameras": [{"type": "perspective", "perspective": {"yfov": 1.0, "znear": 0.1}}],
+			"meshes": [{"name": "Triangle", "primitives": [{"attributes": {"POSITION": 0}, "indices": 1}]}],
+			"buffers": [{"byteLength": 44, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA="}],
+			"bufferViews": [{"buffer": 0, "byteLength": 36}, {"buffer": 0, "byteOffset": 36, "byteLength": 6}],
+			"accessors": [
+				{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]},
+				{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}
+			]
+		}"#;
+		let model = GltfModel::parse_with_options(text.as_bytes(), None, "test.gltf", &GltfLoadOptions {bake_transforms: false}).unwrap();
+		let positions: Vec<Vec3> = model.primitives[0].vertices.iter().map(|vertex| vertex.position).collect();
+		assert_eq!(positions, [Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)]);
+
+		let (nodes, roots) = model.get_scene_nodes();
+		assert_eq!(roots, [0]);
+		assert_eq!((nodes[1].parent, nodes[0].children.as_slice()), (Some(0), &[1][..]));
+		assert_eq!(nodes[1].subsets, ["Child/Triangle/0"]);
+		assert!(matches!(nodes[2].camera, Some(SceneCamera::Perspective {zfar: None, ..})));
+		let scene = Scene {
+			meshset: Meshset::default(),
+			nodes,
+			roots,
+		};
+
+		// The child is moved by (0, 2, 0) in the parent rotated by 90 degrees around Z, then by (1, 0, 0)
+		let world = scene.flatten_world_transforms()["Child/Triangle/0"];
+		let origin = (world * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+		assert!((origin - Vec3::new(-1.0, 0.0, 0.0)).norm() < 1e-5);
+		let expected = translation(&Vec3::new(1.0, 0.0, 0.0)) * rotation(std::f32::consts::FRAC_PI_2, &Vec3::z()) * translation(&Vec3::new(0.0, 2.0, 0.0));
+		assert!((world - expected).norm() < 1e-5);
+		assert_eq!(scene.get_world_transforms()[scene.find_node("Child").unwrap()], world);
+
+		// A cycle of the parents must not hang the walk to the roots
+		let mut cyclic = scene.clone();
+		cyclic.nodes[0].parent = Some(1);
+		assert_eq!(cyclic.get_world_transforms().len(), cyclic.nodes.len());
+	}
+
+	#[test]
+	#[cfg(feature = "gltf")]
+	fn test_gltf_skin() {
+		let text = r#"{
+			"asset": {"version": "2.0"},
+			"scene": 0,
+			"scenes": [{"nodes": [0, 1]}],
+			"nodes": [
+				{"name": "Body", "mesh": 0, "skin": 0, "translation": [5, 0, 0]},
+				{"name": "Root", "translation": [0, 1, 0], "children": [2]},
+				{"name": "Tip", "translation": [0, 1, 0]}
+			],
+			"skins": [{"joints": [1, 2], "inverseBindMatrices": 3}],
+			"meshes": [{"primitives": [{"attributes": {"POSITION": 0, "JOINTS_0": 1, "WEIGHTS_0": 2}}]}],
+			"buffers": [{"byteLength": 224, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAEAAAAAAAABAAAAAAAAQAAAAEAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAAAAAAAAAAABAQAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAAAAAAIA/AAAAAAAAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAgL8AAAAAAACAPwAAgD8AAAAAAAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAAAAAAIA/AAAAAAAAAAAAAADAAAAAAAAAgD8="}],
+			"bufferViews": [
+				{"buffer": 0, "byteLength": 36},
+				{"buffer": 0, "byteOffset": 36, "byteLength": 12},
+				{"buffer": 0, "byteOffset": 48, "byteLength": 48},
+				{"buffer": 0, "byteOffset": 96, "byteLength": 128}
+			],
+			"accessors": [
+				{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]},
+				{"bufferView": 1, "componentType": 5121, "count": 3, "type": "VEC4"},
+				{"bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC4"},
+				{"bufferView": 3, "componentType": 5126, "count": 2, "type": "MAT4"}
+			]
+		}"#;
+		let model = GltfModel::parse(text.as_bytes(), None, "skin.gltf").unwrap();
+		assert_eq!(model.skins.len(), 1);
+		assert_eq!((model.skins[0].name.as_str(), model.skins[0].joints.as_slice()), ("skin0", [1, 2].as_slice()));
+
+		// The transform of the skinned node is ignored, the weights are normalized
+		let primitive = &model.primitives[0];
+		assert_eq!(primitive.skin, Some(0));
+		let positions: Vec<Vec3> = primitive.vertices.iter().map(|vertex| vertex.position).collect();
+		assert_eq!(positions, [Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)]);
+		assert_eq!(primitive.vertices[0].joints, U16Vec4::new(0, 1, 0, 0));
+		assert_eq!(primitive.vertices[0].weights, Vec4::new(0.5, 0.5, 0.0, 0.0));
+		assert_eq!(primitive.vertices[2].weights, Vec4::new(0.0, 1.0, 0.0, 0.0));
+		let skinned = skinning::SkinnedVertex::from(primitive.vertices[0]);
+		assert_eq!((skinned.joints, skinned.weights), (primitive.vertices[0].joints, primitive.vertices[0].weights));
+
+		// The bone matrices are identity in the rest pose, moving the root moves both of the bones
+		assert!(model.get_bone_matrices(0).iter().all(|bone| (bone - Mat4::identity()).norm() < 1e-5));
+		let mut global_transforms = model.get_global_transforms();
+		assert!((global_transforms[2] - translation(&Vec3::new(0.0, 2.0, 0.0))).norm() < 1e-5);
+		for transform in global_transforms.iter_mut() {
+			*transform = translation(&Vec3::new(3.0, 0.0, 0.0)) * *transform;
+		}
+		for bone in model.skins[0].get_bone_matrices(&global_transforms[..]) {
+			assert!((bone - translation(&Vec3::new(3.0, 0.0, 0.0))).norm() < 1e-5);
+		}
+
+		// The joint index 2 is out of the 2 joints of the skin
+		let bad = text.replace("AAEAAAAAAAABAAAA", "AAEAAAAAAAACAAAA");
+		assert!(matches!(GltfModel::parse(bad.as_bytes(), None, "skin.gltf"), Err(MeshLoadError::InvalidModel(..))));
+	}
+
+	#[test]
+	fn test_lod_selector() {
+		assert_eq!(LodSelector::new(Vec::new(), 0.0).unwrap_err(), LodError::NoLevels);
+		assert_eq!(LodSelector::new(vec![10.0, 10.0, 30.0], 0.0).unwrap_err(), LodError::NotAscending(1));
+		assert_eq!(LodSelector::new(vec![10.0], -1.0).unwrap_err(), LodError::InvalidHysteresis(-1.0));
+
+		let selector = LodSelector::new(vec![10.0, 20.0, 30.0], 2.0).unwrap();
+		assert_eq!(selector.select(5.0), 0);
+		// Stays at the current level until the distance passes the boundary plus the hysteresis
+		assert_eq!(selector.select(11.0), 0);
+		assert_eq!(selector.select(12.5), 1);
+		assert_eq!(selector.select(9.0), 1);
+		assert_eq!(selector.select(7.5), 0);
+		// Jumps directly to the level of a far distance, and clamps to the last level
+		assert_eq!(selector.select(100.0), 2);
+		assert_eq!(selector.select(15.0), 1);
+	}
 }
@@ -0,0 +1,139 @@
+use std::{
+	cell::RefCell,
+	fmt::{self, Display, Formatter},
+};
+
+/// The categories of the OpenGL objects counted by the `MemoryTracker`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryCategory {
+	/// The buffer objects, including the PBOs of the textures
+	Buffers,
+
+	/// The texture objects
+	Textures,
+
+	/// The renderbuffer objects
+	Renderbuffers,
+}
+
+/// The number of the objects and their size in bytes of a category
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+	pub count: usize,
+	pub bytes: usize,
+}
+
+/// The totals of the objects alive since the `MemoryTracker` is enabled
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryTrackerReport {
+	pub buffers: MemoryUsage,
+	pub textures: MemoryUsage,
+	pub renderbuffers: MemoryUsage,
+}
+
+/// The opt-in tracker of the GPU memory allocated by this crate on the current thread, the `Buffer`, `Texture` and `Renderbuffer` constructors and `drop()` update it.
+/// The numbers are estimates from the sizes requested to OpenGL, the padding and the alignment of the driver are ignored.
+/// The objects created before `enable()` are not counted in, and the buffers adopted by `Buffer::from_raw()` are never counted.
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryTracker;
+
+thread_local! {
+	static TRACKER: RefCell<Option<MemoryTrackerReport>> = const {RefCell::new(None)};
+}
+
+impl MemoryUsage {
+	fn add(&mut self, bytes: usize) {
+		self.count += 1;
+		self.bytes += bytes;
+	}
+
+	fn remove(&mut self, bytes: usize) {
+		self.count = self.count.saturating_sub(1);
+		self.bytes = self.bytes.saturating_sub(bytes);
+	}
+
+	fn resize(&mut self, old_bytes: usize, new_bytes: usize) {
+		self.bytes = self.bytes.saturating_sub(old_bytes) + new_bytes;
+	}
+}
+
+impl MemoryTrackerReport {
+	/// Get the usage of a category
+	pub fn get(&self, category: MemoryCategory) -> MemoryUsage {
+		match category {
+			MemoryCategory::Buffers => self.buffers,
+			MemoryCategory::Textures => self.textures,
+			MemoryCategory::Renderbuffers => self.renderbuffers,
+		}
+	}
+
+	/// Get the total bytes of all of the categories
+	pub fn total_bytes(&self) -> usize {
+		self.buffers.bytes + self.textures.bytes + self.renderbuffers.bytes
+	}
+
+	fn get_mut(&mut self, category: MemoryCategory) -> &mut MemoryUsage {
+		match category {
+			MemoryCategory::Buffers => &mut self.buffers,
+			MemoryCategory::Textures => &mut self.textures,
+			MemoryCategory::Renderbuffers => &mut self.renderbuffers,
+		}
+	}
+}
+
+impl MemoryTracker {
+	/// Start tracking on the current thread with all of the totals being zero
+	pub fn enable() {
+		TRACKER.with_borrow_mut(|tracker| *tracker = Some(MemoryTrackerReport::default()));
+	}
+
+	/// Stop tracking on the current thread
+	pub fn disable() {
+		TRACKER.with_borrow_mut(|tracker| *tracker = None);
+	}
+
+	/// Check if the tracker is enabled on the current thread
+	pub fn is_enabled() -> bool {
+		TRACKER.with_borrow(|tracker| tracker.is_some())
+	}
+
+	/// Get the current totals, `None` if the tracker is disabled
+	pub fn report() -> Option<MemoryTrackerReport> {
+		TRACKER.with_borrow(|tracker| *tracker)
+	}
+
+	/// Count in a newly allocated object
+	pub(crate) fn track_new(category: MemoryCategory, bytes: usize) {
+		Self::update(category, |usage| usage.add(bytes));
+	}
+
+	/// Count out a deleted object
+	pub(crate) fn track_delete(category: MemoryCategory, bytes: usize) {
+		Self::update(category, |usage| usage.remove(bytes));
+	}
+
+	/// Update the size of a reallocated object
+	pub(crate) fn track_resize(category: MemoryCategory, old_bytes: usize, new_bytes: usize) {
+		Self::update(category, |usage| usage.resize(old_bytes, new_bytes));
+	}
+
+	fn update(category: MemoryCategory, f: impl FnOnce(&mut MemoryUsage)) {
+		TRACKER.with_borrow_mut(|tracker| {
+			if let Some(report) = tracker {
+				f(report.get_mut(category));
+			}
+		});
+	}
+}
+
+impl Display for MemoryUsage {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{} bytes in {} objects", self.bytes, self.count)
+	}
+}
+
+impl Display for MemoryTrackerReport {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{} bytes total, buffers: {}, textures: {}, renderbuffers: {}", self.total_bytes(), self.buffers, self.textures, self.renderbuffers)
+	}
+}
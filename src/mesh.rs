@@ -1,12 +1,24 @@
 
 use crate::prelude::*;
 use std::{
-	any::type_name,
-	fmt::{self, Debug, Formatter},
+	cell::Cell,
+	collections::HashMap,
+	ffi::c_void,
+	fmt::{self, Debug, Display, Formatter},
 	marker::PhantomData,
+	mem::size_of_val,
 	rc::Rc,
 };
 
+/// The procedural shapes, e.g. the cube and the sphere
+pub mod shapes;
+
+/// The skinned vertex and the sample skinning shaders
+pub mod skinning;
+
+/// The per-instance model matrix and the sample instancing shader
+pub mod instancing;
+
 /// The primitive mode of the mesh, indicating how to draw the vertices to which type of the shapes
 #[derive(Clone, Copy, PartialEq)]
 pub enum PrimitiveMode {
@@ -32,6 +44,40 @@ pub enum ElementType {
 	U32 = GL_UNSIGNED_INT as isize,
 }
 
+/// The error for combining the meshes
+#[derive(Debug)]
+pub enum MeshError {
+	GLCoreError(GLCoreError),
+
+	/// The primitive mode of the mesh (the first one) differs from the other mesh (the second one)
+	PrimitiveMismatch(PrimitiveMode, PrimitiveMode),
+
+	/// Only one of the meshes has the element buffer
+	ElementBufferMismatch,
+
+	/// The rebased index (the first one) couldn't be stored in the element type
+	IndexOverflow(usize),
+
+	/// The size of the vertices of the mesh (the first one) differs from the vertex type (the second one)
+	VertexStrideMismatch(usize, usize),
+
+	/// The mesh of the subset (the first one) has the extra vertex streams or the instance buffer, only the primary vertex streams could be merged
+	UnmergedStreams(String),
+}
+
+/// The range of an appended mesh in the merged mesh, see `Mesh::append()`
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubmeshRange {
+	/// The first element, or the first vertex if the mesh has no element buffer
+	pub first_element: usize,
+
+	/// The number of the elements, or the vertices if the mesh has no element buffer
+	pub element_count: usize,
+
+	/// The first vertex of the appended mesh, the appended elements already have it added
+	pub base_vertex: usize,
+}
+
 /// The mesh, every type of buffer is wrapped in a `BufferVec` can be manipulated just like a `Vec`
 #[derive(Debug, Clone)]
 pub struct Mesh<BV, V, BE, E, BI, I, BC, C>
@@ -41,7 +87,7 @@ where
 	BI: BufferVec<I>,
 	BC: BufferVec<C>,
 	V: BufferVecItem,
-	E: BufferVecItem,
+	E: ElementIndexType,
 	I: BufferVecItem,
 	C: BufferVecItem {
 	pub primitive: PrimitiveMode,
@@ -49,6 +95,15 @@ where
 	pub element_buffer: Option<BE>,
 	pub instance_buffer: Option<BI>,
 	pub command_buffer: Option<BC>,
+
+	/// The number of vertices per patch for `PrimitiveMode::Patches`, `GL_PATCH_VERTICES` is left unchanged if it's `None`
+	pub patch_vertices: Option<u32>,
+
+	/// The outer and the inner tessellation levels for `PrimitiveMode::Patches`, only used by GL when there's no tessellation control shader
+	pub patch_default_levels: Option<([f32; 4], [f32; 2])>,
+
+	/// The cached bounding box of the positions, see `GenericMesh::get_bounds()`
+	bounds: Cell<Option<Aabb>>,
 	_vertex_type: PhantomData<V>,
 	_element_type: PhantomData<E>,
 	_instance_type: PhantomData<I>,
@@ -58,6 +113,9 @@ where
 /// The most typical static mesh type: use `BufferVecStatic` for vertices and elements(indices), use `BufferVecDynamic` for instances and draw commands
 pub type StaticMesh<V, E, I, C> = Mesh<BufferVecStatic<V>, V, BufferVecStatic<E>, E, BufferVecDynamic<I>, I, BufferVecDynamic<C>, C>;
 
+/// The fully dynamic mesh type: use `BufferVecDynamic` for all of the buffers, for editing the vertices and the elements frequently
+pub type DynamicMesh<V, E, I, C> = Mesh<BufferVecDynamic<V>, V, BufferVecDynamic<E>, E, BufferVecDynamic<I>, I, BufferVecDynamic<C>, C>;
+
 impl<BV, V, BE, E, BI, I, BC, C> Mesh<BV, V, BE, E, BI, I, BC, C>
 where
 	BV: BufferVec<V>,
@@ -65,17 +123,30 @@ where
 	BI: BufferVec<I>,
 	BC: BufferVec<C>,
 	V: BufferVecItem,
-	E: BufferVecItem,
+	E: ElementIndexType,
 	I: BufferVecItem,
 	C: BufferVecItem {
-	/// Create a new mesh from the buffers
-	pub fn new(primitive: PrimitiveMode, vertex_buffer: BV, element_buffer: Option<BE>, instance_buffer: Option<BI>, command_buffer: Option<BC>) -> Self {
+	/// Create a new mesh from the buffers, the default binding targets of the buffers are set to `ArrayBuffer`, `ElementArrayBuffer`, `ArrayBuffer` and `DrawIndirectBuffer`
+	pub fn new(primitive: PrimitiveMode, mut vertex_buffer: BV, mut element_buffer: Option<BE>, mut instance_buffer: Option<BI>, mut command_buffer: Option<BC>) -> Self {
+		vertex_buffer.set_target(BufferTarget::ArrayBuffer);
+		if let Some(element_buffer) = &mut element_buffer {
+			element_buffer.set_target(BufferTarget::ElementArrayBuffer);
+		}
+		if let Some(instance_buffer) = &mut instance_buffer {
+			instance_buffer.set_target(BufferTarget::ArrayBuffer);
+		}
+		if let Some(command_buffer) = &mut command_buffer {
+			command_buffer.set_target(BufferTarget::DrawIndirectBuffer);
+		}
 		Self {
 			primitive,
 			vertex_buffer,
 			element_buffer,
 			instance_buffer,
 			command_buffer,
+			patch_vertices: None,
+			patch_default_levels: None,
+			bounds: Cell::new(None),
 			_vertex_type: PhantomData,
 			_element_type: PhantomData,
 			_instance_type: PhantomData,
@@ -97,6 +168,323 @@ where
 		}
 		Ok(())
 	}
+
+	/// Replace the instances with `instances`, then flush them to the GPU.
+	/// The instance buffer is created with `BufferUsage::DynamicDraw` if the mesh has none, or is recreated with its usage if it's too small.
+	/// A new instance buffer has a new OpenGL name, the `Pipeline` of the mesh describes it again at its next `bind()`.
+	///
+	/// ```ignore
+	/// let transforms: Vec<Mat4> = (0..1000).map(|i| translate(&Mat4::identity(), &Vec3::new(i as f32 * 2.0, 0.0, 0.0))).collect();
+	/// let mut cubes = StaticMesh::<ShapeVertex, u16, MyInstance, UnusedType>::from_slices(glcore.clone(), PrimitiveMode::Triangles, &vertices, Some(&elements))?;
+	/// cubes.set_instances(&transforms)?; // `MyInstance` implements `From<Mat4>`
+	/// let pipeline = Pipeline::<ShapeVertex, MyInstance>::new(glcore, Rc::new(MeshWithMaterial::new(cubes, material)), shader)?;
+	/// pipeline.bind()?.draw(None)?; // Draws 1000 cubes
+	/// ```
+	pub fn set_instances<I2: Into<I> + Copy>(&mut self, instances: &[I2]) -> Result<(), GLCoreError> {
+		let instances: Vec<I> = instances.iter().map(|instance| (*instance).into()).collect();
+		let usage = match &self.instance_buffer {
+			Some(instance_buffer) if instance_buffer.capacity() >= instances.len() => None,
+			Some(instance_buffer) => Some(instance_buffer.get_buffer().get_usage()),
+			None => Some(BufferUsage::DynamicDraw),
+		};
+		if let Some(usage) = usage {
+			let glcore = self.vertex_buffer.get_buffer().glcore.clone();
+			let buffer = Buffer::new(glcore, BufferTarget::ArrayBuffer, size_of_val(&instances[..]), usage, std::ptr::null())?;
+			self.instance_buffer = Some(BI::from(buffer));
+		}
+		let instance_buffer = self.instance_buffer.as_mut().unwrap();
+		instance_buffer.resize(instances.len(), I::default())?;
+		instance_buffer.set_slice_of_data(0, &instances)?;
+		instance_buffer.flush()
+	}
+
+	/// Get the number of the instances, 0 if there's no instance buffer
+	pub fn instance_count(&self) -> usize {
+		self.instance_buffer.as_ref().map_or(0, |instance_buffer| instance_buffer.len())
+	}
+
+	/// Append the vertices and the elements of `other` to draw both of the meshes in one draw call, the indices of `other` are rebased to its vertices in this mesh.
+	/// Returns the range of `other` in this mesh, e.g. to draw it alone by `PipelineBind::draw_range()`. The instances and the draw commands are kept as is.
+	/// The vertex buffer and the element buffer are reallocated with their usages, the `Pipeline` of the mesh describes them again at its next `bind()`.
+	pub fn append<BV2, BE2, BI2, I2, BC2, C2>(&mut self, other: &Mesh<BV2, V, BE2, E, BI2, I2, BC2, C2>) -> Result<SubmeshRange, MeshError>
+	where
+		BV2: BufferVec<V>,
+		BE2: BufferVec<E>,
+		BI2: BufferVec<I2>,
+		BC2: BufferVec<C2>,
+		I2: BufferVecItem,
+		C2: BufferVecItem {
+		if self.primitive != other.primitive {
+			return Err(MeshError::PrimitiveMismatch(self.primitive, other.primitive));
+		}
+		if self.element_buffer.is_some() != other.element_buffer.is_some() {
+			return Err(MeshError::ElementBufferMismatch);
+		}
+		self.flush()?;
+		let base_vertex = self.vertex_buffer.len();
+		let other_vertices = other.vertex_buffer.get_slice_of_data(0, other.vertex_buffer.len())?;
+		let mut vertices = self.vertex_buffer.get_slice_of_data(0, base_vertex)?;
+		vertices.extend(other_vertices);
+		let range = if let (Some(element_buffer), Some(other_elements)) = (&mut self.element_buffer, &other.element_buffer) {
+			let mut elements = element_buffer.get_slice_of_data(0, element_buffer.len())?;
+			let first_element = elements.len();
+			for element in other_elements.get_slice_of_data(0, other_elements.len())? {
+				let index = element.to_u32() as usize + base_vertex;
+				let Some(element) = u32::try_from(index).ok().and_then(E::from_u32) else {
+					return Err(MeshError::IndexOverflow(index));
+				};
+				elements.push(element);
+			}
+			*element_buffer = reupload(element_buffer, &elements)?;
+			SubmeshRange {
+				first_element,
+				element_count: elements.len() - first_element,
+				base_vertex,
+			}
+		} else {
+			SubmeshRange {
+				first_element: base_vertex,
+				element_count: vertices.len() - base_vertex,
+				base_vertex,
+			}
+		};
+		self.vertex_buffer = reupload(&self.vertex_buffer, &vertices)?;
+		Ok(range)
+	}
+}
+
+/// Create a new `BufferVec` with the target and the usage of `like` and upload `data` to it
+fn reupload<B: BufferVec<T>, T: BufferVecItem>(like: &B, data: &[T]) -> Result<B, GLCoreError> {
+	let like = like.get_buffer();
+	let buffer = Buffer::new(like.glcore.clone(), like.get_target(), size_of_val(data), like.get_usage(), data.as_ptr() as *const c_void)?;
+	let mut ret = B::from(buffer);
+	ret.resize(data.len(), T::default())?;
+	Ok(ret)
+}
+
+impl<V, E, I, C> StaticMesh<V, E, I, C>
+where
+	V: BufferVecItem,
+	E: ElementIndexType,
+	I: BufferVecItem,
+	C: BufferVecItem {
+	/// Create a static mesh by uploading the vertices and the elements, without the instance buffer and the command buffer
+	pub fn from_slices(glcore: Rc<GLCore>, primitive: PrimitiveMode, vertices: &[V], elements: Option<&[E]>) -> Result<Self, GLCoreError> {
+		let vertex_buffer = Buffer::new(glcore.clone(), BufferTarget::ArrayBuffer, size_of_val(vertices), BufferUsage::StaticDraw, vertices.as_ptr() as *const c_void)?;
+		let mut vertex_buffer = BufferVecStatic::<V>::new(vertex_buffer);
+		vertex_buffer.resize(vertices.len(), V::default())?;
+		let element_buffer = if let Some(elements) = elements {
+			let element_buffer = Buffer::new(glcore, BufferTarget::ElementArrayBuffer, size_of_val(elements), BufferUsage::StaticDraw, elements.as_ptr() as *const c_void)?;
+			let mut element_buffer = BufferVecStatic::<E>::new(element_buffer);
+			element_buffer.resize(elements.len(), E::default())?;
+			Some(element_buffer)
+		} else {
+			None
+		};
+		Ok(Self::new(primitive, vertex_buffer, element_buffer, None, None))
+	}
+}
+
+impl<V, E, I, C> StaticMesh<V, E, I, C>
+where
+	V: BufferVecItem,
+	E: ElementIndexType,
+	I: BufferVecItem,
+	C: BufferVecItem {
+	/// Convert to the `DynamicMesh` for editing, the buffers are kept and their data are cached in the system memory.
+	/// The `Pipeline` created from this mesh should be created again from the converted mesh.
+	pub fn into_dynamic(mut self) -> Result<DynamicMesh<V, E, I, C>, GLCoreError> {
+		self.flush()?;
+		let element_buffer = self.element_buffer.map(BufferVecDynamic::new).transpose()?;
+		let mut ret = DynamicMesh::new(self.primitive, BufferVecDynamic::new(self.vertex_buffer)?, element_buffer, self.instance_buffer, self.command_buffer);
+		ret.patch_vertices = self.patch_vertices;
+		ret.patch_default_levels = self.patch_default_levels;
+		ret.bounds = self.bounds;
+		Ok(ret)
+	}
+}
+
+impl<V, E, I, C> DynamicMesh<V, E, I, C>
+where
+	V: BufferVecItem,
+	E: ElementIndexType,
+	I: BufferVecItem,
+	C: BufferVecItem {
+	/// Flush the caches then convert to the `StaticMesh`, the buffers are kept with their usages.
+	/// The `Pipeline` created from this mesh should be created again from the converted mesh.
+	pub fn into_static(mut self) -> Result<StaticMesh<V, E, I, C>, GLCoreError> {
+		self.flush()?;
+		let element_buffer = self.element_buffer.map(BufferVecStatic::from);
+		let mut ret = StaticMesh::new(self.primitive, BufferVecStatic::from(self.vertex_buffer), element_buffer, self.instance_buffer, self.command_buffer);
+		ret.patch_vertices = self.patch_vertices;
+		ret.patch_default_levels = self.patch_default_levels;
+		ret.bounds = self.bounds;
+		Ok(ret)
+	}
+}
+
+impl<BV, V, BE, E, BI, I, BC, C> Mesh<BV, V, BE, E, BI, I, BC, C>
+where
+	BV: BufferVec<V>,
+	BE: BufferVec<E> + From<Buffer>,
+	BI: BufferVec<I>,
+	BC: BufferVec<C>,
+	V: WeldableVertex,
+	E: ElementIndexType,
+	I: BufferVecItem,
+	C: BufferVecItem {
+	/// Download the vertices and the elements, weld the vertices by `weld_vertices()`, then re-upload both of them and shrink the vertex buffer.
+	/// A mesh without the element buffer gets one. Returns `None` and leaves the mesh unchanged if the element type couldn't index the welded vertices.
+	pub fn weld(&mut self, epsilon_positions: Option<f32>) -> Result<Option<WeldStats>, GLCoreError> {
+		self.flush()?;
+		let vertices = self.vertex_buffer.get_slice_of_data(0, self.vertex_buffer.len())?;
+		let (welded, remap, stats) = weld_vertices(&vertices, epsilon_positions);
+		let indices: Vec<u32> = if let Some(element_buffer) = &self.element_buffer {
+			element_buffer.get_slice_of_data(0, element_buffer.len())?.into_iter().map(|element| {
+				let index = element.to_u32();
+				remap.get(index as usize).copied().unwrap_or(index)
+			}).collect()
+		} else {
+			remap
+		};
+		let Some(elements) = indices.into_iter().map(E::from_u32).collect::<Option<Vec<E>>>() else {
+			return Ok(None);
+		};
+
+		self.vertex_buffer.resize(welded.len(), V::default())?;
+		self.vertex_buffer.set_slice_of_data(0, &welded)?;
+		self.vertex_buffer.flush()?;
+		self.vertex_buffer.shrink_to_fit()?;
+		if let Some(element_buffer) = &mut self.element_buffer {
+			element_buffer.set_slice_of_data(0, &elements)?;
+			element_buffer.flush()?;
+		} else {
+			let glcore = self.vertex_buffer.get_buffer().glcore.clone();
+			let buffer = Buffer::new(glcore, BufferTarget::ElementArrayBuffer, size_of_val(&elements[..]), BufferUsage::StaticDraw, elements.as_ptr() as *const c_void)?;
+			let mut element_buffer = BE::from(buffer);
+			element_buffer.resize(elements.len(), E::default())?;
+			self.element_buffer = Some(element_buffer);
+		}
+		Ok(Some(stats))
+	}
+}
+
+/// The vertex types that could be welded by `weld_vertices()`, the position is used for the spatial hash and the epsilon comparison
+pub trait WeldableVertex: BufferVecItem + PartialEq {
+	/// Get the position of the vertex
+	fn get_position(&self) -> Vec3;
+
+	/// Set the position of the vertex
+	fn set_position(&mut self, position: Vec3);
+}
+
+/// The numbers of the vertices before and after welding
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeldStats {
+	pub vertices_before: usize,
+	pub vertices_after: usize,
+}
+
+impl WeldStats {
+	/// Get the ratio of the welded vertices to the original vertices, 1.0 if nothing was welded
+	pub fn ratio(&self) -> f32 {
+		if self.vertices_before == 0 {
+			1.0
+		} else {
+			self.vertices_after as f32 / self.vertices_before as f32
+		}
+	}
+}
+
+impl Display for WeldStats {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{} -> {} vertices", self.vertices_before, self.vertices_after)
+	}
+}
+
+/// The estimated GPU memory of the buffers of a mesh, the sizes of the allocations are counted regardless of the number of the items in use
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshMemoryReport {
+	/// The vertex buffer and the additional vertex streams
+	pub vertex_bytes: usize,
+	pub element_bytes: usize,
+	pub instance_bytes: usize,
+	pub command_bytes: usize,
+}
+
+impl MeshMemoryReport {
+	/// Get the total bytes
+	pub fn total(&self) -> usize {
+		self.vertex_bytes + self.element_bytes + self.instance_bytes + self.command_bytes
+	}
+}
+
+impl Display for MeshMemoryReport {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{} bytes (vertices {} bytes, elements {} bytes, instances {} bytes, commands {} bytes)", self.total(), self.vertex_bytes, self.element_bytes, self.instance_bytes, self.command_bytes)
+	}
+}
+
+/// Merge the identical vertices, returns the unique vertices, the index of the unique vertex for each of the input vertices, and the stats.
+/// With `epsilon_positions`, the vertices whose positions are within the distance and whose other attributes are exactly equal are merged too, the first one's position is kept.
+/// The vertices are found by a spatial hash of the positions, so it's about linear time for the big meshes.
+pub fn weld_vertices<V: WeldableVertex>(vertices: &[V], epsilon_positions: Option<f32>) -> (Vec<V>, Vec<u32>, WeldStats) {
+	let cell_size = epsilon_positions.filter(|epsilon| *epsilon > 0.0);
+	let cell_of = |position: Vec3| -> [i64; 3] {
+		match cell_size {
+			Some(cell_size) => [position.x, position.y, position.z].map(|v| (v / cell_size).floor() as i64),
+			// Adding 0.0 turns -0.0 into 0.0, so they hash the same as they compare the same
+			None => [position.x, position.y, position.z].map(|v| (v + 0.0).to_bits() as i64),
+		}
+	};
+	let mut welded: Vec<V> = Vec::with_capacity(vertices.len());
+	let mut indices: Vec<u32> = Vec::with_capacity(vertices.len());
+	let mut cells: HashMap<[i64; 3], Vec<u32>> = HashMap::with_capacity(vertices.len());
+	for vertex in vertices.iter() {
+		let position = vertex.get_position();
+		let cell = cell_of(position);
+		let found = match cell_size {
+			Some(epsilon) => {
+				let mut found = None;
+				'search: for dx in -1..=1 {
+					for dy in -1..=1 {
+						for dz in -1..=1 {
+							let Some(candidates) = cells.get(&[cell[0] + dx, cell[1] + dy, cell[2] + dz]) else {
+								continue;
+							};
+							for candidate in candidates.iter() {
+								let mut moved = welded[*candidate as usize];
+								if (moved.get_position() - position).norm() <= epsilon {
+									moved.set_position(position);
+									if moved == *vertex {
+										found = Some(*candidate);
+										break 'search;
+									}
+								}
+							}
+						}
+					}
+				}
+				found
+			}
+			None => cells.get(&cell).and_then(|candidates| candidates.iter().copied().find(|candidate| welded[*candidate as usize] == *vertex)),
+		};
+		let index = match found {
+			Some(index) => index,
+			None => {
+				let index = welded.len() as u32;
+				welded.push(*vertex);
+				cells.entry(cell).or_default().push(index);
+				index
+			}
+		};
+		indices.push(index);
+	}
+	let stats = WeldStats {
+		vertices_before: vertices.len(),
+		vertices_after: welded.len(),
+	};
+	(welded, indices, stats)
 }
 
 impl ElementType {
@@ -110,6 +498,45 @@ impl ElementType {
 	}
 }
 
+mod sealed {
+	pub trait Sealed {}
+	impl Sealed for u8 {}
+	impl Sealed for u16 {}
+	impl Sealed for u32 {}
+}
+
+/// The index type of the element buffer, only `u8`, `u16` and `u32` are implemented, so a mesh with a wrong index type is a compile error
+pub trait ElementIndexType: BufferVecItem + sealed::Sealed {
+	/// The `ElementType` of the index type
+	const ELEMENT_TYPE: ElementType;
+
+	/// Convert from a `u32` index, `None` if the index is too big for the type
+	fn from_u32(index: u32) -> Option<Self>;
+
+	/// Convert to a `u32` index
+	fn to_u32(self) -> u32;
+}
+
+macro_rules! impl_element_index_type {
+	($type: ty, $element_type: expr) => {
+		impl ElementIndexType for $type {
+			const ELEMENT_TYPE: ElementType = $element_type;
+
+			fn from_u32(index: u32) -> Option<Self> {
+				Self::try_from(index).ok()
+			}
+
+			fn to_u32(self) -> u32 {
+				self as u32
+			}
+		}
+	};
+}
+
+impl_element_index_type!(u8, ElementType::U8);
+impl_element_index_type!(u16, ElementType::U16);
+impl_element_index_type!(u32, ElementType::U32);
+
 /// The `GenericMesh` trait helps the `Mesh` struct to be able to turn into an object
 pub trait GenericMesh: Debug {
 	/// Get the primitive mode of the mesh
@@ -130,22 +557,48 @@ pub trait GenericMesh: Debug {
 	/// Get the draw command buffer of the mesh
 	fn get_command_buffer(&self) -> Option<&Buffer>;
 
+	/// Get the additional vertex streams besides the vertex buffer, see `MultiStreamMesh`
+	fn get_vertex_streams(&self) -> Vec<&dyn GenericVertexStream> {Vec::new()}
+
+	/// Get the number of vertices per patch for `PrimitiveMode::Patches`
+	fn get_patch_vertices(&self) -> Option<u32> {None}
+
+	/// Get the default outer and inner tessellation levels for `PrimitiveMode::Patches`
+	fn get_patch_default_levels(&self) -> Option<([f32; 4], [f32; 2])> {None}
+
+	/// Get the cached bounding box of the positions in the model space, it's computed from the vertex buffer by `GenericPipeline::get_bounds()` at the first time of the frustum culling.
+	/// `None` if it isn't computed yet or the mesh can't cache it
+	fn get_bounds(&self) -> Option<Aabb> {None}
+
+	/// Set the cached bounding box, set it to `None` after changing the positions of the vertices to compute it again, or set it for the animated meshes to cover all of the poses
+	fn set_bounds(&self, _bounds: Option<Aabb>) {}
+
+	/// Get the estimated GPU memory of the buffers, see `MeshMemoryReport`
+	fn gpu_bytes(&self) -> MeshMemoryReport {
+		MeshMemoryReport {
+			vertex_bytes: self.get_vertex_buffer().size() + self.get_vertex_streams().iter().map(|stream| stream.get_buffer().size()).sum::<usize>(),
+			element_bytes: self.get_element_buffer().map_or(0, |buffer| buffer.size()),
+			instance_bytes: self.get_instance_buffer().map_or(0, |buffer| buffer.size()),
+			command_bytes: self.get_command_buffer().map_or(0, |buffer| buffer.size()),
+		}
+	}
+
 	/// Get the size of each vertex
 	fn get_vertex_stride(&self) -> usize;
 
 	/// Get the size of each instance
 	fn get_instance_stride(&self) -> usize;
 
-	/// Get the number of vertices
+	/// Get the number of vertices, the drawing counts are from these methods, i.e. the `len()` of the buffers instead of their capacities
 	fn get_vertex_count(&self) -> usize;
 
 	/// Get the number of the elements
 	fn get_element_count(&self) -> usize;
 
-	/// Get the number of the instances
+	/// Get the number of the instances, the instances in the reserved capacity are not drawn
 	fn get_instance_count(&self) -> usize;
 
-	/// Get the number of the draw commands
+	/// Get the number of the draw commands, the commands in the reserved capacity are not submitted
 	fn get_command_count(&self) -> usize;
 
 	/// Flush the cache if the mesh has a caching system
@@ -191,7 +644,7 @@ where
 	BI: BufferVec<I>,
 	BC: BufferVec<C>,
 	V: BufferVecItem,
-	E: BufferVecItem,
+	E: ElementIndexType,
 	I: BufferVecItem,
 	C: BufferVecItem {
 	fn get_primitive(&self) -> PrimitiveMode {
@@ -211,12 +664,15 @@ where
 	}
 
 	fn get_element_type(&self) -> ElementType {
-		match size_of::<E>() {
-			1 => ElementType::U8,
-			2 => ElementType::U16,
-			4 => ElementType::U32,
-			_ => panic!("Unsupported element type: {}", type_name::<E>()),
-		}
+		E::ELEMENT_TYPE
+	}
+
+	fn get_bounds(&self) -> Option<Aabb> {
+		self.bounds.get()
+	}
+
+	fn set_bounds(&self, bounds: Option<Aabb>) {
+		self.bounds.set(bounds);
 	}
 
 	fn get_instance_buffer(&self) -> Option<&Buffer> {
@@ -271,11 +727,25 @@ where
 		}
 	}
 
+	fn get_patch_vertices(&self) -> Option<u32> {
+		self.patch_vertices
+	}
+
+	fn get_patch_default_levels(&self) -> Option<([f32; 4], [f32; 2])> {
+		self.patch_default_levels
+	}
+
 	fn flush(&mut self) -> Result<(), GLCoreError> {
 		Mesh::flush(self)
 	}
 }
 
+impl From<GLCoreError> for MeshError {
+	fn from(val: GLCoreError) -> Self {
+		Self::GLCoreError(val)
+	}
+}
+
 impl Debug for PrimitiveMode {
 	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
 		match self {
@@ -324,6 +794,32 @@ impl<M: GenericMesh, Mat: Material> MeshWithMaterial<M, Mat> {
 	}
 }
 
+impl<V, E, I, C, Mat> MeshWithMaterial<StaticMesh<V, E, I, C>, Mat>
+where
+	V: BufferVecItem,
+	E: ElementIndexType,
+	I: BufferVecItem,
+	C: BufferVecItem,
+	Mat: Material {
+	/// Convert the mesh by `StaticMesh::into_dynamic()`, the material is kept
+	pub fn into_dynamic(self) -> Result<MeshWithMaterial<DynamicMesh<V, E, I, C>, Mat>, GLCoreError> {
+		Ok(MeshWithMaterial::new(self.mesh.into_dynamic()?, self.material))
+	}
+}
+
+impl<V, E, I, C, Mat> MeshWithMaterial<DynamicMesh<V, E, I, C>, Mat>
+where
+	V: BufferVecItem,
+	E: ElementIndexType,
+	I: BufferVecItem,
+	C: BufferVecItem,
+	Mat: Material {
+	/// Convert the mesh by `DynamicMesh::into_static()`, the material is kept
+	pub fn into_static(self) -> Result<MeshWithMaterial<StaticMesh<V, E, I, C>, Mat>, GLCoreError> {
+		Ok(MeshWithMaterial::new(self.mesh.into_static()?, self.material))
+	}
+}
+
 impl<M: GenericMesh, Mat: Material> GenericMesh for MeshWithMaterial<M, Mat> {
 	fn get_primitive(&self) -> PrimitiveMode {
 		self.mesh.get_primitive()
@@ -349,6 +845,30 @@ impl<M: GenericMesh, Mat: Material> GenericMesh for MeshWithMaterial<M, Mat> {
 		self.mesh.get_command_buffer()
 	}
 
+	fn get_vertex_streams(&self) -> Vec<&dyn GenericVertexStream> {
+		self.mesh.get_vertex_streams()
+	}
+
+	fn get_patch_vertices(&self) -> Option<u32> {
+		self.mesh.get_patch_vertices()
+	}
+
+	fn get_patch_default_levels(&self) -> Option<([f32; 4], [f32; 2])> {
+		self.mesh.get_patch_default_levels()
+	}
+
+	fn get_bounds(&self) -> Option<Aabb> {
+		self.mesh.get_bounds()
+	}
+
+	fn set_bounds(&self, bounds: Option<Aabb>) {
+		self.mesh.set_bounds(bounds);
+	}
+
+	fn gpu_bytes(&self) -> MeshMemoryReport {
+		self.mesh.gpu_bytes()
+	}
+
 	fn get_vertex_stride(&self) -> usize {
 		self.mesh.get_vertex_stride()
 	}
@@ -372,6 +892,10 @@ impl<M: GenericMesh, Mat: Material> GenericMesh for MeshWithMaterial<M, Mat> {
 	fn get_command_count(&self) -> usize {
 		self.mesh.get_command_count()
 	}
+
+	fn flush(&mut self) -> Result<(), GLCoreError> {
+		self.mesh.flush()
+	}
 }
 
 /// The `GenericMeshWithMaterial` trait helps the `MeshWithMaterial` struct to be able to turn into an object
@@ -386,7 +910,7 @@ where
 	BI: BufferVec<I>,
 	BC: BufferVec<C>,
 	V: BufferVecItem,
-	E: BufferVecItem,
+	E: ElementIndexType,
 	I: BufferVecItem,
 	C: BufferVecItem {
 	fn get_material(&self) -> Option<&dyn Material> {
@@ -399,3 +923,190 @@ impl<M: GenericMesh, Mat: Material> GenericMeshWithMaterial for MeshWithMaterial
 		Some(&*self.material)
 	}
 }
+
+/// An additional vertex stream of a mesh with its own vertex struct, e.g. the positions updated per frame while the texture coordinates stay static
+#[derive(Debug, Clone)]
+pub struct VertexStream<B, V>
+where
+	B: BufferVec<V>,
+	V: VertexType {
+	pub buffer: B,
+
+	/// The attrib divisor, 0 for the per-vertex data, N for advancing once per N instances
+	pub divisor: u32,
+	_vertex_type: PhantomData<V>,
+}
+
+impl<B, V> VertexStream<B, V>
+where
+	B: BufferVec<V>,
+	V: VertexType {
+	/// Create a vertex stream from the buffer
+	pub fn new(buffer: B, divisor: u32) -> Self {
+		Self {
+			buffer,
+			divisor,
+			_vertex_type: PhantomData,
+		}
+	}
+}
+
+/// The `GenericVertexStream` trait helps the `VertexStream` struct to be able to turn into an object
+pub trait GenericVertexStream: Debug {
+	/// Get the buffer of the stream
+	fn get_buffer(&self) -> &Buffer;
+
+	/// Get the layout of the vertex struct of the stream
+	fn get_layout(&self) -> VertexLayout;
+
+	/// Get the attrib divisor
+	fn get_divisor(&self) -> u32;
+
+	/// Get the number of vertices
+	fn get_vertex_count(&self) -> usize;
+
+	/// Flush the cache if the buffer has a caching system
+	fn flush(&mut self) -> Result<(), GLCoreError>;
+}
+
+impl<B, V> GenericVertexStream for VertexStream<B, V>
+where
+	B: BufferVec<V>,
+	V: VertexType {
+	fn get_buffer(&self) -> &Buffer {
+		self.buffer.get_buffer()
+	}
+
+	fn get_layout(&self) -> VertexLayout {
+		VertexLayout::of::<V>()
+	}
+
+	fn get_divisor(&self) -> u32 {
+		self.divisor
+	}
+
+	fn get_vertex_count(&self) -> usize {
+		self.buffer.len()
+	}
+
+	fn flush(&mut self) -> Result<(), GLCoreError> {
+		self.buffer.flush()
+	}
+}
+
+/// The mesh with additional vertex streams. The wrapped mesh provides the first stream, the elements, the instances and the draw commands, so the counts for drawing come from it.
+#[derive(Debug)]
+pub struct MultiStreamMesh<M: GenericMesh> {
+	pub mesh: M,
+	pub streams: Vec<Box<dyn GenericVertexStream>>,
+}
+
+impl<M: GenericMesh> MultiStreamMesh<M> {
+	/// Create a mesh without the additional streams
+	pub fn new(mesh: M) -> Self {
+		Self {
+			mesh,
+			streams: Vec::new(),
+		}
+	}
+
+	/// Add a vertex stream, its vertex struct must have different member names from the other streams
+	pub fn add_stream<B, V>(&mut self, stream: VertexStream<B, V>)
+	where
+		B: BufferVec<V> + 'static,
+		V: VertexType + 'static {
+		self.streams.push(Box::new(stream));
+	}
+}
+
+impl<M: GenericMesh> GenericMesh for MultiStreamMesh<M> {
+	fn get_primitive(&self) -> PrimitiveMode {
+		self.mesh.get_primitive()
+	}
+
+	fn get_vertex_buffer(&self) -> &Buffer {
+		self.mesh.get_vertex_buffer()
+	}
+
+	fn get_element_buffer(&self) -> Option<&Buffer> {
+		self.mesh.get_element_buffer()
+	}
+
+	fn get_element_type(&self) -> ElementType {
+		self.mesh.get_element_type()
+	}
+
+	fn get_instance_buffer(&self) -> Option<&Buffer> {
+		self.mesh.get_instance_buffer()
+	}
+
+	fn get_command_buffer(&self) -> Option<&Buffer> {
+		self.mesh.get_command_buffer()
+	}
+
+	fn get_vertex_streams(&self) -> Vec<&dyn GenericVertexStream> {
+		let mut ret = self.mesh.get_vertex_streams();
+		ret.extend(self.streams.iter().map(|stream| &**stream));
+		ret
+	}
+
+	fn get_patch_vertices(&self) -> Option<u32> {
+		self.mesh.get_patch_vertices()
+	}
+
+	fn get_patch_default_levels(&self) -> Option<([f32; 4], [f32; 2])> {
+		self.mesh.get_patch_default_levels()
+	}
+
+	fn get_bounds(&self) -> Option<Aabb> {
+		self.mesh.get_bounds()
+	}
+
+	fn set_bounds(&self, bounds: Option<Aabb>) {
+		self.mesh.set_bounds(bounds);
+	}
+
+	fn gpu_bytes(&self) -> MeshMemoryReport {
+		let mut ret = self.mesh.gpu_bytes();
+		ret.vertex_bytes += self.streams.iter().map(|stream| stream.get_buffer().size()).sum::<usize>();
+		ret
+	}
+
+	fn get_vertex_stride(&self) -> usize {
+		self.mesh.get_vertex_stride()
+	}
+
+	fn get_instance_stride(&self) -> usize {
+		self.mesh.get_instance_stride()
+	}
+
+	fn get_vertex_count(&self) -> usize {
+		self.mesh.get_vertex_count()
+	}
+
+	fn get_element_count(&self) -> usize {
+		self.mesh.get_element_count()
+	}
+
+	fn get_instance_count(&self) -> usize {
+		self.mesh.get_instance_count()
+	}
+
+	fn get_command_count(&self) -> usize {
+		self.mesh.get_command_count()
+	}
+
+	fn flush(&mut self) -> Result<(), GLCoreError> {
+		self.mesh.flush()?;
+		for stream in self.streams.iter_mut() {
+			stream.flush()?;
+		}
+		Ok(())
+	}
+}
+
+impl<M: GenericMeshWithMaterial> GenericMeshWithMaterial for MultiStreamMesh<M> {
+	fn get_material(&self) -> Option<&dyn Material> {
+		self.mesh.get_material()
+	}
+}
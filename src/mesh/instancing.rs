@@ -0,0 +1,41 @@
+use crate::prelude::*;
+
+derive_vertex_type! {
+	/// The instance of the model matrix, the name of the shader attrib is `model`.
+	/// Declare it as `mat4` in the shader, its 4 columns are 4 attribs at the locations from the location of `model`, each with the divisor 1.
+	#[repr(C)]
+	#[derive(PartialEq)]
+	pub struct ModelInstance {
+		pub model: Mat4,
+	}
+}
+
+/// The sample vertex shader for `ModelInstance`, the vertex attrib is `vec3 position` and the uniform is `mat4 view_projection`
+pub const INSTANCING_VERTEX_SHADER: &str = "
+#version 330\n
+
+in vec3 position;
+in mat4 model;
+
+uniform mat4 view_projection;
+
+void main()
+{
+	gl_Position = view_projection * model * vec4(position, 1.0);
+}
+";
+
+impl ModelInstance {
+	/// Create an instance of the model matrix
+	pub fn new(model: Mat4) -> Self {
+		Self {
+			model,
+		}
+	}
+}
+
+impl From<Mat4> for ModelInstance {
+	fn from(model: Mat4) -> Self {
+		Self::new(model)
+	}
+}
@@ -0,0 +1,256 @@
+use crate::prelude::*;
+use std::{
+	f32::consts::{PI, TAU},
+	rc::Rc,
+};
+
+derive_vertex_type! {
+	/// The vertex of the procedural shapes, the names of the shader attribs are `position`, `normal` and `texcoord`
+	#[derive(PartialEq)]
+	pub struct ShapeVertex {
+		pub position: Vec3,
+		pub normal: Vec3,
+		pub texcoord: Vec2,
+	}
+}
+
+/// The mesh of the procedural shapes, the elements are `u32`
+pub type ShapeMesh<V> = StaticMesh<V, u32, UnusedType, UnusedType>;
+
+/// The geometry of a procedural shape in the system memory, the triangles are counter-clockwise seen from the outside
+#[derive(Debug, Clone)]
+pub struct ShapeGeometry {
+	pub primitive: PrimitiveMode,
+	pub vertices: Vec<ShapeVertex>,
+	pub indices: Vec<u32>,
+}
+
+impl ShapeVertex {
+	/// Create a vertex
+	pub fn new(position: Vec3, normal: Vec3, texcoord: Vec2) -> Self {
+		Self {
+			position,
+			normal,
+			texcoord,
+		}
+	}
+}
+
+impl WeldableVertex for ShapeVertex {
+	fn get_position(&self) -> Vec3 {
+		self.position
+	}
+
+	fn set_position(&mut self, position: Vec3) {
+		self.position = position;
+	}
+}
+
+impl ShapeGeometry {
+	/// Create an empty geometry of triangles
+	fn new_triangles(num_vertices: usize, num_indices: usize) -> Self {
+		Self {
+			primitive: PrimitiveMode::Triangles,
+			vertices: Vec::with_capacity(num_vertices),
+			indices: Vec::with_capacity(num_indices),
+		}
+	}
+
+	/// Append a face of 4 corners from the center, the corners are `center ± u ± v` and the face faces to `u × v`
+	fn push_face(&mut self, center: Vec3, u: Vec3, v: Vec3) {
+		let normal = u.cross(&v).normalize();
+		let base = self.vertices.len() as u32;
+		self.vertices.push(ShapeVertex::new(center - u - v, normal, Vec2::new(0.0, 0.0)));
+		self.vertices.push(ShapeVertex::new(center + u - v, normal, Vec2::new(1.0, 0.0)));
+		self.vertices.push(ShapeVertex::new(center + u + v, normal, Vec2::new(1.0, 1.0)));
+		self.vertices.push(ShapeVertex::new(center - u + v, normal, Vec2::new(0.0, 1.0)));
+		self.indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+	}
+
+	/// Append the two triangles of the grid cell, `top_left` is the index of the top-left corner, the next row starts `row_len` vertices later
+	fn push_cell(&mut self, top_left: u32, row_len: u32) {
+		let bottom_left = top_left + row_len;
+		self.indices.extend_from_slice(&[top_left, bottom_left, bottom_left + 1, top_left, bottom_left + 1, top_left + 1]);
+	}
+
+	/// Create a quad of the size on the XY plane facing +Z, e.g. for the full screen passes with the size of 2
+	pub fn quad(width: f32, height: f32) -> Self {
+		let mut ret = Self::new_triangles(4, 6);
+		ret.push_face(Vec3::zeros(), Vec3::new(width * 0.5, 0.0, 0.0), Vec3::new(0.0, height * 0.5, 0.0));
+		ret
+	}
+
+	/// Create a cube of the edge length centered at the origin, each face has its own 4 vertices for the flat normals and the full texture
+	pub fn cube(size: f32) -> Self {
+		let h = size * 0.5;
+		let mut ret = Self::new_triangles(24, 36);
+		let faces = [
+			(Vec3::new( h, 0.0, 0.0), Vec3::new(0.0, 0.0, -h), Vec3::new(0.0, h, 0.0)),
+			(Vec3::new(-h, 0.0, 0.0), Vec3::new(0.0, 0.0,  h), Vec3::new(0.0, h, 0.0)),
+			(Vec3::new(0.0,  h, 0.0), Vec3::new(h, 0.0, 0.0), Vec3::new(0.0, 0.0, -h)),
+			(Vec3::new(0.0, -h, 0.0), Vec3::new(h, 0.0, 0.0), Vec3::new(0.0, 0.0,  h)),
+			(Vec3::new(0.0, 0.0,  h), Vec3::new( h, 0.0, 0.0), Vec3::new(0.0, h, 0.0)),
+			(Vec3::new(0.0, 0.0, -h), Vec3::new(-h, 0.0, 0.0), Vec3::new(0.0, h, 0.0)),
+		];
+		for (center, u, v) in faces {
+			ret.push_face(center, u, v);
+		}
+		ret
+	}
+
+	/// Create a plane of the size on the XZ plane facing +Y, subdivided into a grid of `subdivisions` x `subdivisions` cells
+	pub fn plane(width: f32, depth: f32, subdivisions: u32) -> Self {
+		let n = subdivisions.max(1);
+		let row_len = n + 1;
+		let mut ret = Self::new_triangles((row_len * row_len) as usize, (n * n * 6) as usize);
+		for iz in 0..=n {
+			for ix in 0..=n {
+				let u = ix as f32 / n as f32;
+				let v = 1.0 - iz as f32 / n as f32;
+				ret.vertices.push(ShapeVertex::new(Vec3::new((u - 0.5) * width, 0.0, (0.5 - v) * depth), Vec3::new(0.0, 1.0, 0.0), Vec2::new(u, v)));
+			}
+		}
+		for iz in 0..n {
+			for ix in 0..n {
+				ret.push_cell(iz * row_len + ix, row_len);
+			}
+		}
+		ret
+	}
+
+	/// Create a sphere of the radius by `segments` around the Y axis and `rings` from the north pole to the south pole.
+	/// The seam and the poles have the duplicated vertices for the continuous texture coordinates, `u` goes around and `v` is 1 at the north pole.
+	pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Self {
+		let segments = segments.max(3);
+		let rings = rings.max(2);
+		let row_len = segments + 1;
+		let mut ret = Self::new_triangles((row_len * (rings + 1)) as usize, (segments * (rings - 1) * 6) as usize);
+		for ring in 0..=rings {
+			let phi = ring as f32 / rings as f32 * PI;
+			for segment in 0..=segments {
+				let theta = segment as f32 / segments as f32 * TAU;
+				let normal = Vec3::new(phi.sin() * theta.sin(), phi.cos(), phi.sin() * theta.cos());
+				let texcoord = Vec2::new(segment as f32 / segments as f32, 1.0 - ring as f32 / rings as f32);
+				ret.vertices.push(ShapeVertex::new(normal * radius, normal, texcoord));
+			}
+		}
+		for ring in 0..rings {
+			for segment in 0..segments {
+				let top_left = ring * row_len + segment;
+				let bottom_left = top_left + row_len;
+				if ring != rings - 1 {
+					ret.indices.extend_from_slice(&[top_left, bottom_left, bottom_left + 1]);
+				}
+				if ring != 0 {
+					ret.indices.extend_from_slice(&[top_left, bottom_left + 1, top_left + 1]);
+				}
+			}
+		}
+		ret
+	}
+
+	/// Create a capped cylinder of the radius and the height along the Y axis centered at the origin, the side and the caps have their own vertices for the sharp edges
+	pub fn cylinder(radius: f32, height: f32, segments: u32) -> Self {
+		let segments = segments.max(3);
+		let row_len = segments + 1;
+		let h = height * 0.5;
+		let mut ret = Self::new_triangles((row_len * 4 + 2) as usize, (segments * 12) as usize);
+		for (y, v) in [(h, 1.0), (-h, 0.0)] {
+			for segment in 0..=segments {
+				let theta = segment as f32 / segments as f32 * TAU;
+				let normal = Vec3::new(theta.sin(), 0.0, theta.cos());
+				ret.vertices.push(ShapeVertex::new(Vec3::new(normal.x * radius, y, normal.z * radius), normal, Vec2::new(segment as f32 / segments as f32, v)));
+			}
+		}
+		for segment in 0..segments {
+			ret.push_cell(segment, row_len);
+		}
+		for (y, normal_y) in [(h, 1.0f32), (-h, -1.0f32)] {
+			let normal = Vec3::new(0.0, normal_y, 0.0);
+			let center = ret.vertices.len() as u32;
+			ret.vertices.push(ShapeVertex::new(Vec3::new(0.0, y, 0.0), normal, Vec2::new(0.5, 0.5)));
+			for segment in 0..=segments {
+				let theta = segment as f32 / segments as f32 * TAU;
+				let (sin, cos) = theta.sin_cos();
+				ret.vertices.push(ShapeVertex::new(Vec3::new(sin * radius, y, cos * radius), normal, Vec2::new(0.5 + sin * 0.5, 0.5 - cos * normal_y * 0.5)));
+			}
+			for segment in 0..segments {
+				let rim = center + 1 + segment;
+				if normal_y > 0.0 {
+					ret.indices.extend_from_slice(&[center, rim, rim + 1]);
+				} else {
+					ret.indices.extend_from_slice(&[center, rim + 1, rim]);
+				}
+			}
+		}
+		ret
+	}
+
+	/// Create a torus around the Y axis, `major_radius` is from the center to the center of the tube and `minor_radius` is of the tube.
+	/// `u` goes around the Y axis and `v` goes around the tube from the outermost.
+	pub fn torus(major_radius: f32, minor_radius: f32, major_segments: u32, minor_segments: u32) -> Self {
+		let major_segments = major_segments.max(3);
+		let minor_segments = minor_segments.max(3);
+		let row_len = minor_segments + 1;
+		let mut ret = Self::new_triangles((row_len * (major_segments + 1)) as usize, (major_segments * minor_segments * 6) as usize);
+		for i in 0..=major_segments {
+			let theta = i as f32 / major_segments as f32 * TAU;
+			let radial = Vec3::new(theta.sin(), 0.0, theta.cos());
+			for j in 0..=minor_segments {
+				let phi = j as f32 / minor_segments as f32 * TAU;
+				let normal = radial * phi.cos() + Vec3::new(0.0, phi.sin(), 0.0);
+				let position = radial * major_radius + normal * minor_radius;
+				ret.vertices.push(ShapeVertex::new(position, normal, Vec2::new(i as f32 / major_segments as f32, j as f32 / minor_segments as f32)));
+			}
+		}
+		for i in 0..major_segments {
+			for j in 0..minor_segments {
+				let a = i * row_len + j;
+				let b = a + row_len;
+				ret.indices.extend_from_slice(&[a, b, b + 1, a, b + 1, a + 1]);
+			}
+		}
+		ret
+	}
+
+	/// Upload the geometry into a mesh of `ShapeVertex`
+	pub fn to_mesh(&self, glcore: Rc<GLCore>) -> Result<ShapeMesh<ShapeVertex>, GLCoreError> {
+		self.to_mesh_with(glcore, |vertex| vertex)
+	}
+
+	/// Upload the geometry into a mesh of your vertex type, `map` converts each of the vertices, e.g. into a struct of `derive_vertex_type!`
+	pub fn to_mesh_with<V: VertexType>(&self, glcore: Rc<GLCore>, map: impl Fn(ShapeVertex) -> V) -> Result<ShapeMesh<V>, GLCoreError> {
+		let vertices: Vec<V> = self.vertices.iter().map(|vertex| map(*vertex)).collect();
+		ShapeMesh::<V>::from_slices(glcore, self.primitive, &vertices, Some(&self.indices))
+	}
+}
+
+/// Create the mesh of a quad on the XY plane facing +Z, see `ShapeGeometry::quad()`
+pub fn quad(glcore: Rc<GLCore>, width: f32, height: f32) -> Result<ShapeMesh<ShapeVertex>, GLCoreError> {
+	ShapeGeometry::quad(width, height).to_mesh(glcore)
+}
+
+/// Create the mesh of a cube centered at the origin, see `ShapeGeometry::cube()`
+pub fn cube(glcore: Rc<GLCore>, size: f32) -> Result<ShapeMesh<ShapeVertex>, GLCoreError> {
+	ShapeGeometry::cube(size).to_mesh(glcore)
+}
+
+/// Create the mesh of a subdivided plane on the XZ plane facing +Y, see `ShapeGeometry::plane()`
+pub fn plane(glcore: Rc<GLCore>, width: f32, depth: f32, subdivisions: u32) -> Result<ShapeMesh<ShapeVertex>, GLCoreError> {
+	ShapeGeometry::plane(width, depth, subdivisions).to_mesh(glcore)
+}
+
+/// Create the mesh of a UV sphere, see `ShapeGeometry::uv_sphere()`
+pub fn uv_sphere(glcore: Rc<GLCore>, radius: f32, segments: u32, rings: u32) -> Result<ShapeMesh<ShapeVertex>, GLCoreError> {
+	ShapeGeometry::uv_sphere(radius, segments, rings).to_mesh(glcore)
+}
+
+/// Create the mesh of a capped cylinder along the Y axis, see `ShapeGeometry::cylinder()`
+pub fn cylinder(glcore: Rc<GLCore>, radius: f32, height: f32, segments: u32) -> Result<ShapeMesh<ShapeVertex>, GLCoreError> {
+	ShapeGeometry::cylinder(radius, height, segments).to_mesh(glcore)
+}
+
+/// Create the mesh of a torus around the Y axis, see `ShapeGeometry::torus()`
+pub fn torus(glcore: Rc<GLCore>, major_radius: f32, minor_radius: f32, major_segments: u32, minor_segments: u32) -> Result<ShapeMesh<ShapeVertex>, GLCoreError> {
+	ShapeGeometry::torus(major_radius, minor_radius, major_segments, minor_segments).to_mesh(glcore)
+}
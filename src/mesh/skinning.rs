@@ -0,0 +1,114 @@
+use crate::prelude::*;
+
+derive_vertex_type! {
+	/// The vertex of the skinned meshes, the names of the shader attribs are `position`, `normal`, `texcoord`, `joints` and `weights`.
+	/// Each vertex is blended by up to 4 bones, `joints` are the indices of the bone matrices and `weights` are their weights that sum to 1.
+	/// Declare `joints` as `uvec4` in the shader, it's passed by `glVertexAttribIPointer()` as the integers.
+	#[repr(C)]
+	#[derive(PartialEq)]
+	pub struct SkinnedVertex {
+		pub position: Vec3,
+		pub normal: Vec3,
+		pub texcoord: Vec2,
+		pub joints: U16Vec4,
+		pub weights: Vec4,
+	}
+}
+
+/// The number of the bone matrices of the sample skinning shader
+pub const SKINNING_SHADER_MAX_BONES: usize = 64;
+
+/// The sample vertex shader for `SkinnedVertex`, the uniforms are `mat4 bones[64]` set by `ShaderUse::set_bone_matrices()` and `mat4 view_projection`
+pub const SKINNING_VERTEX_SHADER: &str = "
+#version 330\n
+
+in vec3 position;
+in vec3 normal;
+in vec2 texcoord;
+in uvec4 joints;
+in vec4 weights;
+
+uniform mat4 bones[64];
+uniform mat4 view_projection;
+
+out vec3 v_normal;
+out vec2 v_texcoord;
+
+void main()
+{
+	mat4 skin =
+		bones[joints.x] * weights.x +
+		bones[joints.y] * weights.y +
+		bones[joints.z] * weights.z +
+		bones[joints.w] * weights.w;
+	v_normal = mat3(skin) * normal;
+	v_texcoord = texcoord;
+	gl_Position = view_projection * skin * vec4(position, 1.0);
+}
+";
+
+/// The sample fragment shader to go with `SKINNING_VERTEX_SHADER`, lights the `color` by the direction to the light `light_direction`
+pub const SKINNING_FRAGMENT_SHADER: &str = "
+#version 330\n
+
+in vec3 v_normal;
+in vec2 v_texcoord;
+
+uniform vec4 color;
+uniform vec3 light_direction;
+
+out vec4 Color;
+
+void main()
+{
+	float diffuse = max(dot(normalize(v_normal), normalize(light_direction)), 0.0);
+	Color = vec4(color.rgb * diffuse, color.a);
+}
+";
+
+impl SkinnedVertex {
+	/// Create a vertex, the `weights` are not normalized, see `normalize_weights()`
+	pub fn new(position: Vec3, normal: Vec3, texcoord: Vec2, joints: U16Vec4, weights: Vec4) -> Self {
+		Self {
+			position,
+			normal,
+			texcoord,
+			joints,
+			weights,
+		}
+	}
+
+	/// Scale the weights to sum to 1, returns `false` and keeps the weights if any of them is negative or all of them are zero
+	pub fn normalize_weights(&mut self) -> bool {
+		match normalize_skin_weights(&self.weights) {
+			Some(weights) => {
+				self.weights = weights;
+				true
+			}
+			None => false,
+		}
+	}
+}
+
+impl WeldableVertex for SkinnedVertex {
+	fn get_position(&self) -> Vec3 {
+		self.position
+	}
+
+	fn set_position(&mut self, position: Vec3) {
+		self.position = position;
+	}
+}
+
+/// Scale the skin weights to sum to 1, `None` if any of them is negative or not finite, or all of them are zero
+pub fn normalize_skin_weights(weights: &Vec4) -> Option<Vec4> {
+	if weights.iter().any(|weight| !weight.is_finite() || *weight < 0.0) {
+		return None;
+	}
+	let sum = weights.sum();
+	if sum > 0.0 {
+		Some(weights / sum)
+	} else {
+		None
+	}
+}
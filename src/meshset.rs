@@ -1,47 +1,699 @@
 
 use crate::prelude::*;
+use crate::mesh::shapes::ShapeVertex;
 use std::{
-	collections::BTreeMap,
+	cell::{Cell, RefCell},
+	cmp::Ordering,
+	collections::{BTreeMap, BTreeSet, HashMap},
+	ffi::c_void,
+	mem::size_of_val,
+	path::Path,
 	rc::Rc,
 };
 
-/// Mesh set, each mesh has its name and material.
+/// The Wavefront OBJ loader
+pub mod obj;
+pub use obj::*;
+
+/// The Wavefront MTL loader for the materials of the OBJ files
+pub mod mtl;
+pub use mtl::*;
+
+/// The binary cache files of the mesh sets
+pub mod cache;
+pub use cache::*;
+
+/// The scene graph of the nodes with the transforms on top of the mesh set
+pub mod scene;
+pub use scene::*;
+
+/// The glTF 2.0 loader
+#[cfg(feature = "gltf")]
+pub mod gltf_loader;
+#[cfg(feature = "gltf")]
+pub use gltf_loader::*;
+
+/// The error for loading a mesh set from a model file
+#[derive(Debug)]
+pub enum MeshLoadError {
+	IOError(std::io::Error),
+	GLCoreError(GLCoreError),
+
+	TextureError(TextureError),
+
+	#[cfg(feature = "gltf")]
+	GltfError(gltf::Error),
+
+	/// The OBJ file (the first one) has an error at the line (the second one), the message (the third one) tells why
+	ObjParseError(String, usize, String),
+
+	/// The model file (the first one) uses a feature that isn't supported, the message (the second one) tells which
+	UnsupportedFeature(String, String),
+
+	/// The model file (the first one) has invalid data, the message (the second one) tells why
+	InvalidModel(String, String),
+}
+
+impl From<std::io::Error> for MeshLoadError {
+	fn from(err: std::io::Error) -> Self {
+		Self::IOError(err)
+	}
+}
+
+impl From<GLCoreError> for MeshLoadError {
+	fn from(err: GLCoreError) -> Self {
+		Self::GLCoreError(err)
+	}
+}
+
+impl From<TextureError> for MeshLoadError {
+	fn from(err: TextureError) -> Self {
+		Self::TextureError(err)
+	}
+}
+
+#[cfg(feature = "gltf")]
+impl From<gltf::Error> for MeshLoadError {
+	fn from(err: gltf::Error) -> Self {
+		Self::GltfError(err)
+	}
+}
+
+/// The error for building a `LodSelector` or a `LodMesh`
+#[derive(Debug, Clone, PartialEq)]
+pub enum LodError {
+	/// There's no level
+	NoLevels,
+
+	/// The max distance of the level (the first one) isn't greater than the one of the previous level
+	NotAscending(usize),
+
+	/// The hysteresis (the first one) is negative or not a number
+	InvalidHysteresis(f32),
+}
+
+/// The error for managing the subsets of a `Meshset`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MeshsetError {
+	/// The name (the first one) is used by another subset
+	DuplicateName(String),
+
+	/// There's no subset of the name (the first one)
+	SubsetNotFound(String),
+}
+
+/// Selects a level of detail by the distance, level `i` is used for the distances below `max_distances[i]`, the last level is used beyond all of them.
+/// The hysteresis keeps the previous level until the distance goes past its boundary by that much, to avoid popping back and forth at the boundary.
+#[derive(Debug, Clone)]
+pub struct LodSelector {
+	max_distances: Vec<f32>,
+	hysteresis: f32,
+	current: Cell<usize>,
+}
+
+/// The level of detail group, each level is a mesh with its max distance
 #[derive(Debug, Clone)]
+pub struct LodMesh {
+	/// The meshes from the most detailed one to the least detailed one
+	pub levels: Vec<Rc<dyn GenericMeshWithMaterial>>,
+
+	/// The selector of the levels
+	pub selector: LodSelector,
+
+	/// The position to measure the distance from the camera
+	pub center: Vec3,
+}
+
+/// Mesh set, each mesh has its name and material.
+#[derive(Default, Debug, Clone)]
 pub struct Meshset {
+	/// The meshes by their names, prefer `add_subset()`, `remove_subset()` and the other methods to changing it directly, it will become private
 	pub subsets: BTreeMap<String, Rc<dyn GenericMeshWithMaterial>>,
+
+	/// The subsets that are level of detail groups, drawn at the level selected by the distance to the camera
+	pub lod_groups: BTreeMap<String, Rc<LodMesh>>,
 }
 
-/// Pipeline set, converted from the mesh set, for batch drawing.
+impl LodSelector {
+	/// Create a selector, the `max_distances` must be ascending
+	pub fn new(max_distances: Vec<f32>, hysteresis: f32) -> Result<Self, LodError> {
+		if max_distances.is_empty() {
+			return Err(LodError::NoLevels);
+		}
+		if let Some(index) = (1..max_distances.len()).find(|&i| max_distances[i].partial_cmp(&max_distances[i - 1]) != Some(Ordering::Greater)) {
+			return Err(LodError::NotAscending(index));
+		}
+		if hysteresis.is_nan() || hysteresis < 0.0 {
+			return Err(LodError::InvalidHysteresis(hysteresis));
+		}
+		Ok(Self {
+			max_distances,
+			hysteresis,
+			current: Cell::new(0),
+		})
+	}
+
+	/// Get the max distances of the levels
+	pub fn get_max_distances(&self) -> &[f32] {
+		&self.max_distances
+	}
+
+	/// Get the hysteresis
+	pub fn get_hysteresis(&self) -> f32 {
+		self.hysteresis
+	}
+
+	/// Get the level selected last time
+	pub fn get_current(&self) -> usize {
+		self.current.get()
+	}
+
+	/// Select the level for the distance, the previous level is kept if the distance is still within its range widened by the hysteresis
+	pub fn select(&self, distance: f32) -> usize {
+		let current = self.current.get();
+		let last = self.max_distances.len() - 1;
+		let lower = if current == 0 {f32::NEG_INFINITY} else {self.max_distances[current - 1] - self.hysteresis};
+		let upper = if current == last {f32::INFINITY} else {self.max_distances[current] + self.hysteresis};
+		let index = if distance >= lower && distance < upper {
+			current
+		} else {
+			self.max_distances.iter().position(|max_distance| distance < *max_distance).unwrap_or(last)
+		};
+		self.current.set(index);
+		index
+	}
+}
+
+impl LodMesh {
+	/// Create a level of detail group from the `(max_distance, mesh)` pairs, the max distances must be ascending
+	pub fn new(levels: Vec<(f32, Rc<dyn GenericMeshWithMaterial>)>, hysteresis: f32, center: Vec3) -> Result<Self, LodError> {
+		let (max_distances, levels): (Vec<f32>, Vec<Rc<dyn GenericMeshWithMaterial>>) = levels.into_iter().unzip();
+		Ok(Self {
+			levels,
+			selector: LodSelector::new(max_distances, hysteresis)?,
+			center,
+		})
+	}
+
+	/// Select the mesh for the distance, see `LodSelector::select()`
+	pub fn select(&self, distance: f32) -> &Rc<dyn GenericMeshWithMaterial> {
+		&self.levels[self.selector.select(distance)]
+	}
+
+	/// Select the mesh for the camera position by the distance to the center
+	pub fn select_for(&self, camera_position: &Vec3) -> &Rc<dyn GenericMeshWithMaterial> {
+		self.select(distance(camera_position, &self.center))
+	}
+}
+
+impl Meshset {
+	/// Add a subset, fails if there's already a subset of the name
+	pub fn add_subset(&mut self, name: &str, mesh: Rc<dyn GenericMeshWithMaterial>) -> Result<(), MeshsetError> {
+		if self.subsets.contains_key(name) {
+			return Err(MeshsetError::DuplicateName(name.to_owned()));
+		}
+		self.subsets.insert(name.to_owned(), mesh);
+		Ok(())
+	}
+
+	/// Remove a subset, returns its mesh
+	pub fn remove_subset(&mut self, name: &str) -> Option<Rc<dyn GenericMeshWithMaterial>> {
+		self.subsets.remove(name)
+	}
+
+	/// Rename a subset, fails if there's no subset of `old` or there's already a subset of `new`
+	pub fn rename_subset(&mut self, old: &str, new: &str) -> Result<(), MeshsetError> {
+		if !self.subsets.contains_key(old) {
+			return Err(MeshsetError::SubsetNotFound(old.to_owned()));
+		}
+		if old == new {
+			return Ok(());
+		}
+		if self.subsets.contains_key(new) {
+			return Err(MeshsetError::DuplicateName(new.to_owned()));
+		}
+		let mesh = self.subsets.remove(old).unwrap();
+		self.subsets.insert(new.to_owned(), mesh);
+		Ok(())
+	}
+
+	/// Get the mesh of a subset
+	pub fn subset(&self, name: &str) -> Option<&Rc<dyn GenericMeshWithMaterial>> {
+		self.subsets.get(name)
+	}
+
+	/// Iterate the subsets by their names
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &Rc<dyn GenericMeshWithMaterial>)> {
+		self.subsets.iter().map(|(name, mesh)| (name.as_str(), mesh))
+	}
+
+	/// Iterate the subsets of the names matching `pattern`. A pattern with `*` or `?` is a glob of the whole name, e.g. `Floor/*`, otherwise it's a prefix of the names.
+	pub fn subsets_matching<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = (&'a str, &'a Rc<dyn GenericMeshWithMaterial>)> {
+		let is_glob = pattern.contains(['*', '?']);
+		self.iter().filter(move |(name, _)| if is_glob {glob_match(pattern, name)} else {name.starts_with(pattern)})
+	}
+
+	/// The number of the subsets, the level of detail groups are not counted
+	pub fn len(&self) -> usize {
+		self.subsets.len()
+	}
+
+	/// Check if there's no subset
+	pub fn is_empty(&self) -> bool {
+		self.subsets.is_empty()
+	}
+
+	/// Load a Wavefront OBJ file, each object/group becomes a subset, the vertices are `ShapeVertex`. See `ObjModel` for the group names and the material names.
+	pub fn from_obj_file(glcore: Rc<GLCore>, path: &Path, options: &ObjLoadOptions) -> Result<Self, MeshLoadError> {
+		Self::from_obj_file_with(glcore, path, options, |vertex| vertex)
+	}
+
+	/// Load a Wavefront OBJ file, `map` converts each of the vertices into your vertex type, e.g. a struct of `derive_vertex_type!`
+	pub fn from_obj_file_with<V: VertexType + 'static>(glcore: Rc<GLCore>, path: &Path, options: &ObjLoadOptions, map: impl Fn(ShapeVertex) -> V) -> Result<Self, MeshLoadError> {
+		ObjModel::from_file(path, options)?.to_meshset_with(glcore, map)
+	}
+
+	/// Load a Wavefront OBJ file with the materials of its MTL files, the mesh of each group is wrapped in a `MeshWithMaterial`, the vertices are `ShapeVertex`.
+	/// Returns the materials by their names and the warnings too. The missing MTL files, materials and texture files don't fail the loading, they are returned as the warnings.
+	pub fn from_obj_with_mtl(glcore: Rc<GLCore>, path: &Path) -> Result<(Self, HashMap<String, Rc<MaterialLegacy>>, Vec<ObjLoadWarning>), MeshLoadError> {
+		let model = ObjModel::from_file(path, &ObjLoadOptions::default())?;
+		let mut warnings = Vec::new();
+		let materials = model.load_materials(&glcore, path.parent().unwrap_or(Path::new("")), &mut warnings)?;
+		Ok((model.to_meshset_with_materials(glcore, &materials, |vertex| vertex)?, materials, warnings))
+	}
+
+	/// Load a glTF or GLB file, each primitive becomes a subset named `node/mesh/primitive-index` with its `MaterialPbr`, the vertices are `GltfVertex` with the node transforms baked
+	#[cfg(feature = "gltf")]
+	pub fn from_gltf_file(glcore: Rc<GLCore>, path: &Path) -> Result<Self, MeshLoadError> {
+		GltfModel::from_file(path)?.to_meshset(glcore)
+	}
+
+	/// Load a glTF or GLB file, `map` converts each of the vertices into your vertex type, e.g. a struct of `derive_vertex_type!`
+	#[cfg(feature = "gltf")]
+	pub fn from_gltf_file_with<V: VertexType + 'static>(glcore: Rc<GLCore>, path: &Path, map: impl Fn(GltfVertex) -> V) -> Result<Self, MeshLoadError> {
+		GltfModel::from_file(path)?.to_meshset_with(glcore, map)
+	}
+
+	/// Merge the subsets into one mesh with a draw command for each of them by their names, to draw all of them by one `glMultiDrawElementsIndirect()`.
+	/// Returns the mesh and the index of the command of each subset, set the `instance_count` of a command to 0 to hide its subset. The level of detail groups aren't merged.
+	/// The vertices are downloaded from the GPU and must be of `V`, the subsets without the element buffers get the elements of their vertices in order. The elements aren't rebased, the commands have the `base_vertex` of the subsets.
+	/// The subsets of the different materials are merged too, the `base_instance` of each command is the index of the first command of its material, i.e. the subsets of a material share it.
+	/// Read it by `gl_BaseInstance` of OpenGL 4.6 or `GL_ARB_shader_draw_parameters` to pick the material, or set the instance buffer of `I` to the data of the materials at the indices, which needs OpenGL 4.2.
+	/// Fails with `MeshError::UnmergedStreams` if a subset has the extra vertex streams or the instance buffer, they would be lost.
+	pub fn bake_into_batch<V: VertexType + 'static, I: VertexType + 'static>(&self, glcore: Rc<GLCore>) -> Result<(StaticMesh<V, u32, I, DrawElementsCommand>, Vec<(String, usize)>), MeshError> {
+		let mut primitive: Option<PrimitiveMode> = None;
+		let mut vertices: Vec<V> = Vec::new();
+		let mut elements: Vec<u32> = Vec::new();
+		let mut commands: Vec<DrawElementsCommand> = Vec::with_capacity(self.subsets.len());
+		let mut names: Vec<(String, usize)> = Vec::with_capacity(self.subsets.len());
+		let mut material_commands: Vec<(Option<*const ()>, u32)> = Vec::new();
+		for (name, mesh) in self.subsets.iter() {
+			if mesh.get_vertex_stride() != size_of::<V>() {
+				return Err(MeshError::VertexStrideMismatch(mesh.get_vertex_stride(), size_of::<V>()));
+			}
+			if !mesh.get_vertex_streams().is_empty() || mesh.get_instance_buffer().is_some() {
+				return Err(MeshError::UnmergedStreams(name.clone()));
+			}
+			match primitive {
+				Some(primitive) if primitive != mesh.get_primitive() => return Err(MeshError::PrimitiveMismatch(primitive, mesh.get_primitive())),
+				_ => primitive = Some(mesh.get_primitive()),
+			}
+			let base_vertex = vertices.len();
+			let Ok(base_vertex_i32) = i32::try_from(base_vertex) else {
+				return Err(MeshError::IndexOverflow(base_vertex));
+			};
+			let vertex_count = mesh.get_vertex_count();
+			let bytes = download_buffer(mesh.get_vertex_buffer(), vertex_count * size_of::<V>())?;
+			vertices.resize(base_vertex + vertex_count, V::default());
+			unsafe {std::ptr::copy_nonoverlapping(bytes.as_ptr(), vertices[base_vertex..].as_mut_ptr() as *mut u8, bytes.len())};
+			let first_index = elements.len();
+			if let Some(element_buffer) = mesh.get_element_buffer() {
+				let element_size = mesh.get_element_type().get_size();
+				let bytes = download_buffer(element_buffer, mesh.get_element_count() * element_size)?;
+				elements.extend(bytes.chunks_exact(element_size).map(|element| match element {
+					[index] => *index as u32,
+					[a, b] => u16::from_ne_bytes([*a, *b]) as u32,
+					_ => u32::from_ne_bytes([element[0], element[1], element[2], element[3]]),
+				}));
+			} else {
+				elements.extend(0..vertex_count as u32);
+			}
+			let material = mesh.get_material().map(|material| material as *const dyn Material as *const ());
+			let base_instance = match material_commands.iter().find(|(address, _)| *address == material) {
+				Some((_, command)) => *command,
+				None => {
+					material_commands.push((material, commands.len() as u32));
+					commands.len() as u32
+				}
+			};
+			names.push((name.clone(), commands.len()));
+			commands.push(DrawElementsCommand::new((elements.len() - first_index) as u32, 1, first_index as u32, base_vertex_i32, base_instance));
+		}
+		let mut mesh = StaticMesh::<V, u32, I, DrawElementsCommand>::from_slices(glcore.clone(), primitive.unwrap_or(PrimitiveMode::Triangles), &vertices, Some(&elements))?;
+		let command_buffer = Buffer::new(glcore, BufferTarget::DrawIndirectBuffer, size_of_val(&commands[..]), BufferUsage::DynamicDraw, commands.as_ptr() as *const c_void)?;
+		let mut command_buffer = BufferVecStatic::<DrawElementsCommand>::new(command_buffer);
+		command_buffer.resize(commands.len(), DrawElementsCommand::default())?;
+		mesh.command_buffer = Some(BufferVecDynamic::new(command_buffer)?);
+		Ok((mesh, names))
+	}
+}
+
+/// What `Pipelineset::draw_with_policy()` does when the `setup` callback returns an error
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupErrorPolicy {
+	/// Stop drawing and return the error
+	#[default]
+	Abort,
+
+	/// Skip the rest of the pipelines of the subset and go on with the next subset, the errors are returned with the names of the subsets
+	SkipSubset,
+}
+
+/// The order of drawing the subsets of a `Pipelineset`
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawOrder {
+	/// By the names of the subsets, then the level of detail groups by their names, e.g. for debugging
+	#[default]
+	Name,
+
+	/// Group the subsets by the shader programs, then by the materials, to skip the redundant `glUseProgram()` and the material setups between the draw calls.
+	/// The level of detail groups are drawn after the subsets by their names. The `setup` callbacks shouldn't change the uniforms and the textures of the materials.
+	StateSorted,
+}
+
+/// The pipelines of `Pipelineset::subsets` sorted for `DrawOrder::StateSorted`
 #[derive(Debug, Clone)]
-pub struct Pipelineset<V: VertexType, I: VertexType> {
-	pub subsets: BTreeMap<String, Vec<Rc<Pipeline<V, I>>>>,
+struct SortedSubsets {
+	/// The names and the addresses of the pipelines of `Pipelineset::subsets` in their order, the sorted pipelines keep them alive so the addresses aren't reused
+	source: Vec<(String, *const ())>,
+
+	/// The names and the pipelines in the drawing order
+	sorted: Vec<(String, Rc<dyn GenericPipeline>)>,
 }
 
-impl<V: VertexType, I: VertexType> Pipelineset<V, I> {
-	/// Create a pipeline set from the mesh set with shaders.
-	pub fn from_meshset(glcore: Rc<GLCore>, meshset: Meshset, shaders: &[Rc<Shader>]) -> Result<Self, PipelineError> {
-		let mut subsets = BTreeMap::new();
-		for (name, mesh) in meshset.subsets.iter() {
-			let mut v = Vec::with_capacity(shaders.len());
+/// Pipeline set, converted from the mesh set, for batch drawing.
+/// The pipelines are `GenericPipeline`s, so a set could mix the pipelines of the different vertex types, e.g. the static meshes from `from_meshset()` and the skinned meshes added by `insert()`.
+#[derive(Debug, Clone, Default)]
+pub struct Pipelineset {
+	pub subsets: BTreeMap<String, Vec<Rc<dyn GenericPipeline>>>,
+
+	/// The pipelines of each level of the level of detail groups
+	pub lod_groups: BTreeMap<String, (Rc<LodMesh>, Vec<Vec<Rc<dyn GenericPipeline>>>)>,
+
+	/// The shaders of `from_meshset()` or `sync_with()`, each of the subsets has a pipeline for each of them in the same order
+	shaders: Vec<Rc<Shader>>,
+
+	/// The order of drawing the subsets
+	draw_order: DrawOrder,
+
+	/// The names of the hidden subsets and level of detail groups, see `set_visible()`
+	hidden: BTreeSet<String>,
+
+	/// The pipelines of the subsets in the `DrawOrder::StateSorted` order and the names and the pipelines of `subsets` it was built from, rebuilt by the first draw after the subsets changed
+	sorted_subsets: RefCell<Option<SortedSubsets>>,
+}
+
+impl Pipelineset {
+	/// Create a pipeline set from the mesh set with shaders, the pipelines are `Pipeline<V, I>`. The meshes are shared with the mesh set, e.g. to create another pipeline set for the shadow pass.
+	/// Fails with `PipelineError::NoShaders` if `shaders` is empty.
+	pub fn from_meshset<V: VertexType + 'static, I: VertexType + 'static>(glcore: Rc<GLCore>, meshset: &Meshset, shaders: &[Rc<Shader>]) -> Result<Self, PipelineError> {
+		let mut pipelineset = Self::default();
+		pipelineset.sync_with::<V, I>(glcore, meshset, shaders)?;
+		Ok(pipelineset)
+	}
+
+	/// Update the pipeline set to match the changed mesh set, like `from_meshset()` but the pipelines are only created for the added subsets and the subsets of the replaced meshes or shaders.
+	/// The subsets and the level of detail groups that are not in the mesh set are removed, including the pipelines added by `insert()`.
+	pub fn sync_with<V: VertexType + 'static, I: VertexType + 'static>(&mut self, glcore: Rc<GLCore>, meshset: &Meshset, shaders: &[Rc<Shader>]) -> Result<(), PipelineError> {
+		if shaders.is_empty() {
+			return Err(PipelineError::NoShaders);
+		}
+		let is_synced = |pipelines: &[Rc<dyn GenericPipeline>], mesh: &Rc<dyn GenericMeshWithMaterial>| {
+			pipelines.len() == shaders.len() && pipelines.iter().zip(shaders.iter()).all(|(pipeline, shader)| Rc::ptr_eq(pipeline.get_mesh(), mesh) && Rc::ptr_eq(pipeline.get_shader(), shader))
+		};
+		let create = |mesh: &Rc<dyn GenericMeshWithMaterial>| -> Result<Vec<Rc<dyn GenericPipeline>>, PipelineError> {
+			let mut v: Vec<Rc<dyn GenericPipeline>> = Vec::with_capacity(shaders.len());
 			for shader in shaders.iter() {
-				v.push(Rc::new(Pipeline::new(glcore.clone(), mesh.clone(), shader.clone())?));
+				v.push(Rc::new(Pipeline::<V, I>::new(glcore.clone(), mesh.clone(), shader.clone())?));
+			}
+			Ok(v)
+		};
+		self.subsets.retain(|name, _| meshset.subsets.contains_key(name));
+		for (name, mesh) in meshset.subsets.iter() {
+			if !self.subsets.get(name).is_some_and(|pipelines| is_synced(pipelines, mesh)) {
+				self.subsets.insert(name.clone(), create(mesh)?);
 			}
-			subsets.insert(name.clone(), v);
 		}
-		Ok(Self {
-			subsets
-		})
+		self.lod_groups.retain(|name, (lod, levels)| meshset.lod_groups.get(name).is_some_and(|other| Rc::ptr_eq(lod, other) && levels.iter().zip(lod.levels.iter()).all(|(pipelines, mesh)| is_synced(pipelines, mesh))));
+		for (name, lod) in meshset.lod_groups.iter() {
+			if !self.lod_groups.contains_key(name) {
+				let mut levels = Vec::with_capacity(lod.levels.len());
+				for mesh in lod.levels.iter() {
+					levels.push(create(mesh)?);
+				}
+				self.lod_groups.insert(name.clone(), (lod.clone(), levels));
+			}
+		}
+		self.shaders = shaders.to_vec();
+		self.invalidate_draw_order();
+		Ok(())
 	}
 
-	/// Draw the pipeline set to a framebuffer
-	pub fn draw(&self, fbo: Option<&Framebuffer>) -> Result<(), PipelineError> {
-		for (_name, pipelines) in self.subsets.iter() {
+	/// Add a pipeline to the subset of the name, the subset is created if it doesn't exist
+	pub fn insert(&mut self, name: &str, pipeline: Rc<dyn GenericPipeline>) {
+		self.subsets.entry(name.to_owned()).or_default().push(pipeline);
+		self.invalidate_draw_order();
+	}
+
+	/// Show or hide a subset or a level of detail group by its name, the hidden ones are skipped by all of the draws, e.g. `draw()` and `draw_subsets()`. All of them are visible by default.
+	pub fn set_visible(&mut self, name: &str, visible: bool) {
+		if visible {
+			self.hidden.remove(name);
+		} else {
+			self.hidden.insert(name.to_owned());
+		}
+	}
+
+	/// Check if a subset or a level of detail group isn't hidden by `set_visible()`
+	pub fn is_visible(&self, name: &str) -> bool {
+		!self.hidden.contains(name)
+	}
+
+	/// Set the order of drawing the subsets, the default is `DrawOrder::Name`
+	pub fn set_draw_order(&mut self, draw_order: DrawOrder) {
+		self.draw_order = draw_order;
+	}
+
+	/// Get the order of drawing the subsets
+	pub fn get_draw_order(&self) -> DrawOrder {
+		self.draw_order
+	}
+
+	/// Rebuild the `DrawOrder::StateSorted` order at the next draw, call it after changing the shaders or the materials of the pipelines. Adding, removing or replacing the pipelines of `subsets` is detected by the draws.
+	pub fn invalidate_draw_order(&mut self) {
+		*self.sorted_subsets.get_mut() = None;
+	}
+
+	/// Iterate the names and the addresses of the pipelines of the subsets, the identity of the subsets that the `DrawOrder::StateSorted` order is built from
+	fn subsets_identity(&self) -> impl Iterator<Item = (&String, *const ())> {
+		self.subsets.iter().flat_map(|(name, pipelines)| pipelines.iter().map(move |pipeline| (name, Rc::as_ptr(pipeline) as *const ())))
+	}
+
+	/// Sort the pipelines of the subsets by the shader programs and then the materials if the order isn't built or any of the subsets changed
+	fn update_sorted_subsets(&self) {
+		if self.sorted_subsets.borrow().as_ref().is_some_and(|cache| cache.source.iter().map(|(name, pipeline)| (name, *pipeline)).eq(self.subsets_identity())) {
+			return;
+		}
+		let mut sorted: Vec<(String, Rc<dyn GenericPipeline>)> = self.subsets.iter().flat_map(|(name, pipelines)| pipelines.iter().map(|pipeline| (name.clone(), pipeline.clone()))).collect();
+		sorted.sort_by_key(|(_, pipeline)| MaterialBatch::key(pipeline.get_shader().get_name(), pipeline.get_mesh().get_material()));
+		let source = self.subsets_identity().map(|(name, pipeline)| (name.clone(), pipeline)).collect();
+		*self.sorted_subsets.borrow_mut() = Some(SortedSubsets {source, sorted});
+	}
+
+	/// Get the shaders of `from_meshset()` or `sync_with()`, the index of a shader is the `shader_index` of `draw_with_shader()`
+	pub fn get_shaders(&self) -> &[Rc<Shader>] {
+		&self.shaders
+	}
+
+	/// Draw the pipeline set to a framebuffer, the levels of the level of detail groups are selected by the distance to `camera_position`, or the most detailed level is drawn if it's `None`.
+	/// Each of the subsets is drawn by all of its pipelines, i.e. once with each of the shaders, see `draw_with_shader()` to draw with one of them, e.g. for a depth prepass.
+	/// Returns the sum of the counts of the draw calls.
+	pub fn draw(&self, fbo: Option<&Framebuffer>, camera_position: Option<&Vec3>) -> Result<DrawStats, PipelineError> {
+		self.draw_with(fbo, camera_position, |_, _| Ok(()))
+	}
+
+	/// Set the `RenderState` of all of the pipelines, see `Pipeline::set_render_state()`
+	pub fn set_render_state(&self, render_state: Option<RenderState>) {
+		for pipelines in self.subsets.values().chain(self.lod_groups.values().flat_map(|(_, levels)| levels.iter())) {
 			for pipeline in pipelines.iter() {
-				let bind = pipeline.bind()?;
-				bind.draw(fbo)?;
-				bind.unbind();
+				pipeline.set_render_state(render_state);
 			}
 		}
-		Ok(())
 	}
+
+	/// Draw the pipeline set like `draw()`, `setup` is called with the name of the subset or the level of detail group before each of the draw calls to set the uniforms, e.g. the model matrix.
+	/// The `RenderState` of the pipelines are applied by their changes between the draw calls, the states are restored after all of the draw calls.
+	/// An error of `setup` stops the drawing, see `draw_with_policy()` to skip the subset instead.
+	pub fn draw_with(&self, fbo: Option<&Framebuffer>, camera_position: Option<&Vec3>, setup: impl FnMut(&str, &ShaderUse) -> Result<(), ShaderError>) -> Result<DrawStats, PipelineError> {
+		Ok(self.draw_with_policy(fbo, camera_position, SetupErrorPolicy::Abort, setup)?.0)
+	}
+
+	/// Draw the pipeline set like `draw_with()`, `policy` decides what to do when `setup` returns an error.
+	/// Returns the sum of the counts of the draw calls and the errors of `setup` of the skipped subsets, the errors are always empty for `SetupErrorPolicy::Abort`.
+	pub fn draw_with_policy(&self, fbo: Option<&Framebuffer>, camera_position: Option<&Vec3>, policy: SetupErrorPolicy, setup: impl FnMut(&str, &ShaderUse) -> Result<(), ShaderError>) -> Result<(DrawStats, Vec<(String, ShaderError)>), PipelineError> {
+		self.draw_selected(fbo, camera_position, policy, |_, _| true, |_, _| Ok(true), setup)
+	}
+
+	/// Draw the pipeline set like `draw()` but only by the pipelines of the shader of `shader_index` in `get_shaders()`, the pipelines of the other shaders added by `insert()` are skipped
+	pub fn draw_with_shader(&self, shader_index: usize, fbo: Option<&Framebuffer>, camera_position: Option<&Vec3>) -> Result<DrawStats, PipelineError> {
+		let Some(shader) = self.shaders.get(shader_index) else {
+			return Err(PipelineError::InvalidShaderIndex(shader_index, self.shaders.len()));
+		};
+		Ok(self.draw_selected(fbo, camera_position, SetupErrorPolicy::Abort, |_, pipeline| Rc::ptr_eq(pipeline.get_shader(), shader), |_, _| Ok(true), |_, _| Ok(()))?.0)
+	}
+
+	/// Draw only the subsets and the level of detail groups of the names like `draw()`, e.g. the transparent ones in a later pass. Returns the sum of the counts of the draw calls.
+	/// Fails with all of the unknown names before drawing anything.
+	pub fn draw_subsets(&self, fbo: Option<&Framebuffer>, names: &[&str]) -> Result<DrawStats, PipelineError> {
+		let unknown: Vec<String> = names.iter().filter(|name| !self.subsets.contains_key(**name) && !self.lod_groups.contains_key(**name)).map(|name| name.to_string()).collect();
+		if !unknown.is_empty() {
+			return Err(PipelineError::SubsetsNotFound(unknown));
+		}
+		Ok(self.draw_selected(fbo, None, SetupErrorPolicy::Abort, |name, _| names.contains(&name), |_, _| Ok(true), |_, _| Ok(()))?.0)
+	}
+
+	/// Draw only the pipelines that `predicate` returns `true` for like `draw()`, it's called with the name of the subset or the level of detail group and the material of the mesh.
+	/// The meshes without material get the default `MaterialLegacy`. Returns the sum of the counts of the draw calls.
+	pub fn draw_filtered(&self, fbo: Option<&Framebuffer>, predicate: impl Fn(&str, &dyn Material) -> bool) -> Result<DrawStats, PipelineError> {
+		let default_material = MaterialLegacy::default();
+		let filter = |name: &str, pipeline: &dyn GenericPipeline| predicate(name, pipeline.get_mesh().get_material().unwrap_or(&default_material));
+		Ok(self.draw_selected(fbo, None, SetupErrorPolicy::Abort, filter, |_, _| Ok(true), |_, _| Ok(()))?.0)
+	}
+
+	/// Draw the pipeline set with the frustum culling, the subsets out of the frustum of `view_projection` are skipped, the number of the skipped draw calls is `DrawStats::culled`.
+	/// `per_subset_transform` gives the model matrix of the subset or the level of detail group of the name, the levels of the level of detail groups are the most detailed ones.
+	/// The bounding boxes of the meshes are computed at the first time and cached, see `Pipeline::get_bounds()`, the meshes without the bounding boxes are always drawn.
+	/// The test of the transformed boxes against the planes of the frustum never skips a visible subset, but some of the invisible subsets near the edges of the frustum are drawn, see `Frustum::intersects_aabb()`.
+	pub fn draw_culled(&self, fbo: Option<&Framebuffer>, view_projection: &Mat4, per_subset_transform: impl Fn(&str) -> Mat4) -> Result<DrawStats, PipelineError> {
+		self.draw_culled_with(fbo, None, view_projection, per_subset_transform, |_, _| Ok(()))
+	}
+
+	/// Draw the pipeline set with the frustum culling like `draw_culled()`, the levels of the level of detail groups are selected by the distance to `camera_position` and `setup` is called before each of the draw calls like `draw_with()`
+	pub fn draw_culled_with(&self, fbo: Option<&Framebuffer>, camera_position: Option<&Vec3>, view_projection: &Mat4, per_subset_transform: impl Fn(&str) -> Mat4, setup: impl FnMut(&str, &ShaderUse) -> Result<(), ShaderError>) -> Result<DrawStats, PipelineError> {
+		let frustum = Frustum::from_matrix(view_projection);
+		let visible = |name: &str, pipeline: &dyn GenericPipeline| -> Result<bool, PipelineError> {
+			Ok(match pipeline.get_bounds()? {
+				Some(bounds) => frustum.intersects_aabb(&bounds.transform(&per_subset_transform(name))),
+				None => true,
+			})
+		};
+		Ok(self.draw_selected(fbo, camera_position, SetupErrorPolicy::Abort, |_, _| true, visible, setup)?.0)
+	}
+
+	/// Draw the visible pipelines that `filter` selects, then `visible` returns `true` for, the ones that `visible` returns `false` for are counted by `DrawStats::culled`
+	fn draw_selected(&self, fbo: Option<&Framebuffer>, camera_position: Option<&Vec3>, policy: SetupErrorPolicy, filter: impl Fn(&str, &dyn GenericPipeline) -> bool, mut visible: impl FnMut(&str, &dyn GenericPipeline) -> Result<bool, PipelineError>, mut setup: impl FnMut(&str, &ShaderUse) -> Result<(), ShaderError>) -> Result<(DrawStats, Vec<(String, ShaderError)>), PipelineError> {
+		let state_sorted = self.draw_order == DrawOrder::StateSorted;
+		if state_sorted {
+			self.update_sorted_subsets();
+		}
+		let sorted_subsets = self.sorted_subsets.borrow();
+		let lod_pipelines = self.lod_groups.iter().flat_map(|(name, (lod, levels))| {
+			let level = camera_position.map_or(0, |camera_position| lod.selector.select(distance(camera_position, &lod.center)));
+			levels[level].iter().map(move |pipeline| (name, pipeline))
+		});
+		let entries: Vec<(&String, &Rc<dyn GenericPipeline>)> = match &*sorted_subsets {
+			Some(cache) if state_sorted => cache.sorted.iter().map(|(name, pipeline)| (name, pipeline)).chain(lod_pipelines).collect(),
+			_ => self.subsets.iter().flat_map(|(name, pipelines)| pipelines.iter().map(move |pipeline| (name, pipeline))).chain(lod_pipelines).collect(),
+		};
+		let mut stats = DrawStats::default();
+		let mut skipped: Vec<(String, ShaderError)> = Vec::new();
+		let mut state_guard: Option<StateGuard> = None;
+		let mut current_state: Option<RenderState> = None;
+		let mut debug_scope: Option<(&String, Option<DebugScope>)> = None;
+		let material_batch = state_sorted.then(MaterialBatch::begin);
+		let mut program: Option<ShaderUse> = None;
+		for (name, pipeline) in entries {
+			if self.hidden.contains(name) || !filter(name, &**pipeline) {
+				continue;
+			}
+			if debug_scope.as_ref().is_none_or(|(current, _)| *current != name) {
+				if let Some((_, Some(s))) = debug_scope.take() { s.pop() }
+				debug_scope = Some((name, DebugScope::push_internal(pipeline.get_glcore(), || name.clone())?));
+			}
+			if skipped.iter().any(|(skipped_name, _)| skipped_name == name) {
+				continue;
+			}
+			if !visible(name, &**pipeline)? {
+				stats.culled += 1;
+				continue;
+			}
+			if state_sorted && program.as_ref().is_none_or(|program| !std::ptr::eq(program.shader, &**pipeline.get_shader())) {
+				// Keep the program in use for the following pipelines of the same shader
+				if let Some(p) = program.take() { p.unuse() }
+				program = Some(pipeline.get_shader().use_program()?);
+				stats.program_switches += 1;
+			}
+			let render_state = pipeline.get_render_state();
+			if render_state != current_state {
+				let glcore = &**pipeline.get_glcore();
+				match (render_state, current_state) {
+					(Some(next), Some(current)) => next.apply_changes(glcore, &current)?,
+					(Some(next), None) => {
+						if state_guard.is_none() {
+							state_guard = Some(StateGuard::new(glcore)?);
+						}
+						next.apply(glcore)?;
+					}
+					(None, _) => if let Some(guard) = &state_guard {
+						guard.reset()?;
+					}
+				}
+				current_state = render_state;
+			}
+			let mut setup_failed = false;
+			let result = pipeline.draw_dyn(fbo, false, &mut |program| setup(name, program).inspect_err(|_| setup_failed = true));
+			match result {
+				Ok(s) => stats += s,
+				Err(PipelineError::ShaderError(error)) if setup_failed && policy == SetupErrorPolicy::SkipSubset => skipped.push((name.clone(), error)),
+				Err(error) => return Err(error),
+			}
+		}
+		if let Some(p) = program { p.unuse() }
+		if let Some(b) = material_batch { b.end() }
+		if let Some((_, Some(s))) = debug_scope { s.pop() }
+		if let Some(g) = state_guard { g.restore() }
+		if stats.culled > 0 || stats.program_switches > 0 {
+			FrameStats::record(DrawStats {
+				culled: stats.culled,
+				program_switches: stats.program_switches,
+				..Default::default()
+			});
+		}
+		Ok((stats, skipped))
+	}
+}
+
+/// Match the whole text by a glob pattern of `*` for any characters and `?` for a character
+fn glob_match(pattern: &str, text: &str) -> bool {
+	let pattern: Vec<char> = pattern.chars().collect();
+	let text: Vec<char> = text.chars().collect();
+	let (mut p, mut t) = (0, 0);
+	let mut backtrack: Option<(usize, usize)> = None;
+	while t < text.len() {
+		if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+			p += 1;
+			t += 1;
+		} else if p < pattern.len() && pattern[p] == '*' {
+			backtrack = Some((p, t));
+			p += 1;
+		} else if let Some((star, matched)) = backtrack {
+			// Let the last `*` take one more character
+			backtrack = Some((star, matched + 1));
+			p = star + 1;
+			t = matched + 1;
+		} else {
+			return false;
+		}
+	}
+	pattern[p..].iter().all(|c| *c == '*')
 }
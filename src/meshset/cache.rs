@@ -0,0 +1,436 @@
+use crate::prelude::*;
+use bincode::{Encode, Decode};
+use std::{
+	collections::HashMap,
+	ffi::c_void,
+	fs::File,
+	io::{BufReader, BufWriter},
+	path::{Path, PathBuf},
+	rc::Rc,
+};
+
+/// The textures loaded from the files by their paths and whether they are sRGB, shared by the materials, see `MtlMaterial::to_material()` and `Meshset::load_from_file()`
+pub type TextureCache = HashMap<(PathBuf, bool), Rc<dyn GenericTexture>>;
+
+/// The magic at the beginning of the mesh set cache files
+pub const MESHSET_CACHE_MAGIC: [u8; 8] = *b"GLOBJMSH";
+
+/// The version of the format of the mesh set cache files, the files of the other versions are rejected
+pub const MESHSET_CACHE_VERSION: u32 = 1;
+
+/// The max size of the mesh set cache files, the decoder claims no more bytes than this so a corrupted length fails the loading instead of the allocation.
+/// The limit of bincode is a const generic, the larger files are rejected by their metadata before decoding.
+pub const MESHSET_CACHE_MAX_BYTES: usize = 1 << 30;
+
+/// The error of saving or loading a mesh set cache file
+#[derive(Debug)]
+pub enum MeshsetCacheError {
+	IOError(std::io::Error),
+	GLCoreError(GLCoreError),
+	TextureError(TextureError),
+	EncodeError(bincode::error::EncodeError),
+	DecodeError(bincode::error::DecodeError),
+	LodError(LodError),
+
+	/// The file isn't a mesh set cache file
+	InvalidMagic,
+
+	/// The version of the file (the first one) isn't `MESHSET_CACHE_VERSION` (the second one), the cache should be built again
+	VersionMismatch(u32, u32),
+
+	/// The size of the vertices of the subset (the first one) in the file (the second one) differs from the vertex type to load (the third one)
+	VertexStrideMismatch(String, usize, usize),
+
+	/// The texture of the component (the second one) of the material of the subset (the first one) isn't in the `TextureCache`, its file is unknown
+	UnknownTexture(String, String),
+
+	/// The file has invalid data, the message (the first one) tells why
+	InvalidData(String),
+}
+
+/// The header of the mesh set cache files
+#[derive(Encode, Decode)]
+struct CacheHeader {
+	magic: [u8; 8],
+	version: u32,
+}
+
+/// A material component in the cache file, the textures are referenced by their files relative to the cache file
+#[derive(Encode, Decode)]
+enum CachedComponent {
+	Texture(String, bool),
+	Color([f32; 4]),
+	Luminance(f32),
+}
+
+/// A material in the cache file, `MaterialPbr` if `pbr` is `true`, or `MaterialLegacy`
+#[derive(Encode, Decode)]
+struct CachedMaterial {
+	pbr: bool,
+	components: Vec<(String, CachedComponent)>,
+}
+
+/// A mesh in the cache file, the buffers are the raw bytes of the vertices and the elements
+#[derive(Encode, Decode)]
+struct CachedMesh {
+	primitive: u32,
+	vertex_stride: u32,
+	vertices: Vec<u8>,
+
+	/// The `ElementType` by its size and the elements
+	elements: Option<(u8, Vec<u8>)>,
+	patch_vertices: Option<u32>,
+	patch_default_levels: Option<([f32; 4], [f32; 2])>,
+	bounds: Option<([f32; 3], [f32; 3])>,
+
+	/// The index of the material in `CachedMeshset::materials`
+	material: Option<u32>,
+}
+
+/// A level of detail group in the cache file
+#[derive(Encode, Decode)]
+struct CachedLod {
+	levels: Vec<(f32, CachedMesh)>,
+	hysteresis: f32,
+	center: [f32; 3],
+}
+
+/// The body of the mesh set cache files after the header, the materials shared by the meshes are stored once
+#[derive(Encode, Decode)]
+struct CachedMeshset {
+	materials: Vec<CachedMaterial>,
+	subsets: Vec<(String, CachedMesh)>,
+	lod_groups: Vec<(String, CachedLod)>,
+}
+
+/// The state of `Meshset::save_to_file()`
+struct CacheWriter<'a> {
+	base: &'a Path,
+	textures: &'a TextureCache,
+	materials: Vec<CachedMaterial>,
+
+	/// The addresses of the saved materials, to store the shared ones once
+	material_addresses: Vec<*const ()>,
+}
+
+/// The state of `Meshset::load_from_file()`
+struct CacheReader<'a> {
+	glcore: Rc<GLCore>,
+	base: &'a Path,
+	textures: &'a mut TextureCache,
+	legacy_materials: HashMap<u32, Rc<MaterialLegacy>>,
+	pbr_materials: HashMap<u32, Rc<MaterialPbr>>,
+}
+
+impl Meshset {
+	/// Save the subsets and the level of detail groups with their materials to a cache file, to load them by `load_from_file()` faster than parsing the model files.
+	/// The buffers are downloaded from the GPU, flush the dynamic meshes before saving. The instance buffers, the draw commands and the additional vertex streams aren't saved.
+	/// The textures of the materials are saved as their files in `textures`, relative to the directory of the cache file, a texture that isn't in `textures` fails the saving.
+	pub fn save_to_file(&self, path: &Path, textures: &TextureCache) -> Result<(), MeshsetCacheError> {
+		let mut writer = CacheWriter {
+			base: path.parent().unwrap_or(Path::new("")),
+			textures,
+			materials: Vec::new(),
+			material_addresses: Vec::new(),
+		};
+		let mut subsets = Vec::with_capacity(self.subsets.len());
+		for (name, mesh) in self.subsets.iter() {
+			subsets.push((name.clone(), writer.save_mesh(name, &**mesh)?));
+		}
+		let mut lod_groups = Vec::with_capacity(self.lod_groups.len());
+		for (name, lod) in self.lod_groups.iter() {
+			let mut levels = Vec::with_capacity(lod.levels.len());
+			for (max_distance, mesh) in lod.selector.get_max_distances().iter().zip(lod.levels.iter()) {
+				levels.push((*max_distance, writer.save_mesh(name, &**mesh)?));
+			}
+			lod_groups.push((name.clone(), CachedLod {
+				levels,
+				hysteresis: lod.selector.get_hysteresis(),
+				center: lod.center.into(),
+			}));
+		}
+		let body = CachedMeshset {
+			materials: writer.materials,
+			subsets,
+			lod_groups,
+		};
+		let config = bincode::config::standard()
+			.with_little_endian()
+			.with_fixed_int_encoding();
+		let mut file = BufWriter::new(File::create(path)?);
+		bincode::encode_into_std_write(CacheHeader {magic: MESHSET_CACHE_MAGIC, version: MESHSET_CACHE_VERSION}, &mut file, config)?;
+		bincode::encode_into_std_write(body, &mut file, config)?;
+		Ok(())
+	}
+
+	/// Load a cache file of `save_to_file()`, the meshes are `StaticMesh`es of `V` wrapped in `MeshWithMaterial`s with their `MaterialLegacy` or `MaterialPbr`.
+	/// `V` must be the vertex type of the saved meshes, only their sizes could be checked. The textures are loaded from their files relative to the cache file, or taken from `textures` if they are loaded already.
+	pub fn load_from_file<V: VertexType + 'static>(glcore: Rc<GLCore>, path: &Path, textures: &mut TextureCache) -> Result<Self, MeshsetCacheError> {
+		let config = bincode::config::standard()
+			.with_little_endian()
+			.with_fixed_int_encoding()
+			.with_limit::<MESHSET_CACHE_MAX_BYTES>();
+		let file = File::open(path)?;
+		let file_size = file.metadata()?.len();
+		if file_size > MESHSET_CACHE_MAX_BYTES as u64 {
+			return Err(MeshsetCacheError::InvalidData(format!("The cache file of {file_size} bytes exceeds `MESHSET_CACHE_MAX_BYTES`")));
+		}
+		let mut file = BufReader::new(file);
+		let header: CacheHeader = bincode::decode_from_std_read(&mut file, config)?;
+		if header.magic != MESHSET_CACHE_MAGIC {
+			return Err(MeshsetCacheError::InvalidMagic);
+		}
+		if header.version != MESHSET_CACHE_VERSION {
+			return Err(MeshsetCacheError::VersionMismatch(header.version, MESHSET_CACHE_VERSION));
+		}
+		let body: CachedMeshset = bincode::decode_from_std_read(&mut file, config)?;
+		let mut reader = CacheReader {
+			glcore,
+			base: path.parent().unwrap_or(Path::new("")),
+			textures,
+			legacy_materials: HashMap::new(),
+			pbr_materials: HashMap::new(),
+		};
+		let mut meshset = Self::default();
+		for (name, mesh) in body.subsets.iter() {
+			meshset.subsets.insert(name.clone(), reader.load_mesh::<V>(name, mesh, &body.materials)?);
+		}
+		for (name, lod) in body.lod_groups.iter() {
+			let mut levels = Vec::with_capacity(lod.levels.len());
+			for (max_distance, mesh) in lod.levels.iter() {
+				levels.push((*max_distance, reader.load_mesh::<V>(name, mesh, &body.materials)?));
+			}
+			meshset.lod_groups.insert(name.clone(), Rc::new(LodMesh::new(levels, lod.hysteresis, lod.center.into())?));
+		}
+		Ok(meshset)
+	}
+}
+
+impl CacheWriter<'_> {
+	/// Download the buffers of a mesh and save its material
+	fn save_mesh(&mut self, name: &str, mesh: &dyn GenericMeshWithMaterial) -> Result<CachedMesh, MeshsetCacheError> {
+		let vertex_stride = mesh.get_vertex_stride();
+		let vertices = download_buffer(mesh.get_vertex_buffer(), mesh.get_vertex_count() * vertex_stride)?;
+		let elements = if let Some(element_buffer) = mesh.get_element_buffer() {
+			let element_size = mesh.get_element_type().get_size();
+			Some((element_size as u8, download_buffer(element_buffer, mesh.get_element_count() * element_size)?))
+		} else {
+			None
+		};
+		let material = if let Some(material) = mesh.get_material() {
+			Some(self.save_material(name, material)?)
+		} else {
+			None
+		};
+		Ok(CachedMesh {
+			primitive: mesh.get_primitive() as u32,
+			vertex_stride: vertex_stride as u32,
+			vertices,
+			elements,
+			patch_vertices: mesh.get_patch_vertices(),
+			patch_default_levels: mesh.get_patch_default_levels(),
+			bounds: mesh.get_bounds().map(|bounds| (bounds.min.into(), bounds.max.into())),
+			material,
+		})
+	}
+
+	/// Save a material once, returns its index
+	fn save_material(&mut self, name: &str, material: &dyn Material) -> Result<u32, MeshsetCacheError> {
+		let address = material as *const dyn Material as *const ();
+		if let Some(index) = self.material_addresses.iter().position(|saved| *saved == address) {
+			return Ok(index as u32);
+		}
+		let mut components = Vec::new();
+		for component_name in material.get_names() {
+			let component = match material.get_by_name(&component_name) {
+				Some(MaterialComponent::Texture(texture)) => {
+					let Some(((path, srgb), _)) = self.textures.iter().find(|(_, cached)| Rc::ptr_eq(cached, texture)) else {
+						return Err(MeshsetCacheError::UnknownTexture(name.to_owned(), component_name));
+					};
+					let path = path.strip_prefix(self.base).unwrap_or(path);
+					CachedComponent::Texture(path.to_string_lossy().into_owned(), *srgb)
+				}
+				Some(MaterialComponent::Color(color)) => CachedComponent::Color((*color).into()),
+				Some(MaterialComponent::Luminance(luminance)) => CachedComponent::Luminance(*luminance),
+				None => continue,
+			};
+			components.push((component_name, component));
+		}
+		self.materials.push(CachedMaterial {
+			pbr: material.get_albedo().is_some(),
+			components,
+		});
+		self.material_addresses.push(address);
+		Ok(self.materials.len() as u32 - 1)
+	}
+}
+
+impl CacheReader<'_> {
+	/// Upload the buffers of a mesh and wrap it with its material
+	fn load_mesh<V: VertexType + 'static>(&mut self, name: &str, cached: &CachedMesh, materials: &[CachedMaterial]) -> Result<Rc<dyn GenericMeshWithMaterial>, MeshsetCacheError> {
+		if cached.vertex_stride as usize != size_of::<V>() {
+			return Err(MeshsetCacheError::VertexStrideMismatch(name.to_owned(), cached.vertex_stride as usize, size_of::<V>()));
+		}
+		match &cached.elements {
+			None => self.wrap_mesh(self.upload_mesh::<V, u32>(name, cached, &[])?, cached.material, materials),
+			Some((1, elements)) => self.wrap_mesh(self.upload_mesh::<V, u8>(name, cached, elements)?, cached.material, materials),
+			Some((2, elements)) => self.wrap_mesh(self.upload_mesh::<V, u16>(name, cached, elements)?, cached.material, materials),
+			Some((4, elements)) => self.wrap_mesh(self.upload_mesh::<V, u32>(name, cached, elements)?, cached.material, materials),
+			Some((size, _)) => Err(MeshsetCacheError::InvalidData(format!("The element size of the subset `{name}` is {size}"))),
+		}
+	}
+
+	/// Create the static mesh from the raw bytes, `elements` is ignored if the mesh has no element buffer.
+	/// The sizes of the buffers must be multiples of the vertex size and the element size, and the elements must index the vertices.
+	fn upload_mesh<V: VertexType, E: ElementIndexType>(&self, name: &str, cached: &CachedMesh, elements: &[u8]) -> Result<StaticMesh<V, E, UnusedType, UnusedType>, MeshsetCacheError> {
+		let Some(primitive) = primitive_from_gl(cached.primitive) else {
+			return Err(MeshsetCacheError::InvalidData(format!("Unknown primitive mode 0x{:04X}", cached.primitive)));
+		};
+		let vertex_size = size_of::<V>().max(1);
+		if cached.vertices.len() % vertex_size != 0 {
+			return Err(MeshsetCacheError::InvalidData(format!("The {} bytes of the vertices of the subset `{name}` aren't a multiple of the vertex size {vertex_size}", cached.vertices.len())));
+		}
+		if elements.len() % size_of::<E>() != 0 {
+			return Err(MeshsetCacheError::InvalidData(format!("The {} bytes of the elements of the subset `{name}` aren't a multiple of the element size {}", elements.len(), size_of::<E>())));
+		}
+		let vertex_count = cached.vertices.len() / vertex_size;
+		let out_of_range = elements.chunks_exact(size_of::<E>())
+			.map(|index| unsafe {(index.as_ptr() as *const E).read_unaligned()}.to_u32())
+			.find(|index| *index as usize >= vertex_count);
+		if let Some(index) = out_of_range {
+			return Err(MeshsetCacheError::InvalidData(format!("The element {index} of the subset `{name}` is out of the {vertex_count} vertices")));
+		}
+		let vertex_buffer = Buffer::new(self.glcore.clone(), BufferTarget::ArrayBuffer, cached.vertices.len(), BufferUsage::StaticDraw, cached.vertices.as_ptr() as *const c_void)?;
+		let mut vertex_buffer = BufferVecStatic::<V>::new(vertex_buffer);
+		vertex_buffer.resize(vertex_count, V::default())?;
+		let element_buffer = if cached.elements.is_some() {
+			let element_buffer = Buffer::new(self.glcore.clone(), BufferTarget::ElementArrayBuffer, elements.len(), BufferUsage::StaticDraw, elements.as_ptr() as *const c_void)?;
+			let mut element_buffer = BufferVecStatic::<E>::new(element_buffer);
+			element_buffer.resize(elements.len() / size_of::<E>(), E::default())?;
+			Some(element_buffer)
+		} else {
+			None
+		};
+		let mut mesh = StaticMesh::new(primitive, vertex_buffer, element_buffer, None, None);
+		mesh.patch_vertices = cached.patch_vertices;
+		mesh.patch_default_levels = cached.patch_default_levels;
+		mesh.set_bounds(cached.bounds.map(|(min, max)| Aabb {min: min.into(), max: max.into()}));
+		Ok(mesh)
+	}
+
+	/// Wrap the mesh with its material, the materials are loaded once and shared by the meshes
+	fn wrap_mesh<M: GenericMeshWithMaterial + 'static>(&mut self, mesh: M, material: Option<u32>, materials: &[CachedMaterial]) -> Result<Rc<dyn GenericMeshWithMaterial>, MeshsetCacheError> {
+		let Some(index) = material else {
+			return Ok(Rc::new(mesh));
+		};
+		let Some(cached) = materials.get(index as usize) else {
+			return Err(MeshsetCacheError::InvalidData(format!("The material index {index} is out of range")));
+		};
+		if cached.pbr {
+			if !self.pbr_materials.contains_key(&index) {
+				let mut material = MaterialPbr::default();
+				self.load_components(&mut material, cached)?;
+				self.pbr_materials.insert(index, Rc::new(material));
+			}
+			Ok(Rc::new(MeshWithMaterial::new(mesh, self.pbr_materials[&index].clone())))
+		} else {
+			if !self.legacy_materials.contains_key(&index) {
+				let mut material = MaterialLegacy::default();
+				self.load_components(&mut material, cached)?;
+				self.legacy_materials.insert(index, Rc::new(material));
+			}
+			Ok(Rc::new(MeshWithMaterial::new(mesh, self.legacy_materials[&index].clone())))
+		}
+	}
+
+	/// Set the components of a material, the textures are loaded by `TextureCache`
+	fn load_components(&mut self, material: &mut dyn Material, cached: &CachedMaterial) -> Result<(), MeshsetCacheError> {
+		for (name, component) in cached.components.iter() {
+			let component = match component {
+				CachedComponent::Texture(file, srgb) => {
+					let path = self.base.join(file);
+					let texture = match self.textures.get(&(path.clone(), *srgb)) {
+						Some(texture) => texture.clone(),
+						None => {
+							let texture: Rc<dyn GenericTexture> = Rc::new(Texture::<BufferVecStatic<u8>, u8>::from_file(self.glcore.clone(), &path, TextureDimension::Tex2d, TextureWrapping::Repeat, TextureWrapping::Repeat, true, SamplerMagFilter::Linear, SamplerFilter::LinearMipmapLinear, *srgb)?);
+							self.textures.insert((path, *srgb), texture.clone());
+							texture
+						}
+					};
+					MaterialComponent::Texture(texture)
+				}
+				CachedComponent::Color(color) => MaterialComponent::Color((*color).into()),
+				CachedComponent::Luminance(luminance) => MaterialComponent::Luminance(*luminance),
+			};
+			material.set_by_name(name, component);
+		}
+		Ok(())
+	}
+}
+
+/// Read the first `size` bytes of a buffer through `GL_COPY_READ_BUFFER`, which doesn't change the bindings of the VAOs
+pub(crate) fn download_buffer(buffer: &Buffer, size: usize) -> Result<Vec<u8>, GLCoreError> {
+	if size == 0 {
+		return Ok(Vec::new());
+	}
+	let bind = buffer.bind_to(BufferTarget::CopyReadBuffer)?;
+	let (mapping, address) = bind.map_ranged(0, size, MapAccess::ReadOnly)?;
+	let data = unsafe {std::slice::from_raw_parts(address as *const u8, size)}.to_vec();
+	mapping.unmap();
+	bind.unbind();
+	Ok(data)
+}
+
+/// Get the primitive mode of its OpenGL enum
+fn primitive_from_gl(primitive: u32) -> Option<PrimitiveMode> {
+	[
+		PrimitiveMode::Points,
+		PrimitiveMode::LineStrip,
+		PrimitiveMode::LineLoop,
+		PrimitiveMode::Lines,
+		PrimitiveMode::LineStripAdjacency,
+		PrimitiveMode::LinesAdjacency,
+		PrimitiveMode::TriangleStrip,
+		PrimitiveMode::TriangleFan,
+		PrimitiveMode::Triangles,
+		PrimitiveMode::TriangleStripAdjacency,
+		PrimitiveMode::TrianglesAdjacency,
+		PrimitiveMode::Patches,
+	].into_iter().find(|mode| *mode as u32 == primitive)
+}
+
+impl From<std::io::Error> for MeshsetCacheError {
+	fn from(err: std::io::Error) -> Self {
+		Self::IOError(err)
+	}
+}
+
+impl From<GLCoreError> for MeshsetCacheError {
+	fn from(err: GLCoreError) -> Self {
+		Self::GLCoreError(err)
+	}
+}
+
+impl From<TextureError> for MeshsetCacheError {
+	fn from(err: TextureError) -> Self {
+		Self::TextureError(err)
+	}
+}
+
+impl From<bincode::error::EncodeError> for MeshsetCacheError {
+	fn from(err: bincode::error::EncodeError) -> Self {
+		Self::EncodeError(err)
+	}
+}
+
+impl From<bincode::error::DecodeError> for MeshsetCacheError {
+	fn from(err: bincode::error::DecodeError) -> Self {
+		Self::DecodeError(err)
+	}
+}
+
+impl From<LodError> for MeshsetCacheError {
+	fn from(err: LodError) -> Self {
+		Self::LodError(err)
+	}
+}
@@ -0,0 +1,588 @@
+use crate::prelude::*;
+use crate::mesh::skinning::{SkinnedVertex, normalize_skin_weights};
+use std::{
+	borrow::Cow,
+	collections::{BTreeMap, HashMap, HashSet},
+	path::{Path, PathBuf},
+	rc::Rc,
+};
+use gltf::{
+	image::Source,
+	texture::{MagFilter, MinFilter, WrappingMode},
+};
+
+derive_vertex_type! {
+	/// The vertex of the glTF meshes, the names of the shader attribs are `position`, `normal`, `texcoord`, `tangent`, `joints` and `weights`.
+	/// The `texcoord` is `TEXCOORD_0`, the `w` of the `tangent` is the handedness of the bitangent. The missing attribs are zero.
+	/// The `joints` and the `weights` are `JOINTS_0` and `WEIGHTS_0`, the joints index the joints of the skin and the weights are normalized to sum to 1.
+	#[repr(C)]
+	#[derive(PartialEq)]
+	pub struct GltfVertex {
+		pub position: Vec3,
+		pub normal: Vec3,
+		pub texcoord: Vec2,
+		pub tangent: Vec4,
+		pub joints: U16Vec4,
+		pub weights: Vec4,
+	}
+}
+
+impl WeldableVertex for GltfVertex {
+	fn get_position(&self) -> Vec3 {
+		self.position
+	}
+
+	fn set_position(&mut self, position: Vec3) {
+		self.position = position;
+	}
+}
+
+/// A primitive of a glTF mesh in the system memory, the node transforms are baked into the vertices
+#[derive(Debug, Clone)]
+pub struct GltfPrimitive {
+	/// The name as `node/mesh/primitive-index`, the unnamed nodes and meshes are named by their indices, e.g. `node3`
+	pub name: String,
+
+	/// The index of the node of the primitive in the document
+	pub node_index: usize,
+
+	/// The index of the mesh in the document
+	pub mesh_index: usize,
+
+	/// The index of the primitive in the mesh
+	pub primitive_index: usize,
+
+	/// The index of the material in the document, `None` for the default material
+	pub material: Option<usize>,
+
+	/// The index of the skin of the node in `GltfModel::skins`, the vertices of the skinned primitives are in the bind pose without the node transforms baked
+	pub skin: Option<usize>,
+
+	pub primitive: PrimitiveMode,
+	pub vertices: Vec<GltfVertex>,
+	pub indices: Option<Vec<u32>>,
+}
+
+/// A skin of the glTF file, the bones are the joint nodes
+#[derive(Debug, Clone)]
+pub struct GltfSkin {
+	/// The name of the skin, the unnamed skins are named by their indices, e.g. `skin0`
+	pub name: String,
+
+	/// The indices of the joint nodes, the `joints` of the vertices index this
+	pub joints: Vec<usize>,
+
+	/// The inverse bind matrices of the joints, the missing ones are identity
+	pub inverse_bind_matrices: Vec<Mat4>,
+}
+
+/// The options for loading the glTF files
+#[derive(Debug, Clone)]
+pub struct GltfLoadOptions {
+	/// Bake the node transforms into the vertices of the primitives, otherwise the vertices are in the space of their meshes, e.g. for `Scene` that keeps the node transforms. It's `true` by default.
+	pub bake_transforms: bool,
+}
+
+/// The parsed glTF file, the primitives of the default scene (or the first scene) are read with the node transforms baked unless `GltfLoadOptions::bake_transforms` is `false`
+#[derive(Debug)]
+pub struct GltfModel {
+	pub document: gltf::Document,
+	pub buffers: Vec<gltf::buffer::Data>,
+	pub primitives: Vec<GltfPrimitive>,
+	pub skins: Vec<GltfSkin>,
+
+	/// The directory of the external files
+	base: Option<PathBuf>,
+
+	/// The file name for the error messages
+	name: String,
+}
+
+impl GltfModel {
+	/// Parse a glTF or GLB file in the memory, the external buffers and images are relative to `base`, the `name` is for the error messages.
+	/// The required extensions (e.g. Draco) and the sparse accessors aren't supported.
+	pub fn parse(bytes: &[u8], base: Option<&Path>, name: &str) -> Result<Self, MeshLoadError> {
+		Self::parse_with_options(bytes, base, name, &GltfLoadOptions::default())
+	}
+
+	/// Parse a glTF or GLB file in the memory like `parse()` with the options
+	pub fn parse_with_options(bytes: &[u8], base: Option<&Path>, name: &str, options: &GltfLoadOptions) -> Result<Self, MeshLoadError> {
+		let gltf::Gltf {document, blob} = gltf::Gltf::from_slice(bytes)?;
+		if let Some(extension) = document.extensions_required().next() {
+			return Err(MeshLoadError::UnsupportedFeature(name.to_owned(), format!("The required extension `{extension}`")));
+		}
+		check_node_hierarchy(&document, name)?;
+		let buffers = gltf::import_buffers(&document, base, blob)?;
+		let mut skins = Vec::new();
+		for skin in document.skins() {
+			let joints: Vec<usize> = skin.joints().map(|joint| joint.index()).collect();
+			let mut inverse_bind_matrices: Vec<Mat4> = skin.reader(|buffer| Some(&*buffers[buffer.index()])).read_inverse_bind_matrices().map_or_else(Vec::new, |matrices| matrices.map(|matrix| make_mat4(matrix.as_flattened())).collect());
+			inverse_bind_matrices.resize(joints.len(), Mat4::identity());
+			skins.push(GltfSkin {
+				name: skin.name().map_or_else(|| format!("skin{}", skin.index()), |name| name.to_owned()),
+				joints,
+				inverse_bind_matrices,
+			});
+		}
+		let mut primitives = Vec::new();
+		let mut visited = HashSet::new();
+		for node in scene_roots(&document) {
+			collect_primitives(node, &Mat4::identity(), options.bake_transforms, &buffers, &skins, name, &mut visited, &mut primitives)?;
+		}
+		Ok(Self {
+			document,
+			buffers,
+			primitives,
+			skins,
+			base: base.map(|base| base.to_path_buf()),
+			name: name.to_owned(),
+		})
+	}
+
+	/// Load and parse a glTF or GLB file
+	pub fn from_file(path: &Path) -> Result<Self, MeshLoadError> {
+		Self::from_file_with_options(path, &GltfLoadOptions::default())
+	}
+
+	/// Load and parse a glTF or GLB file with the options
+	pub fn from_file_with_options(path: &Path, options: &GltfLoadOptions) -> Result<Self, MeshLoadError> {
+		let bytes = std::fs::read(path)?;
+		Self::parse_with_options(&bytes, path.parent(), &path.to_string_lossy(), options)
+	}
+
+	/// Upload the primitives into a mesh set of `GltfVertex` with `MaterialPbr`, see `to_meshset_with()`
+	pub fn to_meshset(&self, glcore: Rc<GLCore>) -> Result<Meshset, MeshLoadError> {
+		self.to_meshset_with(glcore, |vertex| vertex)
+	}
+
+	/// Upload the primitives into a mesh set of `SkinnedVertex` to draw with the bone matrices from `get_bone_matrices()`, see `to_meshset_with()`
+	pub fn to_skinned_meshset(&self, glcore: Rc<GLCore>) -> Result<Meshset, MeshLoadError> {
+		self.to_meshset_with(glcore, SkinnedVertex::from)
+	}
+
+	/// Get the global transforms of all of the nodes in the rest pose, indexed by the node indices
+	pub fn get_global_transforms(&self) -> Vec<Mat4> {
+		let mut parents = vec![None; self.document.nodes().len()];
+		for node in self.document.nodes() {
+			for child in node.children() {
+				parents[child.index()] = Some(node.index());
+			}
+		}
+		let locals: Vec<Mat4> = self.document.nodes().map(|node| make_mat4(node.transform().matrix().as_flattened())).collect();
+		(0..locals.len()).map(|mut index| {
+			let mut transform = locals[index];
+			while let Some(parent) = parents[index] {
+				transform = locals[parent] * transform;
+				index = parent;
+			}
+			transform
+		}).collect()
+	}
+
+	/// Get the bone matrices of the skin in the rest pose, see `GltfSkin::get_bone_matrices()`
+	pub fn get_bone_matrices(&self, skin: usize) -> Vec<Mat4> {
+		self.skins[skin].get_bone_matrices(&self.get_global_transforms())
+	}
+
+	/// Upload the primitives into a mesh set, `map` converts each of the vertices into your vertex type.
+	/// Each subset is named by its primitive, a duplicated name gets a suffix like `#2`. The elements are `u16` if the vertices are few enough, otherwise `u32`.
+	pub fn to_meshset_with<V: VertexType + 'static>(&self, glcore: Rc<GLCore>, map: impl Fn(GltfVertex) -> V) -> Result<Meshset, MeshLoadError> {
+		let mut textures: HashMap<(usize, bool), Rc<dyn GenericTexture>> = HashMap::new();
+		let mut materials: HashMap<Option<usize>, Rc<MaterialPbr>> = HashMap::new();
+		let mut subsets: BTreeMap<String, Rc<dyn GenericMeshWithMaterial>> = BTreeMap::new();
+		for (primitive, name) in self.primitives.iter().zip(self.get_subset_names()) {
+			let material = match materials.get(&primitive.material) {
+				Some(material) => material.clone(),
+				None => {
+					let gltf_primitive = self.document.meshes().nth(primitive.mesh_index).and_then(|mesh| mesh.primitives().nth(primitive.primitive_index)).unwrap();
+					let material = Rc::new(self.load_material(&glcore, &gltf_primitive.material(), &mut textures)?);
+					materials.insert(primitive.material, material.clone());
+					material
+				}
+			};
+			let vertices: Vec<V> = primitive.vertices.iter().map(|vertex| map(*vertex)).collect();
+			let mesh: Rc<dyn GenericMeshWithMaterial> = match &primitive.indices {
+				Some(indices) if vertices.len() <= u16::MAX as usize + 1 => {
+					let indices: Vec<u16> = indices.iter().map(|index| *index as u16).collect();
+					Rc::new(MeshWithMaterial::new(StaticMesh::<V, u16, UnusedType, UnusedType>::from_slices(glcore.clone(), primitive.primitive, &vertices, Some(&indices))?, material))
+				}
+				indices => Rc::new(MeshWithMaterial::new(StaticMesh::<V, u32, UnusedType, UnusedType>::from_slices(glcore.clone(), primitive.primitive, &vertices, indices.as_deref())?, material)),
+			};
+			subsets.insert(name, mesh);
+		}
+		Ok(Meshset {
+			subsets,
+			lod_groups: BTreeMap::new(),
+		})
+	}
+
+	/// Get the names of the subsets of the primitives in `to_meshset_with()`, the names of the primitives with a suffix like `#2` for the duplicated ones
+	pub fn get_subset_names(&self) -> Vec<String> {
+		let mut names: Vec<String> = Vec::with_capacity(self.primitives.len());
+		let mut used: HashSet<String> = HashSet::new();
+		for primitive in self.primitives.iter() {
+			let mut name = primitive.name.clone();
+			let mut suffix = 1;
+			while used.contains(&name) {
+				suffix += 1;
+				name = format!("{}#{suffix}", primitive.name);
+			}
+			used.insert(name.clone());
+			names.push(name);
+		}
+		names
+	}
+
+	/// Upload the primitives into a `Scene` of `GltfVertex`, the model should be loaded without `GltfLoadOptions::bake_transforms`, see `Scene::from_gltf_file()`
+	pub fn to_scene(&self, glcore: Rc<GLCore>) -> Result<Scene, MeshLoadError> {
+		let (nodes, roots) = self.get_scene_nodes();
+		Ok(Scene {
+			meshset: self.to_meshset(glcore)?,
+			nodes,
+			roots,
+		})
+	}
+
+	/// Get the nodes of the document indexed by the node indices and the root nodes of the scene, the subsets of the nodes are named by `get_subset_names()`
+	pub fn get_scene_nodes(&self) -> (Vec<SceneNode>, Vec<usize>) {
+		let mut nodes: Vec<SceneNode> = self.document.nodes().map(|node| {
+			let (translation, [x, y, z, w], scale) = node.transform().decomposed();
+			let camera = node.camera().map(|camera| match camera.projection() {
+				gltf::camera::Projection::Perspective(perspective) => SceneCamera::Perspective {
+					yfov: perspective.yfov(),
+					aspect_ratio: perspective.aspect_ratio(),
+					znear: perspective.znear(),
+					zfar: perspective.zfar(),
+				},
+				gltf::camera::Projection::Orthographic(orthographic) => SceneCamera::Orthographic {
+					xmag: orthographic.xmag(),
+					ymag: orthographic.ymag(),
+					znear: orthographic.znear(),
+					zfar: orthographic.zfar(),
+				},
+			});
+			SceneNode {
+				name: node.name().map_or_else(|| format!("node{}", node.index()), |name| name.to_owned()),
+				parent: None,
+				children: node.children().map(|child| child.index()).collect(),
+				transform: NodeTransform::new(Vec3::from(translation), quat(x, y, z, w), Vec3::from(scale)),
+				subsets: Vec::new(),
+				skin: node.skin().map(|skin| skin.index()),
+				camera,
+				light: None,
+			}
+		}).collect();
+		for index in 0..nodes.len() {
+			for child in nodes[index].children.clone() {
+				nodes[child].parent = Some(index);
+			}
+		}
+		for (primitive, name) in self.primitives.iter().zip(self.get_subset_names()) {
+			nodes[primitive.node_index].subsets.push(name);
+		}
+		let roots = scene_roots(&self.document).into_iter().map(|node| node.index()).collect();
+		(nodes, roots)
+	}
+
+	/// Create the PBR material from the metallic-roughness material. The factors are kept in `others` as `albedo_factor`, `metalness_factor`, `roughness_factor`, `normal_scale`, `ao_strength` and `emissive_factor`.
+	/// The metalness and the roughness share the texture, the metalness is in the blue channel and the roughness is in the green channel.
+	fn load_material(&self, glcore: &Rc<GLCore>, material: &gltf::Material, textures: &mut HashMap<(usize, bool), Rc<dyn GenericTexture>>) -> Result<MaterialPbr, MeshLoadError> {
+		let pbr = material.pbr_metallic_roughness();
+		let mut ret = MaterialPbr::default();
+		let [r, g, b, a] = pbr.base_color_factor();
+		let albedo_factor = MaterialComponent::Color(Vec4::new(r, g, b, a));
+		ret.albedo = match pbr.base_color_texture() {
+			Some(info) => MaterialComponent::Texture(self.load_texture(glcore, info.texture(), true, textures)?),
+			None => albedo_factor.clone(),
+		};
+		ret.others.insert("albedo_factor".to_owned(), albedo_factor);
+
+		let metalness_factor = MaterialComponent::Luminance(pbr.metallic_factor());
+		let roughness_factor = MaterialComponent::Luminance(pbr.roughness_factor());
+		(ret.metalness, ret.roughness) = match pbr.metallic_roughness_texture() {
+			Some(info) => {
+				let texture = self.load_texture(glcore, info.texture(), false, textures)?;
+				(MaterialComponent::Texture(texture.clone()), MaterialComponent::Texture(texture))
+			}
+			None => (metalness_factor.clone(), roughness_factor.clone()),
+		};
+		ret.others.insert("metalness_factor".to_owned(), metalness_factor);
+		ret.others.insert("roughness_factor".to_owned(), roughness_factor);
+
+		ret.normal = match material.normal_texture() {
+			Some(normal) => {
+				ret.others.insert("normal_scale".to_owned(), MaterialComponent::Luminance(normal.scale()));
+				MaterialComponent::Texture(self.load_texture(glcore, normal.texture(), false, textures)?)
+			}
+			None => MaterialComponent::Color(Vec4::new(0.5, 0.5, 1.0, 1.0)),
+		};
+		ret.ao = match material.occlusion_texture() {
+			Some(occlusion) => {
+				ret.others.insert("ao_strength".to_owned(), MaterialComponent::Luminance(occlusion.strength()));
+				MaterialComponent::Texture(self.load_texture(glcore, occlusion.texture(), false, textures)?)
+			}
+			None => MaterialComponent::Luminance(1.0),
+		};
+
+		let [r, g, b] = material.emissive_factor();
+		let emissive_factor = MaterialComponent::Color(Vec4::new(r, g, b, 1.0));
+		ret.emissive = match material.emissive_texture() {
+			Some(info) => MaterialComponent::Texture(self.load_texture(glcore, info.texture(), true, textures)?),
+			None => emissive_factor.clone(),
+		};
+		ret.others.insert("emissive_factor".to_owned(), emissive_factor);
+		ret.displacement = MaterialComponent::Luminance(0.0);
+		Ok(ret)
+	}
+
+	/// Load the image of a texture with its sampler, the textures are shared by the index and whether they're sRGB
+	fn load_texture(&self, glcore: &Rc<GLCore>, texture: gltf::Texture, srgb: bool, textures: &mut HashMap<(usize, bool), Rc<dyn GenericTexture>>) -> Result<Rc<dyn GenericTexture>, MeshLoadError> {
+		if let Some(loaded) = textures.get(&(texture.index(), srgb)) {
+			return Ok(loaded.clone());
+		}
+		let (bytes, mime_type): (Cow<[u8]>, Option<&str>) = match texture.source().source() {
+			Source::View {view, mime_type} => (Cow::Borrowed(&self.buffers[view.buffer().index()][view.offset()..view.offset() + view.length()]), Some(mime_type)),
+			Source::Uri {uri, mime_type} => match uri.strip_prefix("data:") {
+				Some(data) => {
+					let Some(bytes) = data.split_once(";base64,").and_then(|(_, payload)| decode_base64(payload)) else {
+						return Err(MeshLoadError::UnsupportedFeature(self.name.clone(), format!("The data URI of the image {} isn't base64", texture.source().index())));
+					};
+					(Cow::Owned(bytes), mime_type.or_else(|| data.split_once(';').map(|(mime_type, _)| mime_type)))
+				}
+				None => (Cow::Owned(std::fs::read(self.base.as_deref().unwrap_or(Path::new("")).join(uri))?), mime_type),
+			}
+		};
+		let sampler = texture.sampler();
+		let wrapping = |mode| match mode {
+			WrappingMode::ClampToEdge => TextureWrapping::ClampToEdge,
+			WrappingMode::MirroredRepeat => TextureWrapping::MirroredRepeat,
+			WrappingMode::Repeat => TextureWrapping::Repeat,
+		};
+		let mag_filter = match sampler.mag_filter() {
+			Some(MagFilter::Nearest) => SamplerMagFilter::Nearest,
+			_ => SamplerMagFilter::Linear,
+		};
+		let min_filter = match sampler.min_filter() {
+			Some(MinFilter::Nearest) => SamplerFilter::Nearest,
+			Some(MinFilter::Linear) => SamplerFilter::Linear,
+			Some(MinFilter::NearestMipmapNearest) => SamplerFilter::NearestMipmapNearest,
+			Some(MinFilter::LinearMipmapNearest) => SamplerFilter::LinearMipmapNearest,
+			Some(MinFilter::NearestMipmapLinear) => SamplerFilter::NearestMipmapLinear,
+			Some(MinFilter::LinearMipmapLinear) | None => SamplerFilter::LinearMipmapLinear,
+		};
+		let has_mipmap = !matches!(min_filter, SamplerFilter::Nearest | SamplerFilter::Linear);
+		let hint = mime_type.and_then(ImageFormatHint::from_mime_type);
+		let loaded: Rc<dyn GenericTexture> = Rc::new(Texture::<BufferVecStatic<u8>, u8>::from_memory(glcore.clone(), &bytes, hint, TextureDimension::Tex2d, wrapping(sampler.wrap_s()), wrapping(sampler.wrap_t()), has_mipmap, mag_filter, min_filter, srgb)?);
+		textures.insert((texture.index(), srgb), loaded.clone());
+		Ok(loaded)
+	}
+}
+
+impl GltfSkin {
+	/// Get the bone matrices for the `joints` of the vertices, each is the global transform of the joint node times its inverse bind matrix.
+	/// The `global_transforms` are indexed by the node indices, e.g. from `GltfModel::get_global_transforms()` or your animated nodes.
+	pub fn get_bone_matrices(&self, global_transforms: &[Mat4]) -> Vec<Mat4> {
+		self.joints.iter().zip(self.inverse_bind_matrices.iter()).map(|(joint, inverse_bind_matrix)| global_transforms[*joint] * inverse_bind_matrix).collect()
+	}
+}
+
+impl From<GltfVertex> for SkinnedVertex {
+	fn from(vertex: GltfVertex) -> Self {
+		Self::new(vertex.position, vertex.normal, vertex.texcoord, vertex.joints, vertex.weights)
+	}
+}
+
+/// The root nodes of the default scene, or the first scene, or the nodes without parents if there's no scene
+fn scene_roots(document: &gltf::Document) -> Vec<gltf::Node> {
+	match document.default_scene().or_else(|| document.scenes().next()) {
+		Some(scene) => scene.nodes().collect(),
+		None => {
+			let children: HashSet<usize> = document.nodes().flat_map(|node| node.children().map(|child| child.index())).collect();
+			document.nodes().filter(|node| !children.contains(&node.index())).collect()
+		}
+	}
+}
+
+/// Check that each node has one parent at most and no node is its own ancestor, so the walks to the roots end
+fn check_node_hierarchy(document: &gltf::Document, file_name: &str) -> Result<(), MeshLoadError> {
+	let mut parents = vec![None; document.nodes().len()];
+	for node in document.nodes() {
+		for child in node.children() {
+			if let Some(parent) = parents[child.index()].replace(node.index()) {
+				return Err(MeshLoadError::InvalidModel(file_name.to_owned(), format!("The node {} is the child of both the node {parent} and the node {}", child.index(), node.index())));
+			}
+		}
+	}
+	for index in 0..parents.len() {
+		let mut ancestor = index;
+		for _ in 0..=parents.len() {
+			match parents[ancestor] {
+				Some(parent) if parent == index => return Err(MeshLoadError::InvalidModel(file_name.to_owned(), format!("The node {index} is its own ancestor"))),
+				Some(parent) => ancestor = parent,
+				None => break,
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Read the primitives of the node and its children, `parent` is the transform of the parent node, the transforms are baked if `bake` is `true`.
+/// The transform of a skinned node is ignored, its skin places it. The `visited` nodes are tracked to reject a node reached twice, e.g. by a cycle.
+#[allow(clippy::too_many_arguments)]
+fn collect_primitives(node: gltf::Node, parent: &Mat4, bake: bool, buffers: &[gltf::buffer::Data], skins: &[GltfSkin], file_name: &str, visited: &mut HashSet<usize>, primitives: &mut Vec<GltfPrimitive>) -> Result<(), MeshLoadError> {
+	if !visited.insert(node.index()) {
+		return Err(MeshLoadError::InvalidModel(file_name.to_owned(), format!("The node {} is reached twice, the nodes aren't a tree", node.index())));
+	}
+	let transform = parent * make_mat4(node.transform().matrix().as_flattened());
+	if let Some(mesh) = node.mesh() {
+		let node_name = node.name().map_or_else(|| format!("node{}", node.index()), |name| name.to_owned());
+		let mesh_name = mesh.name().map_or_else(|| format!("mesh{}", mesh.index()), |name| name.to_owned());
+		let skin = node.skin().map(|skin| (skin.index(), skins[skin.index()].joints.len()));
+		let mesh_transform = if skin.is_some() || !bake {Mat4::identity()} else {transform};
+		for primitive in mesh.primitives() {
+			let name = format!("{node_name}/{mesh_name}/{}", primitive.index());
+			primitives.push(read_primitive(&primitive, node.index(), mesh.index(), &mesh_transform, skin, buffers, file_name, name)?);
+		}
+	}
+	for child in node.children() {
+		collect_primitives(child, &transform, bake, buffers, skins, file_name, visited, primitives)?;
+	}
+	Ok(())
+}
+
+/// Read the vertices and the indices of a primitive, the positions, normals and tangents are transformed by `transform`.
+/// The triangle lists without normals get flat normals, the triangle lists transformed by a mirroring transform are rewound.
+/// The `skin` is the index of the skin and the number of its joints, the joints of the vertices are validated and the weights are normalized.
+#[allow(clippy::too_many_arguments)]
+fn read_primitive(primitive: &gltf::Primitive, node_index: usize, mesh_index: usize, transform: &Mat4, skin: Option<(usize, usize)>, buffers: &[gltf::buffer::Data], file_name: &str, name: String) -> Result<GltfPrimitive, MeshLoadError> {
+	if let Some((semantic, _)) = primitive.attributes().find(|(_, accessor)| accessor.sparse().is_some()) {
+		return Err(MeshLoadError::UnsupportedFeature(file_name.to_owned(), format!("The sparse accessor of `{semantic:?}` of `{name}`")));
+	}
+	let mode = match primitive.mode() {
+		gltf::mesh::Mode::Points => PrimitiveMode::Points,
+		gltf::mesh::Mode::Lines => PrimitiveMode::Lines,
+		gltf::mesh::Mode::LineLoop => PrimitiveMode::LineLoop,
+		gltf::mesh::Mode::LineStrip => PrimitiveMode::LineStrip,
+		gltf::mesh::Mode::Triangles => PrimitiveMode::Triangles,
+		gltf::mesh::Mode::TriangleStrip => PrimitiveMode::TriangleStrip,
+		gltf::mesh::Mode::TriangleFan => PrimitiveMode::TriangleFan,
+	};
+	let reader = primitive.reader(|buffer| Some(&*buffers[buffer.index()]));
+	let Some(positions) = reader.read_positions() else {
+		return Err(MeshLoadError::UnsupportedFeature(file_name.to_owned(), format!("`{name}` has no `POSITION`, e.g. compressed by Draco")));
+	};
+	let linear = mat4_to_mat3(transform);
+	let normal_matrix = inverse_transpose(linear);
+	let mirrored = linear.determinant() < 0.0;
+	let mut vertices: Vec<GltfVertex> = positions.map(|position| GltfVertex {
+		position: (transform * Vec4::new(position[0], position[1], position[2], 1.0)).xyz(),
+		..Default::default()
+	}).collect();
+	let has_normals = match reader.read_normals() {
+		Some(normals) => {
+			for (vertex, normal) in vertices.iter_mut().zip(normals) {
+				vertex.normal = (normal_matrix * Vec3::from(normal)).try_normalize(1e-12).unwrap_or_else(Vec3::y);
+			}
+			true
+		}
+		None => false,
+	};
+	if let Some(texcoords) = reader.read_tex_coords(0) {
+		for (vertex, texcoord) in vertices.iter_mut().zip(texcoords.into_f32()) {
+			vertex.texcoord = Vec2::from(texcoord);
+		}
+	}
+	if let Some(tangents) = reader.read_tangents() {
+		for (vertex, tangent) in vertices.iter_mut().zip(tangents) {
+			let direction = (linear * Vec3::new(tangent[0], tangent[1], tangent[2])).try_normalize(1e-12).unwrap_or_else(Vec3::x);
+			vertex.tangent = Vec4::new(direction.x, direction.y, direction.z, if mirrored {-tangent[3]} else {tangent[3]});
+		}
+	}
+	if let Some(joints) = reader.read_joints(0) {
+		for (vertex, joints) in vertices.iter_mut().zip(joints.into_u16()) {
+			vertex.joints = U16Vec4::from(joints);
+		}
+	}
+	if let Some(weights) = reader.read_weights(0) {
+		for (i, (vertex, weights)) in vertices.iter_mut().zip(weights.into_f32()).enumerate() {
+			let Some(weights) = normalize_skin_weights(&Vec4::from(weights)) else {
+				return Err(MeshLoadError::InvalidModel(file_name.to_owned(), format!("The weights {weights:?} of the vertex {i} of `{name}` are negative or all zero")));
+			};
+			vertex.weights = weights;
+		}
+	}
+	let num_joints = skin.map_or(usize::MAX, |(_, num_joints)| num_joints);
+	if let Some(i) = vertices.iter().position(|vertex| vertex.joints.iter().any(|joint| *joint as usize >= num_joints)) {
+		return Err(MeshLoadError::InvalidModel(file_name.to_owned(), format!("The joints {:?} of the vertex {i} of `{name}` are out of the {num_joints} joints of the skin", vertices[i].joints)));
+	}
+	let mut indices: Option<Vec<u32>> = reader.read_indices().map(|indices| indices.into_u32().collect());
+	if let Some(index) = indices.iter().flatten().find(|index| **index as usize >= vertices.len()) {
+		return Err(MeshLoadError::InvalidModel(file_name.to_owned(), format!("The index {index} of `{name}` is out of the {} vertices", vertices.len())));
+	}
+	if mode == PrimitiveMode::Triangles {
+		if mirrored {
+			let corners = indices.get_or_insert_with(|| (0..vertices.len() as u32).collect());
+			for triangle in corners.chunks_exact_mut(3) {
+				triangle.swap(1, 2);
+			}
+		}
+		if !has_normals {
+			let corners = indices.take().unwrap_or_else(|| (0..vertices.len() as u32).collect());
+			let mut flat = Vec::with_capacity(corners.len());
+			for triangle in corners.chunks_exact(3) {
+				let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize]);
+				let normal = (b.position - a.position).cross(&(c.position - a.position)).try_normalize(1e-12).unwrap_or_else(Vec3::y);
+				for mut vertex in [a, b, c] {
+					vertex.normal = normal;
+					flat.push(vertex);
+				}
+			}
+			vertices = flat;
+		}
+	}
+	Ok(GltfPrimitive {
+		name,
+		node_index,
+		mesh_index,
+		primitive_index: primitive.index(),
+		material: primitive.material().index(),
+		skin: skin.map(|(skin, _)| skin),
+		primitive: mode,
+		vertices,
+		indices,
+	})
+}
+
+/// Decode the base64 payload of a data URI, both of the standard and the URL-safe alphabets are accepted
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+	let mut ret = Vec::with_capacity(text.len() / 4 * 3);
+	let mut bits = 0u32;
+	let mut num_bits = 0;
+	for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+		let value = match c {
+			b'A'..=b'Z' => c - b'A',
+			b'a'..=b'z' => c - b'a' + 26,
+			b'0'..=b'9' => c - b'0' + 52,
+			b'+' | b'-' => 62,
+			b'/' | b'_' => 63,
+			b'=' => break,
+			_ => return None,
+		};
+		bits = (bits << 6) | value as u32;
+		num_bits += 6;
+		if num_bits >= 8 {
+			num_bits -= 8;
+			ret.push((bits >> num_bits) as u8);
+			bits &= (1 << num_bits) - 1;
+		}
+	}
+	Some(ret)
+}
+
+impl Default for GltfLoadOptions {
+	fn default() -> Self {
+		Self {
+			bake_transforms: true,
+		}
+	}
+}
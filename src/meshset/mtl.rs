@@ -0,0 +1,184 @@
+use crate::prelude::*;
+use std::{
+	collections::HashMap,
+	fmt::{self, Display, Formatter},
+	path::{Path, PathBuf},
+	rc::Rc,
+};
+
+/// A material of the MTL file, the missing colors are the defaults of the MTL format
+#[derive(Debug, Clone, PartialEq)]
+pub struct MtlMaterial {
+	/// The name of `newmtl`, referenced by `usemtl` of the OBJ file
+	pub name: String,
+
+	/// `Ka`
+	pub ambient: Vec3,
+
+	/// `Kd`
+	pub diffuse: Vec3,
+
+	/// `Ks`
+	pub specular: Vec3,
+
+	/// `Ke`
+	pub emissive: Vec3,
+
+	/// `Ns`
+	pub specular_power: f32,
+
+	/// `d`, or `1 - Tr`
+	pub dissolve: f32,
+
+	/// The file of `map_Kd`, relative to the MTL file
+	pub diffuse_map: Option<String>,
+
+	/// The file of `map_Ks`
+	pub specular_map: Option<String>,
+
+	/// The file of `map_bump`, `map_Bump`, `bump` or `norm`
+	pub normal_map: Option<String>,
+}
+
+/// The parsed MTL file, the options of the texture maps, e.g. `-bm`, are ignored
+#[derive(Debug, Clone, Default)]
+pub struct MtlLibrary {
+	pub materials: Vec<MtlMaterial>,
+}
+
+/// The non-fatal issues found while loading the materials of an OBJ file, the loading goes on without the missing files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjLoadWarning {
+	/// The MTL file (the first one) of `mtllib` doesn't exist
+	MissingMaterialLib(PathBuf),
+
+	/// The material (the first one) of `usemtl` isn't in any of the MTL files, the group (the second one) has no material
+	MissingMaterial(String, String),
+
+	/// The texture file (the second one) of the material (the first one) doesn't exist, the color value is used instead
+	MissingTexture(String, PathBuf),
+}
+
+impl Display for ObjLoadWarning {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::MissingMaterialLib(path) => write!(f, "The material library `{}` doesn't exist.", path.display()),
+			Self::MissingMaterial(material, group) => write!(f, "The material `{material}` of the group `{group}` isn't defined."),
+			Self::MissingTexture(material, path) => write!(f, "The texture `{}` of the material `{material}` doesn't exist, using the color instead.", path.display()),
+		}
+	}
+}
+
+impl MtlMaterial {
+	/// Create a material of the default values
+	pub fn new(name: &str) -> Self {
+		Self {
+			name: name.to_owned(),
+			ambient: Vec3::new(0.2, 0.2, 0.2),
+			diffuse: Vec3::new(0.8, 0.8, 0.8),
+			specular: Vec3::new(1.0, 1.0, 1.0),
+			emissive: Vec3::zeros(),
+			specular_power: 0.0,
+			dissolve: 1.0,
+			diffuse_map: None,
+			specular_map: None,
+			normal_map: None,
+		}
+	}
+
+	/// Create the `MaterialLegacy`, the maps are loaded from the files relative to `base` and shared by `textures`.
+	/// The diffuse map is sRGB, a missing texture file is replaced by the color and recorded in `warnings`.
+	pub fn to_material(&self, glcore: &Rc<GLCore>, base: &Path, textures: &mut TextureCache, warnings: &mut Vec<ObjLoadWarning>) -> Result<MaterialLegacy, MeshLoadError> {
+		let mut load = |file: &Option<String>, srgb: bool, color: MaterialComponent| -> Result<MaterialComponent, MeshLoadError> {
+			let Some(file) = file else {
+				return Ok(color);
+			};
+			let path = base.join(file);
+			if let Some(texture) = textures.get(&(path.clone(), srgb)) {
+				return Ok(MaterialComponent::Texture(texture.clone()));
+			}
+			if !path.is_file() {
+				warnings.push(ObjLoadWarning::MissingTexture(self.name.clone(), path));
+				return Ok(color);
+			}
+			let texture: Rc<dyn GenericTexture> = Rc::new(Texture::<BufferVecStatic<u8>, u8>::from_file(glcore.clone(), &path, TextureDimension::Tex2d, TextureWrapping::Repeat, TextureWrapping::Repeat, true, SamplerMagFilter::Linear, SamplerFilter::LinearMipmapLinear, srgb)?);
+			textures.insert((path, srgb), texture.clone());
+			Ok(MaterialComponent::Texture(texture))
+		};
+		let color = |rgb: &Vec3, alpha: f32| MaterialComponent::Color(Vec4::new(rgb.x, rgb.y, rgb.z, alpha));
+		Ok(MaterialLegacy {
+			ambient: color(&self.ambient, 1.0),
+			diffuse: load(&self.diffuse_map, true, color(&self.diffuse, self.dissolve))?,
+			specular: load(&self.specular_map, false, color(&self.specular, 1.0))?,
+			specular_power: MaterialComponent::Luminance(self.specular_power),
+			normal: load(&self.normal_map, false, MaterialComponent::Color(Vec4::new(0.5, 0.5, 1.0, 1.0)))?,
+			emissive: color(&self.emissive, 1.0),
+			others: HashMap::new(),
+		})
+	}
+}
+
+impl MtlLibrary {
+	/// Parse the text of an MTL file, the `name` is for the error messages
+	pub fn parse(text: &str, name: &str) -> Result<Self, MeshLoadError> {
+		let mut materials: Vec<MtlMaterial> = Vec::new();
+		for (line_index, line) in text.lines().enumerate() {
+			let error = |message: String| MeshLoadError::ObjParseError(name.to_owned(), line_index + 1, message);
+			let line = line.split('#').next().unwrap().trim();
+			let mut tokens = line.split_whitespace();
+			let Some(keyword) = tokens.next() else {
+				continue;
+			};
+			if keyword == "newmtl" {
+				materials.push(MtlMaterial::new(&tokens.collect::<Vec<_>>().join(" ")));
+				continue;
+			}
+			let Some(material) = materials.last_mut() else {
+				return Err(error(format!("`{keyword}` before `newmtl`")));
+			};
+			let mut parse_floats = |count: usize| -> Result<Vec<f32>, MeshLoadError> {
+				let values = tokens.by_ref().map(|token| token.parse::<f32>().map_err(|_| error(format!("Invalid number `{token}`")))).collect::<Result<Vec<f32>, _>>()?;
+				if values.len() < count {
+					return Err(error(format!("`{keyword}` needs {count} numbers")));
+				}
+				Ok(values)
+			};
+			let to_color = |v: Vec<f32>| if v.len() < 3 {Vec3::new(v[0], v[0], v[0])} else {Vec3::new(v[0], v[1], v[2])};
+			match keyword {
+				"Ka" => material.ambient = to_color(parse_floats(1)?),
+				"Kd" => material.diffuse = to_color(parse_floats(1)?),
+				"Ks" => material.specular = to_color(parse_floats(1)?),
+				"Ke" => material.emissive = to_color(parse_floats(1)?),
+				"Ns" => material.specular_power = parse_floats(1)?[0],
+				"d" => material.dissolve = parse_floats(1)?[0],
+				"Tr" => material.dissolve = 1.0 - parse_floats(1)?[0],
+				"map_Kd" | "map_Ks" | "map_bump" | "map_Bump" | "bump" | "norm" => {
+					let Some(file) = tokens.last() else {
+						return Err(error(format!("`{keyword}` needs a file name")));
+					};
+					let file = Some(file.to_owned());
+					match keyword {
+						"map_Kd" => material.diffuse_map = file,
+						"map_Ks" => material.specular_map = file,
+						_ => material.normal_map = file,
+					}
+				}
+				_ => {}
+			}
+		}
+		Ok(Self {
+			materials,
+		})
+	}
+
+	/// Load and parse an MTL file
+	pub fn from_file(path: &Path) -> Result<Self, MeshLoadError> {
+		let text = std::fs::read_to_string(path)?;
+		Self::parse(&text, &path.to_string_lossy())
+	}
+
+	/// Find a material by its name
+	pub fn get(&self, name: &str) -> Option<&MtlMaterial> {
+		self.materials.iter().find(|material| material.name == name)
+	}
+}
@@ -0,0 +1,316 @@
+use crate::prelude::*;
+use crate::mesh::shapes::ShapeVertex;
+use std::{
+	collections::{BTreeMap, HashMap},
+	path::Path,
+	rc::Rc,
+};
+
+/// How to generate the normals of the faces that have no `vn` indices
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjNormals {
+	/// Average the normals of the faces sharing the position, weighted by the area
+	#[default]
+	Smooth,
+
+	/// Use the normal of each face, the vertices are not shared between the faces
+	Flat,
+}
+
+/// The options for loading the OBJ files
+#[derive(Default, Debug, Clone)]
+pub struct ObjLoadOptions {
+	/// How to generate the missing normals
+	pub normals: ObjNormals,
+}
+
+/// The parsed OBJ file, the faces are triangulated and the vertices are deduplicated by the `v/vt/vn` indices
+#[derive(Debug, Clone)]
+pub struct ObjModel {
+	/// The groups of the faces, split by `o`, `g` and `usemtl`
+	pub groups: Vec<ObjGroup>,
+
+	/// The material libraries referenced by `mtllib`, relative to the OBJ file
+	pub material_libs: Vec<String>,
+}
+
+/// The faces of an object or a group of the OBJ file that use the same material
+#[derive(Debug, Clone)]
+pub struct ObjGroup {
+	/// The name of the object and the group as `object/group`, or the one of them that's given, or `default`
+	pub name: String,
+
+	/// The material name of `usemtl`
+	pub material: Option<String>,
+
+	/// The vertices in the layout of `ShapeVertex`, the missing texture coordinates are zero
+	pub vertices: Vec<ShapeVertex>,
+
+	/// The triangle list
+	pub indices: Vec<u32>,
+}
+
+/// A corner of a face, the indices into the positions, the texture coordinates and the normals
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct ObjCorner {
+	position: usize,
+	texcoord: Option<usize>,
+	normal: Option<usize>,
+}
+
+impl ObjModel {
+	/// Parse the text of an OBJ file, the `name` is for the error messages
+	pub fn parse(text: &str, name: &str, options: &ObjLoadOptions) -> Result<Self, MeshLoadError> {
+		let mut positions: Vec<Vec3> = Vec::new();
+		let mut texcoords: Vec<Vec2> = Vec::new();
+		let mut normals: Vec<Vec3> = Vec::new();
+		let mut material_libs = Vec::new();
+		let mut object: Option<String> = None;
+		let mut group: Option<String> = None;
+		let mut material: Option<String> = None;
+		let mut triangles: Vec<(String, Option<String>, Vec<[ObjCorner; 3]>)> = Vec::new();
+		let mut current: Option<usize> = None;
+		for (line_index, line) in text.lines().enumerate() {
+			let error = |message: String| MeshLoadError::ObjParseError(name.to_owned(), line_index + 1, message);
+			let line = line.split('#').next().unwrap().trim();
+			let mut tokens = line.split_whitespace();
+			let Some(keyword) = tokens.next() else {
+				continue;
+			};
+			let mut parse_floats = |count: usize| -> Result<Vec<f32>, MeshLoadError> {
+				let values = tokens.by_ref().map(|token| token.parse::<f32>().map_err(|_| error(format!("Invalid number `{token}`")))).collect::<Result<Vec<f32>, _>>()?;
+				if values.len() < count {
+					return Err(error(format!("`{keyword}` needs {count} numbers")));
+				}
+				Ok(values)
+			};
+			match keyword {
+				"v" => {
+					let v = parse_floats(3)?;
+					positions.push(Vec3::new(v[0], v[1], v[2]));
+				}
+				"vt" => {
+					let v = parse_floats(1)?;
+					texcoords.push(Vec2::new(v[0], v.get(1).copied().unwrap_or(0.0)));
+				}
+				"vn" => {
+					let v = parse_floats(3)?;
+					normals.push(Vec3::new(v[0], v[1], v[2]));
+				}
+				"o" => {
+					object = Some(tokens.collect::<Vec<_>>().join(" "));
+					group = None;
+					current = None;
+				}
+				"g" => {
+					group = Some(tokens.collect::<Vec<_>>().join(" "));
+					current = None;
+				}
+				"usemtl" => {
+					material = Some(tokens.collect::<Vec<_>>().join(" "));
+					current = None;
+				}
+				"mtllib" => material_libs.extend(tokens.map(|token| token.to_owned())),
+				"f" => {
+					let mut corners = Vec::with_capacity(4);
+					for token in tokens {
+						let mut indices = token.split('/');
+						let mut resolve = |len: usize, what: &str| -> Result<Option<usize>, MeshLoadError> {
+							match indices.next() {
+								None | Some("") => Ok(None),
+								Some(index) => {
+									let index: i64 = index.parse().map_err(|_| error(format!("Invalid index `{token}`")))?;
+									let resolved = if index < 0 {len as i64 + index} else {index - 1};
+									if resolved < 0 || resolved >= len as i64 {
+										return Err(error(format!("The {what} index {index} is out of range, {len} defined")));
+									}
+									Ok(Some(resolved as usize))
+								}
+							}
+						};
+						let Some(position) = resolve(positions.len(), "position")? else {
+							return Err(error(format!("The face corner `{token}` has no position")));
+						};
+						let texcoord = resolve(texcoords.len(), "texcoord")?;
+						let normal = resolve(normals.len(), "normal")?;
+						corners.push(ObjCorner {position, texcoord, normal});
+					}
+					if corners.len() < 3 {
+						return Err(error("The face has less than 3 corners".to_owned()));
+					}
+					let index = *current.get_or_insert_with(|| {
+						let group_name = match (&object, &group) {
+							(Some(object), Some(group)) => format!("{object}/{group}"),
+							(Some(name), None) | (None, Some(name)) => name.clone(),
+							(None, None) => "default".to_owned(),
+						};
+						triangles.push((group_name, material.clone(), Vec::new()));
+						triangles.len() - 1
+					});
+					for i in 1..corners.len() - 1 {
+						triangles[index].2.push([corners[0], corners[i], corners[i + 1]]);
+					}
+				}
+				_ => {}
+			}
+		}
+
+		// Merge the runs of the same group and material, e.g. a group that switches back to a previous material
+		let mut merged: Vec<(String, Option<String>, Vec<[ObjCorner; 3]>)> = Vec::with_capacity(triangles.len());
+		for (group_name, material, faces) in triangles {
+			if let Some(existing) = merged.iter_mut().find(|(n, m, _)| *n == group_name && *m == material) {
+				existing.2.extend(faces);
+			} else {
+				merged.push((group_name, material, faces));
+			}
+		}
+		let groups = merged.into_iter().map(|(name, material, faces)| {
+			let (vertices, indices) = build_vertices(&faces, &positions, &texcoords, &normals, options.normals);
+			ObjGroup {
+				name,
+				material,
+				vertices,
+				indices,
+			}
+		}).collect();
+		Ok(Self {
+			groups,
+			material_libs,
+		})
+	}
+
+	/// Load and parse an OBJ file
+	pub fn from_file(path: &Path, options: &ObjLoadOptions) -> Result<Self, MeshLoadError> {
+		let text = std::fs::read_to_string(path)?;
+		Self::parse(&text, &path.to_string_lossy(), options)
+	}
+
+	/// Upload the groups into a mesh set, `map` converts each of the vertices into your vertex type.
+	/// The subsets are named by the groups, a group that uses multiple materials has a subset for each material named `group/material`.
+	pub fn to_meshset_with<V: VertexType + 'static>(&self, glcore: Rc<GLCore>, map: impl Fn(ShapeVertex) -> V) -> Result<Meshset, MeshLoadError> {
+		self.to_meshset_with_materials(glcore, &HashMap::new(), map)
+	}
+
+	/// Upload the groups into a mesh set like `to_meshset_with()`, the mesh of each group is wrapped in a `MeshWithMaterial` of its material in `materials`, e.g. from `load_materials()`
+	pub fn to_meshset_with_materials<V: VertexType + 'static>(&self, glcore: Rc<GLCore>, materials: &HashMap<String, Rc<MaterialLegacy>>, map: impl Fn(ShapeVertex) -> V) -> Result<Meshset, MeshLoadError> {
+		let mut name_count: HashMap<&str, usize> = HashMap::new();
+		for group in self.groups.iter() {
+			*name_count.entry(group.name.as_str()).or_default() += 1;
+		}
+		let mut subsets: BTreeMap<String, Rc<dyn GenericMeshWithMaterial>> = BTreeMap::new();
+		for group in self.groups.iter() {
+			let name = match &group.material {
+				Some(material) if name_count[group.name.as_str()] > 1 => format!("{}/{material}", group.name),
+				_ => group.name.clone(),
+			};
+			let material = group.material.as_ref().and_then(|material| materials.get(material));
+			let mesh = match material {
+				Some(material) => group.to_mesh_with_material(glcore.clone(), material.clone(), &map)?,
+				None => group.to_mesh_with(glcore.clone(), &map)?,
+			};
+			subsets.insert(name, mesh);
+		}
+		Ok(Meshset {
+			subsets,
+			lod_groups: BTreeMap::new(),
+		})
+	}
+
+	/// Load the materials of the MTL files of `mtllib`, the files are relative to `base`, the directory of the OBJ file.
+	/// The missing MTL files, materials and texture files are recorded in `warnings`, the textures are shared between the materials.
+	pub fn load_materials(&self, glcore: &Rc<GLCore>, base: &Path, warnings: &mut Vec<ObjLoadWarning>) -> Result<HashMap<String, Rc<MaterialLegacy>>, MeshLoadError> {
+		let mut libraries = Vec::with_capacity(self.material_libs.len());
+		for file in self.material_libs.iter() {
+			let path = base.join(file);
+			if path.is_file() {
+				libraries.push(MtlLibrary::from_file(&path)?);
+			} else {
+				warnings.push(ObjLoadWarning::MissingMaterialLib(path));
+			}
+		}
+		let mut textures = HashMap::new();
+		let mut materials: HashMap<String, Rc<MaterialLegacy>> = HashMap::new();
+		for group in self.groups.iter() {
+			let Some(name) = &group.material else {
+				continue;
+			};
+			if materials.contains_key(name) {
+				continue;
+			}
+			match libraries.iter().find_map(|library| library.get(name)) {
+				Some(material) => {
+					materials.insert(name.clone(), Rc::new(material.to_material(glcore, base, &mut textures, warnings)?));
+				}
+				None => warnings.push(ObjLoadWarning::MissingMaterial(name.clone(), group.name.clone())),
+			}
+		}
+		Ok(materials)
+	}
+}
+
+impl ObjGroup {
+	/// Upload the group into a mesh, the elements are `u16` if the vertices are few enough, otherwise `u32`
+	pub fn to_mesh_with<V: VertexType + 'static>(&self, glcore: Rc<GLCore>, map: impl Fn(ShapeVertex) -> V) -> Result<Rc<dyn GenericMeshWithMaterial>, GLCoreError> {
+		let vertices: Vec<V> = self.vertices.iter().map(|vertex| map(*vertex)).collect();
+		if vertices.len() <= u16::MAX as usize + 1 {
+			let indices: Vec<u16> = self.indices.iter().map(|index| *index as u16).collect();
+			Ok(Rc::new(StaticMesh::<V, u16, UnusedType, UnusedType>::from_slices(glcore, PrimitiveMode::Triangles, &vertices, Some(&indices))?))
+		} else {
+			Ok(Rc::new(StaticMesh::<V, u32, UnusedType, UnusedType>::from_slices(glcore, PrimitiveMode::Triangles, &vertices, Some(&self.indices))?))
+		}
+	}
+
+	/// Upload the group into a mesh like `to_mesh_with()`, wrapped in a `MeshWithMaterial` of the material
+	pub fn to_mesh_with_material<V: VertexType + 'static>(&self, glcore: Rc<GLCore>, material: Rc<MaterialLegacy>, map: impl Fn(ShapeVertex) -> V) -> Result<Rc<dyn GenericMeshWithMaterial>, GLCoreError> {
+		let vertices: Vec<V> = self.vertices.iter().map(|vertex| map(*vertex)).collect();
+		if vertices.len() <= u16::MAX as usize + 1 {
+			let indices: Vec<u16> = self.indices.iter().map(|index| *index as u16).collect();
+			Ok(Rc::new(MeshWithMaterial::new(StaticMesh::<V, u16, UnusedType, UnusedType>::from_slices(glcore, PrimitiveMode::Triangles, &vertices, Some(&indices))?, material)))
+		} else {
+			Ok(Rc::new(MeshWithMaterial::new(StaticMesh::<V, u32, UnusedType, UnusedType>::from_slices(glcore, PrimitiveMode::Triangles, &vertices, Some(&self.indices))?, material)))
+		}
+	}
+}
+
+/// Build the vertices of the triangles, the corners with the same `v/vt/vn` indices share a vertex
+fn build_vertices(faces: &[[ObjCorner; 3]], positions: &[Vec3], texcoords: &[Vec2], normals: &[Vec3], generate: ObjNormals) -> (Vec<ShapeVertex>, Vec<u32>) {
+	let face_normals: Vec<Vec3> = faces.iter().map(|[a, b, c]| {
+		let (a, b, c) = (positions[a.position], positions[b.position], positions[c.position]);
+		(b - a).cross(&(c - a))
+	}).collect();
+	let mut smooth_normals: HashMap<usize, Vec3> = HashMap::new();
+	if generate == ObjNormals::Smooth {
+		for (face, face_normal) in faces.iter().zip(face_normals.iter()) {
+			for corner in face.iter().filter(|corner| corner.normal.is_none()) {
+				*smooth_normals.entry(corner.position).or_insert_with(Vec3::zeros) += face_normal;
+			}
+		}
+	}
+	let mut vertices = Vec::with_capacity(faces.len() * 3);
+	let mut indices = Vec::with_capacity(faces.len() * 3);
+	let mut shared: HashMap<ObjCorner, u32> = HashMap::new();
+	for (face, face_normal) in faces.iter().zip(face_normals.iter()) {
+		for corner in face.iter() {
+			let make_vertex = || {
+				let normal = match (corner.normal, generate) {
+					(Some(normal), _) => normals[normal],
+					(None, ObjNormals::Smooth) => smooth_normals[&corner.position],
+					(None, ObjNormals::Flat) => *face_normal,
+				};
+				let texcoord = corner.texcoord.map_or_else(Vec2::zeros, |texcoord| texcoords[texcoord]);
+				ShapeVertex::new(positions[corner.position], normal.try_normalize(1e-12).unwrap_or_else(Vec3::y), texcoord)
+			};
+			let index = if corner.normal.is_none() && generate == ObjNormals::Flat {
+				vertices.push(make_vertex());
+				vertices.len() as u32 - 1
+			} else {
+				*shared.entry(*corner).or_insert_with(|| {
+					vertices.push(make_vertex());
+					vertices.len() as u32 - 1
+				})
+			};
+			indices.push(index);
+		}
+	}
+	(vertices, indices)
+}
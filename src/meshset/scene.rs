@@ -0,0 +1,186 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+#[cfg(feature = "gltf")]
+use std::{
+	path::Path,
+	rc::Rc,
+};
+
+/// The local transform of a scene node, kept as the translation, the rotation and the scale so that they could be animated separately
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeTransform {
+	pub translation: Vec3,
+	pub rotation: Quat,
+	pub scale: Vec3,
+}
+
+/// The projection of a camera node, looking at -Z of the node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SceneCamera {
+	/// The vertical field of view in radians, the aspect ratio of the viewport is used if `aspect_ratio` is `None`, the far plane is at the infinity if `zfar` is `None`
+	Perspective {
+		yfov: f32,
+		aspect_ratio: Option<f32>,
+		znear: f32,
+		zfar: Option<f32>,
+	},
+
+	/// The half width and the half height of the view volume
+	Orthographic {
+		xmag: f32,
+		ymag: f32,
+		znear: f32,
+		zfar: f32,
+	},
+}
+
+/// The type of a light node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SceneLightKind {
+	/// Lights along -Z of the node
+	Directional,
+
+	/// Lights from the position of the node
+	Point,
+
+	/// Lights from the position of the node along -Z in the cone, the angles are in radians
+	Spot {
+		inner_cone_angle: f32,
+		outer_cone_angle: f32,
+	},
+}
+
+/// A light node, the loaders don't read the lights yet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneLight {
+	pub kind: SceneLightKind,
+	pub color: Vec3,
+	pub intensity: f32,
+
+	/// The distance where the light reaches zero, `None` for the infinity
+	pub range: Option<f32>,
+}
+
+/// A node of the scene graph
+#[derive(Debug, Clone)]
+pub struct SceneNode {
+	pub name: String,
+
+	/// The index of the parent node, `None` for the root nodes
+	pub parent: Option<usize>,
+
+	/// The indices of the child nodes
+	pub children: Vec<usize>,
+
+	/// The transform relative to the parent node
+	pub transform: NodeTransform,
+
+	/// The names of the subsets of `Scene::meshset` drawn at this node
+	pub subsets: Vec<String>,
+
+	/// The index of the skin of the mesh, the transforms of the skinned nodes are ignored, their skins place them
+	pub skin: Option<usize>,
+
+	pub camera: Option<SceneCamera>,
+	pub light: Option<SceneLight>,
+}
+
+/// The mesh set with the node hierarchy, the subsets are in the spaces of their nodes. Use `flatten_world_transforms()` to get the model matrices for `Pipelineset::draw_with()`.
+/// The node transforms could be changed, e.g. by the animations, then get the world transforms again.
+#[derive(Debug, Clone)]
+pub struct Scene {
+	pub meshset: Meshset,
+
+	/// All of the nodes, a node refers to the others by their indices
+	pub nodes: Vec<SceneNode>,
+
+	/// The indices of the root nodes of the scene
+	pub roots: Vec<usize>,
+}
+
+impl NodeTransform {
+	/// Create the transform
+	pub fn new(translation: Vec3, rotation: Quat, scale: Vec3) -> Self {
+		Self {
+			translation,
+			rotation,
+			scale,
+		}
+	}
+
+	/// Get the matrix of the transform, the scale is applied first, then the rotation and the translation
+	pub fn to_matrix(&self) -> Mat4 {
+		translation(&self.translation) * quat_to_mat4(&self.rotation) * scaling(&self.scale)
+	}
+}
+
+impl Default for NodeTransform {
+	fn default() -> Self {
+		Self::new(Vec3::zeros(), quat_identity(), Vec3::new(1.0, 1.0, 1.0))
+	}
+}
+
+impl SceneCamera {
+	/// Get the projection matrix, `aspect_ratio` of the viewport is used if the camera doesn't have it
+	pub fn get_projection(&self, aspect_ratio: f32) -> Mat4 {
+		match *self {
+			Self::Perspective {yfov, aspect_ratio: camera_aspect_ratio, znear, zfar} => {
+				let aspect_ratio = camera_aspect_ratio.unwrap_or(aspect_ratio);
+				match zfar {
+					Some(zfar) => perspective(aspect_ratio, yfov, znear, zfar),
+					None => infinite_perspective_rh_no(aspect_ratio, yfov, znear),
+				}
+			}
+			Self::Orthographic {xmag, ymag, znear, zfar} => ortho(-xmag, xmag, -ymag, ymag, znear, zfar),
+		}
+	}
+}
+
+impl Scene {
+	/// Load a glTF or GLB file with the node hierarchy, the vertices are `GltfVertex` in the spaces of their nodes, see `GltfModel::get_scene_nodes()` for the nodes
+	#[cfg(feature = "gltf")]
+	pub fn from_gltf_file(glcore: Rc<GLCore>, path: &Path) -> Result<Self, MeshLoadError> {
+		GltfModel::from_file_with_options(path, &GltfLoadOptions {bake_transforms: false})?.to_scene(glcore)
+	}
+
+	/// Find a node by its name
+	pub fn find_node(&self, name: &str) -> Option<usize> {
+		self.nodes.iter().position(|node| node.name == name)
+	}
+
+	/// Get the world transforms of all of the nodes, indexed by the node indices.
+	/// The walk to the roots gives up after `nodes.len()` steps, a cycle of the parents is cut there as if the node were a root.
+	pub fn get_world_transforms(&self) -> Vec<Mat4> {
+		let mut world: Vec<Option<Mat4>> = vec![None; self.nodes.len()];
+		for index in 0..self.nodes.len() {
+			let mut chain = vec![index];
+			while let Some(parent) = self.nodes[*chain.last().unwrap()].parent {
+				if world[parent].is_some() || chain.len() > self.nodes.len() {
+					break;
+				}
+				chain.push(parent);
+			}
+			for node in chain.into_iter().rev() {
+				if world[node].is_none() {
+					let parent = self.nodes[node].parent.and_then(|parent| world[parent]).unwrap_or_else(Mat4::identity);
+					world[node] = Some(parent * self.nodes[node].transform.to_matrix());
+				}
+			}
+		}
+		world.into_iter().map(Option::unwrap).collect()
+	}
+
+	/// Get the model matrices of the subsets by their names, e.g. to set the uniform in the callback of `Pipelineset::draw_with()`.
+	/// The subsets of the skinned nodes get the identity matrix, their skins place them.
+	pub fn flatten_world_transforms(&self) -> HashMap<String, Mat4> {
+		let world = self.get_world_transforms();
+		let mut ret = HashMap::new();
+		for (node, transform) in self.nodes.iter().zip(world) {
+			let transform = if node.skin.is_some() {Mat4::identity()} else {transform};
+			for subset in node.subsets.iter() {
+				ret.insert(subset.clone(), transform);
+			}
+		}
+		ret
+	}
+}
@@ -2,11 +2,13 @@
 #![allow(clippy::suspicious_else_formatting)]
 
 use crate::prelude::*;
+use crate::mesh::shapes::ShapeVertex;
 use std::{
 	any::Any,
+	cell::{Cell, RefCell},
 	collections::BTreeMap,
 	ffi::c_void,
-	fmt::{self, Debug, Formatter},
+	fmt::{self, Debug, Display, Formatter},
 	marker::PhantomData,
 	mem::{size_of, align_of},
 	ptr::null,
@@ -14,15 +16,119 @@ use std::{
 };
 
 /// The trait that the struct of vertices or instances must implement
-pub trait VertexType: Copy + Clone + Sized + Default + Debug + Iterable {}
-impl<T> VertexType for T where T: Copy + Clone + Sized + Default + Debug + Iterable {}
+pub trait VertexType: Copy + Clone + Sized + Default + Debug + VertexFields {}
+impl<T> VertexType for T where T: Copy + Clone + Sized + Default + Debug + VertexFields {}
+
+/// The trait implemented by `derive_vertex_type!` to describe the members of the struct
+pub trait VertexFields {
+	/// Get the descriptions of the members in the declaration order
+	fn fields() -> &'static [FieldDesc];
+}
+
+/// The type of the members of the vertex structs, `TYPE_NAME` is the name like the GLSL type, e.g. `vec3`
+pub trait VertexMemberType {
+	const TYPE_NAME: &'static str;
+}
+
+/// The description of a member of the vertex struct, generated by `derive_vertex_type!`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldDesc {
+	/// The name of the member
+	pub name: &'static str,
+
+	/// The type name of the member, see `VertexMemberType`
+	pub type_name: &'static str,
+
+	/// The offset in bytes of the member in the struct
+	pub offset: usize,
+
+	/// The name of the shader attrib, it's the name of the member unless `#[attrib(name = "aPos")]` is given
+	pub attrib_name: &'static str,
+
+	/// The location of the shader attrib from `#[attrib(location = 3)]`, if it's `None` the attrib is found by `attrib_name`
+	pub location: Option<u32>,
+
+	/// The divisor from `#[attrib(divisor = 1)]`, if it's `None` the divisor is 0 for the vertex buffer and 1 for the instance buffer
+	pub divisor: Option<u32>,
+
+	/// Normalize the integers into `[0, 1]` or `[-1, 1]` for the `float` attribs, set by `#[attrib(normalized)]`
+	pub normalized: bool,
+}
 
 /// Use this macro to convert your struct into `VertexType`
+///
+/// The offsets of the members are taken by `offset_of!()`, so the padding inserted by the compiler is respected.
+/// The members are matched to the shader attribs by their names, the `#[attrib(...)]` of a member overrides it:
+/// * `#[attrib(name = "aPos")]` matches the attrib of the name `aPos`
+/// * `#[attrib(location = 3)]` matches the attrib at `layout(location = 3)`
+/// * `#[attrib(divisor = 1)]` sets the divisor of the attrib
+/// * `#[attrib(normalized)]` normalizes the integers for the `float` attrib, e.g. a `U8Vec4` color read as `vec4` in `[0, 1]`
+///
+/// The other attributes of the members, e.g. the doc comments and `#[allow(...)]`, are kept on the members.
+///
+/// ```ignore
+/// derive_vertex_type! {
+/// 	pub struct MyVertex {
+/// 		#[attrib(name = "aPos")]
+/// 		pub position: Vec3,
+/// 		#[attrib(location = 3, divisor = 1, normalized)]
+/// 		pub color: U8Vec4,
+/// 	}
+/// }
+/// ```
 #[macro_export]
 macro_rules! derive_vertex_type {
-	($item: item) => {
+	(
+		$(#[$meta: meta])*
+		$vis: vis struct $name: ident {
+			$(
+				$(#[$($field_attr: tt)*])*
+				$field_vis: vis $field: ident: $type: ty
+			),* $(,)?
+		}
+	) => {
+		$crate::derive_vertex_type! {
+			@struct [$(#[$meta])*] [$vis struct $name] []
+			$({[$(#[$($field_attr)*])*] [$field_vis $field: $type]})*
+		}
+
+		impl $crate::pipeline::VertexFields for $name {
+			fn fields() -> &'static [$crate::pipeline::FieldDesc] {
+				const FIELDS: &[$crate::pipeline::FieldDesc] = &[$(
+					$crate::derive_vertex_type!(@desc [$crate::pipeline::FieldDesc::new(stringify!($field), <$type as $crate::pipeline::VertexMemberType>::TYPE_NAME, ::std::mem::offset_of!($name, $field))] $(#[$($field_attr)*])*)
+				),*];
+				FIELDS
+			}
+		}
+	};
+
+	// Emit the struct when all of the members are done, the `#[attrib(...)]` of the members are dropped and the other attributes are kept
+	(@struct [$($meta: tt)*] [$($head: tt)*] [$($fields: tt)*]) => {
 		#[derive(Iterable, Default, Debug, Clone, Copy)]
-		$item
+		$($meta)*
+		$($head)* {
+			$($fields)*
+		}
+	};
+	(@struct $meta: tt $head: tt [$($fields: tt)*] {[] [$($field: tt)*]} $($rest: tt)*) => {
+		$crate::derive_vertex_type! {@struct $meta $head [$($fields)* $($field)*,] $($rest)*}
+	};
+	(@struct $meta: tt $head: tt $fields: tt {[#[attrib $($args: tt)*] $($attrs: tt)*] $field: tt} $($rest: tt)*) => {
+		$crate::derive_vertex_type! {@struct $meta $head $fields {[$($attrs)*] $field} $($rest)*}
+	};
+	(@struct $meta: tt $head: tt [$($fields: tt)*] {[#[$($attr: tt)*] $($attrs: tt)*] $field: tt} $($rest: tt)*) => {
+		$crate::derive_vertex_type! {@struct $meta $head [$($fields)* #[$($attr)*]] {[$($attrs)*] $field} $($rest)*}
+	};
+
+	// Chain the setters of the `#[attrib(...)]` of a member to its `FieldDesc`, the other attributes are skipped
+	(@desc [$($desc: tt)*]) => {
+		$($desc)*
+	};
+	(@desc [$($desc: tt)*] #[attrib($($key: ident $(= $value: expr)?),* $(,)?)] $($rest: tt)*) => {
+		$crate::derive_vertex_type!(@desc [$($desc)* $(.$key($($value)?))*] $($rest)*)
+	};
+	(@desc [$($desc: tt)*] #[$($attr: tt)*] $($rest: tt)*) => {
+		$crate::derive_vertex_type!(@desc [$($desc)*] $($rest)*)
 	};
 }
 
@@ -34,10 +140,91 @@ pub struct Pipeline<V: VertexType, I: VertexType> {
 	pub shader: Rc<Shader>,
 	vertex_stride: usize,
 	instance_stride: usize,
+	vertex_stream: StreamConfig,
+	instance_stream: StreamConfig,
+
+	/// The names of the buffers described to the VAO, the VAO is described again if the mesh has other buffers, e.g. reallocated by resizing
+	buffer_names: RefCell<Vec<u32>>,
+
+	/// Use the separated vertex format of OpenGL 4.3, the formats are described once and the buffers are bound to the binding points at `bind()`
+	separated: bool,
+
+	/// The binding points of the separated vertex format, the binding index is the index in it
+	bindings: RefCell<Vec<VertexBinding>>,
+
+	/// The non-fatal issues found the last time the VAO was described
+	warnings: RefCell<Vec<PipelineWarning>>,
+
+	/// The fixed-function states applied before drawing and restored after
+	render_state: Cell<Option<RenderState>>,
+
+	/// The viewports applied before drawing and restored after
+	viewport_array: RefCell<Option<ViewportArray>>,
+
+	/// The label of the VAO and the debug group of the draw calls
+	label: RefCell<Option<String>>,
 	_phantom_vertex_type: PhantomData<V>,
 	_phantom_instance_type: PhantomData<I>,
 }
 
+/// The pipeline without its vertex types, for the lists of the pipelines of the different vertex types, e.g. the static meshes, the skinned meshes and the particles in a `Pipelineset`.
+/// The draw calls through it are dispatched dynamically and `setup` is called through `&mut dyn FnMut`, the cost is a few indirect calls per draw call.
+pub trait GenericPipeline: Debug {
+	/// Get the OpenGL core
+	fn get_glcore(&self) -> &Rc<GLCore>;
+
+	/// Get the shader
+	fn get_shader(&self) -> &Rc<Shader>;
+
+	/// Get the mesh
+	fn get_mesh(&self) -> &Rc<dyn GenericMeshWithMaterial>;
+
+	/// Get the fixed-function states of drawing, see `Pipeline::get_render_state()`
+	fn get_render_state(&self) -> Option<RenderState>;
+
+	/// Set the fixed-function states of drawing, see `Pipeline::set_render_state()`
+	fn set_render_state(&self, render_state: Option<RenderState>);
+
+	/// Bind the pipeline and draw like `PipelineBind::draw_with()`, the `RenderState` isn't applied if `apply_render_state` is `false`, the caller manages the states
+	fn draw_dyn(&self, fbo: Option<&Framebuffer>, apply_render_state: bool, setup: &mut dyn FnMut(&ShaderUse) -> Result<(), ShaderError>) -> Result<DrawStats, PipelineError>;
+
+	/// Get the bounding box of the mesh in the model space, see `Pipeline::get_bounds()`
+	fn get_bounds(&self) -> Result<Option<Aabb>, PipelineError>;
+
+	/// Bind the pipeline and draw like `PipelineBind::draw()`
+	fn draw(&self, fbo: Option<&Framebuffer>) -> Result<DrawStats, PipelineError> {
+		self.draw_dyn(fbo, true, &mut |_| Ok(()))
+	}
+}
+
+/// The configuration of a buffer of the pipeline, e.g. the vertex buffer or the instance buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamConfig {
+	/// The divisor of the members without `#[attrib(divisor = ...)]`
+	pub default_divisor: u32,
+}
+
+/// A binding point of the separated vertex format, the members with the same source and divisor share a binding point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VertexBinding {
+	/// 0 for the vertex buffer, 1 for the instance buffer, 2 and on for the vertex streams of the mesh
+	source: usize,
+
+	/// The divisor of the binding point
+	divisor: u32,
+}
+
+/// A row of a member of the vertex struct matched to a shader attrib, a member of a matrix type has a row for each of its columns
+struct AttribRow<'b> {
+	attrib_name: &'static str,
+	location: u32,
+	attrib_type: &'b ShaderInputVarType,
+	datainfo: DataGlType,
+	normalize: u8,
+	offset: usize,
+	divisor: u32,
+}
+
 /// The data type described in the OpenGL way.
 #[derive(Debug, Clone, Copy)]
 struct DataGlType {
@@ -52,6 +239,77 @@ pub enum PipelineError {
 	ShaderError(ShaderError),
 	FramebufferError(FramebufferError),
 	GLCoreError(GLCoreError),
+	ViewportError(ViewportError),
+
+	/// The range of `first` (the first one) and `count` (the second one) exceeds the number of the elements, or the vertices if there's no element buffer (the third one)
+	InvalidDrawRange(i64, usize, usize),
+
+	/// The number of vertices per patch (the first one) is zero or exceeds `GL_MAX_PATCH_VERTICES` (the second one)
+	InvalidPatchVertices(u32, u32),
+
+	/// The number of the instances to draw (the first one) exceeds the number of the instances in the instance buffer (the second one)
+	InvalidInstanceCount(usize, usize),
+
+	/// The base instance (the first one) isn't zero, but the context is older than OpenGL 4.2
+	BaseInstanceNotSupported(u32),
+
+	/// The separated vertex format is requested, but the context is older than OpenGL 4.3
+	SeparatedFormatNotSupported,
+
+	/// The size and rows of the shader attrib (the second one) differ from the member (the first one) of the vertex struct (the third one)
+	AttribSizeMismatch(String, (u32, u32), (u32, u32)),
+
+	/// The shader attrib (the first one, `type name`) isn't of the float, integer or double types
+	UnsupportedAttribType(String),
+
+	/// The maximum number of the draw commands (the first one) exceeds the number of the commands in the command buffer (the second one), 0 if there's no command buffer
+	InvalidDrawCount(usize, usize),
+
+	/// The draw count from a buffer needs OpenGL 4.6 or `GL_ARB_indirect_parameters`
+	IndirectCountNotSupported,
+
+	/// The member (the first one) doesn't fit at its offset (the third one) in the struct, the second one is the end of the members before it, or the last offset where it fits in the struct.
+	/// The offsets generated by `derive_vertex_type!` always fit, the ones of a hand-written `VertexFields` may not.
+	VertexLayoutPadding(String, usize, usize),
+
+	/// The type name (the second one) of the member (the first one) isn't a supported type of the vertex structs, e.g. of a hand-written `VertexFields`
+	UnsupportedMemberType(String, String),
+
+	/// The names (the first one) are neither the subsets nor the level of detail groups of the `Pipelineset`
+	SubsetsNotFound(Vec<String>),
+
+	/// The shader index (the first one) isn't less than the number of the shaders of the `Pipelineset` (the second one)
+	InvalidShaderIndex(usize, usize),
+
+	/// No shader is given to create the pipelines of a `Pipelineset`
+	NoShaders,
+}
+
+/// The non-fatal issues found while matching the members of the vertex structs to the shader attribs, see `Pipeline::warnings()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineWarning {
+	/// The attrib of the member (the first one, `type name`) isn't active in the shader, the member is skipped
+	AttribNotActive(String),
+
+	/// The attrib at the location (the second one) of the member (the first one, `type name`) isn't active in the shader, the member is skipped
+	AttribLocationNotActive(String, u32),
+
+	/// The attrib of the member (the first one, `type name`) is active, but can't get its location, the member is skipped
+	AttribNotLocatable(String),
+
+	/// The member (the first one, `type name`) is normalized by its name, use `#[attrib(normalized)]` instead
+	NormalizedByName(String),
+}
+
+impl Display for PipelineWarning {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::AttribNotActive(attrib) => write!(f, "Attrib `{attrib}` is not active."),
+			Self::AttribLocationNotActive(attrib, location) => write!(f, "Attrib `{attrib}` at location {location} is not active."),
+			Self::AttribNotLocatable(attrib) => write!(f, "Attrib `{attrib}` is active, but can't get its location."),
+			Self::NormalizedByName(attrib) => write!(f, "Normalizing the attrib `{attrib}` by its name is deprecated, use `#[attrib(normalized)]` instead."),
+		}
+	}
 }
 
 impl From<ShaderError> for PipelineError {
@@ -66,18 +324,183 @@ impl From<FramebufferError> for PipelineError {
 	}
 }
 
+impl From<ViewportError> for PipelineError {
+	fn from(val: ViewportError) -> Self {
+		Self::ViewportError(val)
+	}
+}
+
 impl From<GLCoreError> for PipelineError {
 	fn from(val: GLCoreError) -> Self {
 		Self::GLCoreError(val)
 	}
 }
 
+/// The layout of a `VertexType` struct, used to describe the vertex struct to a VAO without knowing its type, e.g. for the streams of `MultiStreamMesh`
+#[derive(Debug, Clone, Copy)]
+pub struct VertexLayout {
+	/// The size of the struct
+	pub stride: usize,
+
+	/// The alignment of the struct
+	pub alignment: usize,
+
+	/// The members of the struct
+	pub fields: &'static [FieldDesc],
+}
+
+impl VertexLayout {
+	/// Get the layout of a vertex struct
+	pub fn of<T: VertexType>() -> Self {
+		Self {
+			stride: size_of::<T>(),
+			alignment: align_of::<T>(),
+			fields: T::fields(),
+		}
+	}
+}
+
+impl StreamConfig {
+	/// The default configuration of the vertex buffer, the divisor is 0
+	pub const VERTEX: Self = Self {default_divisor: 0};
+
+	/// The default configuration of the instance buffer, the divisor is 1
+	pub const INSTANCE: Self = Self {default_divisor: 1};
+
+	/// Create the configuration with the divisor of the members
+	pub fn new(default_divisor: u32) -> Self {
+		Self {
+			default_divisor,
+		}
+	}
+}
+
+impl FieldDesc {
+	/// Create the description of a member matched by its name
+	pub const fn new(name: &'static str, type_name: &'static str, offset: usize) -> Self {
+		Self {
+			name,
+			type_name,
+			offset,
+			attrib_name: name,
+			location: None,
+			divisor: None,
+			normalized: false,
+		}
+	}
+
+	/// Set the name of the shader attrib, for `#[attrib(name = "aPos")]`
+	pub const fn name(self, attrib_name: &'static str) -> Self {
+		Self {attrib_name, ..self}
+	}
+
+	/// Set the location of the shader attrib, for `#[attrib(location = 3)]`
+	pub const fn location(self, location: u32) -> Self {
+		Self {location: Some(location), ..self}
+	}
+
+	/// Set the divisor, for `#[attrib(divisor = 1)]`
+	pub const fn divisor(self, divisor: u32) -> Self {
+		Self {divisor: Some(divisor), ..self}
+	}
+
+	/// Normalize the integers, for `#[attrib(normalized)]`
+	pub const fn normalized(self) -> Self {
+		Self {normalized: true, ..self}
+	}
+}
+
+macro_rules! define_f16_vec {
+	($($(#[$meta: meta])* $name: ident: $vec: ty {$($comp: ident),+}),* $(,)?) => {
+		$(
+			$(#[$meta])*
+			#[repr(C)]
+			#[derive(Default, Debug, Clone, Copy, PartialEq)]
+			pub struct $name {
+				$(pub $comp: f16,)+
+			}
+
+			impl $name {
+				/// Create the vector from the half floats
+				pub const fn new($($comp: f16),+) -> Self {
+					Self {$($comp),+}
+				}
+			}
+
+			impl From<$vec> for $name {
+				/// Round the components to the nearest half floats
+				fn from(v: $vec) -> Self {
+					Self {$($comp: f16::from_f32(v.$comp)),+}
+				}
+			}
+
+			impl From<$name> for $vec {
+				fn from(v: $name) -> Self {
+					Self::new($(v.$comp.to_f32()),+)
+				}
+			}
+		)*
+	};
+}
+
+define_f16_vec! {
+	/// The 2-component half float vector, passed as `GL_HALF_FLOAT` to a `vec2` attrib, e.g. the texture coordinates in half of the space
+	F16Vec2: Vec2 {x, y},
+
+	/// The 3-component half float vector, passed as `GL_HALF_FLOAT` to a `vec3` attrib
+	F16Vec3: Vec3 {x, y, z},
+
+	/// The 4-component half float vector, passed as `GL_HALF_FLOAT` to a `vec4` attrib
+	F16Vec4: Vec4 {x, y, z, w},
+}
+
+macro_rules! impl_vertex_member_type {
+	($($type: ty => $name: literal),* $(,)?) => {
+		$(impl VertexMemberType for $type {
+			const TYPE_NAME: &'static str = $name;
+		})*
+	};
+}
+
+impl_vertex_member_type! {
+	u8 => "u8", u16 => "u16", u32 => "u32",
+	i8 => "i8", i16 => "i16", i32 => "i32",
+	f16 => "f16", f32 => "f32", f64 => "f64",
+	F16Vec2 => "f16vec2", F16Vec3 => "f16vec3", F16Vec4 => "f16vec4",
+	Vec1 => "vec1", Vec2 => "vec2", Vec3 => "vec3", Vec4 => "vec4",
+	DVec1 => "dvec1", DVec2 => "dvec2", DVec3 => "dvec3", DVec4 => "dvec4",
+	BVec1 => "bvec1", BVec2 => "bvec2", BVec3 => "bvec3", BVec4 => "bvec4",
+	IVec1 => "ivec1", IVec2 => "ivec2", IVec3 => "ivec3", IVec4 => "ivec4",
+	I8Vec1 => "i8vec1", I8Vec2 => "i8vec2", I8Vec3 => "i8vec3", I8Vec4 => "i8vec4",
+	I16Vec1 => "i16vec1", I16Vec2 => "i16vec2", I16Vec3 => "i16vec3", I16Vec4 => "i16vec4",
+	UVec1 => "uvec1", UVec2 => "uvec2", UVec3 => "uvec3", UVec4 => "uvec4",
+	U8Vec1 => "u8vec1", U8Vec2 => "u8vec2", U8Vec3 => "u8vec3", U8Vec4 => "u8vec4",
+	U16Vec1 => "u16vec1", U16Vec2 => "u16vec2", U16Vec3 => "u16vec3", U16Vec4 => "u16vec4",
+	Quat => "quat", DQuat => "dquat",
+	Mat2 => "mat2", Mat3 => "mat3", Mat4 => "mat4",
+	Mat2x3 => "mat2x3", Mat2x4 => "mat2x4", Mat3x2 => "mat3x2", Mat3x4 => "mat3x4", Mat4x2 => "mat4x2", Mat4x3 => "mat4x3",
+	DMat2 => "dmat2", DMat3 => "dmat3", DMat4 => "dmat4",
+	DMat2x3 => "dmat2x3", DMat2x4 => "dmat2x4", DMat3x2 => "dmat3x2", DMat3x4 => "dmat3x4", DMat4x2 => "dmat4x2", DMat4x3 => "dmat4x3",
+}
+
 /// The binding state of the pipeline
 #[derive(Debug)]
 pub struct PipelineBind<'a, V: VertexType, I: VertexType> {
 	pub pipeline: &'a Pipeline<V, I>,
+
+	/// Apply the `RenderState` of the pipeline around the draw calls, the `Pipelineset` applies them by itself
+	apply_render_state: bool,
+}
+
+thread_local! {
+	/// The program and the material set up by the last draw call of the current `MaterialBatch`, the outer `None` means there's no batch
+	static MATERIAL_BATCH: Cell<Option<Option<(u32, usize)>>> = const {Cell::new(None)};
 }
 
+/// While it's alive, a draw call skips setting up the uniforms of the material if the previous draw call set up the same material for the same program, used by the state-sorted `Pipelineset`
+#[derive(Debug)]
+pub(crate) struct MaterialBatch;
+
 impl DataGlType {
 	/// Does this data type use integers as the basic component type
 	fn is_integer(&self) -> bool {
@@ -107,17 +530,137 @@ impl<V: VertexType, I: VertexType> Pipeline<V, I> {
 		self.name
 	}
 
+	/// Get the configuration of the vertex buffer
+	pub fn get_vertex_stream(&self) -> StreamConfig {
+		self.vertex_stream
+	}
+
+	/// Get the configuration of the instance buffer
+	pub fn get_instance_stream(&self) -> StreamConfig {
+		self.instance_stream
+	}
+
 	/// Create a new pipeline
 	pub fn new(glcore: Rc<GLCore>, mesh: Rc<dyn GenericMeshWithMaterial>, shader: Rc<Shader>) -> Result<Self, PipelineError> {
+		Self::new_with_streams(glcore, mesh, shader, StreamConfig::VERTEX, StreamConfig::INSTANCE)
+	}
+
+	/// Get the non-fatal issues found the last time the VAO was described, e.g. the members of the vertex structs without the active shader attribs
+	pub fn warnings(&self) -> Vec<PipelineWarning> {
+		self.warnings.borrow().clone()
+	}
+
+	/// Get the fixed-function states of drawing, `None` if the pipeline keeps the current states of OpenGL
+	pub fn get_render_state(&self) -> Option<RenderState> {
+		self.render_state.get()
+	}
+
+	/// Set the fixed-function states to apply before drawing, the states are restored after drawing. Set `None` to keep the current states of OpenGL.
+	pub fn set_render_state(&self, render_state: Option<RenderState>) {
+		self.render_state.set(render_state);
+	}
+
+	/// Get the viewport array, `None` if the pipeline keeps the current viewport of OpenGL
+	pub fn get_viewport_array(&self) -> Option<ViewportArray> {
+		self.viewport_array.borrow().clone()
+	}
+
+	/// Set the viewport array to apply before drawing, the viewports are restored after drawing, e.g. for the geometry shader that writes `gl_ViewportIndex`.
+	/// It's validated against `GL_MAX_VIEWPORTS` and needs OpenGL 4.1 or `GL_ARB_viewport_array`. Set `None` to keep the current viewport of OpenGL.
+	pub fn set_viewport_array(&self, viewport_array: Option<ViewportArray>) -> Result<(), ViewportError> {
+		if let Some(viewport_array) = &viewport_array {
+			viewport_array.validate(&self.glcore)?;
+		}
+		*self.viewport_array.borrow_mut() = viewport_array;
+		Ok(())
+	}
+
+	/// Get the bounding box of the mesh in the model space, it's computed from the `position` member of the vertices by mapping the vertex buffer, then cached on the mesh by `GenericMesh::set_bounds()`.
+	/// `None` if the vertex struct has no `position` member of `vec2`, `vec3` or `vec4`, or the mesh has no vertex.
+	pub fn get_bounds(&self) -> Result<Option<Aabb>, PipelineError> {
+		if let Some(bounds) = self.mesh.get_bounds() {
+			return Ok(Some(bounds));
+		}
+		let Some(field) = V::fields().iter().find(|field| field.name == "position" || field.attrib_name == "position") else {
+			return Ok(None);
+		};
+		let components = match field.type_name {
+			"vec2" => 2,
+			"vec3" => 3,
+			"vec4" => 3,
+			_ => return Ok(None),
+		};
+		let (count, stride) = (self.mesh.get_vertex_count(), self.mesh.get_vertex_stride());
+		if count == 0 {
+			return Ok(None);
+		}
+		let bind = self.mesh.bind_vertex_buffer()?;
+		let (mapping, address) = bind.map(MapAccess::ReadOnly)?;
+		let bounds = Aabb::from_points((0..count).map(|index| {
+			let mut position = Vec3::zeros();
+			for component in 0..components {
+				position[component] = unsafe {(address as *const u8).add(index * stride + field.offset + component * size_of::<f32>()).cast::<f32>().read_unaligned()};
+			}
+			position
+		}));
+		mapping.unmap();
+		bind.unbind();
+		self.mesh.set_bounds(bounds);
+		Ok(bounds)
+	}
+
+	/// Get the label, see `set_label()`
+	pub fn get_label(&self) -> Option<String> {
+		self.label.borrow().clone()
+	}
+
+	/// Set the label of the VAO by `glObjectLabel()`, it's also the name of the debug group pushed around the draw calls, see `DebugScope`
+	pub fn set_label(&self, label: Option<&str>) -> Result<(), GLCoreError> {
+		if has_khr_debug(&self.glcore)? {
+			match label {
+				Some(label) => self.glcore.glObjectLabel(GL_VERTEX_ARRAY, self.name, label.len() as i32, label.as_ptr() as *const _)?,
+				None => self.glcore.glObjectLabel(GL_VERTEX_ARRAY, self.name, 0, null())?,
+			}
+		}
+		*self.label.borrow_mut() = label.map(|label| label.to_owned());
+		Ok(())
+	}
+
+	/// Check if the pipeline uses the separated vertex format of OpenGL 4.3
+	pub fn is_separated(&self) -> bool {
+		self.separated
+	}
+
+	/// Create a new pipeline with the configurations of the vertex buffer and the instance buffer, e.g. the divisors of their members.
+	/// The separated vertex format is used if the context supports it.
+	pub fn new_with_streams(glcore: Rc<GLCore>, mesh: Rc<dyn GenericMeshWithMaterial>, shader: Rc<Shader>, vertex_stream: StreamConfig, instance_stream: StreamConfig) -> Result<Self, PipelineError> {
+		let separated = has_vertex_attrib_binding(&glcore)?;
+		Self::new_with_format(glcore, mesh, shader, vertex_stream, instance_stream, separated)
+	}
+
+	/// Create a new pipeline like `new_with_streams()`, `separated` chooses between the separated vertex format of OpenGL 4.3 (`glVertexAttribFormat()` and `glBindVertexBuffer()`) and the `glVertexAttribPointer()` of OpenGL 3.3
+	pub fn new_with_format(glcore: Rc<GLCore>, mesh: Rc<dyn GenericMeshWithMaterial>, shader: Rc<Shader>, vertex_stream: StreamConfig, instance_stream: StreamConfig, separated: bool) -> Result<Self, PipelineError> {
+		if separated && !has_vertex_attrib_binding(&glcore)? {
+			return Err(PipelineError::SeparatedFormatNotSupported);
+		}
 		let mut name: u32 = 0;
 		glcore.glGenVertexArrays(1, &mut name as *mut u32)?;
-		let mut ret = Self {
+		let ret = Self {
 			glcore,
 			name,
 			mesh,
 			shader,
 			vertex_stride: size_of::<V>(),
 			instance_stride: size_of::<I>(),
+			vertex_stream,
+			instance_stream,
+			buffer_names: RefCell::new(Vec::new()),
+			separated,
+			bindings: RefCell::new(Vec::new()),
+			warnings: RefCell::new(Vec::new()),
+			render_state: Cell::new(None),
+			viewport_array: RefCell::new(None),
+			label: RefCell::new(None),
 			_phantom_vertex_type: PhantomData,
 			_phantom_instance_type: PhantomData,
 		};
@@ -125,79 +668,271 @@ impl<V: VertexType, I: VertexType> Pipeline<V, I> {
 		Ok(ret)
 	}
 
+	/// Replace the shader and keep the VAO and the mesh, e.g. for the hot-reloaded shaders or switching to a depth-only program of the same vertex layout.
+	/// The VAO is described again unless the active attribs of the shaders have the same names, types and locations, returns `true` if it's described again.
+	/// If the new shader fails to be described, the old shader is restored and the error is returned.
+	pub fn set_shader(&mut self, shader: Rc<Shader>) -> Result<bool, PipelineError> {
+		if Self::get_attrib_signature(&self.shader)? == Self::get_attrib_signature(&shader)? {
+			self.shader = shader;
+			return Ok(false);
+		}
+		let old_shader = std::mem::replace(&mut self.shader, shader);
+		if let Err(err) = self.establish_pipeline() {
+			self.shader = old_shader;
+			self.establish_pipeline()?;
+			return Err(err);
+		}
+		Ok(true)
+	}
+
+	/// Get the names, the types and the locations of the active attribs of a shader, sorted by the names
+	fn get_attrib_signature(shader: &Shader) -> Result<Vec<(String, ShaderInputVarType, i32)>, PipelineError> {
+		let mut signature = Vec::new();
+		for (name, attrib_type) in shader.get_active_attribs()? {
+			let location = shader.get_attrib_location(&name)?;
+			signature.push((name, attrib_type, location));
+		}
+		Ok(signature)
+	}
+
+	/// Describe the buffers of the mesh to the VAO again, it's done by `bind()` automatically when the mesh has other buffers than the last time
+	pub fn rebuild(&self) -> Result<(), PipelineError> {
+		self.establish_pipeline()
+	}
+
+	/// Get the names of the vertex buffer, the instance buffer (0 for none) and the buffers of the vertex streams.
+	/// With the separated vertex format, the vertex buffer and the instance buffer are bound at `bind()`, only the presence of the instance buffer matters.
+	fn get_buffer_names(&self) -> Vec<u32> {
+		let instance_name = self.mesh.get_instance_buffer().map_or(0, |buffer| buffer.get_name());
+		let mut names = if self.separated {
+			vec![(instance_name != 0) as u32]
+		} else {
+			vec![self.mesh.get_vertex_buffer().get_name(), instance_name]
+		};
+		names.extend(self.mesh.get_vertex_streams().iter().map(|stream| stream.get_buffer().get_name()));
+		names
+	}
+
 	/// Establish the pipeline by describing the vertex/instance data and the shader attrib inputs to the VAO.
-	fn establish_pipeline(&mut self) -> Result<(), PipelineError> {
+	fn establish_pipeline(&self) -> Result<(), PipelineError> {
 		let program = self.shader.use_program()?;
 		let active_attribs = self.shader.get_active_attribs()?;
-		let bind = self.bind()?;
+		let bind = PipelineBind::new(self)?;
+		self.warnings.borrow_mut().clear();
+
+		// Disable the attribs described before, some of them may not be described again
+		let mut max_attribs: i32 = 0;
+		self.glcore.glGetIntegerv(GL_MAX_VERTEX_ATTRIBS, &mut max_attribs as *mut i32)?;
+		for location in 0..max_attribs as u32 {
+			self.glcore.glDisableVertexAttribArray(location)?;
+		}
+
+		if self.separated {
+			self.establish_pipeline_separated(&active_attribs)?;
+			self.bind_vertex_buffers()?;
+		} else {
+			let vb_bind = self.mesh.bind_vertex_buffer()?;
+			self.describe::<V>(&active_attribs, self.vertex_stream.default_divisor)?;
+			vb_bind.unbind();
 
-		let vb_bind = self.mesh.get_vertex_buffer().bind()?;
-		self.describe::<V>(&active_attribs, 0)?;
-		vb_bind.unbind();
+			if let Some(ib_bind) = self.mesh.bind_instance_buffer()? {
+				self.describe::<I>(&active_attribs, self.instance_stream.default_divisor)?;
+				ib_bind.unbind();
+			}
 
-		if let Some(ib) = self.mesh.get_instance_buffer() {
-			let ib_bind = ib.bind()?;
-			self.describe::<I>(&active_attribs, 1)?;
-			ib_bind.unbind();
+			for stream in self.mesh.get_vertex_streams() {
+				let sb_bind = stream.get_buffer().bind_to(BufferTarget::ArrayBuffer)?;
+				self.describe_layout(&stream.get_layout(), &active_attribs, stream.get_divisor())?;
+				sb_bind.unbind();
+			}
 		}
 
 		bind.unbind();
 		program.unuse();
+		*self.buffer_names.borrow_mut() = self.get_buffer_names();
+		Ok(())
+	}
+
+	/// Describe the formats of the vertex/instance data and the vertex streams to the VAO with the separated vertex format, the VAO must be bound.
+	/// The vertex buffer uses the binding index 0 and the instance buffer uses 1, more binding points are added for the vertex streams and the members with their own divisors.
+	fn establish_pipeline_separated(&self, active_attribs: &BTreeMap<String, ShaderInputVarType>) -> Result<(), PipelineError> {
+		let mut bindings = vec![
+			VertexBinding {source: 0, divisor: self.vertex_stream.default_divisor},
+			VertexBinding {source: 1, divisor: self.instance_stream.default_divisor},
+		];
+		let mut layouts = vec![(0, VertexLayout::of::<V>(), self.vertex_stream.default_divisor)];
+		if self.mesh.get_instance_buffer().is_some() {
+			layouts.push((1, VertexLayout::of::<I>(), self.instance_stream.default_divisor));
+		}
+		for (i, stream) in self.mesh.get_vertex_streams().iter().enumerate() {
+			bindings.push(VertexBinding {source: i + 2, divisor: stream.get_divisor()});
+			layouts.push((i + 2, stream.get_layout(), stream.get_divisor()));
+		}
+		for (source, layout, v_a_d) in layouts.iter() {
+			self.for_each_attrib_row(layout, active_attribs, *v_a_d, |row| {
+				let binding = VertexBinding {source: *source, divisor: row.divisor};
+				let index = match bindings.iter().position(|b| *b == binding) {
+					Some(index) => index,
+					None => {
+						bindings.push(binding);
+						bindings.len() - 1
+					}
+				};
+				let (location, attrib_type, offset) = (row.location, row.attrib_type, row.offset as u32);
+				self.glcore.glEnableVertexAttribArray(location)?;
+				if attrib_type.is_float()	{self.glcore.glVertexAttribFormat (location, row.datainfo.size as i32, row.datainfo.data_type, row.normalize, offset)?} else
+				if attrib_type.is_integer()	{self.glcore.glVertexAttribIFormat(location, row.datainfo.size as i32, row.datainfo.data_type, offset)?} else
+				if attrib_type.is_double()	{self.glcore.glVertexAttribLFormat(location, row.datainfo.size as i32, attrib_type.get_base_type() as u32, offset)?} else
+				{return Err(PipelineError::UnsupportedAttribType(format!("{} {}", attrib_type.get_type(), row.attrib_name)))}
+				self.glcore.glVertexAttribBinding(location, index as u32)?;
+				Ok(())
+			})?;
+		}
+		for (index, binding) in bindings.iter().enumerate() {
+			self.glcore.glVertexBindingDivisor(index as u32, binding.divisor)?;
+		}
+		*self.bindings.borrow_mut() = bindings;
+		Ok(())
+	}
+
+	/// Bind the current buffers of the mesh to the binding points of the separated vertex format, the VAO must be bound
+	fn bind_vertex_buffers(&self) -> Result<(), PipelineError> {
+		let streams = self.mesh.get_vertex_streams();
+		for (index, binding) in self.bindings.borrow().iter().enumerate() {
+			let (name, stride) = match binding.source {
+				0 => (self.mesh.get_vertex_buffer().get_name(), self.vertex_stride),
+				1 => (self.mesh.get_instance_buffer().map_or(0, |buffer| buffer.get_name()), self.instance_stride),
+				source => streams.get(source - 2).map_or((0, 0), |stream| (stream.get_buffer().get_name(), stream.get_layout().stride)),
+			};
+			self.glcore.glBindVertexBuffer(index as u32, name, 0, stride as i32)?;
+		}
 		Ok(())
 	}
 
 	/// Describe a `VertexType` to a VAO
 	fn describe<T: VertexType>(&self, active_attribs: &BTreeMap<String, ShaderInputVarType>, v_a_d: u32) -> Result<(), PipelineError> {
-		let instance = T::default();
-		let stride = size_of::<T>();
-		let alignment = align_of::<T>();
-		let mut cur_offset: usize = 0;
-		for (field_name, field_value) in instance.iter() {
-			let typename = Self::get_typename_of_vertex_struct_member(field_value);
-			let datainfo = Self::get_vertex_struct_member_gltype(typename);
-			if let Some(attrib_type) = active_attribs.get(field_name) {
-				let (p_size, p_rows) = attrib_type.get_size_and_rows();
-				if p_size != datainfo.size || p_rows != datainfo.rows {
-					panic!("The size and rows of the shader attrib is {p_size}x{p_rows}, but the given member of the vertex struct is {}x{}", datainfo.size, datainfo.rows);
-				}
-				let location = self.shader.get_attrib_location(field_name)?;
-				if location >= 0 {
-					let location = location as u32;
-					for row in 0..datainfo.rows {
-						let location = location + row;
-						let do_normalize = if field_name.contains("normalized") && field_name.contains("_") {
-							1
-						} else {
-							0
-						};
-						let ptr_param = cur_offset as *const c_void;
-						self.glcore.glEnableVertexAttribArray(location)?;
-						if attrib_type.is_float()	{self.glcore.glVertexAttribPointer (location, p_size as i32, attrib_type.get_base_type() as u32, do_normalize, stride as i32, ptr_param)?} else
-						if attrib_type.is_integer()	{self.glcore.glVertexAttribIPointer(location, p_size as i32, attrib_type.get_base_type() as u32, stride as i32, ptr_param)?} else
-						if attrib_type.is_double()	{self.glcore.glVertexAttribLPointer(location, p_size as i32, attrib_type.get_base_type() as u32, stride as i32, ptr_param)?} else
-						{panic!("Unknown data type of the attrib `{} {field_name}`", attrib_type.get_type())}
-						self.glcore.glVertexAttribDivisor(location, v_a_d)?;
-					}
-				} else {
-					eprintln!("Attrib `{typename} {field_name}` is active, but can't get its location.");
+		self.describe_layout(&VertexLayout::of::<T>(), active_attribs, v_a_d)
+	}
+
+	/// Record a non-fatal issue, see `warnings()`
+	fn warn(&self, warning: PipelineWarning) {
+		self.warnings.borrow_mut().push(warning);
+	}
+
+	/// Find the active shader attrib of a member by its location if it's given, otherwise by its attrib name. Returns the location and the type of the attrib.
+	fn find_attrib<'b>(&self, field: &FieldDesc, active_attribs: &'b BTreeMap<String, ShaderInputVarType>) -> Result<Option<(u32, &'b ShaderInputVarType)>, PipelineError> {
+		if let Some(location) = field.location {
+			for (name, attrib_type) in active_attribs.iter() {
+				if self.shader.get_attrib_location(name)? == location as i32 {
+					return Ok(Some((location, attrib_type)));
 				}
+			}
+			self.warn(PipelineWarning::AttribLocationNotActive(format!("{} {}", field.type_name, field.name), location));
+		} else if let Some(attrib_type) = active_attribs.get(field.attrib_name) {
+			let location = self.shader.get_attrib_location(field.attrib_name)?;
+			if location >= 0 {
+				return Ok(Some((location as u32, attrib_type)));
+			}
+			self.warn(PipelineWarning::AttribNotLocatable(format!("{} {}", field.type_name, field.attrib_name)));
+		} else {
+			self.warn(PipelineWarning::AttribNotActive(format!("{} {}", field.type_name, field.attrib_name)));
+		}
+		Ok(None)
+	}
+
+	/// Check that the members of a `VertexLayout` don't overlap each other and fit in the struct, the members may be reordered by the compiler
+	fn check_layout(layout: &VertexLayout) -> Result<(), PipelineError> {
+		let mut fields: Vec<(&FieldDesc, usize)> = Vec::with_capacity(layout.fields.len());
+		for field in layout.fields.iter() {
+			fields.push((field, Self::get_member_gltype(field)?.size_in_bytes()));
+		}
+		fields.sort_by_key(|(field, _)| field.offset);
+		let mut end = 0;
+		for (field, size) in fields {
+			if field.offset < end {
+				return Err(PipelineError::VertexLayoutPadding(field.name.to_string(), end, field.offset));
+			}
+			if field.offset + size > layout.stride {
+				return Err(PipelineError::VertexLayoutPadding(field.name.to_string(), layout.stride.saturating_sub(size), field.offset));
+			}
+			end = field.offset + size;
+		}
+		Ok(())
+	}
+
+	/// Find the shader attribs of the members of a `VertexLayout`, then call `describe_row` for each row of the members
+	fn for_each_attrib_row(&self, layout: &VertexLayout, active_attribs: &BTreeMap<String, ShaderInputVarType>, v_a_d: u32, mut describe_row: impl FnMut(&AttribRow) -> Result<(), PipelineError>) -> Result<(), PipelineError> {
+		Self::check_layout(layout)?;
+		for field in layout.fields.iter() {
+			let datainfo = Self::get_member_gltype(field)?;
+			let Some((location, attrib_type)) = self.find_attrib(field, active_attribs)? else {
+				continue;
+			};
+			let (p_size, p_rows) = attrib_type.get_size_and_rows();
+			if p_size != datainfo.size || p_rows != datainfo.rows {
+				return Err(PipelineError::AttribSizeMismatch(field.name.to_string(), (p_size, p_rows), (datainfo.size, datainfo.rows)));
+			}
+			let normalize = if field.normalized {
+				1
+			} else if field.name.contains("normalized") && field.name.contains("_") {
+				self.warn(PipelineWarning::NormalizedByName(format!("{} {}", field.type_name, field.name)));
+				1
 			} else {
-				eprintln!("Attrib `{typename} {field_name}` is not active.");
+				0
+			};
+			let divisor = field.divisor.unwrap_or(v_a_d);
+			for row in 0..datainfo.rows {
+				describe_row(&AttribRow {
+					attrib_name: field.attrib_name,
+					location: location + row,
+					attrib_type,
+					datainfo,
+					normalize,
+					offset: field.offset + row as usize * datainfo.size_in_bytes() / datainfo.rows as usize,
+					divisor,
+				})?;
 			}
-			cur_offset += datainfo.size_in_bytes();
-			cur_offset = ((cur_offset - 1) / alignment + 1) * alignment;
 		}
 		Ok(())
 	}
 
-	/// Bind the pipeline for drawing
+	/// Describe a `VertexLayout` to a VAO, the buffer of the vertices must be bound to `GL_ARRAY_BUFFER`
+	fn describe_layout(&self, layout: &VertexLayout, active_attribs: &BTreeMap<String, ShaderInputVarType>, v_a_d: u32) -> Result<(), PipelineError> {
+		let stride = layout.stride as i32;
+		self.for_each_attrib_row(layout, active_attribs, v_a_d, |row| {
+			let (location, attrib_type, p_size) = (row.location, row.attrib_type, row.datainfo.size as i32);
+			let ptr_param = row.offset as *const c_void;
+			self.glcore.glEnableVertexAttribArray(location)?;
+			if attrib_type.is_float()	{self.glcore.glVertexAttribPointer (location, p_size, row.datainfo.data_type, row.normalize, stride, ptr_param)?} else
+			if attrib_type.is_integer()	{self.glcore.glVertexAttribIPointer(location, p_size, row.datainfo.data_type, stride, ptr_param)?} else
+			if attrib_type.is_double()	{self.glcore.glVertexAttribLPointer(location, p_size, attrib_type.get_base_type() as u32, stride, ptr_param)?} else
+			{return Err(PipelineError::UnsupportedAttribType(format!("{} {}", attrib_type.get_type(), row.attrib_name)))}
+			self.glcore.glVertexAttribDivisor(location, row.divisor)?;
+			Ok(())
+		})
+	}
+
+	/// Bind the pipeline for drawing, the VAO is described again if the buffers of the mesh were reallocated or replaced.
+	/// With the separated vertex format, the current buffers of the mesh are bound to the VAO without describing it again.
 	pub fn bind<'a>(&'a self) -> Result<PipelineBind<'a, V, I>, PipelineError> {
-		PipelineBind::new(self)
+		if *self.buffer_names.borrow() != self.get_buffer_names() {
+			self.establish_pipeline()?;
+		}
+		let bind = PipelineBind::new(self)?;
+		if self.separated {
+			self.bind_vertex_buffers()?;
+		}
+		Ok(bind)
+	}
+
+	/// Get the `DataGlType` of a member of the `VertexType` struct by its type name
+	fn get_member_gltype(field: &FieldDesc) -> Result<DataGlType, PipelineError> {
+		Self::get_vertex_struct_member_gltype(field.type_name).ok_or_else(|| PipelineError::UnsupportedMemberType(field.name.to_string(), field.type_name.to_string()))
 	}
 
-	/// Parse the type name of the `VertexType` struct members, and return a `DataGlType`.
-	fn get_vertex_struct_member_gltype(member_type: &str) -> DataGlType {
-		match member_type {
+	/// Parse the type name of the `VertexType` struct members, and return a `DataGlType`, `None` if the type isn't supported.
+	fn get_vertex_struct_member_gltype(member_type: &str) -> Option<DataGlType> {
+		Some(match member_type {
 			"i8" => DataGlType{data_type: GL_BYTE, size: 1, rows: 1},
 			"i16" => DataGlType{data_type: GL_SHORT, size: 1, rows: 1},
 			"i32" => DataGlType{data_type: GL_INT, size: 1, rows: 1},
@@ -216,17 +951,18 @@ impl<V: VertexType, I: VertexType> Pipeline<V, I> {
 					else if member_type.starts_with("i32") {GL_INT}
 					else if member_type.starts_with("i16") {GL_SHORT}
 					else if member_type.starts_with("i8")  {GL_BYTE}
+					else if member_type.starts_with("f16") {GL_HALF_FLOAT}
 					else {
-						match member_type.chars().next().unwrap() {
+						match member_type.chars().next()? {
 							'v' => GL_FLOAT,
 							'd' => GL_DOUBLE,
 							'b' => GL_BYTE,
 							'i' => GL_INT,
 							'u' => GL_UNSIGNED_INT,
-							_ => panic!("Unsupported type of member: `{member_type}`"),
+							_ => return None,
 						}
 					};
-					let size = u32::from(member_type.chars().last().unwrap()) - u32::from('0');
+					let size = member_type.chars().last()?.to_digit(10).filter(|size| (1..=4).contains(size))?;
 					DataGlType{data_type, size, rows: 1}
 				} else if member_type.contains("mat") {
 					let data_type = if member_type.starts_with("d") {
@@ -245,11 +981,11 @@ impl<V: VertexType, I: VertexType> Pipeline<V, I> {
 					else if member_type.ends_with("4x3") {(4, 3)}
 					else if member_type.ends_with("4x4") {(4, 4)}
 					else {
-						match member_type.chars().last().unwrap() {
+						match member_type.chars().last()? {
 							'2' => (2, 2),
 							'3' => (3, 3),
 							'4' => (4, 4),
-							_ => panic!("Unsupported type of member: `{member_type}`"),
+							_ => return None,
 						}
 					};
 					DataGlType{data_type, size, rows}
@@ -261,10 +997,10 @@ impl<V: VertexType, I: VertexType> Pipeline<V, I> {
 					};
 					DataGlType{data_type, size: 4, rows: 1}
 				} else {
-					panic!("Unsupported type of member: `{member_type}`")
+					return None
 				}
 			}
-		}
+		})
 	}
 
 	/// Get the exact type of the struct member by the member instance
@@ -278,6 +1014,9 @@ impl<V: VertexType, I: VertexType> Pipeline<V, I> {
 		else if data.is::<f16>() {"f16"}
 		else if data.is::<f32>() {"f32"}
 		else if data.is::<f64>() {"f64"}
+		else if data.is::<F16Vec2>() {"f16vec2"}
+		else if data.is::<F16Vec3>() {"f16vec3"}
+		else if data.is::<F16Vec4>() {"f16vec4"}
 		else if data.is::<Vec1>() {"vec1"}
 		else if data.is::<Vec2>() {"vec2"}
 		else if data.is::<Vec3>() {"vec3"}
@@ -358,62 +1097,330 @@ impl<'a, V: VertexType, I: VertexType> PipelineBind<'a, V, I> {
 		pipeline.glcore.glBindVertexArray(pipeline.name)?;
 		Ok(Self {
 			pipeline,
+			apply_render_state: true,
 		})
 	}
 
-	/// Run the pipeline for drawing
-	pub fn draw(&self, fbo: Option<&Framebuffer>) -> Result<(), PipelineError>  {
+	/// Don't apply the `RenderState` of the pipeline, the caller manages the states
+	pub(crate) fn keep_render_state(mut self) -> Self {
+		self.apply_render_state = false;
+		self
+	}
+
+	/// Set up the shader, the framebuffer, the `RenderState` and the material, call `setup` to set the uniforms, bind the element buffer, then call `draw` to issue the draw call
+	fn issue_draw(&self, fbo: Option<&Framebuffer>, setup: impl FnOnce(&ShaderUse) -> Result<(), ShaderError>, draw: impl FnOnce(&GLCore, &dyn GenericMeshWithMaterial) -> Result<DrawStats, PipelineError>) -> Result<DrawStats, PipelineError> {
 		let glcore = &self.pipeline.glcore;
+		let debug_scope = DebugScope::push_internal(glcore, || self.pipeline.get_label().unwrap_or_else(|| format!("Pipeline {}", self.pipeline.name)))?;
 		let program = self.pipeline.shader.use_program()?;
 		let fbo_bind = if let Some(fbo) = fbo {
 			let bind = fbo.bind()?;
 			bind.setup(&self.pipeline.shader)?;
 			Some(bind)
 		} else {
-			Framebuffer::default_bind(glcore)?;
+			Framebuffer::default_bind(glcore, FramebufferBindTarget::Draw)?;
 			None
 		};
+		let state_guard = match self.pipeline.get_render_state() {
+			Some(render_state) if self.apply_render_state => {
+				let guard = StateGuard::new(glcore)?;
+				render_state.apply(glcore)?;
+				Some(guard)
+			}
+			_ => None,
+		};
+		let viewport_guard = match &*self.pipeline.viewport_array.borrow() {
+			Some(viewport_array) => Some(viewport_array.apply(glcore)?),
+			None => None,
+		};
 
-		if let Some(material) = self.pipeline.mesh.get_material() {
-			program.setup_material_uniforms(material, Some("i"), true)?;
-		} else {
-			let default_material = MaterialLegacy::default();
-			program.setup_material_uniforms(&default_material, Some("i"), true)?;
+		let material = self.pipeline.mesh.get_material();
+		let material_set_up = !MaterialBatch::is_set_up(program.shader.get_name(), material);
+		if material_set_up {
+			if let Some(material) = material {
+				program.setup_material_uniforms(material, Some("i"), true, None, None)?;
+			} else {
+				let default_material = MaterialLegacy::default();
+				program.setup_material_uniforms(&default_material, Some("i"), true, None, None)?;
+			}
+			MaterialBatch::set_up(program.shader.get_name(), material);
 		}
+		setup(&program)?;
 
 		let mesh = &self.pipeline.mesh;
-		let element_buffer = mesh.get_element_buffer();
 		let e_bind = mesh.bind_element_buffer()?;
+		if mesh.get_primitive() == PrimitiveMode::Patches {
+			Self::setup_patches(glcore, &**mesh)?;
+		}
+		let mut stats = draw(&**glcore, &**mesh)?;
+		if material_set_up && MaterialBatch::is_active() {
+			stats.material_setups = 1;
+		}
+		FrameStats::record(stats);
 
-		if let Some(command_buffer) = mesh.get_command_buffer() {
-			assert_eq!(command_buffer.get_target(), BufferTarget::DrawIndirectBuffer);
-			let c_bind = command_buffer.bind()?;
-			let num_commands = mesh.get_command_count();
-			if element_buffer.is_some() {
-				glcore.glMultiDrawElementsIndirect(mesh.get_primitive() as u32, mesh.get_element_type() as u32, null(), num_commands as i32, size_of::<DrawElementsCommand>() as i32)?;
-			} else {
-				glcore.glMultiDrawArraysIndirect(mesh.get_primitive() as u32, null(), num_commands as i32, size_of::<DrawArrayCommand>() as i32)?;
-			}
-			c_bind.unbind();
-		} else {
-			let num_vertices = mesh.get_vertex_count();
-			if mesh.get_instance_buffer().is_some() {
-				let num_instances = mesh.get_instance_count();
+		if let Some(b) = e_bind { b.unbind() }
+		if let Some(g) = viewport_guard { g.restore() }
+		if let Some(g) = state_guard { g.restore() }
+		program.unuse();
+		if let Some(b) = fbo_bind { b.unbind() }
+		if let Some(s) = debug_scope { s.pop() }
+		Ok(stats)
+	}
+
+	/// Run the pipeline for drawing, returns the counts of the draw call
+	pub fn draw(&self, fbo: Option<&Framebuffer>) -> Result<DrawStats, PipelineError>  {
+		self.draw_with(fbo, |_| Ok(()))
+	}
+
+	/// Run the pipeline for drawing, `setup` is called while the program is in use to set the uniforms of this draw call, e.g. the model matrix.
+	/// It's called after the uniforms of the material, so it could override them. Returns the counts of the draw call.
+	pub fn draw_with(&self, fbo: Option<&Framebuffer>, setup: impl FnOnce(&ShaderUse) -> Result<(), ShaderError>) -> Result<DrawStats, PipelineError> {
+		self.issue_draw(fbo, setup, |glcore, mesh| {
+			let element_buffer = mesh.get_element_buffer();
+			if let Some(c_bind) = mesh.bind_command_buffer()? {
+				let num_commands = mesh.get_command_count();
 				if element_buffer.is_some() {
-					glcore.glDrawElementsInstanced(mesh.get_primitive() as u32, mesh.get_element_count() as i32, mesh.get_element_type() as u32, null(), num_instances as i32)?;
+					glcore.glMultiDrawElementsIndirect(mesh.get_primitive() as u32, mesh.get_element_type() as u32, null(), num_commands as i32, size_of::<DrawElementsCommand>() as i32)?;
 				} else {
-					glcore.glDrawArraysInstanced(mesh.get_primitive() as u32, 0, num_vertices as i32, num_instances as i32)?;
+					glcore.glMultiDrawArraysIndirect(mesh.get_primitive() as u32, null(), num_commands as i32, size_of::<DrawArrayCommand>() as i32)?;
 				}
-			} else if element_buffer.is_some() {
-				glcore.glDrawElements(mesh.get_primitive() as u32, mesh.get_element_count() as i32, mesh.get_element_type() as u32, null())?;
+				c_bind.unbind();
+				Ok(DrawStats::new(1, 0, 0))
 			} else {
-				glcore.glDrawArrays(mesh.get_primitive() as u32, 0, num_vertices as i32)?;
+				let num_vertices = mesh.get_vertex_count();
+				let num_elements = mesh.get_element_count();
+				if mesh.get_instance_buffer().is_some() {
+					let num_instances = mesh.get_instance_count();
+					if element_buffer.is_some() {
+						glcore.glDrawElementsInstanced(mesh.get_primitive() as u32, num_elements as i32, mesh.get_element_type() as u32, null(), num_instances as i32)?;
+						Ok(DrawStats::single(num_elements, num_instances))
+					} else {
+						glcore.glDrawArraysInstanced(mesh.get_primitive() as u32, 0, num_vertices as i32, num_instances as i32)?;
+						Ok(DrawStats::single(num_vertices, num_instances))
+					}
+				} else if element_buffer.is_some() {
+					glcore.glDrawElements(mesh.get_primitive() as u32, num_elements as i32, mesh.get_element_type() as u32, null())?;
+					Ok(DrawStats::single(num_elements, 1))
+				} else {
+					glcore.glDrawArrays(mesh.get_primitive() as u32, 0, num_vertices as i32)?;
+					Ok(DrawStats::single(num_vertices, 1))
+				}
+			}
+		})
+	}
+
+	/// Draw the elements `[first, first + count)` with `glDrawElementsBaseVertex()`, `base_vertex` is added to each of the indices.
+	/// Without the element buffer, draw the vertices `[first + base_vertex, first + base_vertex + count)` with `glDrawArrays()`. The command buffer and the instances are ignored.
+	/// Returns the counts of the draw call.
+	pub fn draw_range(&self, fbo: Option<&Framebuffer>, first: usize, count: usize, base_vertex: i32) -> Result<DrawStats, PipelineError> {
+		self.issue_draw(fbo, |_| Ok(()), |glcore, mesh| {
+			match Self::check_range(mesh, first, count, base_vertex)? {
+				Some(offset) => glcore.glDrawElementsBaseVertex(mesh.get_primitive() as u32, count as i32, mesh.get_element_type() as u32, offset as *const c_void, base_vertex)?,
+				None => glcore.glDrawArrays(mesh.get_primitive() as u32, first as i32 + base_vertex, count as i32)?,
+			}
+			Ok(DrawStats::single(count, 1))
+		})
+	}
+
+	/// Draw a range of the mesh like `draw_range()` for `instance_count` instances, with `glDrawElementsInstancedBaseVertex()` or `glDrawArraysInstanced()`
+	pub fn draw_range_instanced(&self, fbo: Option<&Framebuffer>, first: usize, count: usize, base_vertex: i32, instance_count: usize) -> Result<DrawStats, PipelineError> {
+		self.issue_draw(fbo, |_| Ok(()), |glcore, mesh| {
+			match Self::check_range(mesh, first, count, base_vertex)? {
+				Some(offset) => glcore.glDrawElementsInstancedBaseVertex(mesh.get_primitive() as u32, count as i32, mesh.get_element_type() as u32, offset as *const c_void, instance_count as i32, base_vertex)?,
+				None => glcore.glDrawArraysInstanced(mesh.get_primitive() as u32, first as i32 + base_vertex, count as i32, instance_count as i32)?,
 			}
+			Ok(DrawStats::single(count, instance_count))
+		})
+	}
+
+	/// Draw all of the elements, or the vertices if there's no element buffer, for `instance_count` instances, which must not exceed the instances in the instance buffer
+	pub fn draw_instanced(&self, fbo: Option<&Framebuffer>, instance_count: usize) -> Result<DrawStats, PipelineError> {
+		let mesh = &self.pipeline.mesh;
+		let count = if mesh.get_element_buffer().is_some() {mesh.get_element_count()} else {mesh.get_vertex_count()};
+		self.draw_counted(fbo, count, Some(instance_count))
+	}
+
+	/// Draw the first `count` elements, or the vertices if there's no element buffer, for `instance_count` instances if it's given.
+	/// The counts are validated against the buffers, nothing is drawn and the returned counts are zero if any of them is zero.
+	pub fn draw_counted(&self, fbo: Option<&Framebuffer>, count: usize, instance_count: Option<usize>) -> Result<DrawStats, PipelineError> {
+		let mesh = &**self.pipeline.mesh;
+		Self::check_range(mesh, 0, count, 0)?;
+		if let Some(instance_count) = instance_count {
+			Self::check_instances(mesh, instance_count)?;
+		}
+		if count == 0 || instance_count == Some(0) {
+			return Ok(DrawStats::default());
 		}
+		match instance_count {
+			Some(instance_count) => self.draw_range_instanced(fbo, 0, count, 0, instance_count),
+			None => self.draw_range(fbo, 0, count, 0),
+		}
+	}
 
-		if let Some(b) = e_bind { b.unbind() }
-		program.unuse();
-		if let Some(b) = fbo_bind { b.unbind() }
+	/// Draw the elements `[first_element, first_element + element_count)` for `instance_count` instances, `base_vertex` is added to each of the indices and the instances are read from `base_instance`.
+	/// Without the element buffer, draw the vertices `[first_element + base_vertex, first_element + base_vertex + element_count)`. The command buffer is ignored.
+	/// A non-zero `base_instance` requires OpenGL 4.2, nothing is drawn and the returned counts are zero if any of the counts is zero.
+	pub fn draw_base(&self, fbo: Option<&Framebuffer>, first_element: usize, element_count: usize, base_vertex: i32, instance_count: usize, base_instance: u32) -> Result<DrawStats, PipelineError> {
+		let mesh = &**self.pipeline.mesh;
+		Self::check_range(mesh, first_element, element_count, base_vertex)?;
+		let Some(end_instance) = (base_instance as usize).checked_add(instance_count) else {
+			return Err(PipelineError::InvalidInstanceCount(instance_count, mesh.get_instance_count()));
+		};
+		Self::check_instances(mesh, end_instance)?;
+		if base_instance != 0 && !has_base_instance(&self.pipeline.glcore)? {
+			return Err(PipelineError::BaseInstanceNotSupported(base_instance));
+		}
+		if element_count == 0 || instance_count == 0 {
+			return Ok(DrawStats::default());
+		}
+		self.issue_draw(fbo, |_| Ok(()), |glcore, mesh| {
+			let mode = mesh.get_primitive() as u32;
+			match (Self::check_range(mesh, first_element, element_count, base_vertex)?, base_instance) {
+				(Some(offset), 0) => glcore.glDrawElementsInstancedBaseVertex(mode, element_count as i32, mesh.get_element_type() as u32, offset as *const c_void, instance_count as i32, base_vertex)?,
+				(Some(offset), _) => glcore.glDrawElementsInstancedBaseVertexBaseInstance(mode, element_count as i32, mesh.get_element_type() as u32, offset as *const c_void, instance_count as i32, base_vertex, base_instance)?,
+				(None, 0) => glcore.glDrawArraysInstanced(mode, first_element as i32 + base_vertex, element_count as i32, instance_count as i32)?,
+				(None, _) => glcore.glDrawArraysInstancedBaseInstance(mode, first_element as i32 + base_vertex, element_count as i32, instance_count as i32, base_instance)?,
+			}
+			Ok(DrawStats::single(element_count, instance_count))
+		})
+	}
+
+	/// Draw the commands in the command buffer of the mesh with `glMultiDrawElementsIndirectCount()` or `glMultiDrawArraysIndirectCount()`, the number of the commands is read by the GPU from `parameter_buffer` at `param_offset`.
+	/// At most `max_draw_count` commands are drawn, it must not exceed the commands in the command buffer. Needs OpenGL 4.6 or `GL_ARB_indirect_parameters`.
+	pub fn draw_indirect_count(&self, fbo: Option<&Framebuffer>, parameter_buffer: &Buffer, param_offset: usize, max_draw_count: usize) -> Result<DrawStats, PipelineError> {
+		let mesh = &**self.pipeline.mesh;
+		let num_commands = mesh.get_command_buffer().map_or(0, |_| mesh.get_command_count());
+		if max_draw_count > num_commands {
+			return Err(PipelineError::InvalidDrawCount(max_draw_count, num_commands));
+		}
+		if !has_indirect_count(&self.pipeline.glcore)? {
+			return Err(PipelineError::IndirectCountNotSupported);
+		}
+		if max_draw_count == 0 {
+			return Ok(DrawStats::default());
+		}
+		self.issue_draw(fbo, |_| Ok(()), |glcore, mesh| {
+			let p_bind = parameter_buffer.bind_to(BufferTarget::ParameterBuffer)?;
+			if let Some(c_bind) = mesh.bind_command_buffer()? {
+				if mesh.get_element_buffer().is_some() {
+					glcore.glMultiDrawElementsIndirectCount(mesh.get_primitive() as u32, mesh.get_element_type() as u32, null(), param_offset, max_draw_count as i32, size_of::<DrawElementsCommand>() as i32)?;
+				} else {
+					glcore.glMultiDrawArraysIndirectCount(mesh.get_primitive() as u32, null(), param_offset, max_draw_count as i32, size_of::<DrawArrayCommand>() as i32)?;
+				}
+				c_bind.unbind();
+			}
+			p_bind.unbind();
+			Ok(DrawStats::new(1, 0, 0))
+		})
+	}
+
+	/// Run the pipeline like `draw()` in the conditional rendering by `query`, the GPU skips the draw call if the query counted zero, e.g. the bounding box drawn by `OcclusionProxy` was occluded
+	pub fn draw_conditional(&self, fbo: Option<&Framebuffer>, query: &Query, mode: ConditionalMode) -> Result<DrawStats, PipelineError> {
+		let conditional = query.begin_conditional_render(mode)?;
+		let stats = self.draw(fbo)?;
+		conditional.end();
+		Ok(stats)
+	}
+
+	/// Run the pipeline like `draw_with()` twice for the debug visualization, filled and then the wireframe of `line_width` on top of it.
+	/// The passes use the `RenderState` of the pipeline in `PolygonMode::Fill` and `PolygonMode::Line`, the lines are pulled towards the viewer by the polygon offset to pass the depth test.
+	/// `setup` is called for each pass with its polygon mode, e.g. to set another color for the lines. Returns the counts of both of the passes.
+	pub fn draw_wireframe_overlay(&self, fbo: Option<&Framebuffer>, line_width: f32, setup: impl Fn(&ShaderUse, PolygonMode) -> Result<(), ShaderError>) -> Result<DrawStats, PipelineError> {
+		let render_state = self.pipeline.get_render_state();
+		let fill = RenderState {
+			polygon_mode: PolygonMode::Fill,
+			..render_state.unwrap_or_default()
+		};
+		let line = RenderState {
+			polygon_mode: PolygonMode::Line,
+			polygon_offset: Some((-1.0, -1.0)),
+			line_width,
+			..fill
+		};
+		let mut result = Ok(DrawStats::default());
+		for state in [fill, line] {
+			self.pipeline.set_render_state(Some(state));
+			match self.draw_with(fbo, |program| setup(program, state.polygon_mode)) {
+				Ok(stats) => result = result.map(|total| total + stats),
+				Err(err) => {
+					result = Err(err);
+					break;
+				}
+			}
+		}
+		self.pipeline.set_render_state(render_state);
+		result
+	}
+
+	/// Draw a submesh returned by `Mesh::append()`, its elements already have its base vertex added
+	pub fn draw_submesh(&self, fbo: Option<&Framebuffer>, range: &SubmeshRange) -> Result<DrawStats, PipelineError> {
+		self.draw_range(fbo, range.first_element, range.element_count, 0)
+	}
+
+	/// Run the pipeline like `draw()` without a framebuffer and capture the outputs of the shader into `capture_into` with the transform feedback.
+	/// The shader must be created by `Shader::new_with_varyings()`, the buffer must be big enough for the captured vertices.
+	pub fn draw_with_capture(&self, tf: &TransformFeedback, capture_into: &Buffer) -> Result<DrawStats, PipelineError> {
+		let tf_bind = tf.bind()?;
+		tf_bind.bind_buffer(0, capture_into)?;
+		let capture = tf_bind.begin(self.pipeline.mesh.get_primitive().into())?;
+		let stats = self.draw(None)?;
+		capture.end();
+		tf_bind.unbind();
+		Ok(stats)
+	}
+
+	/// Draw the vertices captured by the transform feedback with `glDrawTransformFeedback()`, without knowing the number of the captured vertices.
+	/// The captured buffer should be the vertex buffer of the mesh of this pipeline, the element buffer and the instances are ignored.
+	pub fn draw_transform_feedback(&self, fbo: Option<&Framebuffer>, tf: &TransformFeedback) -> Result<DrawStats, PipelineError> {
+		self.issue_draw(fbo, |_| Ok(()), |glcore, mesh| {
+			glcore.glDrawTransformFeedback(mesh.get_primitive() as u32, tf.get_name())?;
+			Ok(DrawStats::new(1, 1, 0))
+		})
+	}
+
+	/// Set `GL_PATCH_VERTICES` and the default tessellation levels of the mesh before drawing the patches
+	fn setup_patches(glcore: &GLCore, mesh: &dyn GenericMeshWithMaterial) -> Result<(), PipelineError> {
+		if let Some(patch_vertices) = mesh.get_patch_vertices() {
+			let mut max_patch_vertices: i32 = 0;
+			glcore.glGetIntegerv(GL_MAX_PATCH_VERTICES, &mut max_patch_vertices as *mut i32)?;
+			if patch_vertices == 0 || patch_vertices > max_patch_vertices as u32 {
+				return Err(PipelineError::InvalidPatchVertices(patch_vertices, max_patch_vertices as u32));
+			}
+			glcore.glPatchParameteri(GL_PATCH_VERTICES, patch_vertices as i32)?;
+		}
+		if let Some((outer, inner)) = mesh.get_patch_default_levels() {
+			glcore.glPatchParameterfv(GL_PATCH_DEFAULT_OUTER_LEVEL, outer.as_ptr())?;
+			glcore.glPatchParameterfv(GL_PATCH_DEFAULT_INNER_LEVEL, inner.as_ptr())?;
+		}
+		Ok(())
+	}
+
+	/// Validate the range against the element count, or the vertex count if there's no element buffer. Returns the byte offset of `first` in the element buffer.
+	fn check_range(mesh: &dyn GenericMeshWithMaterial, first: usize, count: usize, base_vertex: i32) -> Result<Option<usize>, PipelineError> {
+		if mesh.get_element_buffer().is_some() {
+			let num_elements = mesh.get_element_count();
+			let invalid = || PipelineError::InvalidDrawRange(i64::try_from(first).unwrap_or(i64::MAX), count, num_elements);
+			if first.checked_add(count).is_none_or(|end| end > num_elements) {
+				return Err(invalid());
+			}
+			Ok(Some(first.checked_mul(mesh.get_element_type().get_size()).ok_or_else(invalid)?))
+		} else {
+			let num_vertices = mesh.get_vertex_count();
+			let first = i64::try_from(first).unwrap_or(i64::MAX).saturating_add(base_vertex as i64);
+			if first < 0 || (first as usize).checked_add(count).is_none_or(|end| end > num_vertices) {
+				return Err(PipelineError::InvalidDrawRange(first, count, num_vertices));
+			}
+			Ok(None)
+		}
+	}
+
+	/// Validate the number of the instances against the instance buffer, any number is valid without the instance buffer
+	fn check_instances(mesh: &dyn GenericMeshWithMaterial, instance_count: usize) -> Result<(), PipelineError> {
+		if mesh.get_instance_buffer().is_some() {
+			let num_instances = mesh.get_instance_count();
+			if instance_count > num_instances {
+				return Err(PipelineError::InvalidInstanceCount(instance_count, num_instances));
+			}
+		}
 		Ok(())
 	}
 
@@ -421,6 +1428,75 @@ impl<'a, V: VertexType, I: VertexType> PipelineBind<'a, V, I> {
 	pub fn unbind(self) {}
 }
 
+/// Is the base instance of the draw calls available, it's the core of OpenGL 4.2
+fn has_base_instance(glcore: &GLCore) -> Result<bool, GLCoreError> {
+	let mut major: i32 = 0;
+	let mut minor: i32 = 0;
+	glcore.glGetIntegerv(GL_MAJOR_VERSION, &mut major as *mut _)?;
+	glcore.glGetIntegerv(GL_MINOR_VERSION, &mut minor as *mut _)?;
+	Ok((major, minor) >= (4, 2))
+}
+
+/// Is the draw count from a buffer available, it's the core of OpenGL 4.6 or by `GL_ARB_indirect_parameters`
+fn has_indirect_count(glcore: &GLCore) -> Result<bool, GLCoreError> {
+	let mut major: i32 = 0;
+	let mut minor: i32 = 0;
+	glcore.glGetIntegerv(GL_MAJOR_VERSION, &mut major as *mut _)?;
+	glcore.glGetIntegerv(GL_MINOR_VERSION, &mut minor as *mut _)?;
+	if (major, minor) >= (4, 6) {
+		return Ok(true);
+	}
+	has_gl_extension(glcore, "GL_ARB_indirect_parameters")
+}
+
+/// Is the separated vertex format available, `glVertexAttribFormat()` and `glBindVertexBuffer()` are the core of OpenGL 4.3
+fn has_vertex_attrib_binding(glcore: &GLCore) -> Result<bool, GLCoreError> {
+	let mut major: i32 = 0;
+	let mut minor: i32 = 0;
+	glcore.glGetIntegerv(GL_MAJOR_VERSION, &mut major as *mut _)?;
+	glcore.glGetIntegerv(GL_MINOR_VERSION, &mut minor as *mut _)?;
+	Ok((major, minor) >= (4, 3))
+}
+
+impl MaterialBatch {
+	/// Begin skipping the repeated material setups on the current thread
+	pub(crate) fn begin() -> Self {
+		MATERIAL_BATCH.set(Some(None));
+		Self
+	}
+
+	/// The key of the material for the program, the default material of the meshes without material is 0
+	pub(crate) fn key(program: u32, material: Option<&dyn Material>) -> (u32, usize) {
+		(program, material.map_or(0, |material| material as *const dyn Material as *const () as usize))
+	}
+
+	/// Check if there's a batch on the current thread
+	fn is_active() -> bool {
+		MATERIAL_BATCH.get().is_some()
+	}
+
+	/// Check if the material is already set up for the program by the previous draw call of the batch
+	fn is_set_up(program: u32, material: Option<&dyn Material>) -> bool {
+		MATERIAL_BATCH.get().flatten() == Some(Self::key(program, material))
+	}
+
+	/// Remember the material set up for the program if there's a batch
+	fn set_up(program: u32, material: Option<&dyn Material>) {
+		if MATERIAL_BATCH.get().is_some() {
+			MATERIAL_BATCH.set(Some(Some(Self::key(program, material))));
+		}
+	}
+
+	/// End the batch
+	pub(crate) fn end(self) {} // End by owning it in the function and `drop()`
+}
+
+impl Drop for MaterialBatch {
+	fn drop(&mut self) {
+		MATERIAL_BATCH.set(None);
+	}
+}
+
 impl<'a, V: VertexType, I: VertexType> Drop for PipelineBind<'a, V, I> {
 	fn drop(&mut self) {
 		self.pipeline.glcore.glBindVertexArray(0).unwrap();
@@ -433,6 +1509,42 @@ impl<V: VertexType, I: VertexType> Drop for Pipeline<V, I> {
 	}
 }
 
+impl<V: VertexType, I: VertexType> GenericPipeline for Pipeline<V, I> {
+	fn get_glcore(&self) -> &Rc<GLCore> {
+		&self.glcore
+	}
+
+	fn get_shader(&self) -> &Rc<Shader> {
+		&self.shader
+	}
+
+	fn get_mesh(&self) -> &Rc<dyn GenericMeshWithMaterial> {
+		&self.mesh
+	}
+
+	fn get_render_state(&self) -> Option<RenderState> {
+		self.render_state.get()
+	}
+
+	fn set_render_state(&self, render_state: Option<RenderState>) {
+		self.render_state.set(render_state);
+	}
+
+	fn get_bounds(&self) -> Result<Option<Aabb>, PipelineError> {
+		Pipeline::get_bounds(self)
+	}
+
+	fn draw_dyn(&self, fbo: Option<&Framebuffer>, apply_render_state: bool, setup: &mut dyn FnMut(&ShaderUse) -> Result<(), ShaderError>) -> Result<DrawStats, PipelineError> {
+		let mut bind = self.bind()?;
+		if !apply_render_state {
+			bind = bind.keep_render_state();
+		}
+		let stats = bind.draw_with(fbo, setup)?;
+		bind.unbind();
+		Ok(stats)
+	}
+}
+
 impl<V: VertexType, I: VertexType> Debug for Pipeline<V, I> {
 	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
 		f.debug_struct("Pipeline")
@@ -441,10 +1553,87 @@ impl<V: VertexType, I: VertexType> Debug for Pipeline<V, I> {
 		.field("shader", &self.shader)
 		.field("vertex_stride", &self.vertex_stride)
 		.field("instance_stride", &self.instance_stride)
+		.field("vertex_stream", &self.vertex_stream)
+		.field("instance_stream", &self.instance_stream)
+		.field("separated", &self.separated)
+		.field("warnings", &self.warnings)
+		.field("render_state", &self.render_state)
+		.field("viewport_array", &self.viewport_array)
+		.field("label", &self.label)
 		.finish()
 	}
 }
 
+/// Draws the bounding boxes as the unit cubes transformed by `Aabb::to_matrix()` with the queries for the occlusion culling, the colors and the depth are not written.
+/// The boxes are tested against the current depth buffer, it needs the depth of the occluders, e.g. by a depth prepass or from the previous frame.
+///
+/// ```ignore
+/// let proxy = OcclusionProxy::new(glcore.clone())?;
+/// let query = Query::new(glcore.clone(), QueryTarget::AnySamplesPassed)?;
+/// proxy.draw_query(Some(&framebuffer), &query, &bounds, &view_projection)?;
+/// pipeline.bind()?.draw_conditional(Some(&framebuffer), &query, ConditionalMode::Wait)?;
+/// ```
+#[derive(Debug)]
+pub struct OcclusionProxy {
+	pub pipeline: Pipeline<ShapeVertex, UnusedType>,
+}
+
+/// The vertex shader of `OcclusionProxy`, the uniform `mvp` transforms the unit cube
+const OCCLUSION_PROXY_VERTEX_SHADER: &str = "
+#version 330\n
+
+in vec3 position;
+
+uniform mat4 mvp;
+
+void main()
+{
+	gl_Position = mvp * vec4(position, 1.0);
+}
+";
+
+/// The fragment shader of `OcclusionProxy`, nothing is written by the color mask
+const OCCLUSION_PROXY_FRAGMENT_SHADER: &str = "
+#version 330\n
+
+out vec4 Color;
+
+void main()
+{
+	Color = vec4(1.0);
+}
+";
+
+impl OcclusionProxy {
+	/// Create the unit cube and the shader, the depth is tested by `GL_LEQUAL` without writing, and the colors are not written
+	pub fn new(glcore: Rc<GLCore>) -> Result<Self, PipelineError> {
+		let mesh = crate::mesh::shapes::cube(glcore.clone(), 1.0)?;
+		let shader = Rc::new(Shader::new(glcore.clone(), Some(OCCLUSION_PROXY_VERTEX_SHADER), None, Some(OCCLUSION_PROXY_FRAGMENT_SHADER))?);
+		let pipeline = Pipeline::new(glcore, Rc::new(mesh), shader)?;
+		pipeline.set_render_state(Some(RenderState {
+			depth_test: true,
+			depth_write: false,
+			depth_func: CompareFunc::LEqual,
+			color_mask: [false; 4],
+			..Default::default()
+		}));
+		Ok(Self {
+			pipeline,
+		})
+	}
+
+	/// Draw the bounding box transformed by `view_projection` while `query` is counting, then use the query for `PipelineBind::draw_conditional()`
+	pub fn draw_query(&self, fbo: Option<&Framebuffer>, query: &Query, aabb: &Aabb, view_projection: &Mat4) -> Result<(), PipelineError> {
+		let mvp = view_projection * aabb.to_matrix();
+		let p_bind = self.pipeline.bind()?;
+		let active = query.begin()?;
+		p_bind.draw_with(fbo, |program| program.set_uniform("mvp", &mvp))?;
+		active.end();
+		p_bind.unbind();
+		Ok(())
+	}
+}
+
 derive_vertex_type! {
 	/// The unused type for you if you don't want to use the instanced mesh
 	pub struct UnusedType {}
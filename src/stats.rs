@@ -0,0 +1,127 @@
+use std::{
+	cell::Cell,
+	fmt::{self, Display, Formatter},
+	ops::{Add, AddAssign},
+};
+
+/// The counts of the draw calls submitted by `PipelineBind::draw()`, `Pipelineset::draw()` and the other drawing functions.
+/// `elements` is the number of the elements, or the vertices without the element buffer, of one instance, it's not multiplied by `instances`.
+/// The indirect draws and `draw_transform_feedback()` are counted as one draw call without the elements, their counts are only known by the GPU.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawStats {
+	/// The number of the `glDraw*()` calls
+	pub draw_calls: u32,
+
+	/// The number of the instances drawn, 1 for each of the non-instanced draw calls
+	pub instances: u64,
+
+	/// The number of the elements or the vertices submitted
+	pub elements: u64,
+
+	/// The number of the draw calls skipped by the frustum culling of `Pipelineset::draw_culled()`
+	pub culled: u32,
+
+	/// The number of the shader program switches of the `DrawOrder::StateSorted` draws of `Pipelineset`, the other draws use the program for each of the draw calls and don't count
+	pub program_switches: u32,
+
+	/// The number of the material setups of the `DrawOrder::StateSorted` draws of `Pipelineset`, the repeated materials of the same program are skipped
+	pub material_setups: u32,
+}
+
+/// The opt-in collector of the `DrawStats` of all of the draw calls on the current thread, e.g. for a frame-stats overlay. Call `reset()` at the beginning of each frame.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameStats;
+
+thread_local! {
+	static FRAME_STATS: Cell<Option<DrawStats>> = const {Cell::new(None)};
+}
+
+impl DrawStats {
+	/// Create the stats of `draw_calls` draw calls
+	pub fn new(draw_calls: u32, instances: u64, elements: u64) -> Self {
+		Self {
+			draw_calls,
+			instances,
+			elements,
+			culled: 0,
+			program_switches: 0,
+			material_setups: 0,
+		}
+	}
+
+	/// The stats of a single draw call of `elements` elements for `instances` instances
+	pub(crate) fn single(elements: usize, instances: usize) -> Self {
+		Self::new(1, instances as u64, elements as u64)
+	}
+}
+
+impl FrameStats {
+	/// Start collecting on the current thread with all of the counts being zero
+	pub fn enable() {
+		FRAME_STATS.set(Some(DrawStats::default()));
+	}
+
+	/// Stop collecting on the current thread
+	pub fn disable() {
+		FRAME_STATS.set(None);
+	}
+
+	/// Check if the collector is enabled on the current thread
+	pub fn is_enabled() -> bool {
+		FRAME_STATS.get().is_some()
+	}
+
+	/// Get the counts since `enable()` or the last `reset()`, `None` if the collector is disabled
+	pub fn report() -> Option<DrawStats> {
+		FRAME_STATS.get()
+	}
+
+	/// Get the counts and set them to zero for the next frame, `None` if the collector is disabled
+	pub fn reset() -> Option<DrawStats> {
+		let report = FRAME_STATS.get();
+		if report.is_some() {
+			FRAME_STATS.set(Some(DrawStats::default()));
+		}
+		report
+	}
+
+	/// Count in the stats of a draw
+	pub(crate) fn record(stats: DrawStats) {
+		if let Some(mut total) = FRAME_STATS.get() {
+			total += stats;
+			FRAME_STATS.set(Some(total));
+		}
+	}
+}
+
+impl Add for DrawStats {
+	type Output = Self;
+	fn add(mut self, rhs: Self) -> Self {
+		self += rhs;
+		self
+	}
+}
+
+impl AddAssign for DrawStats {
+	fn add_assign(&mut self, rhs: Self) {
+		self.draw_calls += rhs.draw_calls;
+		self.instances += rhs.instances;
+		self.elements += rhs.elements;
+		self.culled += rhs.culled;
+		self.program_switches += rhs.program_switches;
+		self.material_setups += rhs.material_setups;
+	}
+}
+
+impl Display for DrawStats {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{} draw calls, {} instances, {} elements", self.draw_calls, self.instances, self.elements)?;
+		if self.culled > 0 {
+			write!(f, ", {} culled", self.culled)?;
+		}
+		if self.program_switches > 0 || self.material_setups > 0 {
+			write!(f, ", {} program switches, {} material setups", self.program_switches, self.material_setups)?;
+		}
+		Ok(())
+	}
+}